@@ -22,7 +22,7 @@ use crate::{
         export, import, Context,
     },
     common::{infra::config::USERS, meta, migration},
-    service::{compact, db, file_list, users},
+    service::{alerts, compact, db, file_list, users},
 };
 
 pub async fn cli() -> Result<bool, anyhow::Error> {
@@ -117,6 +117,28 @@ pub async fn cli() -> Result<bool, anyhow::Error> {
                         .help("the parquet file name"),
                 ),
             clap::Command::new("migrate-schemas").about("migrate from single row to row per schema version"),
+            clap::Command::new("import-alert-rules")
+                .about("import the alerting rules of a prometheus rule file")
+                .args([
+                    clap::Arg::new("org")
+                        .short('o')
+                        .long("org")
+                        .value_name("org")
+                        .required(true)
+                        .help("organization to import the alerts into"),
+                    clap::Arg::new("file")
+                        .short('f')
+                        .long("file")
+                        .value_name("file")
+                        .required(true)
+                        .help("the prometheus rule file"),
+                    clap::Arg::new("destinations")
+                        .short('d')
+                        .long("destinations")
+                        .value_name("destinations")
+                        .required(true)
+                        .help("comma separated alert destinations"),
+                ]),
         ])
         .get_matches();
 
@@ -273,6 +295,33 @@ pub async fn cli() -> Result<bool, anyhow::Error> {
             println!("Running schema migration to row per schema version");
             migration::schema::run().await?
         }
+        "import-alert-rules" => {
+            let org = command.get_one::<String>("org").unwrap();
+            let file = command.get_one::<String>("file").unwrap();
+            let destinations = command
+                .get_one::<String>("destinations")
+                .unwrap()
+                .split(',')
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect::<Vec<_>>();
+            let body = std::fs::read(file)?;
+            let result = alerts::prom_rules::import(org, &body, destinations, None).await?;
+            for name in result.imported.iter() {
+                println!("imported alert {name}");
+            }
+            for rule in result.skipped.iter() {
+                println!(
+                    "skipped alert {} of group {}: {}",
+                    rule.alert, rule.group, rule.reason
+                );
+            }
+            println!(
+                "imported {} alerts, skipped {} rules",
+                result.imported.len(),
+                result.skipped.len()
+            );
+        }
         _ => {
             return Err(anyhow::anyhow!("unsupported sub command: {name}"));
         }
@@ -17,9 +17,13 @@ use std::sync::Arc;
 
 use config::{
     meta::{
-        alerts::{alert::Alert, destinations::Destination, templates::Template},
+        alerts::{
+            alert::Alert, destinations::Destination, inhibitions::InhibitionRule,
+            recording_rules::RecordingRuleGroup, silences::Silence, templates::Template,
+        },
         dashboards::reports,
         function::Transform,
+        quota::IngestionQuota,
         stream::StreamParams,
     },
     RwAHashMap, RwHashMap,
@@ -66,8 +70,12 @@ pub static REALTIME_ALERT_TRIGGERS: Lazy<RwAHashMap<String, db_scheduler::Trigge
     Lazy::new(Default::default);
 pub static ALERTS_TEMPLATES: Lazy<RwHashMap<String, Template>> = Lazy::new(Default::default);
 pub static ALERTS_DESTINATIONS: Lazy<RwHashMap<String, Destination>> = Lazy::new(Default::default);
+pub static RECORDING_RULES: Lazy<RwHashMap<String, RecordingRuleGroup>> =
+    Lazy::new(Default::default);
+pub static INHIBITION_RULES: Lazy<RwHashMap<String, InhibitionRule>> = Lazy::new(Default::default);
 pub static DASHBOARD_REPORTS: Lazy<RwHashMap<String, reports::Report>> =
     Lazy::new(Default::default);
+pub static INGESTION_QUOTAS: Lazy<RwHashMap<String, IngestionQuota>> = Lazy::new(Default::default);
 pub static SYSLOG_ROUTES: Lazy<RwHashMap<String, SyslogRoute>> = Lazy::new(Default::default);
 pub static SYSLOG_ENABLED: Lazy<Arc<RwLock<bool>>> = Lazy::new(|| Arc::new(RwLock::new(false)));
 pub static ENRICHMENT_TABLES: Lazy<RwHashMap<String, StreamTable>> = Lazy::new(Default::default);
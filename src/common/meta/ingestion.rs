@@ -98,6 +98,24 @@ pub const INGESTION_EP: [&str; 14] = [
     "_json_arrow",
 ];
 
+/// The ingestion endpoints of the compatible APIs, their last segment alone is too common
+/// so they are matched on the end of the path.
+pub const INGESTION_EP_PATHS: [&str; 4] = [
+    "loki/api/v1/push",
+    "services/collector/event",
+    "services/collector/raw",
+    "zipkin/api/v2/spans",
+];
+
+/// Whether the path, `{org_id}/...`, ends with one of [`INGESTION_EP_PATHS`].
+pub fn is_ingestion_ep_path(path: &str) -> bool {
+    let path = path.trim_end_matches('/');
+    INGESTION_EP_PATHS.iter().any(|ep| {
+        path.strip_suffix(ep)
+            .is_some_and(|prefix| prefix.ends_with('/'))
+    })
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct BulkResponse {
     pub took: u128,
@@ -162,6 +180,70 @@ impl BulkResponseError {
     }
 }
 
+/// Metadata of the Splunk HEC events, set in the events or as query parameters of the request.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HecMetadata {
+    #[serde(default)]
+    pub index: Option<String>,
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub sourcetype: Option<String>,
+}
+
+/// Response of the Splunk HEC compatible ingestion API, the code is 0 on success.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct HecResponse {
+    pub text: String,
+    pub code: u16,
+    #[serde(rename = "invalid-event-number")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invalid_event_number: Option<usize>,
+    /// The indexes of the events rejected when the others were ingested
+    #[serde(rename = "invalid-event-numbers")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub invalid_event_numbers: Vec<usize>,
+}
+
+impl HecResponse {
+    pub const NO_DATA: u16 = 5;
+    pub const INVALID_DATA_FORMAT: u16 = 6;
+    pub const SERVER_BUSY: u16 = 9;
+    pub const EVENT_FIELD_REQUIRED: u16 = 12;
+    pub const EVENT_FIELD_BLANK: u16 = 13;
+
+    pub fn success() -> Self {
+        HecResponse {
+            text: "Success".to_string(),
+            code: 0,
+            invalid_event_number: None,
+            invalid_event_numbers: vec![],
+        }
+    }
+
+    /// Some of the events were ingested, the forwarders must not retry the request.
+    pub fn partial_success(text: impl ToString, invalid_event_numbers: Vec<usize>) -> Self {
+        HecResponse {
+            text: text.to_string(),
+            code: 0,
+            invalid_event_number: invalid_event_numbers.first().copied(),
+            invalid_event_numbers,
+        }
+    }
+
+    pub fn error(code: u16, text: impl ToString, invalid_event_number: Option<usize>) -> Self {
+        HecResponse {
+            text: text.to_string(),
+            code,
+            invalid_event_number,
+            invalid_event_numbers: vec![],
+        }
+    }
+}
+
 impl BulkResponseItem {
     pub fn new_failed(
         _index: String,
@@ -325,6 +407,10 @@ pub enum IngestionRequest<'a> {
     KinesisFH(&'a KinesisFHRequest),
     RUM(&'a web::Bytes),
     Usage(&'a web::Bytes),
+    /// Records decoded from a Loki push request
+    Loki(&'a Vec<json::Value>),
+    /// Records decoded from the messages of a Kafka topic
+    Kafka(&'a Vec<json::Value>),
 }
 
 pub enum IngestionData<'a> {
@@ -366,3 +452,23 @@ pub enum IngestionDataIter<'a> {
         Option<KinesisFHIngestionResponse>,
     ),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_ingestion_ep_path() {
+        assert!(is_ingestion_ep_path("default/loki/api/v1/push"));
+        assert!(is_ingestion_ep_path("default/loki/api/v1/push/"));
+        assert!(!is_ingestion_ep_path("default/push"));
+        assert!(!is_ingestion_ep_path("default/users/push"));
+        assert!(!is_ingestion_ep_path("default/xloki/api/v1/push"));
+        assert!(is_ingestion_ep_path("default/services/collector/event"));
+        assert!(is_ingestion_ep_path("default/services/collector/raw"));
+        assert!(!is_ingestion_ep_path("default/alerts/event"));
+        assert!(!is_ingestion_ep_path("default/raw"));
+        assert!(is_ingestion_ep_path("default/zipkin/api/v2/spans"));
+        assert!(!is_ingestion_ep_path("default/spans"));
+    }
+}
@@ -25,6 +25,7 @@ pub mod proxy;
 pub mod saved_view;
 pub mod search;
 pub mod service;
+pub mod sourcemap;
 pub mod stream;
 pub mod syslog;
 pub mod telemetry;
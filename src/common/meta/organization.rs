@@ -124,6 +124,10 @@ pub struct OrganizationSettingPayload {
     pub span_id_field_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub toggle_ingestion_logs: Option<bool>,
+    /// Maximum size in MB of an exported search result file, 0 to use
+    /// `ZO_EXPORT_MAX_SIZE`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub export_max_size: Option<usize>,
 }
 
 #[derive(Serialize, ToSchema, Deserialize, Debug, Clone)]
@@ -138,6 +142,10 @@ pub struct OrganizationSetting {
     pub span_id_field_name: String,
     #[serde(default = "default_toggle_ingestion_logs")]
     pub toggle_ingestion_logs: bool,
+    /// Maximum size in MB of an exported search result file, it can't exceed
+    /// `ZO_EXPORT_MAX_SIZE`. 0 to use `ZO_EXPORT_MAX_SIZE`.
+    #[serde(default)]
+    pub export_max_size: usize,
 }
 
 impl Default for OrganizationSetting {
@@ -147,6 +155,7 @@ impl Default for OrganizationSetting {
             trace_id_field_name: default_trace_id_field_name(),
             span_id_field_name: default_span_id_field_name(),
             toggle_ingestion_logs: default_toggle_ingestion_logs(),
+            export_max_size: 0,
         }
     }
 }
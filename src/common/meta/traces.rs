@@ -86,6 +86,96 @@ pub struct SpanLink {
     pub dropped_attributes_count: u32,
 }
 
+/// A single trace assembled from its spans, see `service::traces::tree`
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct TraceTree {
+    pub trace_id: String,
+    /// nanoseconds
+    pub start_time: u64,
+    /// nanoseconds
+    pub end_time: u64,
+    /// microseconds
+    pub duration: u64,
+    pub total_spans: usize,
+    pub error_spans: usize,
+    pub services: Vec<TraceServiceDuration>,
+    /// span ids on the critical path, from the root span down to the last blocking span
+    pub critical_path: Vec<String>,
+    /// span ids whose parent span can't be found in the trace
+    pub orphaned_spans: Vec<String>,
+    /// root spans, orphaned spans are also returned as roots
+    pub spans: Vec<TraceSpanNode>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct TraceSpanNode {
+    pub span_id: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_span_id: Option<String>,
+    pub service_name: String,
+    pub operation_name: String,
+    pub span_kind: String,
+    pub span_status: String,
+    /// nanoseconds
+    pub start_time: u64,
+    /// nanoseconds
+    pub end_time: u64,
+    /// microseconds
+    pub duration: u64,
+    /// microseconds, the duration not covered by any child span
+    pub self_duration: u64,
+    pub is_critical: bool,
+    /// the original span record
+    #[schema(value_type = Object)]
+    pub span: json::Value,
+    pub children: Vec<TraceSpanNode>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct TraceServiceDuration {
+    pub service_name: String,
+    pub span_count: usize,
+    pub error_count: usize,
+    /// microseconds, sum of the span durations
+    pub duration: u64,
+    /// microseconds, sum of the span self durations
+    pub self_duration: u64,
+}
+
+/// Service dependency graph derived from trace spans, see `service::metadata::service_graph`
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct ServiceGraph {
+    pub nodes: Vec<ServiceGraphNode>,
+    pub edges: Vec<ServiceGraphEdge>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct ServiceGraphNode {
+    pub service_name: String,
+    /// requests received from other services
+    pub request_count: u64,
+    pub error_count: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct ServiceGraphEdge {
+    pub client_service: String,
+    pub server_service: String,
+    pub request_count: u64,
+    pub error_count: u64,
+    /// requests per second
+    pub request_rate: f64,
+    /// error_count / request_count
+    pub error_rate: f64,
+    /// milliseconds
+    pub p50_duration: f64,
+    /// milliseconds
+    pub p95_duration: f64,
+    /// milliseconds
+    pub p99_duration: f64,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
 pub struct ExportTraceServiceResponse {
     // The details of a partially successful export request.
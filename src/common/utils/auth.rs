@@ -26,7 +26,7 @@ use regex::Regex;
 #[cfg(feature = "enterprise")]
 use crate::common::infra::config::USER_SESSIONS;
 #[cfg(feature = "enterprise")]
-use crate::common::meta::ingestion::INGESTION_EP;
+use crate::common::meta::ingestion::{is_ingestion_ep_path, INGESTION_EP};
 use crate::common::{
     infra::config::{PASSWORD_HASH, USERS},
     meta::{
@@ -226,7 +226,9 @@ impl FromRequest for AuthExtractor {
         let url_len = path_columns.len();
         let org_id = path_columns[0].to_string();
 
-        if method.eq("POST") && INGESTION_EP.contains(&path_columns[url_len - 1]) {
+        if method.eq("POST")
+            && (INGESTION_EP.contains(&path_columns[url_len - 1]) || is_ingestion_ep_path(path))
+        {
             if let Some(auth_header) = req.headers().get("Authorization") {
                 if let Ok(auth_str) = auth_header.to_str() {
                     return ready(Ok(AuthExtractor {
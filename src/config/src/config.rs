@@ -348,6 +348,7 @@ pub struct Config {
     pub s3: S3,
     pub sns: Sns,
     pub tcp: TCP,
+    pub kafka: Kafka,
     pub prom: Prometheus,
     pub profiling: Pyroscope,
     pub smtp: Smtp,
@@ -485,6 +486,22 @@ pub struct Grpc {
     pub max_message_size: usize,
     #[env_config(name = "ZO_GRPC_CONNECT_TIMEOUT", default = 5)] // in seconds
     pub connect_timeout: u64,
+    #[env_config(
+        name = "ZO_FLIGHT_SQL_ENABLED",
+        default = false,
+        help = "Serve the Arrow Flight SQL protocol on the querier nodes for JDBC/ADBC clients"
+    )]
+    pub flight_sql_enabled: bool,
+    #[env_config(name = "ZO_FLIGHT_SQL_PORT", default = 5085)]
+    pub flight_sql_port: u16,
+    #[env_config(name = "ZO_FLIGHT_SQL_SESSION_TTL", default = 24)] // hours
+    pub flight_sql_session_ttl: i64,
+    #[env_config(
+        name = "ZO_FLIGHT_SQL_DEFAULT_TIME_RANGE",
+        default = 24,
+        help = "Time range in hours searched by the Flight SQL queries without a _timestamp condition"
+    )]
+    pub flight_sql_default_time_range: i64,
 }
 
 #[derive(EnvConfig)]
@@ -493,6 +510,52 @@ pub struct TCP {
     pub tcp_port: u16,
     #[env_config(name = "ZO_UDP_PORT", default = 5514)]
     pub udp_port: u16,
+    #[env_config(
+        name = "ZO_TCP_MAX_MESSAGE_SIZE",
+        default = 65536,
+        help = "Maximum size in bytes of a syslog message received over TCP, the longer messages are truncated"
+    )]
+    pub tcp_max_message_size: usize,
+    #[env_config(
+        name = "ZO_TCP_TLS_ENABLED",
+        default = false,
+        help = "Also receive syslog messages over TLS (RFC 5425)"
+    )]
+    pub tcp_tls_enabled: bool,
+    #[env_config(name = "ZO_TCP_TLS_PORT", default = 6514)]
+    pub tcp_tls_port: u16,
+    #[env_config(
+        name = "ZO_TCP_TLS_CERT_PATH",
+        default = "",
+        help = "Path of the PEM certificate chain of the syslog TLS listener"
+    )]
+    pub tcp_tls_cert_path: String,
+    #[env_config(
+        name = "ZO_TCP_TLS_KEY_PATH",
+        default = "",
+        help = "Path of the PEM private key of the syslog TLS listener"
+    )]
+    pub tcp_tls_key_path: String,
+    #[env_config(
+        name = "ZO_STATSD_ENABLED",
+        default = false,
+        help = "Receive StatsD and DogStatsD metrics over UDP on the ingester nodes"
+    )]
+    pub statsd_enabled: bool,
+    #[env_config(name = "ZO_STATSD_PORT", default = 8125)]
+    pub statsd_port: u16,
+    #[env_config(
+        name = "ZO_STATSD_FLUSH_INTERVAL",
+        default = 10,
+        help = "Interval in seconds at which the aggregated StatsD metrics are ingested"
+    )]
+    pub statsd_flush_interval: u64,
+    #[env_config(
+        name = "ZO_STATSD_ORG",
+        default = "default",
+        help = "Organization the StatsD metrics are ingested into"
+    )]
+    pub statsd_org: String,
 }
 
 #[derive(EnvConfig)]
@@ -818,6 +881,24 @@ pub struct Common {
         help = "traces span metrics channel send buffer"
     )]
     pub traces_span_metrics_channel_buffer: usize,
+    #[env_config(
+        name = "ZO_TRACES_SERVICE_GRAPH_ENABLED",
+        default = false,
+        help = "enable generating service graph from trace spans"
+    )]
+    pub traces_service_graph_enabled: bool,
+    #[env_config(
+        name = "ZO_TRACES_SERVICE_GRAPH_FLUSH_INTERVAL",
+        default = 60,
+        help = "traces service graph flush interval, unit seconds"
+    )]
+    pub traces_service_graph_flush_interval: u64,
+    #[env_config(
+        name = "ZO_TRACES_SERVICE_GRAPH_MAX_ITEMS",
+        default = 100000,
+        help = "max number of spans per stream kept in memory to pair with their parent or child spans"
+    )]
+    pub traces_service_graph_max_items: usize,
     #[env_config(
         name = "ZO_SELF_METRIC_CONSUMPTION_ENABLED",
         default = false,
@@ -928,6 +1009,38 @@ pub struct Limit {
     pub query_partition_by_secs: usize,
     #[env_config(name = "ZO_QUERY_GROUP_BASE_SPEED", default = 768)] // MB/s/core
     pub query_group_base_speed: usize,
+    #[env_config(name = "ZO_SEARCH_JOB_TTL", default = 24)] // hours
+    pub search_job_ttl: i64,
+    #[env_config(
+        name = "ZO_SEARCH_JOB_MAX_PER_USER",
+        default = 5,
+        help = "Maximum number of pending or running search jobs of a user"
+    )]
+    pub search_job_max_per_user: usize,
+    #[env_config(name = "ZO_SEARCH_JOB_PART_SIZE", default = 10000)] // rows per result file
+    pub search_job_part_size: i64,
+    #[env_config(name = "ZO_SEARCH_JOB_MAX_ROWS", default = 1000000)]
+    pub search_job_max_rows: i64,
+    #[env_config(name = "ZO_SEARCH_JOB_CLEANUP_INTERVAL", default = 600)] // seconds
+    pub search_job_cleanup_interval: u64,
+    #[env_config(name = "ZO_TAIL_POLL_INTERVAL", default = 500)] // milliseconds
+    pub tail_poll_interval: u64,
+    #[env_config(
+        name = "ZO_TAIL_MAX_RECORDS_PER_SEC",
+        default = 1000,
+        help = "Maximum number of records pushed per second to a live tail connection, the others are dropped"
+    )]
+    pub tail_max_records_per_sec: usize,
+    #[env_config(name = "ZO_TAIL_SEND_TIMEOUT", default = 10)] // seconds
+    pub tail_send_timeout: u64,
+    #[env_config(
+        name = "ZO_EXPORT_MAX_SIZE",
+        default = 0,
+        help = "Maximum size in MB of an exported search result file, organizations can lower it. 0 to use the ZO_PAYLOAD_LIMIT"
+    )]
+    pub export_max_size: usize,
+    #[env_config(name = "ZO_EXPORT_LINK_TTL", default = 24)] // hours
+    pub export_link_ttl: i64,
     #[env_config(name = "ZO_INGEST_ALLOWED_UPTO", default = 5)] // in hours - in past
     pub ingest_allowed_upto: i64,
     #[env_config(name = "ZO_INGEST_FLATTEN_LEVEL", default = 3)] // default flatten level
@@ -1074,6 +1187,12 @@ pub struct Limit {
     pub max_enrichment_table_size: usize,
     #[env_config(name = "ZO_SHORT_URL_RETENTION_DAYS", default = 30)] // days
     pub short_url_retention_days: i64,
+    #[env_config(
+        name = "ZO_ALERT_HISTORY_RETENTION_DAYS",
+        default = 30,
+        help = "Days the alert history is kept, 0 keeps it forever"
+    )]
+    pub alert_history_retention_days: i64,
     #[env_config(
         name = "ZO_INVERTED_INDEX_CACHE_MAX_ENTRIES",
         default = 100000,
@@ -1352,6 +1471,60 @@ pub struct Sns {
     pub operation_timeout: u64,
 }
 
+#[derive(Debug, EnvConfig)]
+pub struct Kafka {
+    #[env_config(
+        name = "ZO_KAFKA_ENABLED",
+        default = false,
+        help = "Consume the Kafka topics of ZO_KAFKA_TOPICS on the ingester nodes, requires the kafka feature"
+    )]
+    pub enabled: bool,
+    #[env_config(name = "ZO_KAFKA_BROKERS", default = "localhost:9092")]
+    pub brokers: String,
+    #[env_config(name = "ZO_KAFKA_GROUP_ID", default = "openobserve")]
+    pub group_id: String,
+    #[env_config(
+        name = "ZO_KAFKA_TOPICS",
+        default = "",
+        help = "Comma separated topics to consume as `<topic>=<org>/<stream>[:json|otlp]`, the payloads are json by default, otlp is for OTLP protobuf logs"
+    )]
+    pub topics: String,
+    #[env_config(
+        name = "ZO_KAFKA_AUTO_OFFSET_RESET",
+        default = "earliest",
+        help = "Where to start consuming when the consumer group has no committed offset: earliest or latest"
+    )]
+    pub auto_offset_reset: String,
+    #[env_config(name = "ZO_KAFKA_SECURITY_PROTOCOL", default = "plaintext")]
+    pub security_protocol: String,
+    #[env_config(name = "ZO_KAFKA_SASL_MECHANISM", default = "")]
+    pub sasl_mechanism: String,
+    #[env_config(name = "ZO_KAFKA_SASL_USERNAME", default = "")]
+    pub sasl_username: String,
+    #[env_config(name = "ZO_KAFKA_SASL_PASSWORD", default = "")]
+    pub sasl_password: String,
+    #[env_config(
+        name = "ZO_KAFKA_BATCH_SIZE",
+        default = 1000,
+        help = "Maximum messages ingested at once, the offsets are committed after every batch"
+    )]
+    pub batch_size: usize,
+    #[env_config(name = "ZO_KAFKA_BATCH_TIMEOUT", default = 1000)] // milliseconds
+    pub batch_timeout: u64,
+    #[env_config(
+        name = "ZO_KAFKA_MAX_RETRIES",
+        default = 5,
+        help = "Retries of a batch which failed to be ingested, then the batch is sent to ZO_KAFKA_DEAD_LETTER_TOPIC or dropped and its offsets are committed"
+    )]
+    pub max_retries: u32,
+    #[env_config(
+        name = "ZO_KAFKA_DEAD_LETTER_TOPIC",
+        default = "",
+        help = "Topic receiving the messages which failed to be ingested after all the retries, they are dropped when it's empty"
+    )]
+    pub dead_letter_topic: String,
+}
+
 #[derive(Debug, EnvConfig)]
 pub struct Prometheus {
     #[env_config(name = "ZO_PROMETHEUS_HA_CLUSTER", default = "cluster")]
@@ -1518,6 +1691,21 @@ pub fn init() -> Config {
         panic!("sns config error: {e}");
     }
 
+    // check tcp config
+    if let Err(e) = check_tcp_config(&mut cfg) {
+        panic!("tcp config error: {e}");
+    }
+
+    // check kafka config
+    if let Err(e) = check_kafka_config(&mut cfg) {
+        panic!("kafka config error: {e}");
+    }
+
+    // check grpc config
+    if let Err(e) = check_grpc_config(&mut cfg) {
+        panic!("grpc config error: {e}");
+    }
+
     cfg
 }
 
@@ -1528,6 +1716,9 @@ fn check_common_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
     if cfg.limit.req_cols_per_record_limit == 0 {
         cfg.limit.req_cols_per_record_limit = 1000;
     }
+    if cfg.common.traces_service_graph_flush_interval == 0 {
+        cfg.common.traces_service_graph_flush_interval = 60;
+    }
 
     // check max_file_size_on_disk to MB
     if cfg.limit.max_file_size_on_disk == 0 {
@@ -1805,6 +1996,30 @@ fn check_memory_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
     if cfg.limit.query_default_limit == 0 {
         cfg.limit.query_default_limit = 1000;
     }
+    if cfg.limit.search_job_ttl <= 0 {
+        cfg.limit.search_job_ttl = 24;
+    }
+    if cfg.limit.search_job_part_size <= 0 {
+        cfg.limit.search_job_part_size = 10000;
+    }
+    if cfg.limit.search_job_cleanup_interval == 0 {
+        cfg.limit.search_job_cleanup_interval = 600;
+    }
+    if cfg.limit.tail_poll_interval == 0 {
+        cfg.limit.tail_poll_interval = 500;
+    }
+    if cfg.limit.tail_max_records_per_sec == 0 {
+        cfg.limit.tail_max_records_per_sec = 1000;
+    }
+    if cfg.limit.tail_send_timeout == 0 {
+        cfg.limit.tail_send_timeout = 10;
+    }
+    if cfg.limit.export_max_size == 0 {
+        cfg.limit.export_max_size = (cfg.limit.req_payload_limit / 1024 / 1024).max(1);
+    }
+    if cfg.limit.export_link_ttl <= 0 {
+        cfg.limit.export_link_ttl = 24;
+    }
     Ok(())
 }
 
@@ -1974,6 +2189,56 @@ fn check_sns_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+fn check_tcp_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
+    if cfg.tcp.tcp_max_message_size == 0 {
+        cfg.tcp.tcp_max_message_size = 65536;
+    }
+    if cfg.tcp.tcp_tls_enabled
+        && (cfg.tcp.tcp_tls_cert_path.is_empty() || cfg.tcp.tcp_tls_key_path.is_empty())
+    {
+        return Err(anyhow::anyhow!(
+            "ZO_TCP_TLS_CERT_PATH and ZO_TCP_TLS_KEY_PATH are required when ZO_TCP_TLS_ENABLED is true"
+        ));
+    }
+    if cfg.tcp.statsd_flush_interval == 0 {
+        cfg.tcp.statsd_flush_interval = 10;
+    }
+    if cfg.tcp.statsd_org.is_empty() {
+        cfg.tcp.statsd_org = "default".to_string();
+    }
+    Ok(())
+}
+
+fn check_grpc_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
+    if cfg.grpc.flight_sql_session_ttl <= 0 {
+        cfg.grpc.flight_sql_session_ttl = 24;
+    }
+    if cfg.grpc.flight_sql_default_time_range <= 0 {
+        cfg.grpc.flight_sql_default_time_range = 24;
+    }
+    if cfg.grpc.flight_sql_enabled && cfg.grpc.flight_sql_port == cfg.grpc.port {
+        return Err(anyhow::anyhow!(
+            "ZO_FLIGHT_SQL_PORT must be different from ZO_GRPC_PORT"
+        ));
+    }
+    Ok(())
+}
+
+fn check_kafka_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
+    if cfg.kafka.batch_size == 0 {
+        cfg.kafka.batch_size = 1000;
+    }
+    if cfg.kafka.batch_timeout == 0 {
+        cfg.kafka.batch_timeout = 1000;
+    }
+    if cfg.kafka.enabled && (cfg.kafka.brokers.is_empty() || cfg.kafka.topics.is_empty()) {
+        return Err(anyhow::anyhow!(
+            "ZO_KAFKA_BROKERS and ZO_KAFKA_TOPICS are required when ZO_KAFKA_ENABLED is true"
+        ));
+    }
+    Ok(())
+}
+
 fn check_s3_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
     if !cfg.s3.bucket_prefix.is_empty() && !cfg.s3.bucket_prefix.ends_with('/') {
         cfg.s3.bucket_prefix = format!("{}/", cfg.s3.bucket_prefix);
@@ -2107,4 +2372,18 @@ mod tests {
         assert_eq!(cfg.common.data_dir, "/abc/".to_string());
         assert_eq!(cfg.common.base_uri, "/abc".to_string());
     }
+
+    #[test]
+    fn test_check_tcp_config() {
+        let mut cfg = Config::init().unwrap();
+        cfg.tcp.tcp_max_message_size = 0;
+        check_tcp_config(&mut cfg).unwrap();
+        assert_eq!(cfg.tcp.tcp_max_message_size, 65536);
+
+        cfg.tcp.tcp_tls_enabled = true;
+        assert!(check_tcp_config(&mut cfg).is_err());
+        cfg.tcp.tcp_tls_cert_path = "cert.pem".to_string();
+        cfg.tcp.tcp_tls_key_path = "key.pem".to_string();
+        check_tcp_config(&mut cfg).unwrap();
+    }
 }
@@ -19,7 +19,7 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 use crate::meta::{
-    alerts::{QueryCondition, TriggerCondition},
+    alerts::{grouping::AlertGrouping, QueryCondition, TriggerCondition},
     stream::StreamType,
 };
 
@@ -39,7 +39,16 @@ pub struct Alert {
     pub query_condition: QueryCondition,
     #[serde(default)]
     pub trigger_condition: TriggerCondition,
+    /// Grouping and deduplication of the notifications, every evaluation is notified
+    /// when not set
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grouping: Option<AlertGrouping>,
     pub destinations: Vec<String>,
+    /// Also notify the destinations when a notified group of the alert is resolved, only for
+    /// the scheduled alerts with `grouping` as the realtime alerts are never resolved
+    #[serde(default)]
+    pub send_resolved: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context_attributes: Option<HashMap<String, String>>,
     #[serde(default)]
@@ -83,7 +92,9 @@ impl Default for Alert {
             is_real_time: false,
             query_condition: QueryCondition::default(),
             trigger_condition: TriggerCondition::default(),
+            grouping: None,
             destinations: vec![],
+            send_resolved: false,
             context_attributes: None,
             row_template: "".to_string(),
             description: "".to_string(),
@@ -44,6 +44,13 @@ pub struct Destination {
     pub sns_topic_arn: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aws_region: Option<String>,
+    /// Integration key of the PagerDuty service, required for `PagerDuty` destination_type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routing_key: Option<String>,
+    /// Default severity of the PagerDuty events: `critical`, `error`, `warning` or `info`.
+    /// The `severity` context attribute of the alert takes precedence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
     #[serde(rename = "type")]
     #[serde(default)]
     pub destination_type: DestinationType,
@@ -58,6 +65,15 @@ pub enum DestinationType {
     Email,
     #[serde(rename = "sns")]
     Sns,
+    /// Slack incoming webhook, the message is sent as blocks
+    #[serde(rename = "slack")]
+    Slack,
+    /// PagerDuty Events API v2, `url` defaults to the PagerDuty events endpoint
+    #[serde(rename = "pagerduty")]
+    PagerDuty,
+    /// Microsoft Teams incoming webhook, the message is sent as an adaptive card
+    #[serde(rename = "teams")]
+    Teams,
 }
 
 impl Destination {
@@ -73,6 +89,8 @@ impl Destination {
             destination_type: self.destination_type.clone(),
             sns_topic_arn: self.sns_topic_arn.clone(),
             aws_region: self.aws_region.clone(),
+            routing_key: self.routing_key.clone(),
+            severity: self.severity.clone(),
         }
     }
 }
@@ -93,6 +111,10 @@ pub struct DestinationWithTemplate {
     pub sns_topic_arn: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aws_region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routing_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, ToSchema)]
@@ -0,0 +1,85 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::history::AlertStatus;
+
+/// How the rows of an alert are grouped into notifications, like the Alertmanager routes.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct AlertGrouping {
+    /// Row columns or context attributes the rows are grouped by, all the rows of an
+    /// evaluation are in the same group when empty
+    #[serde(default)]
+    pub group_by: Vec<String>,
+    /// Seconds to wait before sending the first notification of a new group
+    #[serde(default)]
+    pub group_wait: i64,
+    /// Seconds to wait before notifying the changes of a group already notified
+    #[serde(default = "default_group_interval")]
+    pub group_interval: i64,
+    /// Seconds to wait before notifying again a group which didn't change
+    #[serde(default = "default_repeat_interval")]
+    pub repeat_interval: i64,
+}
+
+impl Default for AlertGrouping {
+    fn default() -> Self {
+        Self {
+            group_by: vec![],
+            group_wait: 0,
+            group_interval: default_group_interval(),
+            repeat_interval: default_repeat_interval(),
+        }
+    }
+}
+
+fn default_group_interval() -> i64 {
+    300
+}
+
+fn default_repeat_interval() -> i64 {
+    14400
+}
+
+/// The notification state of a group of firing rows of an alert.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct AlertGroupState {
+    pub fingerprint: String,
+    /// The alert key, `stream_type/stream_name/alert_name`
+    pub alert: String,
+    /// The alert name, stream, context attributes and grouping labels of the group
+    pub labels: HashMap<String, String>,
+    /// Fingerprint of the rows of the last notification
+    #[serde(default)]
+    pub rows_fingerprint: String,
+    pub started_at: i64,
+    pub last_seen_at: i64,
+    /// The group is considered resolved when it isn't seen again before this time
+    pub expires_at: i64,
+    #[serde(default)]
+    pub last_notified_at: i64,
+    /// The states saved before the statuses were tracked are the firing groups
+    #[serde(default)]
+    pub status: AlertStatus,
+}
+
+impl AlertGroupState {
+    pub fn is_active(&self, now: i64) -> bool {
+        self.status != AlertStatus::Resolved && self.expires_at > now
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// The state of a group of an alert.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertStatus {
+    /// The condition is satisfied, the group waits for `group_wait`
+    Pending,
+    /// The condition is satisfied, the group is notified
+    #[default]
+    Firing,
+    /// The condition isn't satisfied anymore
+    Resolved,
+}
+
+impl fmt::Display for AlertStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertStatus::Pending => write!(f, "pending"),
+            AlertStatus::Firing => write!(f, "firing"),
+            AlertStatus::Resolved => write!(f, "resolved"),
+        }
+    }
+}
+
+impl From<&str> for AlertStatus {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "pending" => AlertStatus::Pending,
+            "resolved" => AlertStatus::Resolved,
+            _ => AlertStatus::Firing,
+        }
+    }
+}
+
+/// A change of state or a notification of a group of an alert.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct AlertHistoryEntry {
+    /// Unix timestamp in microseconds
+    pub timestamp: i64,
+    pub alert_name: String,
+    pub stream_type: String,
+    pub stream_name: String,
+    /// Fingerprint of the group of the alert
+    pub fingerprint: String,
+    pub status: AlertStatus,
+    /// The alert name, stream, context attributes and grouping labels of the group
+    pub labels: HashMap<String, String>,
+    /// Why the state changed or what the notification did
+    pub reason: String,
+}
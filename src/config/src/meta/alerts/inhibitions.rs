@@ -0,0 +1,124 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Mutes the notifications of the alerts matching `target_matchers` while an alert
+/// matching `source_matchers` is firing.
+///
+/// The labels of an alert are `alert_name`, `stream_type`, `stream_name`, its context
+/// attributes and the values of its `group_by` columns.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct InhibitionRule {
+    #[serde(default)]
+    pub name: String,
+    /// Labels of the firing alert which inhibits the others
+    #[serde(default)]
+    pub source_matchers: HashMap<String, String>,
+    /// Labels of the inhibited alerts
+    #[serde(default)]
+    pub target_matchers: HashMap<String, String>,
+    /// Labels which must have the same value in the source and the target alerts
+    #[serde(default)]
+    pub equal: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub description: String,
+}
+
+impl Default for InhibitionRule {
+    fn default() -> Self {
+        Self {
+            name: "".to_string(),
+            source_matchers: HashMap::new(),
+            target_matchers: HashMap::new(),
+            equal: vec![],
+            enabled: true,
+            description: "".to_string(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl InhibitionRule {
+    /// Whether the firing alert with `source` labels inhibits the alert with `target` labels.
+    pub fn inhibits(
+        &self,
+        source: &HashMap<String, String>,
+        target: &HashMap<String, String>,
+    ) -> bool {
+        self.enabled
+            && matches(&self.source_matchers, source)
+            && matches(&self.target_matchers, target)
+            && self
+                .equal
+                .iter()
+                .all(|label| source.get(label) == target.get(label))
+    }
+
+    /// Whether an alert with `target` labels can be inhibited by the rule.
+    pub fn targets(&self, target: &HashMap<String, String>) -> bool {
+        self.enabled && matches(&self.target_matchers, target)
+    }
+}
+
+fn matches(matchers: &HashMap<String, String>, labels: &HashMap<String, String>) -> bool {
+    matchers
+        .iter()
+        .all(|(name, value)| labels.get(name) == Some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inhibits() {
+        let rule = InhibitionRule {
+            name: "cluster_down".to_string(),
+            source_matchers: HashMap::from([("alert_name".to_string(), "ClusterDown".to_string())]),
+            target_matchers: HashMap::from([("severity".to_string(), "warning".to_string())]),
+            equal: vec!["cluster".to_string()],
+            ..Default::default()
+        };
+        let source = HashMap::from([
+            ("alert_name".to_string(), "ClusterDown".to_string()),
+            ("cluster".to_string(), "eu-1".to_string()),
+        ]);
+        let mut target = HashMap::from([
+            ("alert_name".to_string(), "HighLatency".to_string()),
+            ("severity".to_string(), "warning".to_string()),
+            ("cluster".to_string(), "eu-1".to_string()),
+        ]);
+        assert!(rule.inhibits(&source, &target));
+        assert!(!rule.inhibits(&target, &source));
+
+        target.insert("cluster".to_string(), "us-1".to_string());
+        assert!(!rule.inhibits(&source, &target));
+
+        target.insert("cluster".to_string(), "eu-1".to_string());
+        let rule = InhibitionRule {
+            enabled: false,
+            ..rule
+        };
+        assert!(!rule.inhibits(&source, &target));
+    }
+}
@@ -21,6 +21,12 @@ use crate::{meta::search::SearchEventType, utils::json::Value};
 pub mod alert;
 pub mod derived_streams;
 pub mod destinations;
+pub mod grouping;
+pub mod history;
+pub mod inhibitions;
+pub mod prom_rules;
+pub mod recording_rules;
+pub mod silences;
 pub mod templates;
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema, PartialEq)]
@@ -0,0 +1,133 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The Prometheus rule file format, see
+//! <https://prometheus.io/docs/prometheus/latest/configuration/recording_rules/>
+
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct PromRuleFile {
+    #[serde(default)]
+    pub groups: Vec<PromRuleGroup>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct PromRuleGroup {
+    pub name: String,
+    /// Prometheus duration, e.g. `1m`
+    #[serde(default)]
+    pub interval: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<PromRule>,
+}
+
+/// Either a recording rule (`record`) or an alerting rule (`alert`).
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct PromRule {
+    #[serde(default)]
+    pub record: Option<String>,
+    #[serde(default)]
+    pub alert: Option<String>,
+    pub expr: String,
+    #[serde(default)]
+    #[serde(rename = "for")]
+    pub for_duration: Option<String>,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
+}
+
+/// The outcome of importing the alerting rules of a Prometheus rule file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct PromAlertImportResult {
+    /// The imported alerts, as `stream_name/alert_name`
+    pub imported: Vec<String>,
+    /// The rules that could not be mapped or saved
+    pub skipped: Vec<PromRuleSkipped>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct PromRuleSkipped {
+    pub group: String,
+    pub alert: String,
+    pub reason: String,
+}
+
+/// Parses a Prometheus duration like `1h30m` or `90s` into seconds.
+pub fn parse_duration(s: &str) -> Result<i64, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err("empty duration".to_string());
+    }
+    let mut total = 0;
+    let mut num = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() {
+            num.push(c);
+            continue;
+        }
+        let unit = if c == 'm' && chars.peek() == Some(&'s') {
+            chars.next();
+            "ms"
+        } else {
+            match c {
+                's' => "s",
+                'm' => "m",
+                'h' => "h",
+                'd' => "d",
+                'w' => "w",
+                'y' => "y",
+                _ => return Err(format!("invalid duration: {s}")),
+            }
+        };
+        let value: i64 = num.parse().map_err(|_| format!("invalid duration: {s}"))?;
+        num.clear();
+        total += match unit {
+            "ms" => value / 1000,
+            "s" => value,
+            "m" => value * 60,
+            "h" => value * 3600,
+            "d" => value * 86400,
+            "w" => value * 7 * 86400,
+            _ => value * 365 * 86400,
+        };
+    }
+    if !num.is_empty() {
+        return Err(format!("missing unit in duration: {s}"));
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s"), Ok(30));
+        assert_eq!(parse_duration("5m"), Ok(300));
+        assert_eq!(parse_duration("1h30m"), Ok(5400));
+        assert_eq!(parse_duration("1d"), Ok(86400));
+        assert_eq!(parse_duration("1500ms"), Ok(1));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("10").is_err());
+        assert!(parse_duration("1x").is_err());
+    }
+}
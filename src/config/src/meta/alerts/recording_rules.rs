@@ -0,0 +1,62 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A group of Prometheus recording rules, evaluated together at the same interval.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct RecordingRuleGroup {
+    #[serde(default)]
+    pub name: String,
+    /// Evaluation interval in seconds
+    #[serde(default)]
+    pub interval: i64,
+    #[serde(default)]
+    pub rules: Vec<RecordingRule>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub description: String,
+}
+
+impl Default for RecordingRuleGroup {
+    fn default() -> Self {
+        Self {
+            name: "".to_string(),
+            interval: 0,
+            rules: vec![],
+            enabled: true,
+            description: "".to_string(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct RecordingRule {
+    /// Name of the metric the result is written to
+    pub record: String,
+    /// PromQL expression evaluated as an instant query
+    pub expr: String,
+    /// Labels added to, or overwritten in, the result
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+}
@@ -0,0 +1,99 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A window during which the notifications of the matching alerts are suppressed, e.g. for
+/// a maintenance.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct Silence {
+    #[serde(default)]
+    pub id: String,
+    /// Labels the silenced alerts must have: `alert_name`, `stream_type`, `stream_name` or
+    /// any of their context attributes
+    pub matchers: HashMap<String, String>,
+    /// Start of the silence, unix timestamp in microseconds
+    pub starts_at: i64,
+    /// End of the silence, unix timestamp in microseconds
+    pub ends_at: i64,
+    /// Cron expression of a recurring window, the silence is then only active for
+    /// `duration` minutes after each occurrence between `starts_at` and `ends_at`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+    /// Minutes of each recurring window
+    #[serde(default)]
+    pub duration: i64,
+    /// Timezone offset in minutes of the cron expression.
+    /// The negative secs means the Western Hemisphere
+    #[serde(default)]
+    pub tz_offset: i32,
+    #[serde(default)]
+    pub comment: String,
+    #[serde(default)]
+    pub created_by: Option<String>,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+impl Silence {
+    /// Whether the alert with these labels is matched by the silence.
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        !self.matchers.is_empty()
+            && self
+                .matchers
+                .iter()
+                .all(|(name, value)| labels.get(name) == Some(value))
+    }
+
+    /// Whether `now` is within the bounds of the silence, the recurring windows are not
+    /// considered.
+    pub fn is_within(&self, now: i64) -> bool {
+        self.starts_at <= now && now < self.ends_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_silence_matches() {
+        let silence = Silence {
+            matchers: HashMap::from([
+                ("stream_name".to_string(), "k8s_logs".to_string()),
+                ("team".to_string(), "infra".to_string()),
+            ]),
+            starts_at: 100,
+            ends_at: 200,
+            ..Default::default()
+        };
+        let mut labels = HashMap::from([
+            ("alert_name".to_string(), "PodRestarts".to_string()),
+            ("stream_name".to_string(), "k8s_logs".to_string()),
+            ("team".to_string(), "infra".to_string()),
+        ]);
+        assert!(silence.matches(&labels));
+        labels.remove("team");
+        assert!(!silence.matches(&labels));
+        assert!(!Silence::default().matches(&labels));
+
+        assert!(silence.is_within(100));
+        assert!(!silence.is_within(200));
+        assert!(!silence.is_within(99));
+    }
+}
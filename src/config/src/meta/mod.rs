@@ -23,7 +23,9 @@ pub mod inverted_index;
 pub mod logger;
 pub mod meta_store;
 pub mod pipeline;
+pub mod quota;
 pub mod search;
+pub mod search_job;
 pub mod self_reporting;
 pub mod short_url;
 pub mod sql;
@@ -0,0 +1,119 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::meta::stream::StreamType;
+
+/// Ingestion limits, the ones not set are unlimited.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct QuotaLimits {
+    /// Maximum bytes ingested per second
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes_per_sec: Option<u64>,
+    /// Maximum records ingested per second
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub events_per_sec: Option<u64>,
+    /// Maximum gigabytes ingested per day, the day starts at 00:00 UTC
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_gb: Option<f64>,
+}
+
+impl QuotaLimits {
+    pub fn is_empty(&self) -> bool {
+        self.bytes_per_sec.is_none() && self.events_per_sec.is_none() && self.daily_gb.is_none()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct StreamQuota {
+    pub stream_type: StreamType,
+    pub stream_name: String,
+    #[serde(flatten)]
+    pub limits: QuotaLimits,
+}
+
+/// The ingestion quota of an organization, the limits of the organization apply to the
+/// sum of all its streams.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct IngestionQuota {
+    #[serde(default)]
+    pub limits: QuotaLimits,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub streams: Vec<StreamQuota>,
+}
+
+impl IngestionQuota {
+    pub fn stream_limits(
+        &self,
+        stream_type: StreamType,
+        stream_name: &str,
+    ) -> Option<&QuotaLimits> {
+        self.streams
+            .iter()
+            .find(|s| s.stream_type == stream_type && s.stream_name == stream_name)
+            .map(|s| &s.limits)
+    }
+}
+
+/// The limit that rejected an ingestion request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaKind {
+    BytesPerSec,
+    EventsPerSec,
+    DailyGb,
+}
+
+impl std::fmt::Display for QuotaKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            QuotaKind::BytesPerSec => write!(f, "bytes_per_sec"),
+            QuotaKind::EventsPerSec => write!(f, "events_per_sec"),
+            QuotaKind::DailyGb => write!(f, "daily_gb"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::json;
+
+    #[test]
+    fn test_ingestion_quota() {
+        let quota: IngestionQuota = json::from_str(
+            r#"{
+                "limits": {"bytes_per_sec": 1048576},
+                "streams": [
+                    {"stream_type": "logs", "stream_name": "k8s", "events_per_sec": 1000, "daily_gb": 1.5}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(quota.limits.bytes_per_sec, Some(1048576));
+        assert!(quota.limits.events_per_sec.is_none());
+        let limits = quota.stream_limits(StreamType::Logs, "k8s").unwrap();
+        assert_eq!(limits.events_per_sec, Some(1000));
+        assert_eq!(limits.daily_gb, Some(1.5));
+        assert!(quota.stream_limits(StreamType::Metrics, "k8s").is_none());
+        assert!(QuotaLimits::default().is_empty());
+    }
+}
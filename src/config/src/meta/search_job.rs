@@ -0,0 +1,150 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    meta::{search::Request, stream::StreamType},
+    utils::json,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchJobStatus {
+    #[default]
+    Pending,
+    Running,
+    Finished,
+    Failed,
+    Canceled,
+}
+
+impl SearchJobStatus {
+    pub fn is_active(&self) -> bool {
+        matches!(self, Self::Pending | Self::Running)
+    }
+}
+
+/// A search executed in the background by a querier, its result rows are written by parts
+/// into the object storage and paged through once it's finished.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct SearchJob {
+    pub id: String,
+    pub org_id: String,
+    pub user_id: String,
+    pub stream_type: StreamType,
+    #[schema(value_type = SearchRequest)]
+    pub request: Request,
+    pub status: SearchJobStatus,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Querier running the job
+    pub node: String,
+    /// Unix timestamps in microseconds
+    pub created_at: i64,
+    #[serde(default)]
+    pub started_at: i64,
+    #[serde(default)]
+    pub ended_at: i64,
+    /// The job and its results are deleted after this time
+    pub expires_at: i64,
+    /// Number of result rows written so far
+    #[serde(default)]
+    pub total: usize,
+    /// Number of rows of each result part
+    #[serde(default)]
+    pub parts: Vec<usize>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
+    #[serde(default)]
+    pub scan_size: usize,
+    #[serde(default)]
+    pub scan_records: usize,
+}
+
+impl SearchJob {
+    /// Parts holding the result rows `offset..offset + size`, with the index of their first
+    /// row.
+    pub fn parts_in_range(&self, offset: usize, size: usize) -> Vec<(usize, usize)> {
+        let end = offset.saturating_add(size);
+        let mut parts = Vec::new();
+        let mut part_offset = 0;
+        for (i, rows) in self.parts.iter().enumerate() {
+            if part_offset >= end {
+                break;
+            }
+            if part_offset + rows > offset {
+                parts.push((i, part_offset));
+            }
+            part_offset += rows;
+        }
+        parts
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct SearchJobList {
+    pub list: Vec<SearchJob>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct SearchJobResults {
+    pub job_id: String,
+    pub status: SearchJobStatus,
+    pub total: usize,
+    pub offset: usize,
+    pub size: usize,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
+    #[schema(value_type = Vec<Object>)]
+    pub hits: Vec<json::Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parts_in_range() {
+        let job = SearchJob {
+            id: "job".to_string(),
+            org_id: "default".to_string(),
+            user_id: "root@example.com".to_string(),
+            stream_type: StreamType::Logs,
+            request: json::from_str(r#"{"query": {"sql": "select * from k8s"}}"#).unwrap(),
+            status: SearchJobStatus::Finished,
+            error: None,
+            node: "querier-0".to_string(),
+            created_at: 0,
+            started_at: 0,
+            ended_at: 0,
+            expires_at: 0,
+            total: 25,
+            parts: vec![10, 10, 5],
+            columns: vec![],
+            scan_size: 0,
+            scan_records: 0,
+        };
+        assert_eq!(job.parts_in_range(0, 10), vec![(0, 0)]);
+        assert_eq!(job.parts_in_range(5, 10), vec![(0, 0), (1, 10)]);
+        assert_eq!(job.parts_in_range(20, 100), vec![(2, 20)]);
+        assert_eq!(job.parts_in_range(25, 10), vec![]);
+        assert_eq!(job.parts_in_range(0, 0), vec![]);
+    }
+}
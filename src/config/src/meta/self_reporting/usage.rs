@@ -38,6 +38,9 @@ pub enum TriggerDataStatus {
     ConditionNotSatisfied,
     #[serde(rename = "skipped")]
     Skipped,
+    /// The condition was satisfied but the notification was suppressed by a silence
+    #[serde(rename = "silenced")]
+    Silenced,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -50,6 +53,8 @@ pub enum TriggerDataType {
     Alert,
     #[serde(rename = "derived_stream")]
     DerivedStream,
+    #[serde(rename = "recording_rule")]
+    RecordingRule,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -169,14 +174,19 @@ impl From<UsageType> for UsageEvent {
             | UsageType::JsonMetrics
             | UsageType::RUM
             | UsageType::EnrichmentTable
-            | UsageType::Syslog => UsageEvent::Ingestion,
+            | UsageType::Syslog
+            | UsageType::Loki
+            | UsageType::Hec
+            | UsageType::InfluxDB
+            | UsageType::StatsD
+            | UsageType::Kafka => UsageEvent::Ingestion,
             UsageType::Search
             | UsageType::SearchAround
             | UsageType::SearchTopNValues
             | UsageType::MetricSearch
             | UsageType::SearchHistory => UsageEvent::Search,
             UsageType::Functions => UsageEvent::Functions,
-            UsageType::Retention => UsageEvent::Other,
+            UsageType::Retention | UsageType::QuotaExceeded => UsageEvent::Other,
         }
     }
 }
@@ -205,6 +215,16 @@ pub enum UsageType {
     JsonMetrics,
     #[serde(rename = "/v1/rum")]
     RUM,
+    #[serde(rename = "/loki/api/v1/push")]
+    Loki,
+    #[serde(rename = "/services/collector")]
+    Hec,
+    #[serde(rename = "/influxdb/write")]
+    InfluxDB,
+    #[serde(rename = "statsd")]
+    StatsD,
+    #[serde(rename = "kafka")]
+    Kafka,
     #[serde(rename = "/_search")]
     Search,
     #[serde(rename = "/metrics/_search")]
@@ -223,6 +243,8 @@ pub enum UsageType {
     Syslog,
     #[serde(rename = "enrichment_table")]
     EnrichmentTable,
+    #[serde(rename = "quota_exceeded")]
+    QuotaExceeded,
 }
 
 impl std::fmt::Display for UsageType {
@@ -239,6 +261,11 @@ impl std::fmt::Display for UsageType {
             UsageType::PrometheusRemoteWrite => write!(f, "/prometheus/v1/write"),
             UsageType::JsonMetrics => write!(f, "/metrics/_json"),
             UsageType::RUM => write!(f, "/v1/rum"),
+            UsageType::Loki => write!(f, "/loki/api/v1/push"),
+            UsageType::Hec => write!(f, "/services/collector"),
+            UsageType::InfluxDB => write!(f, "/influxdb/write"),
+            UsageType::StatsD => write!(f, "statsd"),
+            UsageType::Kafka => write!(f, "kafka"),
             UsageType::Search => write!(f, "/_search"),
             UsageType::MetricSearch => write!(f, "/metrics/_search"),
             UsageType::SearchAround => write!(f, "/_around"),
@@ -248,6 +275,7 @@ impl std::fmt::Display for UsageType {
             UsageType::Retention => write!(f, "data_retention"),
             UsageType::Syslog => write!(f, "syslog"),
             UsageType::EnrichmentTable => write!(f, "enrichment_table"),
+            UsageType::QuotaExceeded => write!(f, "quota_exceeded"),
         }
     }
 }
@@ -535,6 +535,8 @@ pub struct UpdateStreamSettings {
     pub store_original_data: Option<bool>,
     #[serde(default)]
     pub approx_partition: Option<bool>,
+    #[serde(default)]
+    pub span_metrics_enabled: Option<bool>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, ToSchema)]
@@ -564,6 +566,9 @@ pub struct StreamSettings {
     pub store_original_data: bool,
     #[serde(default)]
     pub approx_partition: bool,
+    /// generate RED metrics from the spans ingested into this traces stream
+    #[serde(default)]
+    pub span_metrics_enabled: bool,
 }
 
 impl Serialize for StreamSettings {
@@ -588,6 +593,7 @@ impl Serialize for StreamSettings {
         state.serialize_field("max_query_range", &self.max_query_range)?;
         state.serialize_field("store_original_data", &self.store_original_data)?;
         state.serialize_field("approx_partition", &self.approx_partition)?;
+        state.serialize_field("span_metrics_enabled", &self.span_metrics_enabled)?;
 
         match self.defined_schema_fields.as_ref() {
             Some(fields) => {
@@ -703,6 +709,11 @@ impl From<&str> for StreamSettings {
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
 
+        let span_metrics_enabled = settings
+            .get("span_metrics_enabled")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
         Self {
             partition_time_level,
             partition_keys,
@@ -715,6 +726,7 @@ impl From<&str> for StreamSettings {
             defined_schema_fields,
             store_original_data,
             approx_partition,
+            span_metrics_enabled,
         }
     }
 }
@@ -14,7 +14,8 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 pub use serde_json::{
-    from_slice, from_str, from_value, json, to_string, to_value, to_vec, Error, Map, Number, Value,
+    from_slice, from_str, from_value, json, to_string, to_value, to_vec, Deserializer, Error, Map,
+    Number, Value,
 };
 
 pub fn get_float_value(val: &Value) -> f64 {
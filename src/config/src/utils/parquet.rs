@@ -26,7 +26,7 @@ use futures::TryStreamExt;
 use parquet::{
     arrow::{
         arrow_reader::ArrowReaderMetadata, async_reader::ParquetRecordBatchStream,
-        AsyncArrowWriter, ParquetRecordBatchStreamBuilder,
+        async_writer::AsyncFileWriter, AsyncArrowWriter, ParquetRecordBatchStreamBuilder,
     },
     basic::{Compression, Encoding},
     file::{metadata::KeyValue, properties::WriterProperties},
@@ -34,12 +34,12 @@ use parquet::{
 
 use crate::{config::*, ider, meta::stream::FileMeta};
 
-pub fn new_parquet_writer<'a>(
-    buf: &'a mut Vec<u8>,
-    schema: &'a Arc<Schema>,
-    bloom_filter_fields: &'a [String],
-    metadata: &'a FileMeta,
-) -> AsyncArrowWriter<&'a mut Vec<u8>> {
+pub fn new_parquet_writer<W: AsyncFileWriter>(
+    buf: W,
+    schema: &Arc<Schema>,
+    bloom_filter_fields: &[String],
+    metadata: &FileMeta,
+) -> AsyncArrowWriter<W> {
     let cfg = get_config();
     let mut writer_props = WriterProperties::builder()
         .set_write_batch_size(PARQUET_BATCH_SIZE) // in bytes
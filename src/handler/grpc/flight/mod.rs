@@ -51,6 +51,8 @@ use crate::{
     },
 };
 
+pub mod sql;
+
 #[derive(Default)]
 pub struct FlightServiceImpl;
 
@@ -0,0 +1,622 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Arrow Flight SQL server of the BI tools (JDBC, ADBC). The clients authenticate with the basic
+//! credentials of the HTTP API, either on each call or once with a handshake which returns a
+//! bearer token. The sessions are kept in the meta store, so the calls of a client can be balanced
+//! across the queriers. The statements are stateless: the handle of a prepared statement is its
+//! query.
+
+use std::{pin::Pin, sync::Arc};
+
+use arrow::{
+    array::{RecordBatch, StringArray},
+    error::ArrowError,
+    ipc::writer::IpcWriteOptions,
+};
+use arrow_flight::{
+    encode::FlightDataEncoderBuilder,
+    error::FlightError,
+    flight_service_server::FlightService,
+    sql::{
+        metadata::{SqlInfoData, SqlInfoDataBuilder},
+        server::FlightSqlService,
+        ActionClosePreparedStatementRequest, ActionCreatePreparedStatementRequest,
+        ActionCreatePreparedStatementResult, CommandGetCatalogs, CommandGetDbSchemas,
+        CommandGetSqlInfo, CommandGetTableTypes, CommandGetTables, CommandPreparedStatementQuery,
+        CommandStatementQuery, ProstMessageExt, SqlInfo, TicketStatementQuery,
+    },
+    Action, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo, HandshakeRequest,
+    HandshakeResponse, IpcMessage, SchemaAsIpc, Ticket,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use chrono::{Duration, Utc};
+use config::{get_config, ider, utils::rand::generate_random_string, VERSION};
+use dashmap::DashMap;
+use futures::{stream, stream::BoxStream, Stream, TryStreamExt};
+use http_auth_basic::Credentials;
+use once_cell::sync::Lazy;
+use prost::Message;
+use tonic::{
+    metadata::{MetadataMap, MetadataValue},
+    Request, Response, Status, Streaming,
+};
+
+use crate::{
+    handler::http::auth::validator::validate_credentials,
+    service::{
+        db::{self, flight_sql_session::Session},
+        search::flight_sql::{self as SqlService, Statement},
+    },
+};
+
+type DoGetStream = BoxStream<'static, Result<FlightData, Status>>;
+
+/// Sessions used on this querier, by bearer token
+static SESSIONS: Lazy<DashMap<String, Session>> = Lazy::new(DashMap::new);
+
+/// Tokens not found in the meta store recently, with the time in microseconds until which they
+/// aren't looked up again
+static UNKNOWN_TOKENS: Lazy<DashMap<String, i64>> = Lazy::new(DashMap::new);
+
+const UNKNOWN_TOKEN_TTL: i64 = 10_000_000; // microseconds
+const MAX_UNKNOWN_TOKENS: usize = 10_000;
+
+static SQL_INFO: Lazy<SqlInfoData> = Lazy::new(|| {
+    let mut builder = SqlInfoDataBuilder::new();
+    builder.append(SqlInfo::FlightSqlServerName, "OpenObserve");
+    builder.append(SqlInfo::FlightSqlServerVersion, VERSION);
+    builder.append(SqlInfo::FlightSqlServerArrowVersion, "1.3");
+    builder.append(SqlInfo::FlightSqlServerReadOnly, true);
+    builder.build().unwrap()
+});
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct User {
+    org_id: String,
+    user_id: String,
+}
+
+#[derive(Default)]
+pub struct FlightSqlServiceImpl;
+
+#[tonic::async_trait]
+impl FlightSqlService for FlightSqlServiceImpl {
+    type FlightService = FlightSqlServiceImpl;
+
+    async fn do_handshake(
+        &self,
+        request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<
+        Response<Pin<Box<dyn Stream<Item = Result<HandshakeResponse, Status>> + Send>>>,
+        Status,
+    > {
+        let user = authenticate(request.metadata()).await?;
+        let token = open_session(user).await?;
+        let bearer = MetadataValue::try_from(format!("Bearer {token}"))
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let output = stream::iter(vec![Ok(HandshakeResponse {
+            protocol_version: 0,
+            payload: token.into(),
+        })]);
+        let mut resp: Response<
+            Pin<Box<dyn Stream<Item = Result<HandshakeResponse, Status>> + Send>>,
+        > = Response::new(Box::pin(output));
+        resp.metadata_mut().insert("authorization", bearer);
+        Ok(resp)
+    }
+
+    async fn get_flight_info_statement(
+        &self,
+        query: CommandStatementQuery,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let user = authenticate(request.metadata()).await?;
+        let (_, schema) = plan(&user, &query.query).await?;
+        let ticket = TicketStatementQuery {
+            statement_handle: query.query.into(),
+        };
+        flight_info(
+            &schema,
+            ticket.as_any().encode_to_vec(),
+            request.into_inner(),
+        )
+    }
+
+    async fn do_get_statement(
+        &self,
+        ticket: TicketStatementQuery,
+        request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let user = authenticate(request.metadata()).await?;
+        execute(&user, &ticket.statement_handle).await
+    }
+
+    async fn get_flight_info_prepared_statement(
+        &self,
+        cmd: CommandPreparedStatementQuery,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let user = authenticate(request.metadata()).await?;
+        let (_, schema) = plan(&user, &get_query(&cmd.prepared_statement_handle)?).await?;
+        flight_info(&schema, cmd.as_any().encode_to_vec(), request.into_inner())
+    }
+
+    async fn do_get_prepared_statement(
+        &self,
+        query: CommandPreparedStatementQuery,
+        request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let user = authenticate(request.metadata()).await?;
+        execute(&user, &query.prepared_statement_handle).await
+    }
+
+    async fn do_action_create_prepared_statement(
+        &self,
+        query: ActionCreatePreparedStatementRequest,
+        request: Request<Action>,
+    ) -> Result<ActionCreatePreparedStatementResult, Status> {
+        let user = authenticate(request.metadata()).await?;
+        let (_, schema) = plan(&user, &query.query).await?;
+        let IpcMessage(dataset_schema) = SchemaAsIpc::new(&schema, &IpcWriteOptions::default())
+            .try_into()
+            .map_err(|e: ArrowError| Status::internal(e.to_string()))?;
+        Ok(ActionCreatePreparedStatementResult {
+            prepared_statement_handle: query.query.into(),
+            dataset_schema,
+            parameter_schema: Default::default(),
+        })
+    }
+
+    async fn do_action_close_prepared_statement(
+        &self,
+        _query: ActionClosePreparedStatementRequest,
+        request: Request<Action>,
+    ) -> Result<(), Status> {
+        // nothing is kept for the prepared statements
+        authenticate(request.metadata()).await?;
+        Ok(())
+    }
+
+    async fn get_flight_info_catalogs(
+        &self,
+        query: CommandGetCatalogs,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        authenticate(request.metadata()).await?;
+        let schema = query.clone().into_builder().schema();
+        flight_info(
+            &schema,
+            query.as_any().encode_to_vec(),
+            request.into_inner(),
+        )
+    }
+
+    async fn do_get_catalogs(
+        &self,
+        query: CommandGetCatalogs,
+        request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let user = authenticate(request.metadata()).await?;
+        let mut builder = query.into_builder();
+        builder.append(&user.org_id);
+        encode(builder.schema(), builder.build())
+    }
+
+    async fn get_flight_info_schemas(
+        &self,
+        query: CommandGetDbSchemas,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        authenticate(request.metadata()).await?;
+        let schema = query.clone().into_builder().schema();
+        flight_info(
+            &schema,
+            query.as_any().encode_to_vec(),
+            request.into_inner(),
+        )
+    }
+
+    async fn do_get_schemas(
+        &self,
+        query: CommandGetDbSchemas,
+        request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let user = authenticate(request.metadata()).await?;
+        let mut builder = query.into_builder();
+        for stream_type in SqlService::SCHEMAS {
+            builder.append(&user.org_id, stream_type.to_string());
+        }
+        encode(builder.schema(), builder.build())
+    }
+
+    async fn get_flight_info_tables(
+        &self,
+        query: CommandGetTables,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        authenticate(request.metadata()).await?;
+        let schema = query.clone().into_builder().schema();
+        flight_info(
+            &schema,
+            query.as_any().encode_to_vec(),
+            request.into_inner(),
+        )
+    }
+
+    async fn do_get_tables(
+        &self,
+        query: CommandGetTables,
+        request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let user = authenticate(request.metadata()).await?;
+        let tables = SqlService::list_tables(&user.org_id)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let mut builder = query.into_builder();
+        for (stream_type, stream_name, schema) in tables {
+            builder
+                .append(
+                    &user.org_id,
+                    stream_type.to_string(),
+                    stream_name,
+                    "TABLE",
+                    &schema,
+                )
+                .map_err(|e| Status::internal(e.to_string()))?;
+        }
+        encode(builder.schema(), builder.build())
+    }
+
+    async fn get_flight_info_table_types(
+        &self,
+        query: CommandGetTableTypes,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        authenticate(request.metadata()).await?;
+        flight_info(
+            &table_types_schema(),
+            query.as_any().encode_to_vec(),
+            request.into_inner(),
+        )
+    }
+
+    async fn do_get_table_types(
+        &self,
+        _query: CommandGetTableTypes,
+        request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        authenticate(request.metadata()).await?;
+        let schema = table_types_schema();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(StringArray::from(vec!["TABLE"]))],
+        );
+        encode(schema, batch)
+    }
+
+    async fn get_flight_info_sql_info(
+        &self,
+        query: CommandGetSqlInfo,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        authenticate(request.metadata()).await?;
+        let schema = query.clone().into_builder(&SQL_INFO).schema();
+        flight_info(
+            &schema,
+            query.as_any().encode_to_vec(),
+            request.into_inner(),
+        )
+    }
+
+    async fn do_get_sql_info(
+        &self,
+        query: CommandGetSqlInfo,
+        request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        authenticate(request.metadata()).await?;
+        let builder = query.into_builder(&SQL_INFO);
+        encode(builder.schema(), builder.build())
+    }
+
+    async fn register_sql_info(&self, _id: i32, _result: &SqlInfo) {}
+}
+
+/// Authenticates the request with the bearer token of a session or with basic credentials, the
+/// organization of the basic credentials is given by the `ZO_GRPC_ORG_HEADER_KEY` header.
+async fn authenticate(metadata: &MetadataMap) -> Result<User, Status> {
+    let Some(auth) = metadata.get("authorization").and_then(|v| v.to_str().ok()) else {
+        return Err(Status::unauthenticated("No valid auth token"));
+    };
+    if let Some(token) = auth.strip_prefix("Bearer ") {
+        return get_session(token.trim(), Utc::now().timestamp_micros())
+            .await?
+            .ok_or_else(|| {
+                Status::unauthenticated("The session expired, please authenticate again")
+            });
+    }
+
+    let cfg = get_config();
+    let Some(org_id) = metadata
+        .get(&cfg.grpc.org_header_key)
+        .and_then(|v| v.to_str().ok())
+    else {
+        return Err(Status::invalid_argument(format!(
+            "Please specify organization id with header key '{}' ",
+            &cfg.grpc.org_header_key
+        )));
+    };
+    let credentials = match Credentials::from_header(auth.to_string()) {
+        Ok(c) => c,
+        Err(err) => {
+            log::info!("Err authenticating {}", err);
+            return Err(Status::unauthenticated("No valid auth token"));
+        }
+    };
+    match validate_credentials(
+        &credentials.user_id,
+        &credentials.password,
+        &format!("{org_id}/_search"),
+    )
+    .await
+    {
+        Ok(res) if res.is_valid => Ok(User {
+            org_id: org_id.to_string(),
+            user_id: res.user_email,
+        }),
+        _ => Err(Status::unauthenticated("No valid auth token")),
+    }
+}
+
+/// Opens a session of `ZO_FLIGHT_SQL_SESSION_TTL` hours and returns its token.
+async fn open_session(user: User) -> Result<String, Status> {
+    let ttl = Duration::try_hours(get_config().grpc.flight_sql_session_ttl).unwrap();
+    let token = generate_random_string(32);
+    let session = Session {
+        org_id: user.org_id,
+        user_id: user.user_id,
+        expires_at: (Utc::now() + ttl).timestamp_micros(),
+    };
+    db::flight_sql_session::set(&token, &session)
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?;
+    SESSIONS.insert(token.clone(), session);
+    Ok(token)
+}
+
+/// Returns the user of the session, which may have been opened on another querier.
+async fn get_session(token: &str, now: i64) -> Result<Option<User>, Status> {
+    let session = match SESSIONS.get(token) {
+        Some(session) => Some(session.clone()),
+        None => {
+            // the invalid tokens, e.g. guessed ones, don't hit the meta store every time
+            if UNKNOWN_TOKENS.get(token).is_some_and(|until| *until > now) {
+                return Ok(None);
+            }
+            let session = db::flight_sql_session::get(token)
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
+            match session.as_ref() {
+                Some(session) => {
+                    SESSIONS.insert(token.to_string(), session.clone());
+                }
+                None => add_unknown_token(token, now),
+            }
+            session
+        }
+    };
+    Ok(session
+        .filter(|session| session.expires_at > now)
+        .map(|session| User {
+            org_id: session.org_id,
+            user_id: session.user_id,
+        }))
+}
+
+fn add_unknown_token(token: &str, now: i64) {
+    if UNKNOWN_TOKENS.len() >= MAX_UNKNOWN_TOKENS {
+        UNKNOWN_TOKENS.retain(|_, until| *until > now);
+        if UNKNOWN_TOKENS.len() >= MAX_UNKNOWN_TOKENS {
+            return;
+        }
+    }
+    UNKNOWN_TOKENS.insert(token.to_string(), now + UNKNOWN_TOKEN_TTL);
+}
+
+/// Deletes the expired sessions.
+pub async fn cleanup_sessions() -> Result<(), anyhow::Error> {
+    let now = Utc::now().timestamp_micros();
+    SESSIONS.retain(|_, session| session.expires_at > now);
+    UNKNOWN_TOKENS.retain(|_, until| *until > now);
+    for (token, session) in db::flight_sql_session::list().await? {
+        if session.expires_at <= now {
+            db::flight_sql_session::delete(&token).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses the query, checks the permissions of the user on its streams and returns the schema of
+/// its rows.
+async fn plan(user: &User, sql: &str) -> Result<(Statement, SchemaRef), Status> {
+    let statement =
+        Statement::parse(&user.org_id, sql).map_err(|e| Status::invalid_argument(e.to_string()))?;
+    check_permissions(user, &statement).await?;
+    let schema = statement
+        .schema(&user.org_id)
+        .await
+        .map_err(|e| Status::invalid_argument(e.to_string()))?;
+    Ok((statement, schema))
+}
+
+/// Searches the query of the statement handle, a query without rows returns an empty batch so
+/// the clients get the schema.
+async fn execute(user: &User, handle: &[u8]) -> Result<Response<DoGetStream>, Status> {
+    let (statement, schema) = plan(user, &get_query(handle)?).await?;
+    let trace_id = ider::uuid();
+    let rx = SqlService::search(&trace_id, &user.org_id, &user.user_id, &statement)
+        .await
+        .map_err(|e| {
+            log::error!("[trace_id {trace_id}] flight sql search error: {}", e);
+            Status::internal(e.to_string())
+        })?;
+
+    let batches = stream::unfold(Some((rx, false)), move |state| {
+        let schema = schema.clone();
+        async move {
+            let (mut rx, sent) = state?;
+            match rx.recv().await {
+                Some(Ok(batch)) => Some((Ok(batch), Some((rx, true)))),
+                Some(Err(e)) => Some((Err(FlightError::ExternalError(e.to_string().into())), None)),
+                None if !sent => Some((Ok(RecordBatch::new_empty(schema)), None)),
+                None => None,
+            }
+        }
+    });
+    let stream = FlightDataEncoderBuilder::new()
+        .build(batches)
+        .map_err(Status::from);
+    Ok(Response::new(Box::pin(stream)))
+}
+
+fn get_query(handle: &[u8]) -> Result<String, Status> {
+    String::from_utf8(handle.to_vec())
+        .map_err(|_| Status::invalid_argument("Invalid statement handle"))
+}
+
+fn flight_info(
+    schema: &Schema,
+    ticket: Vec<u8>,
+    descriptor: FlightDescriptor,
+) -> Result<Response<FlightInfo>, Status> {
+    let info = FlightInfo::new()
+        .try_with_schema(schema)
+        .map_err(|e| Status::internal(e.to_string()))?
+        .with_endpoint(FlightEndpoint::new().with_ticket(Ticket::new(ticket)))
+        .with_descriptor(descriptor);
+    Ok(Response::new(info))
+}
+
+fn encode(
+    schema: SchemaRef,
+    batch: Result<RecordBatch, ArrowError>,
+) -> Result<Response<DoGetStream>, Status> {
+    let batch = batch.map_err(|e| Status::internal(e.to_string()))?;
+    let stream = FlightDataEncoderBuilder::new()
+        .with_schema(schema)
+        .build(stream::iter(vec![Ok(batch)]))
+        .map_err(Status::from);
+    Ok(Response::new(Box::pin(stream)))
+}
+
+fn table_types_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![Field::new(
+        "table_type",
+        DataType::Utf8,
+        false,
+    )]))
+}
+
+#[cfg(feature = "enterprise")]
+async fn check_permissions(user: &User, statement: &Statement) -> Result<(), Status> {
+    use o2_enterprise::enterprise::openfga::meta::mapping::OFGA_MODELS;
+
+    use crate::common::{
+        infra::config::USERS,
+        meta,
+        utils::auth::{is_root_user, AuthExtractor},
+    };
+
+    if is_root_user(&user.user_id) {
+        return Ok(());
+    }
+    let Some(db_user): Option<meta::user::User> = USERS
+        .get(&format!("{}/{}", user.org_id, user.user_id))
+        .map(|v| v.clone())
+    else {
+        return Err(Status::permission_denied("Unauthorized Access"));
+    };
+    if !db_user.is_external {
+        return Ok(());
+    }
+    let stream_type_str = statement.stream_type.to_string();
+    for stream_name in statement.stream_names.iter() {
+        if !crate::handler::http::auth::validator::check_permissions(
+            &user.user_id,
+            AuthExtractor {
+                auth: "".to_string(),
+                method: "GET".to_string(),
+                o2_type: format!(
+                    "{}:{}",
+                    OFGA_MODELS
+                        .get(stream_type_str.as_str())
+                        .map_or(stream_type_str.as_str(), |model| model.key),
+                    stream_name
+                ),
+                org_id: user.org_id.clone(),
+                bypass_check: false,
+                parent_id: "".to_string(),
+            },
+            Some(db_user.role.clone()),
+        )
+        .await
+        {
+            return Err(Status::permission_denied("Unauthorized Access"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "enterprise"))]
+async fn check_permissions(_user: &User, _statement: &Statement) -> Result<(), Status> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use infra::db as infra_db;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sessions() {
+        infra_db::create_table().await.unwrap();
+        let user = User {
+            org_id: "default".to_string(),
+            user_id: "root@example.com".to_string(),
+        };
+        let token = open_session(user.clone()).await.unwrap();
+        assert_eq!(token.len(), 32);
+
+        let now = Utc::now().timestamp_micros();
+        assert_eq!(get_session(&token, now).await.unwrap(), Some(user.clone()));
+        assert_eq!(get_session("unknown", now).await.unwrap(), None);
+        let expired = now + Duration::try_hours(25).unwrap().num_microseconds().unwrap();
+        assert_eq!(get_session(&token, expired).await.unwrap(), None);
+
+        // the session opened on another querier is loaded from the meta store
+        SESSIONS.remove(&token);
+        assert_eq!(get_session(&token, now).await.unwrap(), Some(user));
+        db::flight_sql_session::delete(&token).await.unwrap();
+
+        // the unknown tokens aren't looked up again for a while
+        assert!(UNKNOWN_TOKENS
+            .get("unknown")
+            .is_some_and(|until| *until == now + UNKNOWN_TOKEN_TTL));
+        SESSIONS.remove(&token);
+        assert_eq!(get_session(&token, now).await.unwrap(), None);
+        assert!(UNKNOWN_TOKENS.contains_key(&token));
+    }
+}
@@ -19,6 +19,8 @@ use opentelemetry_proto::tonic::collector::logs::v1::{
 };
 use tonic::{Response, Status};
 
+use crate::service::ingestion::quota::QuotaExceeded;
+
 #[derive(Default)]
 pub struct LogsServer;
 
@@ -81,7 +83,10 @@ impl LogsService for LogsServer {
                     partial_success: None,
                 }))
             }
-            Err(e) => Err(Status::internal(e.to_string())),
+            Err(e) => match e.downcast_ref::<QuotaExceeded>() {
+                Some(e) => Err(e.grpc_status()),
+                None => Err(Status::internal(e.to_string())),
+            },
         }
     }
 }
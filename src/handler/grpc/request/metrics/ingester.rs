@@ -20,6 +20,8 @@ use opentelemetry_proto::tonic::collector::metrics::v1::{
 };
 use tonic::{Response, Status};
 
+use crate::service::ingestion::quota::QuotaExceeded;
+
 #[derive(Default)]
 pub struct MetricsIngester;
 
@@ -66,7 +68,11 @@ impl MetricsService for MetricsIngester {
                 partial_success: None,
             }));
         } else {
-            Err(Status::internal(resp.err().unwrap().to_string()))
+            let err = resp.err().unwrap();
+            match err.downcast_ref::<QuotaExceeded>() {
+                Some(e) => Err(e.grpc_status()),
+                None => Err(Status::internal(err.to_string())),
+            }
         }
     }
 }
@@ -15,11 +15,12 @@
 
 #[cfg(feature = "enterprise")]
 use config::metrics;
+use config::utils::json;
 #[cfg(feature = "enterprise")]
 use o2_enterprise::enterprise::search::{QueryManager, TaskStatus, WorkGroup};
 use proto::cluster_rpc::{
     search_server::Search, CancelQueryRequest, CancelQueryResponse, QueryStatusRequest,
-    QueryStatusResponse,
+    QueryStatusResponse, TailRequest, TailResponse,
 };
 use tonic::{Request, Response, Status};
 
@@ -189,4 +190,13 @@ impl Search for Searcher {
     ) -> Result<Response<CancelQueryResponse>, Status> {
         Err(Status::unimplemented("Not Supported"))
     }
+
+    async fn tail(&self, req: Request<TailRequest>) -> Result<Response<TailResponse>, Status> {
+        let req = req.into_inner();
+        let hits = crate::service::search::tail::read_memtable(&req)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let hits = json::to_vec(&hits).map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(TailResponse { hits }))
+    }
 }
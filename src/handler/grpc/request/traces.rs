@@ -19,7 +19,10 @@ use opentelemetry_proto::tonic::collector::trace::v1::{
 };
 use tonic::{Response, Status};
 
-use crate::service::traces::{handle_trace_request, RequestType};
+use crate::service::{
+    ingestion::quota::QuotaExceeded,
+    traces::{handle_trace_request, RequestType},
+};
 
 #[derive(Default)]
 pub struct TraceServer;
@@ -74,9 +77,15 @@ impl TraceService for TraceServer {
                 partial_success: None,
             }));
         } else {
-            let err = resp.err().unwrap().to_string();
+            let err = resp.err().unwrap();
+            if let Some(e) = err
+                .get_ref()
+                .and_then(|e| e.downcast_ref::<QuotaExceeded>())
+            {
+                return Err(e.grpc_status());
+            }
             log::error!("handle_trace_request err {}", err);
-            Err(Status::internal(err))
+            Err(Status::internal(err.to_string()))
         }
     }
 }
@@ -27,7 +27,7 @@ use o2_enterprise::enterprise::common::infra::config::get_config as get_o2_confi
 use crate::{
     common::{
         meta::{
-            ingestion::INGESTION_EP,
+            ingestion::{is_ingestion_ep_path, INGESTION_EP},
             user::{
                 AuthTokensExt, DBUser, TokenValidationResponse, TokenValidationResponseBuilder,
                 UserRole,
@@ -175,7 +175,9 @@ pub async fn validate_credentials(
     }
     let user = user.unwrap();
 
-    if (path_columns.len() == 1 || INGESTION_EP.iter().any(|s| path_columns.contains(s)))
+    if (path_columns.len() == 1
+        || INGESTION_EP.iter().any(|s| path_columns.contains(s))
+        || is_ingestion_ep_path(path))
         && user.token.eq(&user_password)
     {
         return Ok(TokenValidationResponse {
@@ -540,13 +542,36 @@ pub async fn validator_rum(
     }
 }
 
+/// Returns the base64 encoded credentials of the `Basic` scheme, Splunk HEC and InfluxDB v2
+/// clients send them as `Splunk <token>` and `Token <token>`. The `Splunk` and `Token` schemes
+/// are only accepted on the HEC and InfluxDB routes.
+fn basic_credentials<'a>(auth: &'a str, path: &str) -> Option<&'a str> {
+    auth.strip_prefix("Basic")
+        .or_else(|| auth.strip_prefix("Splunk").filter(|_| is_hec_path(path)))
+        .or_else(|| {
+            auth.strip_prefix("Token")
+                .filter(|_| is_influxdb_path(path))
+        })
+}
+
+fn is_hec_path(path: &str) -> bool {
+    let path = path.trim_end_matches('/');
+    path.ends_with("/services/collector/event") || path.ends_with("/services/collector/raw")
+}
+
+/// Whether the path is one of `/api/{org_id}/influxdb/...`
+fn is_influxdb_path(path: &str) -> bool {
+    path.strip_prefix(&format!("{}/api/", get_config().common.base_uri))
+        .is_some_and(|path| path.split('/').nth(1) == Some("influxdb"))
+}
+
 async fn oo_validator_internal(
     req: ServiceRequest,
     auth_info: AuthExtractor,
     path_prefix: &str,
 ) -> Result<ServiceRequest, (Error, ServiceRequest)> {
-    if auth_info.auth.starts_with("Basic") {
-        let decoded = match base64::decode(auth_info.auth.strip_prefix("Basic").unwrap().trim()) {
+    if let Some(credentials) = basic_credentials(&auth_info.auth, req.path()) {
+        let decoded = match base64::decode(credentials.trim()) {
             Ok(val) => val,
             Err(_) => return Err((ErrorUnauthorized("Unauthorized Access"), req)),
         };
@@ -587,8 +612,8 @@ async fn oo_validator_internal(
 
 #[cfg(feature = "enterprise")]
 pub async fn get_user_email_from_auth_str(auth_str: &str) -> Option<String> {
-    if auth_str.starts_with("Basic") {
-        let decoded = match base64::decode(auth_str.strip_prefix("Basic").unwrap().trim()) {
+    if let Some(credentials) = auth_str.strip_prefix("Basic") {
+        let decoded = match base64::decode(credentials.trim()) {
             Ok(val) => val,
             Err(_) => return None,
         };
@@ -917,6 +942,28 @@ mod tests {
         assert!(expected1 == expected2);
     }
 
+    #[test]
+    fn test_basic_credentials() {
+        let hec_path = "/api/default/services/collector/event";
+        assert_eq!(
+            basic_credentials("Basic abc", "/api/default/_search"),
+            Some(" abc")
+        );
+        assert_eq!(basic_credentials("Splunk abc", hec_path), Some(" abc"));
+        assert_eq!(
+            basic_credentials("Splunk abc", "/api/default/_search"),
+            None
+        );
+        assert_eq!(basic_credentials("Bearer abc", hec_path), None);
+        let influxdb_path = "/api/default/influxdb/api/v2/write";
+        assert_eq!(basic_credentials("Token abc", influxdb_path), Some(" abc"));
+        assert_eq!(basic_credentials("Token abc", hec_path), None);
+        assert_eq!(
+            basic_credentials("Token abc", "/api/default/_search/influxdb"),
+            None
+        );
+    }
+
     #[tokio::test]
     async fn test_validate() {
         let org_id = "default";
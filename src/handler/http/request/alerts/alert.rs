@@ -17,7 +17,11 @@ use std::{collections::HashMap, io::Error};
 
 use actix_web::{delete, get, http, post, put, web, HttpRequest, HttpResponse};
 use config::meta::{
-    alerts::alert::{Alert, AlertListFilter},
+    alerts::{
+        alert::{Alert, AlertListFilter},
+        history::AlertHistoryEntry,
+        prom_rules::PromAlertImportResult,
+    },
     dashboards::datetime_now,
 };
 
@@ -26,7 +30,7 @@ use crate::{
         meta::http::HttpResponse as MetaHttpResponse,
         utils::{auth::UserEmail, http::get_stream_type_from_request},
     },
-    service::alerts::alert,
+    service::alerts::{alert, history, prom_rules},
 };
 
 /// CreateAlert
@@ -426,3 +430,106 @@ async fn trigger_alert(
         },
     }
 }
+
+/// GetAlertHistory
+///
+/// Lists the changes of state (pending, firing, resolved) and the notifications of the
+/// groups of the alert, the most recent first.
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "GetAlertHistory",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("stream_name" = String, Path, description = "Stream name"),
+        ("alert_name" = String, Path, description = "Alert name"),
+        ("start_time" = Option<i64>, Query, description = "Start time in microseconds"),
+        ("end_time" = Option<i64>, Query, description = "End time in microseconds"),
+        ("size" = Option<u64>, Query, description = "Maximum number of entries, 100 by default"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = Vec<AlertHistoryEntry>),
+        (status = 400, description = "Error",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/{stream_name}/alerts/{alert_name}/history")]
+async fn get_alert_history(
+    path: web::Path<(String, String, String)>,
+    req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let (org_id, stream_name, name) = path.into_inner();
+    let query = web::Query::<HashMap<String, String>>::from_query(req.query_string()).unwrap();
+    let stream_type = match get_stream_type_from_request(&query) {
+        Ok(v) => v.unwrap_or_default(),
+        Err(e) => {
+            return Ok(MetaHttpResponse::bad_request(e));
+        }
+    };
+    let start_time = query.get("start_time").and_then(|v| v.parse::<i64>().ok());
+    let end_time = query.get("end_time").and_then(|v| v.parse::<i64>().ok());
+    let size = query
+        .get("size")
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(100);
+    match history::list(
+        &org_id,
+        stream_type,
+        &stream_name,
+        &name,
+        (start_time, end_time),
+        Some(size),
+    )
+    .await
+    {
+        Ok(data) => Ok(MetaHttpResponse::json(data)),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
+
+/// ImportPrometheusAlertRules
+///
+/// Imports the alerting rules of a Prometheus rule file (YAML) as PromQL alerts on the
+/// streams of the metrics they query. The rules which cannot be mapped are reported.
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "ImportPrometheusAlertRules",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("destinations" = String, Query, description = "Comma separated alert destinations"),
+    ),
+    request_body(content = String, description = "Prometheus rule file", content_type = "application/yaml"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = PromAlertImportResult),
+        (status = 400, description = "Error",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/alerts/_import")]
+async fn import_prometheus_alerts(
+    path: web::Path<String>,
+    body: web::Bytes,
+    user_email: UserEmail,
+    req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let org_id = path.into_inner();
+    let query = web::Query::<HashMap<String, String>>::from_query(req.query_string()).unwrap();
+    let destinations = query
+        .get("destinations")
+        .map(|v| {
+            v.split(',')
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    match prom_rules::import(&org_id, &body, destinations, Some(&user_email.user_id)).await {
+        Ok(result) => Ok(MetaHttpResponse::json(result)),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::Error;
+
+use actix_web::{delete, get, http, post, put, web, HttpResponse};
+use config::meta::alerts::inhibitions::InhibitionRule;
+
+use crate::{common::meta::http::HttpResponse as MetaHttpResponse, service::alerts::inhibitions};
+
+/// CreateAlertInhibitionRule
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "CreateAlertInhibitionRule",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+      ),
+    request_body(content = InhibitionRule, description = "Inhibition rule data", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = HttpResponse),
+        (status = 400, description = "Error",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/alerts/inhibitions")]
+pub async fn save_inhibition_rule(
+    path: web::Path<String>,
+    rule: web::Json<InhibitionRule>,
+) -> Result<HttpResponse, Error> {
+    let org_id = path.into_inner();
+    let rule = rule.into_inner();
+    match inhibitions::save(&org_id, "", rule, true).await {
+        Ok(_) => Ok(MetaHttpResponse::ok("Alert inhibition rule saved")),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
+
+/// UpdateAlertInhibitionRule
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "UpdateAlertInhibitionRule",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("rule_name" = String, Path, description = "Inhibition rule name"),
+      ),
+    request_body(content = InhibitionRule, description = "Inhibition rule data", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = HttpResponse),
+        (status = 400, description = "Error",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[put("/{org_id}/alerts/inhibitions/{rule_name}")]
+pub async fn update_inhibition_rule(
+    path: web::Path<(String, String)>,
+    rule: web::Json<InhibitionRule>,
+) -> Result<HttpResponse, Error> {
+    let (org_id, name) = path.into_inner();
+    let rule = rule.into_inner();
+    match inhibitions::save(&org_id, &name, rule, false).await {
+        Ok(_) => Ok(MetaHttpResponse::ok("Alert inhibition rule updated")),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
+
+/// GetAlertInhibitionRule
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "GetAlertInhibitionRule",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("rule_name" = String, Path, description = "Inhibition rule name"),
+      ),
+    responses(
+        (status = 200, description = "Success",  content_type = "application/json", body = InhibitionRule),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/alerts/inhibitions/{rule_name}")]
+async fn get_inhibition_rule(path: web::Path<(String, String)>) -> Result<HttpResponse, Error> {
+    let (org_id, name) = path.into_inner();
+    match inhibitions::get(&org_id, &name).await {
+        Ok(data) => Ok(MetaHttpResponse::json(data)),
+        Err(e) => Ok(MetaHttpResponse::not_found(e)),
+    }
+}
+
+/// ListAlertInhibitionRules
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "ListAlertInhibitionRules",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+      ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = Vec<InhibitionRule>),
+        (status = 400, description = "Error",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/alerts/inhibitions")]
+async fn list_inhibition_rules(path: web::Path<String>) -> Result<HttpResponse, Error> {
+    let org_id = path.into_inner();
+    match inhibitions::list(&org_id).await {
+        Ok(data) => Ok(MetaHttpResponse::json(data)),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
+
+/// DeleteAlertInhibitionRule
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "DeleteAlertInhibitionRule",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("rule_name" = String, Path, description = "Inhibition rule name"),
+    ),
+    responses(
+        (status = 200, description = "Success",   content_type = "application/json", body = HttpResponse),
+        (status = 404, description = "NotFound",  content_type = "application/json", body = HttpResponse),
+        (status = 500, description = "Failure",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[delete("/{org_id}/alerts/inhibitions/{rule_name}")]
+async fn delete_inhibition_rule(path: web::Path<(String, String)>) -> Result<HttpResponse, Error> {
+    let (org_id, name) = path.into_inner();
+    match inhibitions::delete(&org_id, &name).await {
+        Ok(_) => Ok(MetaHttpResponse::ok("Alert inhibition rule deleted")),
+        Err(e) => match e {
+            (http::StatusCode::NOT_FOUND, e) => Ok(MetaHttpResponse::not_found(e)),
+            (_, e) => Ok(MetaHttpResponse::internal_error(e)),
+        },
+    }
+}
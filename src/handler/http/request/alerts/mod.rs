@@ -15,4 +15,7 @@
 
 pub mod alert;
 pub mod destinations;
+pub mod inhibitions;
+pub mod recording_rules;
+pub mod silences;
 pub mod templates;
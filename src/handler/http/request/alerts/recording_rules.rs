@@ -0,0 +1,197 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::Error;
+
+use actix_web::{delete, get, http, post, put, web, HttpResponse};
+use config::meta::alerts::recording_rules::RecordingRuleGroup;
+
+use crate::{
+    common::meta::http::HttpResponse as MetaHttpResponse, service::alerts::recording_rules,
+};
+
+/// CreateRecordingRuleGroup
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "CreateRecordingRuleGroup",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+      ),
+    request_body(content = RecordingRuleGroup, description = "Recording rule group data", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = HttpResponse),
+        (status = 400, description = "Error",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/recording_rules")]
+pub async fn save_recording_rule(
+    path: web::Path<String>,
+    group: web::Json<RecordingRuleGroup>,
+) -> Result<HttpResponse, Error> {
+    let org_id = path.into_inner();
+    let group = group.into_inner();
+    match recording_rules::save(&org_id, "", group, true).await {
+        Ok(_) => Ok(MetaHttpResponse::ok("Recording rule group saved")),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
+
+/// UpdateRecordingRuleGroup
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "UpdateRecordingRuleGroup",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("group_name" = String, Path, description = "Recording rule group name"),
+      ),
+    request_body(content = RecordingRuleGroup, description = "Recording rule group data", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = HttpResponse),
+        (status = 400, description = "Error",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[put("/{org_id}/recording_rules/{group_name}")]
+pub async fn update_recording_rule(
+    path: web::Path<(String, String)>,
+    group: web::Json<RecordingRuleGroup>,
+) -> Result<HttpResponse, Error> {
+    let (org_id, name) = path.into_inner();
+    let group = group.into_inner();
+    match recording_rules::save(&org_id, &name, group, false).await {
+        Ok(_) => Ok(MetaHttpResponse::ok("Recording rule group updated")),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
+
+/// GetRecordingRuleGroup
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "GetRecordingRuleGroup",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("group_name" = String, Path, description = "Recording rule group name"),
+      ),
+    responses(
+        (status = 200, description = "Success",  content_type = "application/json", body = RecordingRuleGroup),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/recording_rules/{group_name}")]
+async fn get_recording_rule(path: web::Path<(String, String)>) -> Result<HttpResponse, Error> {
+    let (org_id, name) = path.into_inner();
+    match recording_rules::get(&org_id, &name).await {
+        Ok(data) => Ok(MetaHttpResponse::json(data)),
+        Err(e) => Ok(MetaHttpResponse::not_found(e)),
+    }
+}
+
+/// ListRecordingRuleGroups
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "ListRecordingRuleGroups",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+      ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = Vec<RecordingRuleGroup>),
+        (status = 400, description = "Error",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/recording_rules")]
+async fn list_recording_rules(path: web::Path<String>) -> Result<HttpResponse, Error> {
+    let org_id = path.into_inner();
+    match recording_rules::list(&org_id).await {
+        Ok(data) => Ok(MetaHttpResponse::json(data)),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
+
+/// DeleteRecordingRuleGroup
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "DeleteRecordingRuleGroup",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("group_name" = String, Path, description = "Recording rule group name"),
+    ),
+    responses(
+        (status = 200, description = "Success",   content_type = "application/json", body = HttpResponse),
+        (status = 404, description = "NotFound",  content_type = "application/json", body = HttpResponse),
+        (status = 500, description = "Failure",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[delete("/{org_id}/recording_rules/{group_name}")]
+async fn delete_recording_rule(path: web::Path<(String, String)>) -> Result<HttpResponse, Error> {
+    let (org_id, name) = path.into_inner();
+    match recording_rules::delete(&org_id, &name).await {
+        Ok(_) => Ok(MetaHttpResponse::ok("Recording rule group deleted")),
+        Err(e) => match e {
+            (http::StatusCode::NOT_FOUND, e) => Ok(MetaHttpResponse::not_found(e)),
+            (_, e) => Ok(MetaHttpResponse::internal_error(e)),
+        },
+    }
+}
+
+/// ImportRecordingRules
+///
+/// Imports the recording rules of a Prometheus rule file (YAML). Alerting rules of the
+/// file are ignored and existing groups with the same name are replaced.
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "ImportRecordingRules",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+      ),
+    request_body(content = String, description = "Prometheus rule file", content_type = "application/yaml"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = Vec<String>),
+        (status = 400, description = "Error",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/recording_rules/_import")]
+pub async fn import_recording_rules(
+    path: web::Path<String>,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let org_id = path.into_inner();
+    match recording_rules::import(&org_id, &body).await {
+        Ok(names) => Ok(MetaHttpResponse::json(names)),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
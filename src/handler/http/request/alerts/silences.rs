@@ -0,0 +1,174 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{collections::HashMap, io::Error};
+
+use actix_web::{delete, get, http, post, put, web, HttpResponse};
+use config::meta::alerts::silences::Silence;
+
+use crate::{
+    common::{meta::http::HttpResponse as MetaHttpResponse, utils::auth::UserEmail},
+    service::alerts::silences,
+};
+
+/// CreateAlertSilence
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "CreateAlertSilence",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+      ),
+    request_body(content = Silence, description = "Silence data", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = Silence),
+        (status = 400, description = "Error",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/alerts/silences")]
+pub async fn create_silence(
+    path: web::Path<String>,
+    silence: web::Json<Silence>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    let org_id = path.into_inner();
+    let mut silence = silence.into_inner();
+    silence.created_by = Some(user_email.user_id);
+    match silences::create(&org_id, silence).await {
+        Ok(silence) => Ok(MetaHttpResponse::json(silence)),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
+
+/// GetAlertSilence
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "GetAlertSilence",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("silence_id" = String, Path, description = "Silence id"),
+      ),
+    responses(
+        (status = 200, description = "Success",  content_type = "application/json", body = Silence),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/alerts/silences/{silence_id}")]
+async fn get_silence(path: web::Path<(String, String)>) -> Result<HttpResponse, Error> {
+    let (org_id, id) = path.into_inner();
+    match silences::get(&org_id, &id).await {
+        Ok(data) => Ok(MetaHttpResponse::json(data)),
+        Err(e) => Ok(MetaHttpResponse::not_found(e)),
+    }
+}
+
+/// ListAlertSilences
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "ListAlertSilences",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("active" = Option<bool>, Query, description = "Only list the silences not expired yet"),
+      ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = Vec<Silence>),
+        (status = 400, description = "Error",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/alerts/silences")]
+async fn list_silences(
+    path: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+) -> Result<HttpResponse, Error> {
+    let org_id = path.into_inner();
+    let active_only = query
+        .get("active")
+        .is_some_and(|v| v.to_lowercase() == "true");
+    match silences::list(&org_id, active_only).await {
+        Ok(data) => Ok(MetaHttpResponse::json(data)),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
+
+/// ExpireAlertSilence
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "ExpireAlertSilence",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("silence_id" = String, Path, description = "Silence id"),
+    ),
+    responses(
+        (status = 200, description = "Success",   content_type = "application/json", body = HttpResponse),
+        (status = 404, description = "NotFound",  content_type = "application/json", body = HttpResponse),
+        (status = 500, description = "Failure",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[put("/{org_id}/alerts/silences/{silence_id}/expire")]
+async fn expire_silence(path: web::Path<(String, String)>) -> Result<HttpResponse, Error> {
+    let (org_id, id) = path.into_inner();
+    match silences::expire(&org_id, &id).await {
+        Ok(_) => Ok(MetaHttpResponse::ok("Silence expired")),
+        Err(e) => match e {
+            (http::StatusCode::NOT_FOUND, e) => Ok(MetaHttpResponse::not_found(e)),
+            (_, e) => Ok(MetaHttpResponse::internal_error(e)),
+        },
+    }
+}
+
+/// DeleteAlertSilence
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "DeleteAlertSilence",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("silence_id" = String, Path, description = "Silence id"),
+    ),
+    responses(
+        (status = 200, description = "Success",   content_type = "application/json", body = HttpResponse),
+        (status = 404, description = "NotFound",  content_type = "application/json", body = HttpResponse),
+        (status = 500, description = "Failure",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[delete("/{org_id}/alerts/silences/{silence_id}")]
+async fn delete_silence(path: web::Path<(String, String)>) -> Result<HttpResponse, Error> {
+    let (org_id, id) = path.into_inner();
+    match silences::delete(&org_id, &id).await {
+        Ok(_) => Ok(MetaHttpResponse::ok("Silence deleted")),
+        Err(e) => match e {
+            (http::StatusCode::NOT_FOUND, e) => Ok(MetaHttpResponse::not_found(e)),
+            (_, e) => Ok(MetaHttpResponse::internal_error(e)),
+        },
+    }
+}
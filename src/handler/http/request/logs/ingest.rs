@@ -21,11 +21,13 @@ use crate::{
     common::meta::{
         http::HttpResponse as MetaHttpResponse,
         ingestion::{
-            GCPIngestionRequest, IngestionRequest, KinesisFHIngestionResponse, KinesisFHRequest,
+            GCPIngestionRequest, HecMetadata, HecResponse, IngestionRequest,
+            KinesisFHIngestionResponse, KinesisFHRequest,
         },
     },
     handler::http::request::{CONTENT_TYPE_JSON, CONTENT_TYPE_PROTO},
     service::{
+        ingestion::quota::QuotaExceeded,
         logs,
         logs::otlp_http::{logs_json_handler, logs_proto_handler},
     },
@@ -61,6 +63,9 @@ pub async fn bulk(
         match logs::bulk::ingest(**thread_id, &org_id, body, user_email).await {
             Ok(v) => MetaHttpResponse::json(v),
             Err(e) => {
+                if let Some(e) = e.downcast_ref::<QuotaExceeded>() {
+                    return Ok(e.http_response());
+                }
                 log::error!("Error processing request {org_id}/_bulk: {:?}", e);
                 HttpResponse::BadRequest().json(MetaHttpResponse::error(
                     http::StatusCode::BAD_REQUEST.into(),
@@ -114,6 +119,9 @@ pub async fn multi(
                 _ => MetaHttpResponse::json(v),
             },
             Err(e) => {
+                if let Some(e) = e.downcast_ref::<QuotaExceeded>() {
+                    return Ok(e.http_response());
+                }
                 log::error!(
                     "Error processing request {org_id}/{stream_name}/_multi: {:?}",
                     e
@@ -170,6 +178,9 @@ pub async fn json(
                 _ => MetaHttpResponse::json(v),
             },
             Err(e) => {
+                if let Some(e) = e.downcast_ref::<QuotaExceeded>() {
+                    return Ok(e.http_response());
+                }
                 log::error!(
                     "Error processing request {org_id}/{stream_name}/_json: {:?}",
                     e
@@ -231,6 +242,9 @@ pub async fn handle_kinesis_request(
                 error_message: None,
             }),
             Err(e) => {
+                if let Some(e) = e.downcast_ref::<QuotaExceeded>() {
+                    return Ok(e.http_response());
+                }
                 log::error!("Error processing kinesis request: {:?}", e);
                 HttpResponse::BadRequest().json(KinesisFHIngestionResponse {
                     request_id,
@@ -264,6 +278,9 @@ pub async fn handle_gcp_request(
         {
             Ok(v) => MetaHttpResponse::json(v),
             Err(e) => {
+                if let Some(e) = e.downcast_ref::<QuotaExceeded>() {
+                    return Ok(e.http_response());
+                }
                 log::error!(
                     "Error processing request {org_id}/{stream_name}/_gcp: {:?}",
                     e
@@ -307,6 +324,9 @@ pub async fn otlp_logs_write(
         match logs_proto_handler(**thread_id, &org_id, body, in_stream_name, user_email).await {
             Ok(v) => Ok(v),
             Err(e) => {
+                if let Some(e) = e.downcast_ref::<QuotaExceeded>() {
+                    return Ok(e.http_response());
+                }
                 log::error!(
                     "Error processing otlp pb logs write request {org_id}/{:?}: {:?}",
                     in_stream_name,
@@ -323,6 +343,9 @@ pub async fn otlp_logs_write(
         match logs_json_handler(**thread_id, &org_id, body, in_stream_name, user_email).await {
             Ok(v) => Ok(v),
             Err(e) => {
+                if let Some(e) = e.downcast_ref::<QuotaExceeded>() {
+                    return Ok(e.http_response());
+                }
                 log::error!(
                     "Error processing otlp json logs write request {org_id}/{:?}: {:?}",
                     in_stream_name,
@@ -341,3 +364,187 @@ pub async fn otlp_logs_write(
         )))
     }
 }
+
+/// Loki push API
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Logs",
+    operation_id = "LokiPush",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+    ),
+    request_body(content = String, description = "Loki PushRequest, snappy compressed protobuf or json", content_type = "application/x-protobuf"),
+    responses(
+        (status = 204, description = "Success"),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/loki/api/v1/push")]
+pub async fn loki_push(
+    thread_id: web::Data<usize>,
+    org_id: web::Path<String>,
+    req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let org_id = org_id.into_inner();
+    let content_type = req
+        .headers()
+        .get("Content-Type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or(CONTENT_TYPE_PROTO);
+    let user_email = req.headers().get("user_id").unwrap().to_str().unwrap();
+    let in_stream_name = req
+        .headers()
+        .get(&config::get_config().grpc.stream_header_key)
+        .map(|header| header.to_str().unwrap());
+    let is_protobuf = if content_type.eq(CONTENT_TYPE_PROTO) {
+        true
+    } else if content_type.starts_with(CONTENT_TYPE_JSON) {
+        false
+    } else {
+        return Ok(MetaHttpResponse::bad_request("Bad Request"));
+    };
+    Ok(
+        match logs::loki::ingest(
+            **thread_id,
+            &org_id,
+            in_stream_name,
+            body,
+            is_protobuf,
+            user_email,
+        )
+        .await
+        {
+            Ok(v) => match v.code {
+                503 => HttpResponse::ServiceUnavailable().json(v),
+                _ => HttpResponse::NoContent().finish(),
+            },
+            Err(e) => {
+                if let Some(e) = e.downcast_ref::<QuotaExceeded>() {
+                    return Ok(e.http_response());
+                }
+                log::error!("Error processing loki push request {org_id}: {:?}", e);
+                MetaHttpResponse::bad_request(e)
+            }
+        },
+    )
+}
+
+/// Splunk HEC compatible ingestion API
+///
+/// The events are ingested into the stream named by their `index`. Clients authenticate with
+/// `Authorization: Splunk <token>` where the token is the base64 encoded `email:passcode` of the
+/// user. When only some of the events are ingested, the response is a success listing the
+/// indexes of the rejected ones in `invalid-event-numbers`.
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Logs",
+    operation_id = "SplunkHecEvent",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("index" = Option<String>, Query, description = "Stream of the events without index"),
+    ),
+    request_body(content = String, description = "HEC events", content_type = "application/json", example = json!({"time": 1426279439.123, "host": "web-01", "index": "k8s", "event": "a log line", "fields": {"env": "prod"}})),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = HecResponse, example = json!({"text": "Success", "code": 0})),
+        (status = 400, description = "Failure", content_type = "application/json", body = HecResponse, example = json!({"text": "Event field is required", "code": 12, "invalid-event-number": 0})),
+    )
+)]
+#[post("/{org_id}/services/collector/event")]
+pub async fn hec_event(
+    thread_id: web::Data<usize>,
+    org_id: web::Path<String>,
+    metadata: web::Query<HecMetadata>,
+    body: web::Bytes,
+    in_req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    hec_ingest(
+        **thread_id,
+        &org_id.into_inner(),
+        body,
+        false,
+        metadata.into_inner(),
+        in_req,
+    )
+    .await
+}
+
+/// Splunk HEC compatible raw ingestion API
+///
+/// Each line of the body is an event.
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Logs",
+    operation_id = "SplunkHecRaw",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("index" = Option<String>, Query, description = "Stream of the events"),
+        ("host" = Option<String>, Query, description = "Host of the events"),
+        ("source" = Option<String>, Query, description = "Source of the events"),
+        ("sourcetype" = Option<String>, Query, description = "Source type of the events"),
+    ),
+    request_body(content = String, description = "Log lines", content_type = "text/plain"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = HecResponse, example = json!({"text": "Success", "code": 0})),
+        (status = 400, description = "Failure", content_type = "application/json", body = HecResponse, example = json!({"text": "No data", "code": 5})),
+    )
+)]
+#[post("/{org_id}/services/collector/raw")]
+pub async fn hec_raw(
+    thread_id: web::Data<usize>,
+    org_id: web::Path<String>,
+    metadata: web::Query<HecMetadata>,
+    body: web::Bytes,
+    in_req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    hec_ingest(
+        **thread_id,
+        &org_id.into_inner(),
+        body,
+        true,
+        metadata.into_inner(),
+        in_req,
+    )
+    .await
+}
+
+async fn hec_ingest(
+    thread_id: usize,
+    org_id: &str,
+    body: web::Bytes,
+    is_raw: bool,
+    metadata: HecMetadata,
+    in_req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let user_email = in_req.headers().get("user_id").unwrap().to_str().unwrap();
+    Ok(
+        match logs::bulk::ingest_hec(thread_id, org_id, body, is_raw, metadata, user_email).await {
+            Ok(v) if v.code == 0 => HttpResponse::Ok().json(v),
+            Ok(v) => HttpResponse::BadRequest().json(v),
+            Err(e) => {
+                if let Some(e) = e.downcast_ref::<QuotaExceeded>() {
+                    return Ok(e.http_response());
+                }
+                // the forwarders retry the requests rejected as busy
+                log::error!(
+                    "Error processing request {org_id}/services/collector: {:?}",
+                    e
+                );
+                HttpResponse::ServiceUnavailable().json(HecResponse::error(
+                    HecResponse::SERVER_BUSY,
+                    e,
+                    None,
+                ))
+            }
+        },
+    )
+}
@@ -13,16 +13,20 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::io::Error;
+use std::{collections::HashMap, io::Error};
 
 use actix_web::{http, post, web, HttpRequest, HttpResponse};
+use config::utils::json;
 
 use crate::{
     common::meta::http::HttpResponse as MetaHttpResponse,
     handler::http::request::{CONTENT_TYPE_JSON, CONTENT_TYPE_PROTO},
-    service::metrics::{
-        otlp_http::{metrics_json_handler, metrics_proto_handler},
-        {self},
+    service::{
+        ingestion::quota::QuotaExceeded,
+        metrics::{
+            otlp_http::{metrics_json_handler, metrics_proto_handler},
+            {self},
+        },
     },
 };
 
@@ -49,6 +53,9 @@ pub async fn json(org_id: web::Path<String>, body: web::Bytes) -> Result<HttpRes
     Ok(match metrics::json::ingest(&org_id, body).await {
         Ok(v) => HttpResponse::Ok().json(v),
         Err(e) => {
+            if let Some(e) = e.downcast_ref::<QuotaExceeded>() {
+                return Ok(e.http_response());
+            }
             log::error!("Error processing request {org_id}/metrics/_json: {:?}", e);
             HttpResponse::BadRequest().json(MetaHttpResponse::error(
                 http::StatusCode::BAD_REQUEST.into(),
@@ -58,6 +65,83 @@ pub async fn json(org_id: web::Path<String>, body: web::Bytes) -> Result<HttpRes
     })
 }
 
+/// InfluxDB v2 line protocol ingestion API
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Metrics",
+    operation_id = "MetricsIngestionInfluxDBV2",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("precision" = Option<String>, Query, description = "Precision of the timestamps: ns (default), us, ms or s"),
+    ),
+    request_body(content = String, description = "Ingest data (line protocol)", content_type = "text/plain", example = "cpu,host=server01 usage_idle=98.5,usage_user=1i 1687175143000000000"),
+    responses(
+        (status = 204, description = "Success"),
+        (status = 400, description = "Failure", content_type = "application/json", body = Object, example = json!({"code": "invalid","message": "line 1: missing fields"})),
+    )
+)]
+#[post("/{org_id}/influxdb/api/v2/write")]
+pub async fn influxdb_v2_write(
+    org_id: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    influxdb_write(org_id.into_inner(), query.get("precision"), body).await
+}
+
+/// InfluxDB v1 line protocol ingestion API
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Metrics",
+    operation_id = "MetricsIngestionInfluxDBV1",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("precision" = Option<String>, Query, description = "Precision of the timestamps: n (default), u, ms, s, m or h"),
+    ),
+    request_body(content = String, description = "Ingest data (line protocol)", content_type = "text/plain", example = "cpu,host=server01 usage_idle=98.5,usage_user=1i 1687175143000000000"),
+    responses(
+        (status = 204, description = "Success"),
+        (status = 400, description = "Failure", content_type = "application/json", body = Object, example = json!({"code": "invalid","message": "line 1: missing fields"})),
+    )
+)]
+#[post("/{org_id}/influxdb/write")]
+pub async fn influxdb_v1_write(
+    org_id: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    influxdb_write(org_id.into_inner(), query.get("precision"), body).await
+}
+
+async fn influxdb_write(
+    org_id: String,
+    precision: Option<&String>,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    Ok(
+        match metrics::influxdb::ingest(&org_id, body, precision.map(|v| v.as_str())).await {
+            Ok(_) => HttpResponse::NoContent().finish(),
+            Err(e) => {
+                if let Some(e) = e.downcast_ref::<QuotaExceeded>() {
+                    return Ok(e.http_response());
+                }
+                log::error!("Error processing request {org_id}/influxdb/write: {:?}", e);
+                // InfluxDB clients such as Telegraf expect the error in the InfluxDB format
+                HttpResponse::BadRequest().json(json::json!({
+                    "code": "invalid",
+                    "message": e.to_string(),
+                }))
+            }
+        },
+    )
+}
+
 /// MetricsIngest
 #[utoipa::path(
     context_path = "/api",
@@ -16,6 +16,13 @@
 use std::io::Error;
 
 use actix_web::{get, http, put, route, web, HttpRequest, HttpResponse, Result};
+use config::utils::json;
+use infra::errors;
+
+use crate::{
+    common::utils::http::get_or_create_trace_id,
+    service::search::es::{EsSearch, SearchRequest},
+};
 
 #[route("/{org_id}/", method = "GET", method = "HEAD")]
 async fn org_index(_org_id: web::Path<String>, req: HttpRequest) -> Result<HttpResponse, Error> {
@@ -149,3 +156,130 @@ async fn org_pipeline_create(
         .insert_header(("X-Elastic-Product", "Elasticsearch"))
         .body(es_info))
 }
+
+#[route("/{org_id}/{index}/_search", method = "GET", method = "POST")]
+async fn org_search(
+    path: web::Path<(String, String)>,
+    in_req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let (org_id, index) = path.into_inner();
+    search(&org_id, &index, &in_req, &body).await
+}
+
+/// Query DSL search on the stream named by `index`, the `/{org_id}/_search` API runs the query DSL
+/// requests with it on the stream of its `index` parameter.
+pub async fn search(
+    org_id: &str,
+    index: &str,
+    in_req: &HttpRequest,
+    body: &[u8],
+) -> Result<HttpResponse, Error> {
+    let user_id = in_req
+        .headers()
+        .get("user_id")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    let req: SearchRequest = if body.iter().all(|c| c.is_ascii_whitespace()) {
+        SearchRequest::default()
+    } else {
+        match json::from_slice(body) {
+            Ok(v) => v,
+            Err(e) => {
+                return Ok(es_error(
+                    http::StatusCode::BAD_REQUEST,
+                    "parsing_exception",
+                    e,
+                ))
+            }
+        }
+    };
+    let search = match EsSearch::new(index, &req) {
+        Ok(v) => v,
+        Err(e) => {
+            return Ok(es_error(
+                http::StatusCode::BAD_REQUEST,
+                "parsing_exception",
+                e,
+            ))
+        }
+    };
+
+    // Check permissions on stream
+    #[cfg(feature = "enterprise")]
+    {
+        use o2_enterprise::enterprise::openfga::meta::mapping::OFGA_MODELS;
+
+        use crate::common::{
+            infra::config::USERS,
+            meta::user::User,
+            utils::auth::{is_root_user, AuthExtractor},
+        };
+
+        if !is_root_user(&user_id) {
+            let user: Option<User> = USERS
+                .get(&format!("{org_id}/{user_id}"))
+                .map(|v| v.value().clone());
+            if let Some(user) = user {
+                if user.is_external
+                    && !crate::handler::http::auth::validator::check_permissions(
+                        &user_id,
+                        AuthExtractor {
+                            auth: "".to_string(),
+                            method: "GET".to_string(),
+                            o2_type: format!(
+                                "{}:{index}",
+                                OFGA_MODELS.get("logs").map_or("logs", |model| model.key),
+                            ),
+                            org_id: org_id.to_string(),
+                            bypass_check: false,
+                            parent_id: "".to_string(),
+                        },
+                        Some(user.role),
+                    )
+                    .await
+                {
+                    return Ok(es_error(
+                        http::StatusCode::FORBIDDEN,
+                        "security_exception",
+                        "Unauthorized Access",
+                    ));
+                }
+            }
+        }
+    }
+
+    let trace_id = get_or_create_trace_id(in_req.headers(), &tracing::Span::none());
+    match search.search(&trace_id, org_id, Some(user_id)).await {
+        Ok(res) => Ok(HttpResponse::Ok()
+            .insert_header(("X-Elastic-Product", "Elasticsearch"))
+            .json(res)),
+        Err(e) => {
+            log::error!("[trace_id {trace_id}] es search error: {}", e);
+            let reason = match &e {
+                errors::Error::ErrorCode(code) => code.get_message(),
+                e => e.to_string(),
+            };
+            Ok(es_error(
+                http::StatusCode::INTERNAL_SERVER_ERROR,
+                "search_phase_execution_exception",
+                reason,
+            ))
+        }
+    }
+}
+
+fn es_error(status: http::StatusCode, err_type: &str, reason: impl ToString) -> HttpResponse {
+    let reason = reason.to_string();
+    HttpResponse::build(status)
+        .insert_header(("X-Elastic-Product", "Elasticsearch"))
+        .json(json::json!({
+            "error": {
+                "root_cause": [{"type": err_type, "reason": reason}],
+                "type": err_type,
+                "reason": reason,
+            },
+            "status": status.as_u16(),
+        }))
+}
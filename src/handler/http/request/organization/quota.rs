@@ -0,0 +1,128 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::Error;
+
+use actix_web::{delete, get, http, put, web, HttpResponse};
+use config::meta::quota::IngestionQuota;
+
+use crate::{
+    common::{
+        meta::http::HttpResponse as MetaHttpResponse,
+        utils::auth::{is_root_user, UserEmail},
+    },
+    service::ingestion::quota,
+};
+
+/// GetIngestionQuota
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Organizations",
+    operation_id = "GetIngestionQuota",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+    ),
+    responses(
+        (status = 200, description = "Success",  content_type = "application/json", body = IngestionQuota),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/ingestion_quota")]
+async fn get_quota(path: web::Path<String>) -> Result<HttpResponse, Error> {
+    let org_id = path.into_inner();
+    match quota::get(&org_id).await {
+        Ok(data) => Ok(MetaHttpResponse::json(data)),
+        Err(e) => Ok(MetaHttpResponse::not_found(e)),
+    }
+}
+
+/// SetIngestionQuota
+///
+/// Only the root user can change the quotas.
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Organizations",
+    operation_id = "SetIngestionQuota",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+    ),
+    request_body(content = IngestionQuota, description = "Ingestion quota data", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Success",   content_type = "application/json", body = IngestionQuota),
+        (status = 400, description = "Error",     content_type = "application/json", body = HttpResponse),
+        (status = 403, description = "Forbidden", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[put("/{org_id}/ingestion_quota")]
+async fn set_quota(
+    path: web::Path<String>,
+    data: web::Json<IngestionQuota>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    let org_id = path.into_inner();
+    if !is_root_user(&user_email.user_id) {
+        return Ok(MetaHttpResponse::forbidden(
+            "Only the root user can change the ingestion quotas",
+        ));
+    }
+    match quota::set(&org_id, data.into_inner()).await {
+        Ok(data) => Ok(MetaHttpResponse::json(data)),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
+
+/// DeleteIngestionQuota
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Organizations",
+    operation_id = "DeleteIngestionQuota",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+    ),
+    responses(
+        (status = 200, description = "Success",   content_type = "application/json", body = HttpResponse),
+        (status = 403, description = "Forbidden", content_type = "application/json", body = HttpResponse),
+        (status = 404, description = "NotFound",  content_type = "application/json", body = HttpResponse),
+        (status = 500, description = "Failure",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[delete("/{org_id}/ingestion_quota")]
+async fn delete_quota(
+    path: web::Path<String>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    let org_id = path.into_inner();
+    if !is_root_user(&user_email.user_id) {
+        return Ok(MetaHttpResponse::forbidden(
+            "Only the root user can change the ingestion quotas",
+        ));
+    }
+    match quota::delete(&org_id).await {
+        Ok(_) => Ok(MetaHttpResponse::ok("Ingestion quota deleted")),
+        Err(e) => match e {
+            (http::StatusCode::NOT_FOUND, e) => Ok(MetaHttpResponse::not_found(e)),
+            (_, e) => Ok(MetaHttpResponse::internal_error(e)),
+        },
+    }
+}
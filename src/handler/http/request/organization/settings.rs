@@ -91,6 +91,10 @@ async fn create(
         field_found = true;
         data.toggle_ingestion_logs = toggle_ingestion_logs;
     }
+    if let Some(export_max_size) = settings.export_max_size {
+        field_found = true;
+        data.export_max_size = export_max_size;
+    }
 
     if !field_found {
         return Ok(MetaHttpResponse::bad_request("No valid field found"));
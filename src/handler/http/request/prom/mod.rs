@@ -27,7 +27,7 @@ use promql_parser::parser;
 
 use crate::{
     common::meta::{self, http::HttpResponse as MetaHttpResponse},
-    service::{metrics, promql, promql::MetricsQueryRequest},
+    service::{ingestion::quota::QuotaExceeded, metrics, promql, promql::MetricsQueryRequest},
 };
 
 /// prometheus remote-write endpoint for metrics
@@ -58,10 +58,13 @@ pub async fn remote_write(
     if content_type == "application/x-protobuf" {
         Ok(match metrics::prom::remote_write(&org_id, body).await {
             Ok(_) => HttpResponse::Ok().into(),
-            Err(e) => HttpResponse::BadRequest().json(MetaHttpResponse::error(
-                http::StatusCode::BAD_REQUEST.into(),
-                e.to_string(),
-            )),
+            Err(e) => match e.downcast_ref::<QuotaExceeded>() {
+                Some(e) => e.http_response(),
+                None => HttpResponse::BadRequest().json(MetaHttpResponse::error(
+                    http::StatusCode::BAD_REQUEST.into(),
+                    e.to_string(),
+                )),
+            },
         })
     } else {
         Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
@@ -26,7 +26,7 @@ use crate::{
         http::HttpResponse as MetaHttpResponse, ingestion::IngestionRequest,
         middleware_data::RumExtraData,
     },
-    service::logs,
+    service::{logs, rum},
 };
 
 pub const RUM_LOG_STREAM: &str = "_rumlog";
@@ -113,6 +113,7 @@ pub async fn data(
 ) -> Result<HttpResponse, Error> {
     let org_id: String = path.into_inner();
     let extend_json = &rum_query_data.data;
+    let body = rum::sourcemap::symbolicate_records(&org_id, body, extend_json).await;
     Ok(
         match logs::ingest::ingest(
             0,
@@ -155,6 +156,7 @@ pub async fn log(
 ) -> Result<HttpResponse, Error> {
     let org_id = path.into_inner();
     let extend_json = &rum_query_data.data;
+    let body = rum::sourcemap::symbolicate_records(&org_id, body, extend_json).await;
     Ok(
         match logs::ingest::ingest(
             0,
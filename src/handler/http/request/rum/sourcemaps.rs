@@ -0,0 +1,139 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{collections::HashMap, io::Error};
+
+use actix_web::{delete, get, post, web, HttpResponse};
+
+use crate::{
+    common::meta::{http::HttpResponse as MetaHttpResponse, sourcemap::SourceMapList},
+    service::rum::sourcemap,
+};
+
+/// UploadSourceMap
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Rum",
+    operation_id = "UploadSourceMap",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("service" = String, Query, description = "Service of the RUM application"),
+        ("version" = String, Query, description = "Version of the RUM application"),
+        ("minified_file" = String, Query, description = "Url or path of the minified file, e.g. /static/js/main.js"),
+    ),
+    request_body(content = String, description = "Source map of the minified file", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = HttpResponse),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/rum/sourcemaps")]
+pub async fn upload(
+    path: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let org_id = path.into_inner();
+    let (Some(service), Some(version), Some(minified_file)) = (
+        query.get("service"),
+        query.get("version"),
+        query.get("minified_file"),
+    ) else {
+        return Ok(MetaHttpResponse::bad_request(
+            "service, version and minified_file are required",
+        ));
+    };
+    match sourcemap::upload(&org_id, service, version, minified_file, body).await {
+        Ok(_) => Ok(MetaHttpResponse::ok("Source map uploaded")),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
+
+/// ListSourceMaps
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Rum",
+    operation_id = "ListSourceMaps",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("service" = Option<String>, Query, description = "Service of the RUM application"),
+        ("version" = Option<String>, Query, description = "Version of the RUM application"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = SourceMapList),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/rum/sourcemaps")]
+pub async fn list(
+    path: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+) -> Result<HttpResponse, Error> {
+    let org_id = path.into_inner();
+    match sourcemap::list(
+        &org_id,
+        query.get("service").map(|v| v.as_str()),
+        query.get("version").map(|v| v.as_str()),
+    )
+    .await
+    {
+        Ok(list) => Ok(MetaHttpResponse::json(SourceMapList { list })),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
+
+/// DeleteSourceMaps
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Rum",
+    operation_id = "DeleteSourceMaps",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("service" = String, Query, description = "Service of the RUM application"),
+        ("version" = String, Query, description = "Version of the RUM application"),
+        ("minified_file" = Option<String>, Query, description = "Minified file of the source map, all the source maps of the version are deleted when missing"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = HttpResponse),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[delete("/{org_id}/rum/sourcemaps")]
+pub async fn delete(
+    path: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+) -> Result<HttpResponse, Error> {
+    let org_id = path.into_inner();
+    let (Some(service), Some(version)) = (query.get("service"), query.get("version")) else {
+        return Ok(MetaHttpResponse::bad_request(
+            "service and version are required",
+        ));
+    };
+    let minified_file = query.get("minified_file").map(|v| v.as_str());
+    match sourcemap::delete(&org_id, service, version, minified_file).await {
+        Ok(0) => Ok(MetaHttpResponse::not_found("Source map not found")),
+        Ok(n) => Ok(MetaHttpResponse::ok(format!("{n} source maps deleted"))),
+        Err(e) => Ok(MetaHttpResponse::bad_request(e)),
+    }
+}
@@ -0,0 +1,338 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{collections::HashMap, io::Error, str::FromStr};
+
+use actix_web::{
+    get,
+    http::{
+        header::{ContentDisposition, DispositionParam, DispositionType},
+        StatusCode,
+    },
+    post, web, HttpRequest, HttpResponse,
+};
+use config::{
+    meta::{search::Request, sql::resolve_stream_names, stream::StreamType},
+    utils::json,
+};
+use futures::StreamExt;
+use tracing::Span;
+
+use crate::{
+    common::{
+        meta::http::HttpResponse as MetaHttpResponse,
+        utils::http::{get_or_create_trace_id, get_stream_type_from_request},
+    },
+    service::search::export::{self as ExportService, CsvOptions, ExportFormat, ExportTooLarge},
+};
+
+/// ExportSearchResults
+///
+/// Exports the rows of the SQL query as a CSV, Parquet or Arrow IPC file. The query functions
+/// aren't applied and the time range is limited to the `max_query_range` of the streams. With
+/// `storage=true` the file is written into the object storage and a download link, valid for
+/// `ZO_EXPORT_LINK_TTL` hours, is returned instead. The file is streamed as it's written, so an
+/// error occurring once it's started, e.g. the size limit being exceeded, aborts the response.
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Search",
+    operation_id = "ExportSearchResults",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("type" = Option<String>, Query, description = "Stream type, logs by default"),
+        ("format" = String, Query, description = "File format: csv, parquet or arrow"),
+        ("header" = Option<bool>, Query, description = "Writes the column names as the first CSV line, true by default"),
+        ("delimiter" = Option<String>, Query, description = "CSV field delimiter, `,` by default, `\\t` for tabs"),
+        ("quote" = Option<String>, Query, description = "CSV quote character, `\"` by default"),
+        ("storage" = Option<bool>, Query, description = "Writes the file into the object storage and returns a download link"),
+    ),
+    request_body(content = SearchRequest, description = "Search query, `size` limits the number of rows, all the rows are exported by default", content_type = "application/json", example = json!({
+        "query": {
+            "sql": "select * from k8s",
+            "start_time": 1675182660872049i64,
+            "end_time": 1675185660872049i64,
+            "from": 0,
+            "size": 0
+        }
+    })),
+    responses(
+        (status = 200, description = "The exported file, or its download link with `storage=true`", body = ExportLink),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+        (status = 413, description = "The file exceeds the size limit of the organization", content_type = "application/json", body = HttpResponse),
+        (status = 500, description = "Failure", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/_export")]
+pub async fn export(
+    org_id: web::Path<String>,
+    in_req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let org_id = org_id.into_inner();
+    let trace_id = get_or_create_trace_id(in_req.headers(), &Span::none());
+    let user_id = in_req
+        .headers()
+        .get("user_id")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+
+    let query = web::Query::<HashMap<String, String>>::from_query(in_req.query_string()).unwrap();
+    let stream_type = match get_stream_type_from_request(&query) {
+        Ok(v) => v.unwrap_or(StreamType::Logs),
+        Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+    };
+    let format = match ExportFormat::from_str(query.get("format").map_or("", |v| v.as_str())) {
+        Ok(v) => v,
+        Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+    };
+    let csv_options = match get_csv_options(&query) {
+        Ok(v) => v,
+        Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+    };
+    let to_storage = query
+        .get("storage")
+        .is_some_and(|v| v.to_lowercase() == "true");
+
+    let mut req: Request = match json::from_slice(&body) {
+        Ok(v) => v,
+        Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+    };
+    if let Err(e) = req.decode() {
+        return Ok(MetaHttpResponse::bad_request(e));
+    }
+    if req.query.query_fn.is_some() {
+        return Ok(MetaHttpResponse::bad_request(
+            "Query functions aren't supported by the exports",
+        ));
+    }
+
+    let stream_names = match resolve_stream_names(&req.query.sql) {
+        Ok(v) => v,
+        Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+    };
+    for stream_name in stream_names {
+        if let Some(settings) =
+            infra::schema::get_settings(&org_id, &stream_name, stream_type).await
+        {
+            let max_query_range = settings.max_query_range;
+            if max_query_range > 0
+                && (req.query.end_time - req.query.start_time) > max_query_range * 3600 * 1_000_000
+            {
+                req.query.start_time = req.query.end_time - max_query_range * 3600 * 1_000_000;
+            }
+        }
+
+        // Check permissions on stream
+        #[cfg(feature = "enterprise")]
+        {
+            use o2_enterprise::enterprise::openfga::meta::mapping::OFGA_MODELS;
+
+            use crate::common::{
+                infra::config::USERS,
+                meta,
+                utils::auth::{is_root_user, AuthExtractor},
+            };
+
+            if !is_root_user(&user_id) {
+                let user: meta::user::User =
+                    USERS.get(&format!("{org_id}/{}", user_id)).unwrap().clone();
+                let stream_type_str = stream_type.to_string();
+
+                if user.is_external
+                    && !crate::handler::http::auth::validator::check_permissions(
+                        &user_id,
+                        AuthExtractor {
+                            auth: "".to_string(),
+                            method: "GET".to_string(),
+                            o2_type: format!(
+                                "{}:{}",
+                                OFGA_MODELS
+                                    .get(stream_type_str.as_str())
+                                    .map_or(stream_type_str.as_str(), |model| model.key),
+                                stream_name
+                            ),
+                            org_id: org_id.clone(),
+                            bypass_check: false,
+                            parent_id: "".to_string(),
+                        },
+                        Some(user.role),
+                    )
+                    .await
+                {
+                    return Ok(MetaHttpResponse::forbidden("Unauthorized Access"));
+                }
+                // Check permissions on stream ends
+            }
+        }
+    }
+
+    let mut chunks = match ExportService::export(
+        &trace_id,
+        &org_id,
+        stream_type,
+        Some(user_id),
+        &req,
+        format,
+        &csv_options,
+    )
+    .await
+    {
+        Ok(v) => v,
+        Err(e) => return Ok(error_response(&trace_id, e)),
+    };
+
+    if to_storage {
+        return match ExportService::upload(&org_id, format, chunks).await {
+            Ok(link) => Ok(HttpResponse::Ok().json(link)),
+            Err(e) => Ok(error_response(&trace_id, e)),
+        };
+    }
+
+    // the errors before the first chunk, e.g. of the search, still get an error response, the
+    // later ones abort the response
+    let first = match chunks.recv().await {
+        Some(Ok(v)) => Some(v),
+        Some(Err(e)) => return Ok(error_response(&trace_id, e)),
+        None => None,
+    };
+    let body = futures::stream::iter(first.map(Ok)).chain(futures::stream::unfold(
+        chunks,
+        move |mut chunks| {
+            let trace_id = trace_id.clone();
+            async move {
+                let ret = chunks.recv().await?;
+                if let Err(e) = &ret {
+                    log::error!("[trace_id {trace_id}] export error: {}", e);
+                }
+                Some((ret, chunks))
+            }
+        },
+    ));
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(attachment(&format!("export.{}", format.extension())))
+        .streaming(body))
+}
+
+fn error_response(trace_id: &str, e: anyhow::Error) -> HttpResponse {
+    log::error!("[trace_id {trace_id}] export error: {}", e);
+    match e.downcast_ref::<ExportTooLarge>() {
+        Some(e) => HttpResponse::PayloadTooLarge().json(MetaHttpResponse::error(
+            StatusCode::PAYLOAD_TOO_LARGE.into(),
+            e.to_string(),
+        )),
+        None => MetaHttpResponse::internal_error(e),
+    }
+}
+
+/// DownloadExport
+///
+/// Downloads an exported file with the link returned by the export, no credentials are needed.
+#[utoipa::path(
+    context_path = "/exports",
+    tag = "Search",
+    operation_id = "DownloadExport",
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("file_name" = String, Path, description = "Exported file name"),
+    ),
+    responses(
+        (status = 200, description = "The exported file"),
+        (status = 404, description = "The link is invalid or expired", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/{file_name}")]
+pub async fn download(path: web::Path<(String, String)>) -> Result<HttpResponse, Error> {
+    let (org_id, file_name) = path.into_inner();
+    match ExportService::download(&org_id, &file_name).await {
+        Ok(Some((format, size, data))) => Ok(HttpResponse::Ok()
+            .content_type(format.content_type())
+            .insert_header(attachment(&file_name))
+            .no_chunking(size as u64)
+            .streaming(data)),
+        Ok(None) => Ok(MetaHttpResponse::not_found(
+            "The link is invalid or expired",
+        )),
+        Err(e) => Ok(MetaHttpResponse::internal_error(e)),
+    }
+}
+
+fn get_csv_options(query: &HashMap<String, String>) -> Result<CsvOptions, String> {
+    let mut options = CsvOptions::default();
+    if let Some(v) = query.get("header") {
+        options.header = v.to_lowercase() != "false";
+    }
+    if let Some(v) = query.get("delimiter") {
+        options.delimiter = parse_csv_char("delimiter", v)?;
+    }
+    if let Some(v) = query.get("quote") {
+        options.quote = parse_csv_char("quote", v)?;
+    }
+    if options.delimiter == options.quote {
+        return Err("The CSV delimiter and quote must be different".to_string());
+    }
+    Ok(options)
+}
+
+fn parse_csv_char(name: &str, value: &str) -> Result<u8, String> {
+    match value.as_bytes() {
+        b"\\t" => Ok(b'\t'),
+        [c] if c.is_ascii() && *c != b'\n' && *c != b'\r' => Ok(*c),
+        _ => Err(format!(
+            "Invalid CSV {name}: {value}, it must be a single ASCII character"
+        )),
+    }
+}
+
+fn attachment(file_name: &str) -> ContentDisposition {
+    ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![DispositionParam::Filename(file_name.to_string())],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_csv_options() {
+        let query = HashMap::new();
+        assert_eq!(get_csv_options(&query).unwrap(), CsvOptions::default());
+
+        let query = HashMap::from([
+            ("header".to_string(), "false".to_string()),
+            ("delimiter".to_string(), "\\t".to_string()),
+            ("quote".to_string(), "'".to_string()),
+        ]);
+        assert_eq!(
+            get_csv_options(&query).unwrap(),
+            CsvOptions {
+                header: false,
+                delimiter: b'\t',
+                quote: b'\'',
+            }
+        );
+
+        let query = HashMap::from([("delimiter".to_string(), ";;".to_string())]);
+        assert!(get_csv_options(&query).is_err());
+        let query = HashMap::from([("quote".to_string(), ",".to_string())]);
+        assert!(get_csv_options(&query).is_err());
+    }
+}
@@ -13,13 +13,27 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::io::Error;
+use std::{collections::HashMap, io::Error};
 
-use actix_web::{delete, get, put, web, HttpResponse};
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse};
+use config::{
+    get_config,
+    meta::{
+        search::Request,
+        search_job::{SearchJob, SearchJobList},
+        sql::resolve_stream_names,
+        stream::StreamType,
+    },
+    utils::json,
+};
 #[cfg(feature = "enterprise")]
-use {
-    crate::common::meta::http::HttpResponse as MetaHttpResponse,
-    o2_enterprise::enterprise::common::infra::config::get_config as get_o2_config,
+use o2_enterprise::enterprise::common::infra::config::get_config as get_o2_config;
+
+use crate::{
+    common::{
+        meta::http::HttpResponse as MetaHttpResponse, utils::http::get_stream_type_from_request,
+    },
+    service::search::job::{self as SearchJobService, TooManySearchJobs},
 };
 
 #[cfg(feature = "enterprise")]
@@ -99,3 +113,309 @@ async fn cancel_query_inner(org_id: &str, trace_ids: &[&str]) -> Result<HttpResp
     }
     Ok(HttpResponse::Ok().json(res))
 }
+
+/// SubmitSearchJob
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Search",
+    operation_id = "SubmitSearchJob",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("type" = Option<String>, Query, description = "Stream type, logs by default"),
+    ),
+    request_body(content = SearchRequest, description = "Search query, `size` limits the number of result rows", content_type = "application/json", example = json!({
+        "query": {
+            "sql": "select * from k8s",
+            "start_time": 1675182660872049i64,
+            "end_time": 1675185660872049i64,
+            "from": 0,
+            "size": 0
+        }
+    })),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = SearchJob),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+        (status = 429, description = "Too many search jobs", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/search_jobs")]
+pub async fn submit_job(
+    org_id: web::Path<String>,
+    in_req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let org_id = org_id.into_inner();
+    let user_id = get_user_id(&in_req);
+    let query = web::Query::<HashMap<String, String>>::from_query(in_req.query_string()).unwrap();
+    let stream_type = match get_stream_type_from_request(&query) {
+        Ok(v) => v.unwrap_or(StreamType::Logs),
+        Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+    };
+    let mut req: Request = match json::from_slice(&body) {
+        Ok(v) => v,
+        Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+    };
+    if let Err(e) = req.decode() {
+        return Ok(MetaHttpResponse::bad_request(e));
+    }
+    // the job pages through the results itself
+    if req.query.size < 0 {
+        req.query.size = 0;
+    }
+
+    let stream_names = match resolve_stream_names(&req.query.sql) {
+        Ok(v) => v,
+        Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+    };
+    for _stream_name in stream_names {
+        // Check permissions on stream
+        #[cfg(feature = "enterprise")]
+        {
+            use o2_enterprise::enterprise::openfga::meta::mapping::OFGA_MODELS;
+
+            use crate::common::{
+                infra::config::USERS,
+                meta,
+                utils::auth::{is_root_user, AuthExtractor},
+            };
+
+            if !is_root_user(&user_id) {
+                let user: meta::user::User =
+                    USERS.get(&format!("{org_id}/{}", user_id)).unwrap().clone();
+                let stream_type_str = stream_type.to_string();
+
+                if user.is_external
+                    && !crate::handler::http::auth::validator::check_permissions(
+                        &user_id,
+                        AuthExtractor {
+                            auth: "".to_string(),
+                            method: "GET".to_string(),
+                            o2_type: format!(
+                                "{}:{}",
+                                OFGA_MODELS
+                                    .get(stream_type_str.as_str())
+                                    .map_or(stream_type_str.as_str(), |model| model.key),
+                                _stream_name
+                            ),
+                            org_id: org_id.clone(),
+                            bypass_check: false,
+                            parent_id: "".to_string(),
+                        },
+                        Some(user.role),
+                    )
+                    .await
+                {
+                    return Ok(MetaHttpResponse::forbidden("Unauthorized Access"));
+                }
+                // Check permissions on stream ends
+            }
+        }
+    }
+
+    match SearchJobService::submit(&org_id, &user_id, stream_type, req).await {
+        Ok(job) => Ok(HttpResponse::Ok().json(job)),
+        Err(e) => match e.downcast_ref::<TooManySearchJobs>() {
+            Some(e) => Ok(
+                HttpResponse::TooManyRequests().json(MetaHttpResponse::error(
+                    actix_web::http::StatusCode::TOO_MANY_REQUESTS.into(),
+                    e.to_string(),
+                )),
+            ),
+            None => Ok(MetaHttpResponse::internal_error(e)),
+        },
+    }
+}
+
+/// ListSearchJobs
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Search",
+    operation_id = "ListSearchJobs",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = SearchJobList),
+    )
+)]
+#[get("/{org_id}/search_jobs")]
+pub async fn list_jobs(
+    org_id: web::Path<String>,
+    in_req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let user_id = get_user_id(&in_req);
+    match SearchJobService::list(&org_id, &user_id).await {
+        Ok(list) => Ok(HttpResponse::Ok().json(SearchJobList { list })),
+        Err(e) => Ok(MetaHttpResponse::internal_error(e)),
+    }
+}
+
+/// GetSearchJob
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Search",
+    operation_id = "GetSearchJob",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("job_id" = String, Path, description = "Search job id"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = SearchJob),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/search_jobs/{job_id}")]
+pub async fn get_job(
+    path: web::Path<(String, String)>,
+    in_req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let (org_id, job_id) = path.into_inner();
+    match get_user_job(&org_id, &job_id, &in_req).await {
+        Ok(job) => Ok(HttpResponse::Ok().json(job)),
+        Err(resp) => Ok(resp),
+    }
+}
+
+/// GetSearchJobResults
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Search",
+    operation_id = "GetSearchJobResults",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("job_id" = String, Path, description = "Search job id"),
+        ("offset" = Option<usize>, Query, description = "Index of the first result row"),
+        ("size" = Option<usize>, Query, description = "Number of result rows"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = SearchJobResults),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/search_jobs/{job_id}/results")]
+pub async fn get_job_results(
+    path: web::Path<(String, String)>,
+    in_req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let (org_id, job_id) = path.into_inner();
+    let job = match get_user_job(&org_id, &job_id, &in_req).await {
+        Ok(job) => job,
+        Err(resp) => return Ok(resp),
+    };
+    let cfg = get_config();
+    let query = web::Query::<HashMap<String, String>>::from_query(in_req.query_string()).unwrap();
+    let offset = query
+        .get("offset")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or_default();
+    // at most a part is read by page
+    let size = query
+        .get("size")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(cfg.limit.query_default_limit as usize)
+        .min(cfg.limit.search_job_part_size as usize);
+    match SearchJobService::results(&job, offset, size).await {
+        Ok(results) => Ok(HttpResponse::Ok().json(results)),
+        Err(e) => Ok(MetaHttpResponse::internal_error(e)),
+    }
+}
+
+/// CancelSearchJob
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Search",
+    operation_id = "CancelSearchJob",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("job_id" = String, Path, description = "Search job id"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = SearchJob),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/search_jobs/{job_id}/cancel")]
+pub async fn cancel_job(
+    path: web::Path<(String, String)>,
+    in_req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let (org_id, job_id) = path.into_inner();
+    let job = match get_user_job(&org_id, &job_id, &in_req).await {
+        Ok(job) => job,
+        Err(resp) => return Ok(resp),
+    };
+    match SearchJobService::cancel(job).await {
+        Ok(job) => Ok(HttpResponse::Ok().json(job)),
+        Err(e) => Ok(MetaHttpResponse::internal_error(e)),
+    }
+}
+
+/// DeleteSearchJob
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Search",
+    operation_id = "DeleteSearchJob",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("job_id" = String, Path, description = "Search job id"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = HttpResponse),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[delete("/{org_id}/search_jobs/{job_id}")]
+pub async fn delete_job(
+    path: web::Path<(String, String)>,
+    in_req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let (org_id, job_id) = path.into_inner();
+    let job = match get_user_job(&org_id, &job_id, &in_req).await {
+        Ok(job) => job,
+        Err(resp) => return Ok(resp),
+    };
+    match SearchJobService::delete(job).await {
+        Ok(_) => Ok(MetaHttpResponse::ok("Search job deleted")),
+        Err(e) => Ok(MetaHttpResponse::internal_error(e)),
+    }
+}
+
+fn get_user_id(in_req: &HttpRequest) -> String {
+    in_req
+        .headers()
+        .get("user_id")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string()
+}
+
+/// The jobs are only visible to the users who submitted them.
+async fn get_user_job(
+    org_id: &str,
+    job_id: &str,
+    in_req: &HttpRequest,
+) -> Result<SearchJob, HttpResponse> {
+    match SearchJobService::get(org_id, &get_user_id(in_req), job_id).await {
+        Ok(Some(job)) => Ok(job),
+        Ok(None) => Err(MetaHttpResponse::not_found("Search job not found")),
+        Err(e) => Err(MetaHttpResponse::internal_error(e)),
+    }
+}
@@ -13,7 +13,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{collections::HashMap, io::Error};
+use std::{collections::HashMap, io::Error, str::FromStr};
 
 use actix_web::{get, http::StatusCode, post, web, HttpRequest, HttpResponse};
 use arrow_schema::Schema;
@@ -46,14 +46,16 @@ use crate::{
         },
     },
     service::{
-        search as SearchService,
+        search::{self as SearchService, stream::StreamFormat},
         self_reporting::{http_report_metrics, report_request_usage_stats},
     },
 };
 
+pub mod export;
 pub mod job;
 pub mod multi_streams;
 pub mod saved_view;
+pub mod tail;
 
 /// SearchStreamData
 #[utoipa::path(
@@ -65,6 +67,8 @@ pub mod saved_view;
     ),
     params(
         ("org_id" = String, Path, description = "Organization name"),
+        ("format" = Option<String>, Query, description = "Streams the rows as `ndjson` or as an `arrow` IPC stream instead of returning a search response, all the rows are returned when `size` isn't set"),
+        ("index" = Option<String>, Query, description = "Stream searched by an Elasticsearch query DSL body"),
     ),
     request_body(content = SearchRequest, description = "Search query", content_type = "application/json", example = json!({
         "query": {
@@ -140,7 +144,21 @@ pub async fn search(
     // handle encoding for query and aggs
     let mut req: config::meta::search::Request = match json::from_slice(&body) {
         Ok(v) => v,
-        Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+        Err(e) => {
+            // Elasticsearch clients send their query DSL to the same endpoint
+            if !SearchService::es::is_dsl_request(&body) {
+                return Ok(MetaHttpResponse::bad_request(e));
+            }
+            let Some(index) = query.get("index") else {
+                return Ok(MetaHttpResponse::bad_request(
+                    "The query DSL requires the stream to search, use /api/{org_id}/{stream}/_search or the index parameter",
+                ));
+            };
+            return crate::handler::http::request::organization::es::search(
+                &org_id, index, &in_req, &body,
+            )
+            .await;
+        }
     };
     if let Err(e) = req.decode() {
         return Ok(MetaHttpResponse::bad_request(e));
@@ -233,6 +251,43 @@ pub async fn search(
         }
     }
 
+    // stream the rows instead of buffering the whole response
+    if let Some(format) = query.get("format") {
+        let format = match StreamFormat::from_str(format) {
+            Ok(v) => v,
+            Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+        };
+        if req.query.query_fn.is_some() {
+            return Ok(MetaHttpResponse::bad_request(
+                "Query functions aren't supported by the streamed responses",
+            ));
+        }
+        return match SearchService::stream::search(
+            &trace_id,
+            &org_id,
+            stream_type,
+            Some(user_id),
+            &req,
+        )
+        .instrument(http_span)
+        .await
+        {
+            Ok(rx) => Ok(HttpResponse::Ok()
+                .content_type(format.content_type())
+                .streaming(SearchService::stream::encode(rx, format))),
+            Err(err) => {
+                http_report_metrics(start, &org_id, stream_type, "", "500", "_search");
+                log::error!("[trace_id {trace_id}] search error: {}", err);
+                Ok(match err {
+                    errors::Error::ErrorCode(code) => HttpResponse::InternalServerError().json(
+                        meta::http::HttpResponse::error_code_with_trace_id(code, Some(trace_id)),
+                    ),
+                    _ => MetaHttpResponse::internal_error(err),
+                })
+            }
+        };
+    }
+
     // run search with cache
     let res = SearchService::cache::search(
         &trace_id,
@@ -0,0 +1,197 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{collections::HashMap, io::Error};
+
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use actix_ws::{CloseCode, CloseReason, Message, MessageStream, Session};
+use config::{get_config, meta::stream::StreamType, utils::json};
+use futures::StreamExt;
+use tokio::time::{self, Duration, MissedTickBehavior};
+
+use crate::{
+    common::{
+        meta::http::HttpResponse as MetaHttpResponse, utils::http::get_stream_type_from_request,
+    },
+    service::search::tail::{self as TailService, Tail, TailEvent},
+};
+
+/// TailStream
+///
+/// Pushes the records ingested into the stream, from now on, as JSON text messages of the
+/// websocket: `{"type": "hits", "hits": [...]}`, `{"type": "dropped", "count": 10}` when the rate
+/// limit is exceeded, or `{"type": "error", "message": "..."}`.
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Search",
+    operation_id = "TailStream",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("stream_name" = String, Path, description = "Stream name"),
+        ("type" = Option<String>, Query, description = "Stream type, logs by default"),
+        ("filter" = Option<String>, Query, description = "SQL condition of the records, e.g. `level = 'error' AND match_all('timeout')`"),
+    ),
+    responses(
+        (status = 101, description = "Switching to the websocket protocol"),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+        (status = 403, description = "Forbidden", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/{stream_name}/_tail")]
+pub async fn tail(
+    path: web::Path<(String, String)>,
+    in_req: HttpRequest,
+    body: web::Payload,
+) -> Result<HttpResponse, Error> {
+    let (org_id, stream_name) = path.into_inner();
+    let query = web::Query::<HashMap<String, String>>::from_query(in_req.query_string()).unwrap();
+    let stream_type = match get_stream_type_from_request(&query) {
+        Ok(v) => v.unwrap_or(StreamType::Logs),
+        Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+    };
+    let filter = query.get("filter").map(|v| v.as_str()).unwrap_or_default();
+    if let Err(e) = TailService::validate_filter(filter) {
+        return Ok(MetaHttpResponse::bad_request(e));
+    }
+
+    // Check permissions on stream
+    #[cfg(feature = "enterprise")]
+    {
+        use o2_enterprise::enterprise::openfga::meta::mapping::OFGA_MODELS;
+
+        use crate::common::{
+            infra::config::USERS,
+            meta,
+            utils::auth::{is_root_user, AuthExtractor},
+        };
+
+        let user_id = in_req
+            .headers()
+            .get("user_id")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        if !is_root_user(&user_id) {
+            let user: meta::user::User =
+                USERS.get(&format!("{org_id}/{}", user_id)).unwrap().clone();
+            let stream_type_str = stream_type.to_string();
+
+            if user.is_external
+                && !crate::handler::http::auth::validator::check_permissions(
+                    &user_id,
+                    AuthExtractor {
+                        auth: "".to_string(),
+                        method: "GET".to_string(),
+                        o2_type: format!(
+                            "{}:{}",
+                            OFGA_MODELS
+                                .get(stream_type_str.as_str())
+                                .map_or(stream_type_str.as_str(), |model| model.key),
+                            stream_name
+                        ),
+                        org_id: org_id.clone(),
+                        bypass_check: false,
+                        parent_id: "".to_string(),
+                    },
+                    Some(user.role),
+                )
+                .await
+            {
+                return Ok(MetaHttpResponse::forbidden("Unauthorized Access"));
+            }
+        }
+        // Check permissions on stream ends
+    }
+
+    let (resp, session, msg_stream) = match actix_ws::handle(&in_req, body) {
+        Ok(v) => v,
+        Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+    };
+    let tail = Tail::new(&org_id, stream_type, &stream_name, filter);
+    actix_web::rt::spawn(run(tail, session, msg_stream));
+    Ok(resp)
+}
+
+async fn run(mut tail: Tail, mut session: Session, mut msg_stream: MessageStream) {
+    let cfg = get_config();
+    let mut interval = time::interval(Duration::from_millis(cfg.limit.tail_poll_interval));
+    // the ingesters aren't polled again before the previous records are sent
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let reason = loop {
+        tokio::select! {
+            msg = msg_stream.next() => match msg {
+                Some(Ok(Message::Ping(bytes))) => {
+                    if session.pong(&bytes).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Ok(Message::Close(reason))) => break reason,
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    log::error!("[TAIL] Error while reading websocket message: {}", e);
+                    break None;
+                }
+                None => break None,
+            },
+            _ = interval.tick() => {
+                if let Err(reason) = push(&mut tail, &mut session).await {
+                    break reason;
+                }
+            }
+        }
+    };
+    let _ = session.close(reason).await;
+}
+
+/// Sends the new records, the connection is closed when the client doesn't read them within
+/// `ZO_TAIL_SEND_TIMEOUT` seconds.
+async fn push(tail: &mut Tail, session: &mut Session) -> Result<(), Option<CloseReason>> {
+    let events = match tail.poll().await {
+        Ok((hits, dropped)) => {
+            let mut events = Vec::with_capacity(2);
+            if !hits.is_empty() {
+                events.push(TailEvent::Hits { hits });
+            }
+            if dropped > 0 {
+                events.push(TailEvent::Dropped { count: dropped });
+            }
+            events
+        }
+        Err(e) => vec![TailEvent::Error {
+            message: e.to_string(),
+        }],
+    };
+
+    let timeout = Duration::from_secs(get_config().limit.tail_send_timeout);
+    for event in events {
+        let text = json::to_string(&event).unwrap();
+        match time::timeout(timeout, session.text(text)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(_)) => return Err(None), // closed by the client
+            Err(_) => {
+                return Err(Some(CloseReason {
+                    code: CloseCode::Policy,
+                    description: Some(
+                        "The client doesn't read the records fast enough".to_string(),
+                    ),
+                }));
+            }
+        }
+    }
+    Ok(())
+}
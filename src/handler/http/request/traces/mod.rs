@@ -62,6 +62,88 @@ pub async fn otlp_traces_write(
     handle_req(org_id, req, body).await
 }
 
+/// ZipkinSpansIngest
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Traces",
+    operation_id = "PostZipkinSpans",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+    ),
+    request_body(content = String, description = "Zipkin v2 json spans, or v1 json spans with binary annotations", content_type = "application/json"),
+    responses(
+        (status = 202, description = "Success"),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+        (status = 500, description = "Failure", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/zipkin/api/v2/spans")]
+pub async fn zipkin_spans_write(
+    org_id: web::Path<String>,
+    req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let org_id = org_id.into_inner();
+    let in_stream_name = req
+        .headers()
+        .get(&get_config().grpc.stream_header_key)
+        .and_then(|header| header.to_str().ok());
+    traces::zipkin::ingest(&org_id, body, in_stream_name).await
+}
+
+/// JaegerTracesIngest
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Traces",
+    operation_id = "PostJaegerTraces",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+    ),
+    request_body(content = String, description = "Thrift binary encoded jaeger batch (application/x-thrift), or jaeger json traces (application/json)", content_type = "application/x-thrift"),
+    responses(
+        (status = 202, description = "Success"),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+        (status = 500, description = "Failure", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/jaeger/api/traces")]
+pub async fn jaeger_traces_write(
+    org_id: web::Path<String>,
+    req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let org_id = org_id.into_inner();
+    let content_type = req
+        .headers()
+        .get("Content-Type")
+        .and_then(|header| header.to_str().ok())
+        .unwrap_or_default();
+    let in_stream_name = req
+        .headers()
+        .get(&get_config().grpc.stream_header_key)
+        .and_then(|header| header.to_str().ok());
+    if content_type.starts_with(CONTENT_TYPE_JSON) {
+        traces::jaeger::ingest(&org_id, body, false, in_stream_name).await
+    } else if content_type.starts_with("application/x-thrift")
+        || content_type.starts_with("application/vnd.apache.thrift.binary")
+    {
+        traces::jaeger::ingest(&org_id, body, true, in_stream_name).await
+    } else {
+        Ok(
+            HttpResponse::BadRequest().json(meta::http::HttpResponse::error(
+                http::StatusCode::BAD_REQUEST.into(),
+                "Bad Request".to_string(),
+            )),
+        )
+    }
+}
+
 async fn handle_req(
     org_id: web::Path<String>,
     req: HttpRequest,
@@ -504,6 +586,361 @@ pub async fn get_latest_traces(
     Ok(HttpResponse::Ok().json(resp))
 }
 
+/// GetServiceGraph
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Traces",
+    operation_id = "GetServiceGraph",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("stream_name" = String, Path, description = "Stream name"),
+        ("start_time" = Option<i64>, Query, description = "start time, default is one hour ago"),
+        ("end_time" = Option<i64>, Query, description = "end time, default is now"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = ServiceGraph),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+        (status = 500, description = "Failure", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/{stream_name}/traces/service_graph")]
+pub async fn get_service_graph(
+    path: web::Path<(String, String)>,
+    in_req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let start = std::time::Instant::now();
+    let cfg = get_config();
+
+    let (org_id, stream_name) = path.into_inner();
+    let http_span = if cfg.common.tracing_search_enabled {
+        tracing::info_span!(
+            "/api/{org_id}/{stream_name}/traces/service_graph",
+            org_id = org_id.clone(),
+            stream_name = stream_name.clone()
+        )
+    } else {
+        Span::none()
+    };
+    let trace_id = get_or_create_trace_id(in_req.headers(), &http_span);
+
+    if !cfg.common.traces_service_graph_enabled {
+        return Ok(MetaHttpResponse::bad_request(
+            "Service graph is not enabled, set ZO_TRACES_SERVICE_GRAPH_ENABLED=true",
+        ));
+    }
+    // stream_name is used in the sql directly
+    if stream_name.contains('\'') {
+        return Ok(MetaHttpResponse::bad_request("Invalid stream_name"));
+    }
+
+    let query = web::Query::<HashMap<String, String>>::from_query(in_req.query_string()).unwrap();
+
+    // Check permissions on stream
+
+    #[cfg(feature = "enterprise")]
+    {
+        use o2_enterprise::enterprise::openfga::meta::mapping::OFGA_MODELS;
+
+        use crate::common::{
+            infra::config::USERS,
+            utils::auth::{is_root_user, AuthExtractor},
+        };
+        let user_id = in_req.headers().get("user_id").unwrap();
+        if !is_root_user(user_id.to_str().unwrap()) {
+            let user: meta::user::User = USERS
+                .get(&format!("{org_id}/{}", user_id.to_str().unwrap()))
+                .unwrap()
+                .clone();
+            let stream_type_str = StreamType::Traces.to_string();
+
+            if user.is_external
+                && !crate::handler::http::auth::validator::check_permissions(
+                    user_id.to_str().unwrap(),
+                    AuthExtractor {
+                        auth: "".to_string(),
+                        method: "GET".to_string(),
+                        o2_type: format!(
+                            "{}:{}",
+                            OFGA_MODELS
+                                .get(stream_type_str.as_str())
+                                .map_or(stream_type_str.as_str(), |model| model.key),
+                            stream_name
+                        ),
+                        org_id: org_id.clone(),
+                        bypass_check: false,
+                        parent_id: "".to_string(),
+                    },
+                    Some(user.role),
+                )
+                .await
+            {
+                return Ok(MetaHttpResponse::forbidden("Unauthorized Access"));
+            }
+        }
+        // Check permissions on stream ends
+    }
+
+    let end_time = query
+        .get("end_time")
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or_else(|| chrono::Utc::now().timestamp_micros());
+    let start_time = query
+        .get("start_time")
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or_else(|| {
+            end_time
+                - chrono::Duration::try_hours(1)
+                    .unwrap()
+                    .num_microseconds()
+                    .unwrap()
+        });
+    if start_time >= end_time {
+        return Ok(MetaHttpResponse::bad_request(
+            "start_time should be less than end_time",
+        ));
+    }
+
+    let stream_type = StreamType::Traces;
+    let res = crate::service::metadata::service_graph::get_service_graph(
+        &trace_id,
+        &org_id,
+        &stream_name,
+        (start_time, end_time),
+    )
+    .instrument(http_span)
+    .await;
+
+    let (status, resp) = match res {
+        Ok(graph) => ("200", HttpResponse::Ok().json(graph)),
+        Err(err) => {
+            log::error!("get service graph of {stream_name} error: {:?}", err);
+            (
+                "500",
+                match err {
+                    errors::Error::ErrorCode(code) => HttpResponse::InternalServerError()
+                        .json(meta::http::HttpResponse::error_code(code)),
+                    _ => HttpResponse::InternalServerError().json(meta::http::HttpResponse::error(
+                        http::StatusCode::INTERNAL_SERVER_ERROR.into(),
+                        err.to_string(),
+                    )),
+                },
+            )
+        }
+    };
+
+    let time = start.elapsed().as_secs_f64();
+    metrics::HTTP_RESPONSE_TIME
+        .with_label_values(&[
+            "/api/org/traces/service_graph",
+            status,
+            &org_id,
+            &stream_name,
+            stream_type.to_string().as_str(),
+        ])
+        .observe(time);
+    metrics::HTTP_INCOMING_REQUESTS
+        .with_label_values(&[
+            "/api/org/traces/service_graph",
+            status,
+            &org_id,
+            &stream_name,
+            stream_type.to_string().as_str(),
+        ])
+        .inc();
+
+    Ok(resp)
+}
+
+/// GetTrace
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Traces",
+    operation_id = "GetTrace",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("stream_name" = String, Path, description = "Stream name"),
+        ("trace_id" = String, Path, description = "Trace ID"),
+        ("start_time" = Option<i64>, Query, description = "start time, default is the data retention days ago"),
+        ("end_time" = Option<i64>, Query, description = "end time, default is now"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = TraceTree),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+        (status = 500, description = "Failure", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/{stream_name}/traces/{trace_id}")]
+pub async fn get_trace(
+    path: web::Path<(String, String, String)>,
+    in_req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let start = std::time::Instant::now();
+    let cfg = get_config();
+
+    let (org_id, stream_name, trace_id) = path.into_inner();
+    let http_span = if cfg.common.tracing_search_enabled {
+        tracing::info_span!(
+            "/api/{org_id}/{stream_name}/traces/{trace_id}",
+            org_id = org_id.clone(),
+            stream_name = stream_name.clone()
+        )
+    } else {
+        Span::none()
+    };
+    let search_trace_id = get_or_create_trace_id(in_req.headers(), &http_span);
+
+    // trace_id is used in the sql directly
+    if trace_id.is_empty() || !trace_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Ok(MetaHttpResponse::bad_request("Invalid trace_id"));
+    }
+    // stream_name is used in the sql directly
+    if stream_name.contains('\'') || stream_name.contains('"') {
+        return Ok(MetaHttpResponse::bad_request("Invalid stream_name"));
+    }
+
+    let query = web::Query::<HashMap<String, String>>::from_query(in_req.query_string()).unwrap();
+
+    // Check permissions on stream
+
+    #[cfg(feature = "enterprise")]
+    {
+        use o2_enterprise::enterprise::openfga::meta::mapping::OFGA_MODELS;
+
+        use crate::common::{
+            infra::config::USERS,
+            utils::auth::{is_root_user, AuthExtractor},
+        };
+        let user_id = in_req.headers().get("user_id").unwrap();
+        if !is_root_user(user_id.to_str().unwrap()) {
+            let user: meta::user::User = USERS
+                .get(&format!("{org_id}/{}", user_id.to_str().unwrap()))
+                .unwrap()
+                .clone();
+            let stream_type_str = StreamType::Traces.to_string();
+
+            if user.is_external
+                && !crate::handler::http::auth::validator::check_permissions(
+                    user_id.to_str().unwrap(),
+                    AuthExtractor {
+                        auth: "".to_string(),
+                        method: "GET".to_string(),
+                        o2_type: format!(
+                            "{}:{}",
+                            OFGA_MODELS
+                                .get(stream_type_str.as_str())
+                                .map_or(stream_type_str.as_str(), |model| model.key),
+                            stream_name
+                        ),
+                        org_id: org_id.clone(),
+                        bypass_check: false,
+                        parent_id: "".to_string(),
+                    },
+                    Some(user.role),
+                )
+                .await
+            {
+                return Ok(MetaHttpResponse::forbidden("Unauthorized Access"));
+            }
+        }
+        // Check permissions on stream ends
+    }
+
+    let now = chrono::Utc::now().timestamp_micros();
+    let start_time = query
+        .get("start_time")
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or_else(|| {
+            now - chrono::Duration::try_days(cfg.compact.data_retention_days)
+                .unwrap()
+                .num_microseconds()
+                .unwrap()
+        });
+    let end_time = query
+        .get("end_time")
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(now);
+    if start_time >= end_time {
+        return Ok(MetaHttpResponse::bad_request(
+            "start_time should be less than end_time",
+        ));
+    }
+
+    let stream_type = StreamType::Traces;
+    let user_id = in_req
+        .headers()
+        .get("user_id")
+        .unwrap()
+        .to_str()
+        .ok()
+        .map(|v| v.to_string());
+
+    let res = traces::tree::get_trace(
+        &search_trace_id,
+        &org_id,
+        &stream_name,
+        &trace_id,
+        user_id,
+        (start_time, end_time),
+    )
+    .instrument(http_span)
+    .await;
+
+    let (status, resp) = match res {
+        Ok(Some(tree)) => ("200", HttpResponse::Ok().json(tree)),
+        Ok(None) => (
+            "404",
+            MetaHttpResponse::not_found(format!("Trace {trace_id} not found")),
+        ),
+        Err(err) => {
+            log::error!("get trace {trace_id} error: {:?}", err);
+            (
+                "500",
+                match err {
+                    errors::Error::ErrorCode(code) => match code {
+                        errors::ErrorCodes::SearchCancelQuery(_) => HttpResponse::TooManyRequests()
+                            .json(meta::http::HttpResponse::error_code(code)),
+                        _ => HttpResponse::InternalServerError()
+                            .json(meta::http::HttpResponse::error_code(code)),
+                    },
+                    _ => HttpResponse::InternalServerError().json(meta::http::HttpResponse::error(
+                        http::StatusCode::INTERNAL_SERVER_ERROR.into(),
+                        err.to_string(),
+                    )),
+                },
+            )
+        }
+    };
+
+    let time = start.elapsed().as_secs_f64();
+    metrics::HTTP_RESPONSE_TIME
+        .with_label_values(&[
+            "/api/org/traces/trace_id",
+            status,
+            &org_id,
+            &stream_name,
+            stream_type.to_string().as_str(),
+        ])
+        .observe(time);
+    metrics::HTTP_INCOMING_REQUESTS
+        .with_label_values(&[
+            "/api/org/traces/trace_id",
+            status,
+            &org_id,
+            &stream_name,
+            stream_type.to_string().as_str(),
+        ])
+        .inc();
+
+    Ok(resp)
+}
+
 #[derive(Debug, Serialize)]
 struct TraceResponseItem {
     trace_id: String,
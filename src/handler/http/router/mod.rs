@@ -344,6 +344,9 @@ pub fn get_service_routes(cfg: &mut web::ServiceConfig) {
             .service(organization::settings::delete_logo)
             .service(organization::settings::set_logo_text)
             .service(organization::settings::delete_logo_text)
+            .service(organization::quota::get_quota)
+            .service(organization::quota::set_quota)
+            .service(organization::quota::delete_quota)
             .service(organization::org::org_summary)
             .service(organization::org::get_user_passcode)
             .service(organization::org::update_user_passcode)
@@ -360,6 +363,7 @@ pub fn get_service_routes(cfg: &mut web::ServiceConfig) {
             .service(organization::es::org_data_stream_create)
             .service(organization::es::org_pipeline)
             .service(organization::es::org_pipeline_create)
+            .service(organization::es::org_search)
             .service(stream::schema)
             .service(stream::settings)
             .service(stream::update_settings)
@@ -370,10 +374,22 @@ pub fn get_service_routes(cfg: &mut web::ServiceConfig) {
             .service(logs::ingest::multi)
             .service(logs::ingest::json)
             .service(logs::ingest::otlp_logs_write)
+            .service(logs::ingest::loki_push)
+            .service(logs::ingest::hec_event)
+            .service(logs::ingest::hec_raw)
             .service(traces::traces_write)
             .service(traces::otlp_traces_write)
+            .service(traces::zipkin_spans_write)
+            .service(traces::jaeger_traces_write)
+            .service(rum::sourcemaps::upload)
+            .service(rum::sourcemaps::list)
+            .service(rum::sourcemaps::delete)
             .service(traces::get_latest_traces)
+            .service(traces::get_service_graph)
+            .service(traces::get_trace)
             .service(metrics::ingest::json)
+            .service(metrics::ingest::influxdb_v2_write)
+            .service(metrics::ingest::influxdb_v1_write)
             .service(metrics::ingest::otlp_metrics_write)
             .service(prom::remote_write)
             .service(prom::query_get)
@@ -393,8 +409,16 @@ pub fn get_service_routes(cfg: &mut web::ServiceConfig) {
             .service(search::job::cancel_multiple_query)
             .service(search::job::cancel_query)
             .service(search::job::query_status)
+            .service(search::job::submit_job)
+            .service(search::job::list_jobs)
+            .service(search::job::get_job)
+            .service(search::job::get_job_results)
+            .service(search::job::cancel_job)
+            .service(search::job::delete_job)
             .service(search::search_partition)
             .service(search::around)
+            .service(search::tail::tail)
+            .service(search::export::export)
             .service(search::values)
             .service(search::search_history)
             .service(search::saved_view::create_view)
@@ -433,6 +457,8 @@ pub fn get_service_routes(cfg: &mut web::ServiceConfig) {
             .service(alerts::alert::delete_alert)
             .service(alerts::alert::enable_alert)
             .service(alerts::alert::trigger_alert)
+            .service(alerts::alert::get_alert_history)
+            .service(alerts::alert::import_prometheus_alerts)
             .service(alerts::templates::save_template)
             .service(alerts::templates::update_template)
             .service(alerts::templates::get_template)
@@ -443,6 +469,22 @@ pub fn get_service_routes(cfg: &mut web::ServiceConfig) {
             .service(alerts::destinations::get_destination)
             .service(alerts::destinations::list_destinations)
             .service(alerts::destinations::delete_destination)
+            .service(alerts::inhibitions::save_inhibition_rule)
+            .service(alerts::inhibitions::update_inhibition_rule)
+            .service(alerts::inhibitions::get_inhibition_rule)
+            .service(alerts::inhibitions::list_inhibition_rules)
+            .service(alerts::inhibitions::delete_inhibition_rule)
+            .service(alerts::silences::create_silence)
+            .service(alerts::silences::get_silence)
+            .service(alerts::silences::list_silences)
+            .service(alerts::silences::expire_silence)
+            .service(alerts::silences::delete_silence)
+            .service(alerts::recording_rules::import_recording_rules)
+            .service(alerts::recording_rules::save_recording_rule)
+            .service(alerts::recording_rules::update_recording_rule)
+            .service(alerts::recording_rules::get_recording_rule)
+            .service(alerts::recording_rules::list_recording_rules)
+            .service(alerts::recording_rules::delete_recording_rule)
             .service(kv::get)
             .service(kv::set)
             .service(kv::delete)
@@ -458,6 +500,8 @@ pub fn get_service_routes(cfg: &mut web::ServiceConfig) {
             .service(traces::otlp_traces_write)
             .service(dashboards::move_dashboard)
             .service(traces::get_latest_traces)
+            .service(traces::get_service_graph)
+            .service(traces::get_trace)
             .service(logs::ingest::multi)
             .service(logs::ingest::json)
             .service(logs::ingest::handle_kinesis_request)
@@ -510,6 +554,13 @@ pub fn get_other_service_routes(cfg: &mut web::ServiceConfig) {
             .service(logs::ingest::handle_gcp_request),
     );
 
+    // the download links of the exports are checked by the handler
+    cfg.service(
+        web::scope("/exports")
+            .wrap(cors.clone())
+            .service(search::export::download),
+    );
+
     // NOTE: Here the order of middlewares matter. Once we consume the api-token in
     // `rum_auth`, we drop it in the RumExtraData data.
     // https://docs.rs/actix-web/latest/actix_web/middleware/index.html#ordering
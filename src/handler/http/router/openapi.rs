@@ -36,6 +36,9 @@ use crate::{common::meta, handler::http::request};
         request::organization::org::create_user_rumtoken,
         request::organization::settings::get,
         request::organization::settings::create,
+        request::organization::quota::get_quota,
+        request::organization::quota::set_quota,
+        request::organization::quota::delete_quota,
         request::stream::list,
         request::stream::schema,
         request::stream::settings,
@@ -45,9 +48,18 @@ use crate::{common::meta, handler::http::request};
         request::logs::ingest::bulk,
         request::logs::ingest::multi,
         request::logs::ingest::json,
+        request::logs::ingest::loki_push,
+        request::logs::ingest::hec_event,
+        request::logs::ingest::hec_raw,
         request::traces::traces_write,
+        request::traces::zipkin_spans_write,
+        request::traces::jaeger_traces_write,
         request::traces::get_latest_traces,
+        request::traces::get_service_graph,
+        request::traces::get_trace,
         request::metrics::ingest::json,
+        request::metrics::ingest::influxdb_v2_write,
+        request::metrics::ingest::influxdb_v1_write,
         request::prom::remote_write,
         request::prom::query_get,
         request::prom::query_range_get,
@@ -60,11 +72,23 @@ use crate::{common::meta, handler::http::request};
         request::rum::ingest::log,
         request::rum::ingest::data,
         request::rum::ingest::sessionreplay,
+        request::rum::sourcemaps::upload,
+        request::rum::sourcemaps::list,
+        request::rum::sourcemaps::delete,
         request::search::search,
         request::search::search_partition,
         request::search::around,
+        request::search::tail::tail,
+        request::search::export::export,
+        request::search::export::download,
         request::search::values,
         request::search::search_history,
+        request::search::job::submit_job,
+        request::search::job::list_jobs,
+        request::search::job::get_job,
+        request::search::job::get_job_results,
+        request::search::job::cancel_job,
+        request::search::job::delete_job,
         request::search::saved_view::create_view,
         request::search::saved_view::delete_view,
         request::search::saved_view::get_view,
@@ -94,6 +118,8 @@ use crate::{common::meta, handler::http::request};
         request::alerts::alert::delete_alert,
         request::alerts::alert::enable_alert,
         request::alerts::alert::trigger_alert,
+        request::alerts::alert::get_alert_history,
+        request::alerts::alert::import_prometheus_alerts,
         request::alerts::templates::list_templates,
         request::alerts::templates::get_template,
         request::alerts::templates::save_template,
@@ -104,6 +130,22 @@ use crate::{common::meta, handler::http::request};
         request::alerts::destinations::save_destination,
         request::alerts::destinations::update_destination,
         request::alerts::destinations::delete_destination,
+        request::alerts::inhibitions::list_inhibition_rules,
+        request::alerts::inhibitions::get_inhibition_rule,
+        request::alerts::inhibitions::save_inhibition_rule,
+        request::alerts::inhibitions::update_inhibition_rule,
+        request::alerts::inhibitions::delete_inhibition_rule,
+        request::alerts::silences::list_silences,
+        request::alerts::silences::get_silence,
+        request::alerts::silences::create_silence,
+        request::alerts::silences::expire_silence,
+        request::alerts::silences::delete_silence,
+        request::alerts::recording_rules::list_recording_rules,
+        request::alerts::recording_rules::get_recording_rule,
+        request::alerts::recording_rules::save_recording_rule,
+        request::alerts::recording_rules::update_recording_rule,
+        request::alerts::recording_rules::delete_recording_rule,
+        request::alerts::recording_rules::import_recording_rules,
         request::kv::get,
         request::kv::set,
         request::kv::delete,
@@ -148,6 +190,8 @@ use crate::{common::meta, handler::http::request};
             config::meta::dashboards::v1::VariableList,
             config::meta::dashboards::MoveDashboard,
             config::meta::alerts::alert::Alert,
+            config::meta::alerts::prom_rules::PromAlertImportResult,
+            config::meta::alerts::prom_rules::PromRuleSkipped,
             config::meta::alerts::Aggregation,
             config::meta::alerts::AggFunction,
             config::meta::alerts::Condition,
@@ -156,12 +200,19 @@ use crate::{common::meta, handler::http::request};
             config::meta::alerts::destinations::DestinationWithTemplate,
             config::meta::alerts::destinations::HTTPType,
             config::meta::alerts::destinations::DestinationType,
+            config::meta::alerts::grouping::AlertGrouping,
+            config::meta::alerts::history::AlertHistoryEntry,
+            config::meta::alerts::history::AlertStatus,
+            config::meta::alerts::inhibitions::InhibitionRule,
+            config::meta::alerts::silences::Silence,
             config::meta::alerts::FrequencyType,
             config::meta::alerts::Operator,
             config::meta::alerts::QueryType,
             config::meta::alerts::QueryCondition,
             config::meta::alerts::TriggerCondition,
             config::meta::alerts::templates::Template,
+            config::meta::alerts::recording_rules::RecordingRuleGroup,
+            config::meta::alerts::recording_rules::RecordingRule,
             config::meta::folder::Folder,
             config::meta::folder::FolderList,
             config::meta::function::Transform,
@@ -214,15 +265,32 @@ use crate::{common::meta, handler::http::request};
             meta::organization::OrganizationSettingResponse,
             meta::organization::RumIngestionResponse,
             meta::organization::RumIngestionToken,
+            meta::sourcemap::SourceMapFile,
+            meta::sourcemap::SourceMapList,
+            config::meta::search_job::SearchJob,
+            config::meta::search_job::SearchJobStatus,
+            config::meta::search_job::SearchJobList,
+            config::meta::search_job::SearchJobResults,
+            crate::service::search::export::ExportLink,
+            config::meta::quota::IngestionQuota,
+            config::meta::quota::QuotaLimits,
+            config::meta::quota::StreamQuota,
             request::status::HealthzResponse,
             meta::ingestion::BulkResponse,
             meta::ingestion::BulkResponseItem,
             meta::ingestion::ShardResponse,
             meta::ingestion::BulkResponseError,
+            meta::ingestion::HecResponse,
             meta::syslog::SyslogRoute,
             meta::syslog::SyslogRoutes,
             meta::prom::Metadata,
             meta::prom::MetricType,
+            meta::traces::TraceTree,
+            meta::traces::TraceSpanNode,
+            meta::traces::TraceServiceDuration,
+            meta::traces::ServiceGraph,
+            meta::traces::ServiceGraphNode,
+            meta::traces::ServiceGraphEdge,
          ),
     ),
     modifiers(&SecurityAddon),
@@ -0,0 +1,245 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Framing of the syslog messages received over TCP and TLS (RFC 6587): each message is
+//! either prefixed by its length in bytes (octet-counting), or followed by a line feed
+//! (non-transparent framing). Both methods can be mixed on the same connection.
+
+use bytes::{Buf, Bytes, BytesMut};
+
+/// The longest MSG-LEN prefix accepted, more digits can't be a valid octet count.
+const MAX_OCTET_COUNT_DIGITS: usize = 10;
+
+#[derive(Debug)]
+pub struct SyslogFramer {
+    max_size: usize,
+    /// Bytes of a truncated octet-counted message still to be dropped
+    discard: usize,
+    /// A truncated non-transparent message is dropped up to its line feed
+    discard_line: bool,
+}
+
+impl SyslogFramer {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            discard: 0,
+            discard_line: false,
+        }
+    }
+
+    /// Returns the next complete message of the buffer, or `None` when more data is needed.
+    /// Messages longer than the max size are truncated.
+    pub fn decode(&mut self, buf: &mut BytesMut) -> Option<Bytes> {
+        loop {
+            if self.discard > 0 {
+                let n = std::cmp::min(self.discard, buf.len());
+                buf.advance(n);
+                self.discard -= n;
+                if self.discard > 0 {
+                    return None;
+                }
+            }
+            if self.discard_line {
+                match buf.iter().position(|b| is_trailer(*b)) {
+                    Some(pos) => {
+                        buf.advance(pos + 1);
+                        self.discard_line = false;
+                    }
+                    None => {
+                        buf.clear();
+                        return None;
+                    }
+                }
+            }
+            // skip the empty lines between the messages
+            let skip = buf
+                .iter()
+                .take_while(|b| is_trailer(**b) || **b == b'\r')
+                .count();
+            buf.advance(skip);
+            if buf.is_empty() {
+                return None;
+            }
+
+            if buf[0].is_ascii_digit() {
+                match octet_count(buf) {
+                    OctetCount::Incomplete => return None,
+                    OctetCount::Length(prefix_len, len) => {
+                        let truncated = std::cmp::min(len, self.max_size);
+                        if buf.len() < prefix_len + truncated {
+                            return None;
+                        }
+                        if truncated < len {
+                            log::warn!(
+                                "syslog message of {len} bytes truncated to {truncated} bytes"
+                            );
+                            self.discard = len - truncated;
+                        }
+                        buf.advance(prefix_len);
+                        let msg = buf.split_to(truncated).freeze();
+                        if msg.is_empty() {
+                            continue;
+                        }
+                        return Some(msg);
+                    }
+                    OctetCount::None => {}
+                }
+            }
+
+            return match buf.iter().position(|b| is_trailer(*b)) {
+                Some(pos) if pos <= self.max_size => {
+                    let msg = buf.split_to(pos);
+                    buf.advance(1);
+                    Some(trim_cr(msg.freeze()))
+                }
+                _ if buf.len() > self.max_size => {
+                    log::warn!(
+                        "syslog message longer than {} bytes truncated",
+                        self.max_size
+                    );
+                    self.discard_line = true;
+                    Some(buf.split_to(self.max_size).freeze())
+                }
+                _ => None,
+            };
+        }
+    }
+
+    /// Returns the last message when the connection is closed, a non-transparent message
+    /// may not be followed by a line feed.
+    pub fn decode_eof(&mut self, buf: &mut BytesMut) -> Option<Bytes> {
+        if let Some(msg) = self.decode(buf) {
+            return Some(msg);
+        }
+        if self.discard > 0 || self.discard_line || buf.is_empty() {
+            buf.clear();
+            return None;
+        }
+        let len = std::cmp::min(buf.len(), self.max_size);
+        let msg = buf.split_to(len).freeze();
+        buf.clear();
+        Some(trim_cr(msg))
+    }
+}
+
+enum OctetCount {
+    /// The length of the `MSG-LEN SP` prefix and of the message
+    Length(usize, usize),
+    Incomplete,
+    /// The message isn't octet-counted
+    None,
+}
+
+fn octet_count(buf: &[u8]) -> OctetCount {
+    for (i, b) in buf.iter().enumerate().take(MAX_OCTET_COUNT_DIGITS + 1) {
+        match b {
+            b'0'..=b'9' => continue,
+            b' ' => {
+                return match std::str::from_utf8(&buf[..i])
+                    .ok()
+                    .and_then(|v| v.parse::<usize>().ok())
+                {
+                    Some(len) => OctetCount::Length(i + 1, len),
+                    None => OctetCount::None,
+                };
+            }
+            _ => return OctetCount::None,
+        }
+    }
+    if buf.len() <= MAX_OCTET_COUNT_DIGITS {
+        OctetCount::Incomplete
+    } else {
+        OctetCount::None
+    }
+}
+
+/// Line feed is the trailer of the non-transparent framing, some senders use NUL.
+fn is_trailer(b: u8) -> bool {
+    b == b'\n' || b == 0
+}
+
+fn trim_cr(msg: Bytes) -> Bytes {
+    if msg.ends_with(b"\r") {
+        msg.slice(..msg.len() - 1)
+    } else {
+        msg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_all(framer: &mut SyslogFramer, buf: &mut BytesMut) -> Vec<String> {
+        let mut msgs = vec![];
+        while let Some(msg) = framer.decode(buf) {
+            msgs.push(String::from_utf8(msg.to_vec()).unwrap());
+        }
+        msgs
+    }
+
+    #[test]
+    fn test_non_transparent_framing() {
+        let mut framer = SyslogFramer::new(1024);
+        let mut buf = BytesMut::from("<13>first\n<13>second\r\n<13>thi");
+        assert_eq!(
+            decode_all(&mut framer, &mut buf),
+            vec!["<13>first", "<13>second"]
+        );
+        buf.extend_from_slice(b"rd\n\n");
+        assert_eq!(decode_all(&mut framer, &mut buf), vec!["<13>third"]);
+        assert!(buf.is_empty());
+
+        buf.extend_from_slice(b"<13>no trailer");
+        assert!(framer.decode(&mut buf).is_none());
+        assert_eq!(
+            framer.decode_eof(&mut buf).unwrap(),
+            Bytes::from("<13>no trailer")
+        );
+    }
+
+    #[test]
+    fn test_octet_counting_framing() {
+        let mut framer = SyslogFramer::new(1024);
+        let mut buf = BytesMut::from("10 <13>a\nline9 <13>secon");
+        assert_eq!(decode_all(&mut framer, &mut buf), vec!["<13>a\nline"]);
+        buf.extend_from_slice(b"d<13>mixed\n");
+        assert_eq!(
+            decode_all(&mut framer, &mut buf),
+            vec!["<13>secon", "d<13>mixed"]
+        );
+
+        let mut buf = BytesMut::from("123");
+        assert!(framer.decode(&mut buf).is_none());
+        assert_eq!(buf.len(), 3);
+    }
+
+    #[test]
+    fn test_max_size() {
+        let mut framer = SyslogFramer::new(8);
+        let mut buf = BytesMut::from("12 <13>abcdefgh<13>ok\n");
+        assert_eq!(
+            decode_all(&mut framer, &mut buf),
+            vec!["<13>abcd", "<13>ok"]
+        );
+
+        let mut buf = BytesMut::from("<13>abcdefgh\n<13>ok\n");
+        assert_eq!(
+            decode_all(&mut framer, &mut buf),
+            vec!["<13>abcd", "<13>ok"]
+        );
+    }
+}
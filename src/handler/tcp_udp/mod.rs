@@ -13,14 +13,21 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::net::SocketAddr;
+
 use bytes::BytesMut;
 use tokio::{
-    io::AsyncReadExt,
+    io::{AsyncRead, AsyncReadExt},
     net::{TcpListener, UdpSocket},
 };
+use tokio_rustls::TlsAcceptor;
 
 use crate::{job::syslog_server::BROADCASTER, service::logs::syslog};
 
+mod framing;
+pub mod statsd;
+pub mod tls;
+
 pub static STOP_SRV: &str = "ZO_STOP_TCP_UDP";
 
 pub async fn udp_server(socket: UdpSocket) {
@@ -55,63 +62,90 @@ pub async fn udp_server(socket: UdpSocket) {
     }
 }
 
-pub async fn tcp_server(listener: TcpListener) {
+/// Accepts the syslog connections, over TLS when the acceptor is given.
+pub async fn tcp_server(listener: TcpListener, tls_acceptor: Option<TlsAcceptor>) {
     let sender = BROADCASTER.read().await;
     let mut tcp_receiver_rx = sender.subscribe();
     loop {
-        let (mut stream, _) = match listener.accept().await {
+        let (stream, peer_addr) = match listener.accept().await {
             Ok(val) => val,
             Err(e) => {
                 log::error!("Error while accepting TCP connection: {}", e);
                 continue;
             }
         };
+        if let Ok(val) = tcp_receiver_rx.try_recv() {
+            if !val {
+                log::warn!("TCP server - received the stop signal, exiting.");
+                drop(listener);
+                break;
+            }
+        };
+        let tls_acceptor = tls_acceptor.clone();
         tokio::task::spawn(async move {
-            let mut buf_tcp = vec![0u8; 1460];
-            let peer_addr = match stream.peer_addr() {
-                Ok(addr) => addr,
-                Err(e) => {
-                    log::error!("Error while reading peer_addr from TCP stream: {}", e);
-                    return;
-                }
-            };
-            log::info!("spawned new syslog tcp receiver for peer {}", peer_addr);
-            loop {
-                let n = match stream.read(&mut buf_tcp).await {
-                    Ok(0) => {
-                        log::info!("received 0 bytes, closing for peer {}", peer_addr);
-                        break;
-                    }
-                    Ok(n) => n,
-                    Err(e) => {
-                        log::error!("Error while reading from TCP stream: {}", e);
-                        break;
+            match tls_acceptor {
+                Some(acceptor) => match acceptor.accept(stream).await {
+                    Ok(stream) => {
+                        log::info!("spawned new syslog tls receiver for peer {}", peer_addr);
+                        handle_tcp_stream(stream, peer_addr).await;
                     }
-                };
-                let message = BytesMut::from(&buf_tcp[..n]);
-                let input_str = match String::from_utf8(message.to_vec()) {
-                    Ok(val) => val,
                     Err(e) => {
-                        log::error!("Error while converting TCP message to UTF8 string: {}", e);
-                        continue;
+                        log::error!("Error during TLS handshake with peer {}: {}", peer_addr, e);
                     }
-                };
-                if input_str != STOP_SRV {
-                    if let Err(e) = syslog::ingest(&input_str, peer_addr).await {
-                        log::error!("Error while ingesting TCP message: {}", e);
-                    }
-                } else {
-                    log::info!("received stop signal, closing for peer {}", peer_addr);
-                    break;
+                },
+                None => {
+                    log::info!("spawned new syslog tcp receiver for peer {}", peer_addr);
+                    handle_tcp_stream(stream, peer_addr).await;
                 }
             }
         });
-        if let Ok(val) = tcp_receiver_rx.try_recv() {
-            if !val {
-                log::warn!("TCP server - received the stop signal, exiting.");
-                drop(listener);
-                break;
+    }
+}
+
+/// Reads the framed syslog messages of a connection until it's closed, see
+/// [`framing::SyslogFramer`].
+async fn handle_tcp_stream<S: AsyncRead + Unpin>(mut stream: S, peer_addr: SocketAddr) {
+    let max_size = config::get_config().tcp.tcp_max_message_size;
+    let mut framer = framing::SyslogFramer::new(max_size);
+    let mut buf = BytesMut::with_capacity(8192);
+    loop {
+        let eof = match stream.read_buf(&mut buf).await {
+            Ok(0) => {
+                log::info!("received 0 bytes, closing for peer {}", peer_addr);
+                true
+            }
+            Ok(_) => false,
+            Err(e) => {
+                log::error!("Error while reading from TCP stream: {}", e);
+                return;
             }
         };
+        loop {
+            let frame = if eof {
+                framer.decode_eof(&mut buf)
+            } else {
+                framer.decode(&mut buf)
+            };
+            let Some(frame) = frame else {
+                break;
+            };
+            let input_str = match String::from_utf8(frame.to_vec()) {
+                Ok(val) => val,
+                Err(e) => {
+                    log::error!("Error while converting TCP message to UTF8 string: {}", e);
+                    continue;
+                }
+            };
+            if input_str == STOP_SRV {
+                log::info!("received stop signal, closing for peer {}", peer_addr);
+                return;
+            }
+            if let Err(e) = syslog::ingest(&input_str, peer_addr).await {
+                log::error!("Error while ingesting TCP message: {}", e);
+            }
+        }
+        if eof {
+            return;
+        }
     }
 }
@@ -0,0 +1,41 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use tokio::net::UdpSocket;
+
+use crate::service::metrics::statsd;
+
+/// Receives the StatsD packets, a packet holds one or more newline separated metrics.
+pub async fn udp_server(socket: UdpSocket) {
+    let mut buf = vec![0u8; 65535];
+    loop {
+        let recv_len = match socket.recv_from(&mut buf).await {
+            Ok((len, _)) => len,
+            Err(e) => {
+                log::error!("Error while reading from StatsD UDP socket: {}", e);
+                continue;
+            }
+        };
+        match std::str::from_utf8(&buf[..recv_len]) {
+            Ok(packet) => statsd::ingest(packet),
+            Err(e) => {
+                log::error!(
+                    "Error while converting StatsD message to UTF8 string: {}",
+                    e
+                );
+            }
+        }
+    }
+}
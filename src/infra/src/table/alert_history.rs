@@ -0,0 +1,125 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use config::meta::alerts::history::AlertHistoryEntry;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, DatabaseConnection, EntityTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set,
+};
+
+use super::entity::alert_history::{ActiveModel, Column, Entity, Model};
+use crate::{
+    db::{connect_to_orm, ORM_CLIENT},
+    errors,
+};
+
+impl From<Model> for AlertHistoryEntry {
+    fn from(value: Model) -> Self {
+        Self {
+            timestamp: value.created_at,
+            alert_name: value.alert_name,
+            stream_type: value.stream_type,
+            stream_name: value.stream_name,
+            fingerprint: value.fingerprint,
+            status: value.status.as_str().into(),
+            labels: serde_json::from_value(value.labels).unwrap_or_default(),
+            reason: value.reason,
+        }
+    }
+}
+
+/// Adds an entry to the history of the alerts of the organization.
+pub async fn add(org_id: &str, entry: &AlertHistoryEntry) -> Result<(), errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let active = ActiveModel {
+        id: NotSet, // Set by DB.
+        org: Set(org_id.to_owned()),
+        stream_type: Set(entry.stream_type.clone()),
+        stream_name: Set(entry.stream_name.clone()),
+        alert_name: Set(entry.alert_name.clone()),
+        fingerprint: Set(entry.fingerprint.clone()),
+        status: Set(entry.status.to_string()),
+        labels: Set(serde_json::to_value(&entry.labels)?),
+        reason: Set(entry.reason.clone()),
+        created_at: Set(entry.timestamp),
+    };
+    active.insert(client).await?;
+    Ok(())
+}
+
+/// Lists the history of an alert within the time range, the most recent entries first.
+pub async fn list(
+    org_id: &str,
+    stream_type: &str,
+    stream_name: &str,
+    alert_name: &str,
+    (start_time, end_time): (Option<i64>, Option<i64>),
+    limit: Option<u64>,
+) -> Result<Vec<AlertHistoryEntry>, errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let entries = list_models(
+        client,
+        org_id,
+        stream_type,
+        stream_name,
+        alert_name,
+        (start_time, end_time),
+        limit,
+    )
+    .await?
+    .into_iter()
+    .map(AlertHistoryEntry::from)
+    .collect();
+    Ok(entries)
+}
+
+/// Deletes the entries of all the organizations older than `created_at`.
+pub async fn delete_before(created_at: i64) -> Result<u64, errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let res = Entity::delete_many()
+        .filter(Column::CreatedAt.lt(created_at))
+        .exec(client)
+        .await?;
+    Ok(res.rows_affected)
+}
+
+/// Lists the alert history ORM models of an alert.
+async fn list_models(
+    db: &DatabaseConnection,
+    org_id: &str,
+    stream_type: &str,
+    stream_name: &str,
+    alert_name: &str,
+    (start_time, end_time): (Option<i64>, Option<i64>),
+    limit: Option<u64>,
+) -> Result<Vec<Model>, sea_orm::DbErr> {
+    let mut query = Entity::find()
+        .filter(Column::Org.eq(org_id))
+        .filter(Column::StreamType.eq(stream_type))
+        .filter(Column::StreamName.eq(stream_name))
+        .filter(Column::AlertName.eq(alert_name));
+    if let Some(start_time) = start_time {
+        query = query.filter(Column::CreatedAt.gte(start_time));
+    }
+    if let Some(end_time) = end_time {
+        query = query.filter(Column::CreatedAt.lt(end_time));
+    }
+    query
+        .order_by(Column::CreatedAt, sea_orm::Order::Desc)
+        .order_by(Column::Id, sea_orm::Order::Desc)
+        .limit(limit)
+        .all(db)
+        .await
+}
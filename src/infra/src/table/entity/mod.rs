@@ -2,5 +2,6 @@
 
 pub mod prelude;
 
+pub mod alert_history;
 pub mod dashboards;
 pub mod folders;
@@ -1,3 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
-pub use super::{dashboards::Entity as Dashboards, folders::Entity as Folders};
+pub use super::{
+    alert_history::Entity as AlertHistory, dashboards::Entity as Dashboards,
+    folders::Entity as Folders,
+};
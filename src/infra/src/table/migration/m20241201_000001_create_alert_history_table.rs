@@ -0,0 +1,200 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const ALERT_HISTORY_ORG_ALERT_IDX: &str = "alert_history_org_alert_idx";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(create_alert_history_table_statement())
+            .await?;
+        manager
+            .create_index(create_alert_history_org_alert_idx_stmnt())
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name(ALERT_HISTORY_ORG_ALERT_IDX).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(AlertHistory::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+/// Statement to create the alert history table.
+fn create_alert_history_table_statement() -> TableCreateStatement {
+    Table::create()
+        .table(AlertHistory::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(AlertHistory::Id)
+                .big_integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(AlertHistory::Org).string_len(100).not_null())
+        .col(
+            ColumnDef::new(AlertHistory::StreamType)
+                .string_len(32)
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(AlertHistory::StreamName)
+                .string_len(256)
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(AlertHistory::AlertName)
+                .string_len(256)
+                .not_null(),
+        )
+        // Fingerprint of the group of the alert the entry is about.
+        .col(
+            ColumnDef::new(AlertHistory::Fingerprint)
+                .string_len(64)
+                .not_null(),
+        )
+        // One of pending, firing or resolved.
+        .col(ColumnDef::new(AlertHistory::Status).string_len(16).not_null())
+        .col(ColumnDef::new(AlertHistory::Labels).json().not_null())
+        .col(ColumnDef::new(AlertHistory::Reason).text().not_null())
+        // Unix timestamp in microseconds.
+        .col(
+            ColumnDef::new(AlertHistory::CreatedAt)
+                .big_integer()
+                .not_null(),
+        )
+        .to_owned()
+}
+
+/// Statement to create index on org, alert and created_at.
+fn create_alert_history_org_alert_idx_stmnt() -> IndexCreateStatement {
+    sea_query::Index::create()
+        .if_not_exists()
+        .name(ALERT_HISTORY_ORG_ALERT_IDX)
+        .table(AlertHistory::Table)
+        .col(AlertHistory::Org)
+        .col(AlertHistory::StreamType)
+        .col(AlertHistory::StreamName)
+        .col(AlertHistory::AlertName)
+        .col(AlertHistory::CreatedAt)
+        .to_owned()
+}
+
+/// Identifiers used in queries on the alert history table.
+#[derive(DeriveIden)]
+enum AlertHistory {
+    Table,
+    Id,
+    Org,
+    StreamType,
+    StreamName,
+    AlertName,
+    Fingerprint,
+    Status,
+    Labels,
+    Reason,
+    CreatedAt,
+}
+
+#[cfg(test)]
+mod tests {
+    use collapse::*;
+
+    use super::*;
+
+    #[test]
+    fn postgres() {
+        collapsed_eq!(
+            &create_alert_history_table_statement().to_string(PostgresQueryBuilder),
+            r#"
+                CREATE TABLE IF NOT EXISTS "alert_history" (
+                "id" bigserial NOT NULL PRIMARY KEY,
+                "org" varchar(100) NOT NULL,
+                "stream_type" varchar(32) NOT NULL,
+                "stream_name" varchar(256) NOT NULL,
+                "alert_name" varchar(256) NOT NULL,
+                "fingerprint" varchar(64) NOT NULL,
+                "status" varchar(16) NOT NULL,
+                "labels" json NOT NULL,
+                "reason" text NOT NULL,
+                "created_at" bigint NOT NULL
+            )"#
+        );
+        assert_eq!(
+            &create_alert_history_org_alert_idx_stmnt().to_string(PostgresQueryBuilder),
+            r#"CREATE INDEX IF NOT EXISTS "alert_history_org_alert_idx" ON "alert_history" ("org", "stream_type", "stream_name", "alert_name", "created_at")"#
+        );
+    }
+
+    #[test]
+    fn mysql() {
+        collapsed_eq!(
+            &create_alert_history_table_statement().to_string(MysqlQueryBuilder),
+            r#"
+                CREATE TABLE IF NOT EXISTS `alert_history` (
+                `id` bigint NOT NULL AUTO_INCREMENT PRIMARY KEY,
+                `org` varchar(100) NOT NULL,
+                `stream_type` varchar(32) NOT NULL,
+                `stream_name` varchar(256) NOT NULL,
+                `alert_name` varchar(256) NOT NULL,
+                `fingerprint` varchar(64) NOT NULL,
+                `status` varchar(16) NOT NULL,
+                `labels` json NOT NULL,
+                `reason` text NOT NULL,
+                `created_at` bigint NOT NULL
+            )"#
+        );
+        assert_eq!(
+            &create_alert_history_org_alert_idx_stmnt().to_string(MysqlQueryBuilder),
+            r#"CREATE INDEX `alert_history_org_alert_idx` ON `alert_history` (`org`, `stream_type`, `stream_name`, `alert_name`, `created_at`)"#
+        );
+    }
+
+    #[test]
+    fn sqlite() {
+        collapsed_eq!(
+            &create_alert_history_table_statement().to_string(SqliteQueryBuilder),
+            r#"
+                CREATE TABLE IF NOT EXISTS "alert_history" (
+                "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT,
+                "org" varchar(100) NOT NULL,
+                "stream_type" varchar(32) NOT NULL,
+                "stream_name" varchar(256) NOT NULL,
+                "alert_name" varchar(256) NOT NULL,
+                "fingerprint" varchar(64) NOT NULL,
+                "status" varchar(16) NOT NULL,
+                "labels" json_text NOT NULL,
+                "reason" text NOT NULL,
+                "created_at" bigint NOT NULL
+            )"#
+        );
+        assert_eq!(
+            &create_alert_history_org_alert_idx_stmnt().to_string(SqliteQueryBuilder),
+            r#"CREATE INDEX IF NOT EXISTS "alert_history_org_alert_idx" ON "alert_history" ("org", "stream_type", "stream_name", "alert_name", "created_at")"#
+        );
+    }
+}
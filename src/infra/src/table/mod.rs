@@ -19,6 +19,7 @@ use sea_orm_migration::MigratorTrait;
 
 use crate::db::{connect_to_orm, sqlite::CLIENT_RW, ORM_CLIENT, SQLITE_STORE};
 
+pub mod alert_history;
 pub mod dashboards;
 #[allow(unused_imports)]
 mod entity;
@@ -61,6 +61,8 @@ pub async fn run() -> Result<(), anyhow::Error> {
     tokio::task::spawn(async move { run_schedule_jobs().await });
     tokio::task::spawn(async move { clean_complete_jobs().await });
     tokio::task::spawn(async move { watch_timeout_jobs().await });
+    tokio::task::spawn(async move { clean_alert_history().await });
+    tokio::task::spawn(async move { clean_alert_groups().await });
 
     Ok(())
 }
@@ -106,3 +108,36 @@ async fn watch_timeout_jobs() -> Result<(), anyhow::Error> {
         }
     }
 }
+
+async fn clean_alert_history() -> Result<(), anyhow::Error> {
+    let retention_days = get_config().limit.alert_history_retention_days;
+    if retention_days <= 0 {
+        return Ok(());
+    }
+    let mut interval = time::interval(time::Duration::from_secs(3600));
+    loop {
+        interval.tick().await;
+        let created_at = chrono::Utc::now().timestamp_micros()
+            - chrono::Duration::try_days(retention_days)
+                .unwrap()
+                .num_microseconds()
+                .unwrap();
+        match infra::table::alert_history::delete_before(created_at).await {
+            Ok(n) if n > 0 => log::info!("[ALERT MANAGER] deleted {n} alert history entries"),
+            Ok(_) => {}
+            Err(e) => log::error!("[ALERT MANAGER] clean alert history error: {}", e),
+        }
+    }
+}
+
+async fn clean_alert_groups() -> Result<(), anyhow::Error> {
+    let mut interval = time::interval(time::Duration::from_secs(3600));
+    loop {
+        interval.tick().await;
+        match service::alerts::grouping::cleanup().await {
+            Ok(n) if n > 0 => log::info!("[ALERT MANAGER] deleted {n} expired alert group states"),
+            Ok(_) => {}
+            Err(e) => log::error!("[ALERT MANAGER] clean alert group states error: {}", e),
+        }
+    }
+}
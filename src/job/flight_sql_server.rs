@@ -0,0 +1,67 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::net::SocketAddr;
+
+use arrow_flight::flight_service_server::FlightServiceServer;
+use config::cluster::LOCAL_NODE;
+use tokio::time;
+use tonic::codec::CompressionEncoding;
+
+use crate::handler::grpc::flight::sql::{cleanup_sessions, FlightSqlServiceImpl};
+
+/// Interval of the deletion of the expired sessions
+const SESSION_CLEANUP_INTERVAL: u64 = 3600;
+
+pub async fn run() -> Result<(), anyhow::Error> {
+    if !LOCAL_NODE.is_querier() {
+        return Ok(()); // not a querier, no need to init job
+    }
+
+    let cfg = config::get_config();
+    if !cfg.grpc.flight_sql_enabled {
+        return Ok(());
+    }
+
+    let ip = if !cfg.grpc.addr.is_empty() {
+        cfg.grpc.addr.clone()
+    } else {
+        "0.0.0.0".to_string()
+    };
+    let addr: SocketAddr = format!("{}:{}", ip, cfg.grpc.flight_sql_port).parse()?;
+    let flight_sql_svc = FlightServiceServer::new(FlightSqlServiceImpl)
+        .send_compressed(CompressionEncoding::Gzip)
+        .accept_compressed(CompressionEncoding::Gzip)
+        .max_decoding_message_size(cfg.grpc.max_message_size * 1024 * 1024)
+        .max_encoding_message_size(cfg.grpc.max_message_size * 1024 * 1024);
+
+    tokio::task::spawn(async move {
+        let mut interval = time::interval(time::Duration::from_secs(SESSION_CLEANUP_INTERVAL));
+        interval.tick().await; // trigger the first run
+        loop {
+            interval.tick().await;
+            if let Err(e) = cleanup_sessions().await {
+                log::error!("[FLIGHT_SQL] Error while cleaning up sessions: {}", e);
+            }
+        }
+    });
+
+    log::info!("Starting Flight SQL server at {addr}");
+    tonic::transport::Server::builder()
+        .add_service(flight_sql_svc)
+        .serve(addr)
+        .await?;
+    Ok(())
+}
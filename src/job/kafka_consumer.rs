@@ -0,0 +1,318 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Consumes the Kafka topics of `ZO_KAFKA_TOPICS` into their streams. The messages are ingested
+//! by batches and the offsets of a batch are only committed once it's written to the WAL, so the
+//! messages are delivered at least once. A batch which still fails after `ZO_KAFKA_MAX_RETRIES`
+//! is sent to `ZO_KAFKA_DEAD_LETTER_TOPIC`, or dropped when there is none, and committed anyway,
+//! otherwise a persistent error would block all the partitions and the consumer would miss
+//! `max.poll.interval.ms` and be kicked out of the group. To try it against a local broker:
+//!
+//! ```text
+//! docker run -d -p 9092:9092 apache/kafka
+//! ZO_KAFKA_ENABLED=true ZO_KAFKA_TOPICS=app-logs=default/app cargo run --features kafka
+//! ```
+
+use std::collections::HashMap;
+
+use config::cluster::LOCAL_NODE;
+use futures::future::try_join_all;
+use rdkafka::{
+    consumer::{CommitMode, Consumer, StreamConsumer},
+    error::KafkaError,
+    message::{Message, OwnedMessage},
+    producer::{FutureProducer, FutureRecord},
+    ClientConfig, Offset, TopicPartitionList,
+};
+use tokio::time::{self, Duration, Instant};
+
+use crate::service::logs::kafka::{self, TopicMapping};
+
+/// Maximum delay between the retries of a batch which failed to be ingested
+const MAX_RETRY_DELAY: u64 = 60; // seconds
+
+pub async fn run() -> Result<(), anyhow::Error> {
+    if !LOCAL_NODE.is_ingester() {
+        return Ok(()); // not an ingester, no need to init job
+    }
+
+    let cfg = config::get_config();
+    if !cfg.kafka.enabled {
+        return Ok(());
+    }
+
+    let mappings: HashMap<String, TopicMapping> = kafka::parse_topic_mappings(&cfg.kafka.topics)?
+        .into_iter()
+        .map(|m| (m.topic.clone(), m))
+        .collect();
+    let consumer = create_consumer()?;
+    let producer = if cfg.kafka.dead_letter_topic.is_empty() {
+        None
+    } else {
+        Some(create_producer()?)
+    };
+    let topics: Vec<&str> = mappings.keys().map(|v| v.as_str()).collect();
+    consumer.subscribe(&topics)?;
+    log::info!(
+        "[KAFKA] Consuming topics {:?} of {} as group {}",
+        topics,
+        cfg.kafka.brokers,
+        cfg.kafka.group_id
+    );
+
+    let batch_timeout = Duration::from_millis(cfg.kafka.batch_timeout);
+    loop {
+        let batch = receive_batch(&consumer, cfg.kafka.batch_size, batch_timeout).await;
+        if batch.is_empty() {
+            continue;
+        }
+
+        let mut payloads: HashMap<&str, Vec<&[u8]>> = HashMap::new();
+        for msg in batch.iter() {
+            if let Some(payload) = msg.payload() {
+                payloads.entry(msg.topic()).or_default().push(payload);
+            }
+        }
+        for (topic, payloads) in payloads {
+            let Some(mapping) = mappings.get(topic) else {
+                continue;
+            };
+            if ingest_with_retry(mapping, &payloads, cfg.kafka.max_retries).await {
+                continue;
+            }
+            match producer.as_ref() {
+                Some(producer) => {
+                    if let Err(e) =
+                        dead_letter(producer, &cfg.kafka.dead_letter_topic, &payloads).await
+                    {
+                        log::error!(
+                            "[KAFKA] Error while sending {} messages of topic {} to the dead letter topic {}, dropping them: {}",
+                            payloads.len(),
+                            topic,
+                            cfg.kafka.dead_letter_topic,
+                            e
+                        );
+                    }
+                }
+                None => log::error!(
+                    "[KAFKA] Dropping {} messages of topic {} after {} retries",
+                    payloads.len(),
+                    topic,
+                    cfg.kafka.max_retries
+                ),
+            }
+        }
+
+        let mut tpl = TopicPartitionList::new();
+        for ((topic, partition), offset) in next_offsets(&batch) {
+            tpl.add_partition_offset(topic, partition, Offset::Offset(offset))?;
+        }
+        if let Err(e) = consumer.commit(&tpl, CommitMode::Async) {
+            // the messages will be consumed again, e.g. after a rebalance
+            log::error!("[KAFKA] Error while committing offsets: {}", e);
+        }
+    }
+}
+
+fn client_config() -> ClientConfig {
+    let cfg = config::get_config();
+    let mut client_config = ClientConfig::new();
+    client_config
+        .set("bootstrap.servers", &cfg.kafka.brokers)
+        .set("security.protocol", &cfg.kafka.security_protocol);
+    if !cfg.kafka.sasl_mechanism.is_empty() {
+        client_config
+            .set("sasl.mechanism", &cfg.kafka.sasl_mechanism)
+            .set("sasl.username", &cfg.kafka.sasl_username)
+            .set("sasl.password", &cfg.kafka.sasl_password);
+    }
+    client_config
+}
+
+fn create_consumer() -> Result<StreamConsumer, anyhow::Error> {
+    let cfg = config::get_config();
+    Ok(client_config()
+        .set("group.id", &cfg.kafka.group_id)
+        .set("enable.auto.commit", "false")
+        .set("auto.offset.reset", &cfg.kafka.auto_offset_reset)
+        .create()?)
+}
+
+fn create_producer() -> Result<FutureProducer, anyhow::Error> {
+    Ok(client_config().create()?)
+}
+
+/// Receives messages until the batch is full or the timeout is reached.
+async fn receive_batch(
+    consumer: &StreamConsumer,
+    batch_size: usize,
+    timeout: Duration,
+) -> Vec<OwnedMessage> {
+    let mut batch = Vec::new();
+    let deadline = Instant::now() + timeout;
+    while batch.len() < batch_size {
+        match time::timeout_at(deadline, consumer.recv()).await {
+            Ok(Ok(msg)) => batch.push(msg.detach()),
+            Ok(Err(e)) => log::error!("[KAFKA] Error while receiving message: {}", e),
+            Err(_) => break,
+        }
+    }
+    batch
+}
+
+/// The offsets to commit for the batch, the one following the last message of each partition.
+fn next_offsets(batch: &[OwnedMessage]) -> HashMap<(&str, i32), i64> {
+    let mut offsets: HashMap<(&str, i32), i64> = HashMap::new();
+    for msg in batch.iter() {
+        let offset = offsets.entry((msg.topic(), msg.partition())).or_default();
+        *offset = (*offset).max(msg.offset() + 1);
+    }
+    offsets
+}
+
+/// Ingests the batch, retrying at most `max_retries` times. Returns false if it still fails.
+async fn ingest_with_retry(mapping: &TopicMapping, payloads: &[&[u8]], max_retries: u32) -> bool {
+    let mut delay = 1;
+    let mut retries = 0;
+    loop {
+        let Err(e) = kafka::ingest(mapping, payloads).await else {
+            return true;
+        };
+        if retries >= max_retries {
+            log::error!(
+                "[KAFKA] Error while ingesting messages of topic {} into {}/{}, giving up: {}",
+                mapping.topic,
+                mapping.org_id,
+                mapping.stream_name,
+                e
+            );
+            return false;
+        }
+        log::error!(
+            "[KAFKA] Error while ingesting messages of topic {} into {}/{}, retrying in {}s: {}",
+            mapping.topic,
+            mapping.org_id,
+            mapping.stream_name,
+            delay,
+            e
+        );
+        time::sleep(Duration::from_secs(delay)).await;
+        delay = (delay * 2).min(MAX_RETRY_DELAY);
+        retries += 1;
+    }
+}
+
+/// Sends the payloads which failed to be ingested to the dead letter topic.
+async fn dead_letter(
+    producer: &FutureProducer,
+    topic: &str,
+    payloads: &[&[u8]],
+) -> Result<(), KafkaError> {
+    try_join_all(payloads.iter().map(|payload| async move {
+        producer
+            .send(
+                FutureRecord::<(), [u8]>::to(topic).payload(*payload),
+                Duration::from_secs(0),
+            )
+            .await
+            .map_err(|(e, _)| e)
+    }))
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rdkafka::Timestamp;
+
+    use super::*;
+
+    #[test]
+    fn test_next_offsets() {
+        let msg = |topic: &str, partition: i32, offset: i64| {
+            OwnedMessage::new(
+                Some(b"{}".to_vec()),
+                None,
+                topic.to_string(),
+                Timestamp::NotAvailable,
+                partition,
+                offset,
+                None,
+            )
+        };
+        let batch = vec![
+            msg("app-logs", 0, 5),
+            msg("app-logs", 0, 7),
+            msg("app-logs", 1, 3),
+            msg("otel.logs", 0, 10),
+            // no payload, still committed
+            OwnedMessage::new(
+                None,
+                None,
+                "app-logs".to_string(),
+                Timestamp::NotAvailable,
+                0,
+                8,
+                None,
+            ),
+        ];
+        let offsets = next_offsets(&batch);
+        assert_eq!(offsets.len(), 3);
+        assert_eq!(offsets[&("app-logs", 0)], 9);
+        assert_eq!(offsets[&("app-logs", 1)], 4);
+        assert_eq!(offsets[&("otel.logs", 0)], 11);
+    }
+
+    // needs a local broker, see the module doc
+    #[tokio::test]
+    #[ignore]
+    async fn test_local_broker() {
+        let topic = format!("o2-test-{}", config::ider::generate());
+        let payloads: Vec<&[u8]> = vec![br#"{"n":1}"#, br#"{"n":2}"#];
+        let producer = create_producer().unwrap();
+        dead_letter(&producer, &topic, &payloads).await.unwrap();
+
+        let consumer: StreamConsumer = client_config()
+            .set("group.id", &topic)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .unwrap();
+        consumer.subscribe(&[topic.as_str()]).unwrap();
+        let mut batch = Vec::new();
+        // the first polls are empty until the consumer joins the group
+        for _ in 0..30 {
+            let size = payloads.len() - batch.len();
+            batch.extend(receive_batch(&consumer, size, Duration::from_secs(1)).await);
+            if batch.len() == payloads.len() {
+                break;
+            }
+        }
+        let received: Vec<&[u8]> = batch.iter().filter_map(|msg| msg.payload()).collect();
+        assert_eq!(received, payloads);
+
+        let mut tpl = TopicPartitionList::new();
+        for ((topic, partition), offset) in next_offsets(&batch) {
+            tpl.add_partition_offset(topic, partition, Offset::Offset(offset))
+                .unwrap();
+        }
+        consumer.commit(&tpl, CommitMode::Sync).unwrap();
+        let committed = consumer.committed(Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            committed.find_partition(&topic, 0).unwrap().offset(),
+            Offset::Offset(2)
+        );
+    }
+}
@@ -24,18 +24,23 @@ use crate::{
         infra::config::SYSLOG_ENABLED,
         meta::{organization::DEFAULT_ORG, user::UserRequest},
     },
-    service::{db, self_reporting, users},
+    service::{db, ingestion, self_reporting, traces, users},
 };
 
 mod alert_manager;
 mod compactor;
 pub(crate) mod files;
 mod flatten_compactor;
+mod flight_sql_server;
+#[cfg(feature = "kafka")]
+mod kafka_consumer;
 pub mod metrics;
 mod mmdb_downloader;
 mod prom;
 mod prom_self_consume;
+mod search_jobs;
 mod stats;
+mod statsd_server;
 pub(crate) mod syslog_server;
 mod telemetry;
 
@@ -117,10 +122,14 @@ pub async fn init() -> Result<(), anyhow::Error> {
     tokio::task::spawn(async move { db::metrics::watch_prom_cluster_leader().await });
     tokio::task::spawn(async move { db::alerts::templates::watch().await });
     tokio::task::spawn(async move { db::alerts::destinations::watch().await });
+    tokio::task::spawn(async move { db::alerts::recording_rules::watch().await });
+    tokio::task::spawn(async move { db::alerts::inhibitions::watch().await });
+    tokio::task::spawn(async move { db::alerts::silences::watch().await });
     tokio::task::spawn(async move { db::alerts::realtime_triggers::watch().await });
     tokio::task::spawn(async move { db::alerts::alert::watch().await });
     tokio::task::spawn(async move { db::dashboards::reports::watch().await });
     tokio::task::spawn(async move { db::organization::watch().await });
+    tokio::task::spawn(async move { db::quota::watch().await });
     #[cfg(feature = "enterprise")]
     tokio::task::spawn(async move { db::ofga::watch().await });
 
@@ -153,6 +162,15 @@ pub async fn init() -> Result<(), anyhow::Error> {
     db::alerts::destinations::cache()
         .await
         .expect("alerts destinations cache failed");
+    db::alerts::recording_rules::cache()
+        .await
+        .expect("recording rules cache failed");
+    db::alerts::inhibitions::cache()
+        .await
+        .expect("alerts inhibition rules cache failed");
+    db::alerts::silences::cache()
+        .await
+        .expect("alerts silences cache failed");
     db::alerts::realtime_triggers::cache()
         .await
         .expect("alerts realtime triggers cache failed");
@@ -170,6 +188,11 @@ pub async fn init() -> Result<(), anyhow::Error> {
     // cache pipeline
     db::pipeline::cache().await.expect("Pipeline cache failed");
 
+    // cache ingestion quotas
+    db::quota::cache()
+        .await
+        .expect("ingestion quotas cache failed");
+
     infra_file_list::create_table_index().await?;
     infra_file_list::LOCAL_CACHE.create_table_index().await?;
     tokio::task::spawn(async move { db::file_list::cache_stats().await });
@@ -197,8 +220,11 @@ pub async fn init() -> Result<(), anyhow::Error> {
     tokio::task::spawn(async move { compactor::run().await });
     tokio::task::spawn(async move { flatten_compactor::run().await });
     tokio::task::spawn(async move { metrics::run().await });
+    tokio::task::spawn(async move { traces::span_metrics::run().await });
+    tokio::task::spawn(async move { ingestion::quota::run().await });
     tokio::task::spawn(async move { prom::run().await });
     tokio::task::spawn(async move { alert_manager::run().await });
+    tokio::task::spawn(async move { search_jobs::run().await });
 
     #[cfg(feature = "enterprise")]
     o2_enterprise::enterprise::openfga::authorizer::authz::init_open_fga().await;
@@ -225,5 +251,31 @@ pub async fn init() -> Result<(), anyhow::Error> {
             .expect("syslog server run failed");
     }
 
+    // StatsD server start
+    tokio::task::spawn(async move {
+        if let Err(e) = statsd_server::run().await {
+            log::error!("StatsD server run failed: {}", e);
+        }
+    });
+
+    // Flight SQL server start
+    tokio::task::spawn(async move {
+        if let Err(e) = flight_sql_server::run().await {
+            log::error!("Flight SQL server run failed: {}", e);
+        }
+    });
+
+    // Kafka consumer start
+    #[cfg(feature = "kafka")]
+    tokio::task::spawn(async move {
+        if let Err(e) = kafka_consumer::run().await {
+            log::error!("Kafka consumer run failed: {}", e);
+        }
+    });
+    #[cfg(not(feature = "kafka"))]
+    if cfg.kafka.enabled {
+        log::warn!("ZO_KAFKA_ENABLED is ignored, this build doesn't have the kafka feature");
+    }
+
     Ok(())
 }
@@ -0,0 +1,38 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use config::{cluster::LOCAL_NODE, get_config};
+use tokio::time;
+
+use crate::service::search::{export, job};
+
+pub async fn run() -> Result<(), anyhow::Error> {
+    if !LOCAL_NODE.is_querier() {
+        return Ok(());
+    }
+
+    let mut interval = time::interval(time::Duration::from_secs(
+        get_config().limit.search_job_cleanup_interval,
+    ));
+    loop {
+        interval.tick().await;
+        if let Err(e) = job::cleanup().await {
+            log::error!("[SEARCH_JOB] Error while cleaning up search jobs: {}", e);
+        }
+        if let Err(e) = export::cleanup().await {
+            log::error!("[EXPORT] Error while cleaning up exported files: {}", e);
+        }
+    }
+}
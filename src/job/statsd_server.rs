@@ -0,0 +1,51 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::net::SocketAddr;
+
+use config::cluster::LOCAL_NODE;
+use tokio::{
+    net::UdpSocket,
+    time::{self, Duration},
+};
+
+use crate::{handler::tcp_udp::statsd::udp_server, service::metrics::statsd};
+
+pub async fn run() -> Result<(), anyhow::Error> {
+    if !LOCAL_NODE.is_ingester() {
+        return Ok(()); // not an ingester, no need to init job
+    }
+
+    let cfg = config::get_config();
+    if !cfg.tcp.statsd_enabled {
+        return Ok(());
+    }
+
+    let udp_addr: SocketAddr = format!("0.0.0.0:{}", cfg.tcp.statsd_port).parse()?;
+    let udp_socket = UdpSocket::bind(udp_addr).await?;
+    log::info!("Starting StatsD UDP server on {udp_addr}");
+    tokio::task::spawn(async move {
+        udp_server(udp_socket).await;
+    });
+
+    let mut interval = time::interval(Duration::from_secs(cfg.tcp.statsd_flush_interval));
+    interval.tick().await; // trigger the first run
+    loop {
+        interval.tick().await;
+        if let Err(e) = statsd::flush().await {
+            log::error!("Error while ingesting StatsD metrics: {}", e);
+        }
+    }
+}
@@ -26,7 +26,7 @@ use tokio::{
 
 use crate::{
     common::infra::config::SYSLOG_ENABLED,
-    handler::tcp_udp::{tcp_server, udp_server, STOP_SRV},
+    handler::tcp_udp::{tcp_server, tls, udp_server, STOP_SRV},
     service::db::syslog::toggle_syslog_setting,
 };
 
@@ -42,13 +42,22 @@ pub async fn run(start_srv: bool, is_init: bool) -> Result<(), anyhow::Error> {
     let bind_addr = "0.0.0.0";
     let tcp_addr: SocketAddr = format!("{bind_addr}:{}", cfg.tcp.tcp_port).parse()?;
     let udp_addr: SocketAddr = format!("{bind_addr}:{}", cfg.tcp.udp_port).parse()?;
+    let tls_addr: SocketAddr = format!("{bind_addr}:{}", cfg.tcp.tcp_tls_port).parse()?;
     if (!server_running || is_init) && start_srv {
         log::info!("Starting TCP UDP server");
         let tcp_listener: TcpListener = TcpListener::bind(tcp_addr).await?;
         let udp_socket = UdpSocket::bind(udp_addr).await?;
         tokio::task::spawn(async move {
-            _ = tcp_server(tcp_listener).await;
+            _ = tcp_server(tcp_listener, None).await;
         });
+        if cfg.tcp.tcp_tls_enabled {
+            let acceptor = tls::acceptor(&cfg.tcp.tcp_tls_cert_path, &cfg.tcp.tcp_tls_key_path)?;
+            let tls_listener: TcpListener = TcpListener::bind(tls_addr).await?;
+            log::info!("Starting syslog TLS server on {tls_addr}");
+            tokio::task::spawn(async move {
+                _ = tcp_server(tls_listener, Some(acceptor)).await;
+            });
+        }
         tokio::task::spawn(async move {
             _ = udp_server(udp_socket).await;
         });
@@ -62,6 +71,10 @@ pub async fn run(start_srv: bool, is_init: bool) -> Result<(), anyhow::Error> {
         socket.send_to(STOP_SRV.as_bytes(), udp_addr).await?;
         let mut stream = TcpStream::connect(tcp_addr)?;
         stream.write_all(STOP_SRV.as_bytes())?;
+        if cfg.tcp.tcp_tls_enabled {
+            // wake up the TLS listener, it checks the stop signal before the handshake
+            drop(TcpStream::connect(tls_addr)?);
+        }
 
         drop(socket);
         drop(stream);
@@ -529,6 +529,7 @@ async fn init_http_server() -> Result<(), anyhow::Error> {
                         .service(router::http::aws)
                         .service(router::http::gcp)
                         .service(router::http::rum)
+                        .service(router::http::exports)
                         .configure(get_basic_routes)
                         .configure(get_proxy_routes),
                 )
@@ -618,6 +619,7 @@ async fn init_http_server_without_tracing() -> Result<(), anyhow::Error> {
                         .service(router::http::aws)
                         .service(router::http::gcp)
                         .service(router::http::rum)
+                        .service(router::http::exports)
                         .configure(get_basic_routes)
                         .configure(get_proxy_routes),
                 )
@@ -141,5 +141,20 @@ fn main() -> Result<()> {
         .unwrap();
     file.write_all(code.as_str().as_ref()).unwrap();
 
+    tonic_build::configure()
+        .compile(&["proto/loki/push.proto"], &["proto"])
+        .unwrap();
+
+    let path = "src/generated/loki.rs";
+    let generated_source_path = out.join("logproto.rs");
+    let code = std::fs::read_to_string(generated_source_path).unwrap();
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .open(path)
+        .unwrap();
+    file.write_all(code.as_str().as_ref()).unwrap();
+
     Ok(())
 }
@@ -1056,6 +1056,32 @@ pub struct CancelQueryResponse {
     #[prost(bool, tag = "1")]
     pub is_success: bool,
 }
+/// Live tail request, it reads the records of the memtables of an ingester
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TailRequest {
+    #[prost(string, tag = "1")]
+    pub org_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub stream_type: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub stream_name: ::prost::alloc::string::String,
+    /// microseconds, inclusive
+    #[prost(int64, tag = "4")]
+    pub start_time: i64,
+    /// SQL condition, e.g. match_all('error')
+    #[prost(string, tag = "5")]
+    pub filter: ::prost::alloc::string::String,
+    #[prost(int64, tag = "6")]
+    pub limit: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TailResponse {
+    /// json array, sorted by _timestamp
+    #[prost(bytes = "vec", tag = "1")]
+    pub hits: ::prost::alloc::vec::Vec<u8>,
+}
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
 pub enum AggregateMode {
@@ -1268,6 +1294,25 @@ pub mod search_client {
                 .insert(GrpcMethod::new("cluster.Search", "ClusterCancelQuery"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn tail(
+            &mut self,
+            request: impl tonic::IntoRequest<super::TailRequest>,
+        ) -> std::result::Result<tonic::Response<super::TailResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/cluster.Search/Tail");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("cluster.Search", "Tail"));
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -1298,6 +1343,10 @@ pub mod search_server {
             tonic::Response<super::CancelQueryResponse>,
             tonic::Status,
         >;
+        async fn tail(
+            &self,
+            request: tonic::Request<super::TailRequest>,
+        ) -> std::result::Result<tonic::Response<super::TailResponse>, tonic::Status>;
     }
     #[derive(Debug)]
     pub struct SearchServer<T: Search> {
@@ -1516,6 +1565,50 @@ pub mod search_server {
                     };
                     Box::pin(fut)
                 }
+                "/cluster.Search/Tail" => {
+                    #[allow(non_camel_case_types)]
+                    struct TailSvc<T: Search>(pub Arc<T>);
+                    impl<T: Search> tonic::server::UnaryService<super::TailRequest>
+                    for TailSvc<T> {
+                        type Response = super::TailResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TailRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Search>::tail(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = TailSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
@@ -14,4 +14,5 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 pub mod cluster;
+pub mod loki;
 pub mod prometheus;
@@ -20,11 +20,15 @@ use ::config::{
     meta::cluster::{Role, RoleGroup},
     utils::rand::get_rand_element,
 };
-use actix_web::{http::Error, route, web, HttpRequest, HttpResponse};
+use actix_web::{
+    http::{header, Error},
+    route, web, HttpRequest, HttpResponse,
+};
+use futures::{SinkExt, StreamExt};
 
 use crate::common::{infra::cluster, utils::http::get_search_type_from_request};
 
-const QUERIER_ROUTES: [&str; 18] = [
+const QUERIER_ROUTES: [&str; 22] = [
     "/config",
     "/summary",
     "/organizations",
@@ -33,8 +37,12 @@ const QUERIER_ROUTES: [&str; 18] = [
     "/streams",
     "/clusters",
     "/query_manager",
+    "/search_jobs",
     "/_search",
     "/_around",
+    "/_tail",
+    "/_export",
+    "/exports/",
     "/_values",
     "/functions?page_num=",
     "/prometheus/api/v1/series",
@@ -132,6 +140,15 @@ pub async fn gcp(
     dispatch(req, payload, client).await
 }
 
+#[route("/exports/{path:.*}", method = "GET")]
+pub async fn exports(
+    req: HttpRequest,
+    payload: web::Payload,
+    client: web::Data<awc::Client>,
+) -> actix_web::Result<HttpResponse, Error> {
+    dispatch(req, payload, client).await
+}
+
 #[route(
     "/rum/{path:.*}",
     // method = "GET",
@@ -159,6 +176,11 @@ async fn dispatch(
         return Ok(HttpResponse::ServiceUnavailable().body(new_url.value));
     }
 
+    // the websocket connections, e.g. of the live tail, are bridged to the node
+    if is_websocket_upgrade(&req) {
+        return proxy_websocket(req, payload, client, new_url.value).await;
+    }
+
     // send query
     let cfg = get_config();
     let resp = if cfg.route.connection_pool_disabled {
@@ -188,29 +210,121 @@ async fn dispatch(
     }
 
     // handle response
-    let mut resp = resp.unwrap();
+    let resp = resp.unwrap();
     let mut new_resp = HttpResponse::build(resp.status());
 
-    // copy headers
+    // copy headers, the body is decompressed and streamed with its own framing
     for (key, value) in resp.headers() {
-        if !key.eq("content-encoding") {
+        if *key != header::CONTENT_ENCODING
+            && *key != header::CONTENT_LENGTH
+            && *key != header::TRANSFER_ENCODING
+        {
             new_resp.insert_header((key.clone(), value.clone()));
         }
     }
 
-    // set body
-    let body = match resp
-        .body()
-        .limit(get_config().limit.req_payload_limit)
-        .await
-    {
-        Ok(b) => b,
+    // stream the body, so the large responses, e.g. the exports or the streamed searches, aren't
+    // buffered by the router
+    let url = new_url.value;
+    Ok(new_resp.streaming(resp.inspect(move |ret| {
+        if let Err(e) = ret {
+            log::error!("{}: {}", url, e);
+        }
+    })))
+}
+
+fn is_websocket_upgrade(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::UPGRADE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("websocket"))
+}
+
+/// Opens a websocket connection to the node and relays the messages in both directions until
+/// one of the sides closes it.
+async fn proxy_websocket(
+    req: HttpRequest,
+    payload: web::Payload,
+    client: web::Data<awc::Client>,
+    url: String,
+) -> actix_web::Result<HttpResponse, Error> {
+    let mut ws_req = client
+        .ws(url.as_str())
+        .max_frame_size(get_config().limit.req_payload_limit);
+    for key in [header::AUTHORIZATION, header::COOKIE] {
+        if let Some(value) = req.headers().get(&key) {
+            ws_req = ws_req.header(key, value.clone());
+        }
+    }
+    let upstream = match ws_req.connect().await {
+        Ok((_, upstream)) => upstream,
+        Err(awc::error::WsClientError::InvalidResponseStatus(status)) => {
+            return Ok(HttpResponse::build(status).finish());
+        }
         Err(e) => {
-            log::error!("{}: {}", new_url.value, e);
+            log::error!("dispatch: {}, error: {}", url, e);
             return Ok(HttpResponse::ServiceUnavailable().body(e.to_string()));
         }
     };
-    Ok(new_resp.body(body))
+    let (resp, mut session, mut msg_stream) = match actix_ws::handle(&req, payload) {
+        Ok(v) => v,
+        Err(e) => return Ok(HttpResponse::BadRequest().body(e.to_string())),
+    };
+
+    let (mut upstream_tx, mut upstream_rx) = upstream.split();
+    actix_web::rt::spawn(async move {
+        let reason = loop {
+            tokio::select! {
+                msg = msg_stream.next() => match msg {
+                    Some(Ok(awc::ws::Message::Close(reason))) => {
+                        let _ = upstream_tx
+                            .send(awc::ws::Message::Close(reason.clone()))
+                            .await;
+                        break reason;
+                    }
+                    Some(Ok(msg)) => {
+                        if upstream_tx.send(msg).await.is_err() {
+                            break None;
+                        }
+                    }
+                    Some(Err(e)) => {
+                        log::error!("dispatch: {}, error: {}", url, e);
+                        break None;
+                    }
+                    None => break None,
+                },
+                frame = upstream_rx.next() => {
+                    let ret = match frame {
+                        Some(Ok(awc::ws::Frame::Text(v))) => match String::from_utf8(v.to_vec()) {
+                            Ok(v) => session.text(v).await,
+                            Err(e) => {
+                                log::error!("dispatch: {}, error: {}", url, e);
+                                break None;
+                            }
+                        },
+                        Some(Ok(awc::ws::Frame::Binary(v))) => session.binary(v).await,
+                        Some(Ok(awc::ws::Frame::Ping(v))) => session.ping(&v).await,
+                        Some(Ok(awc::ws::Frame::Pong(v))) => session.pong(&v).await,
+                        Some(Ok(awc::ws::Frame::Close(reason))) => break reason,
+                        // the nodes don't fragment their messages
+                        Some(Ok(awc::ws::Frame::Continuation(_))) => Ok(()),
+                        Some(Err(e)) => {
+                            log::error!("dispatch: {}, error: {}", url, e);
+                            break None;
+                        }
+                        None => break None,
+                    };
+                    // closed by the client
+                    if ret.is_err() {
+                        let _ = upstream_tx.send(awc::ws::Message::Close(None)).await;
+                        return;
+                    }
+                }
+            }
+        };
+        let _ = session.close(reason).await;
+    });
+    Ok(resp)
 }
 
 async fn get_url(path: &str) -> URLDetails {
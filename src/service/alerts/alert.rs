@@ -27,6 +27,8 @@ use config::{
         alerts::{
             alert::{Alert, AlertListFilter},
             destinations::{DestinationType, DestinationWithTemplate, HTTPType},
+            grouping::AlertGroupState,
+            history::AlertStatus,
             FrequencyType, Operator, QueryType,
         },
         search::{SearchEventContext, SearchEventType},
@@ -34,7 +36,7 @@ use config::{
     },
     utils::{
         base64,
-        json::{Map, Value},
+        json::{self, Map, Value},
     },
     SMTP_CLIENT,
 };
@@ -47,7 +49,13 @@ use crate::{
         utils::auth::{is_ofga_unsupported, remove_ownership, set_ownership},
     },
     service::{
-        alerts::{build_sql, destinations, QueryConditionExt},
+        alerts::{
+            build_sql, destinations,
+            grouping::{self, GroupAction},
+            history,
+            payloads::{self, PAGERDUTY_EVENTS_URL},
+            QueryConditionExt,
+        },
         db,
         search::sql::RE_ONLY_SELECT,
         short_url,
@@ -295,6 +303,11 @@ pub async fn delete(
     }
     match db::alerts::alert::delete(org_id, stream_type, stream_name, name).await {
         Ok(_) => {
+            if let Err(e) = grouping::clear(org_id, stream_type, stream_name, name).await {
+                log::error!(
+                    "Error deleting the group states of alert {org_id}/{stream_type}/{stream_name}/{name}: {e}"
+                );
+            }
             remove_ownership(org_id, "alerts", Authz::new(name)).await;
             Ok(())
         }
@@ -363,6 +376,20 @@ pub trait AlertExt: Sync + Send + 'static {
         rows_end_time: i64,
         start_time: Option<i64>,
     ) -> Result<(String, String), anyhow::Error>;
+
+    /// Groups the rows, then sends the notifications of the groups which are neither
+    /// waiting, duplicated nor inhibited, see [`super::grouping`]. Returns the same
+    /// messages as [`AlertExt::send_notification`].
+    async fn notify(
+        &self,
+        rows: &[Map<String, Value>],
+        rows_end_time: i64,
+        start_time: Option<i64>,
+    ) -> Result<(String, String), anyhow::Error>;
+
+    /// Resolves the pending and firing groups of the alert, when its condition isn't
+    /// satisfied anymore.
+    async fn resolve(&self) -> Result<(), anyhow::Error>;
 }
 
 #[async_trait]
@@ -399,40 +426,196 @@ impl AlertExt for Alert {
         rows_end_time: i64,
         start_time: Option<i64>,
     ) -> Result<(String, String), anyhow::Error> {
+        send_notifications(self, rows, rows_end_time, start_time, None).await
+    }
+
+    async fn notify(
+        &self,
+        rows: &[Map<String, Value>],
+        rows_end_time: i64,
+        start_time: Option<i64>,
+    ) -> Result<(String, String), anyhow::Error> {
+        let now = Utc::now().timestamp_micros();
+        let groups = grouping::prepare(self, rows, now).await?;
         let mut err_message = "".to_string();
         let mut success_message = "".to_string();
+        let mut no_of_sent = 0;
         let mut no_of_error = 0;
-        for dest in self.destinations.iter() {
-            let dest = destinations::get_with_template(&self.org_id, dest).await?;
-            match send_notification(self, &dest, rows, rows_end_time, start_time).await {
-                Ok(resp) => {
-                    success_message =
-                        format!("{success_message} destination {} {resp};", dest.name);
+        let mut seen = Vec::with_capacity(groups.len());
+        for mut group in groups {
+            let satisfied = format!("condition satisfied by {} rows", group.rows.len());
+            let reason = match &group.action {
+                GroupAction::Notify => {
+                    match send_notifications(
+                        self,
+                        &group.rows,
+                        rows_end_time,
+                        start_time,
+                        Some(&group.state),
+                    )
+                    .await
+                    {
+                        Ok((success, err)) => {
+                            no_of_sent += 1;
+                            group.state.last_notified_at = now;
+                            group.state.rows_fingerprint = group.rows_fingerprint.clone();
+                            success_message = format!("{success_message} {success}");
+                            err_message = format!("{err_message} {err}");
+                            format!("{satisfied}, notified")
+                        }
+                        Err(e) => {
+                            no_of_error += 1;
+                            err_message = format!("{err_message} {e}");
+                            format!("{satisfied}, notification failed: {}", e.to_string().trim())
+                        }
+                    }
                 }
-                Err(e) => {
-                    log::error!(
-                        "Error sending notification for {}/{}/{}/{} for destination {} err: {}",
+                GroupAction::Wait => {
+                    success_message = format!(
+                        "{success_message} group {} waiting;",
+                        group.state.fingerprint
+                    );
+                    format!("{satisfied}, waiting before notifying")
+                }
+                GroupAction::Duplicate => {
+                    success_message = format!(
+                        "{success_message} group {} already notified;",
+                        group.state.fingerprint
+                    );
+                    format!("{satisfied}, already notified")
+                }
+                GroupAction::Inhibited(rule) => {
+                    log::info!(
+                        "Alert {}/{}/{}/{} group {} inhibited by rule {rule}",
                         self.org_id,
                         self.stream_type,
                         self.stream_name,
                         self.name,
-                        dest.name,
-                        e
+                        group.state.fingerprint
                     );
-                    no_of_error += 1;
-                    err_message = format!(
-                        "{err_message} Error sending notification for destination {} err: {e};",
-                        dest.name
+                    success_message = format!(
+                        "{success_message} group {} inhibited by {rule};",
+                        group.state.fingerprint
                     );
+                    format!("{satisfied}, inhibited by rule {rule}")
+                }
+            };
+            // only the changes of status and the notifications are kept in the history
+            if group.prev_status != Some(group.state.status) || group.action == GroupAction::Notify
+            {
+                if self.is_real_time {
+                    // keeps the history out of the ingestion
+                    let (alert, state) = (self.clone(), group.state.clone());
+                    tokio::task::spawn(async move {
+                        history::record(&alert, &state, now, reason).await;
+                    });
+                } else {
+                    history::record(self, &group.state, now, reason).await;
                 }
             }
+            if let Err(e) = grouping::save(self, &group).await {
+                log::error!(
+                    "Error saving the state of alert group {}: {e}",
+                    group.state.fingerprint
+                );
+            }
+            seen.push(group.state.fingerprint);
         }
-        if no_of_error == self.destinations.len() {
+        // only the groups with a state can be resolved
+        if grouping::is_stateful(self) {
+            if let Err(e) = resolve_groups(self, &seen, now).await {
+                log::error!(
+                    "Error resolving the groups of alert {}/{}/{}/{}: {e}",
+                    self.org_id,
+                    self.stream_type,
+                    self.stream_name,
+                    self.name
+                );
+            }
+        }
+        if no_of_error > 0 && no_of_sent == 0 {
             Err(anyhow::anyhow!(err_message))
         } else {
             Ok((success_message, err_message))
         }
     }
+
+    async fn resolve(&self) -> Result<(), anyhow::Error> {
+        if !grouping::is_stateful(self) {
+            return Ok(());
+        }
+        resolve_groups(self, &[], Utc::now().timestamp_micros()).await
+    }
+}
+
+/// Resolves the groups of the alert which aren't firing anymore, see
+/// [`grouping::resolvable`]. The destinations are notified when the alert has
+/// `send_resolved` and the group was notified, the state of the group is then deleted.
+async fn resolve_groups(alert: &Alert, seen: &[String], now: i64) -> Result<(), anyhow::Error> {
+    for mut state in grouping::resolvable(alert, seen).await? {
+        let was_notified = state.status == AlertStatus::Firing && state.last_notified_at > 0;
+        state.status = AlertStatus::Resolved;
+        let mut reason = "condition not satisfied anymore".to_string();
+        if was_notified && alert.send_resolved {
+            let rows = grouping::resolved_rows(alert, &state);
+            match send_notifications(alert, &rows, now, None, Some(&state)).await {
+                Ok(_) => {
+                    state.last_notified_at = now;
+                    reason = format!("{reason}, notified");
+                }
+                Err(e) => {
+                    reason = format!("{reason}, notification failed: {}", e.to_string().trim());
+                }
+            }
+        }
+        history::record(alert, &state, now, reason).await;
+        db::alerts::groups::delete(&alert.org_id, &state.alert, &state.fingerprint).await?;
+    }
+    Ok(())
+}
+
+/// Sends the notification of the group to all the destinations of the alert, returns the
+/// success and the error messages, or an error if none of the destinations could be
+/// notified. Without group, e.g. for a manual trigger, the alert is notified as firing.
+async fn send_notifications(
+    alert: &Alert,
+    rows: &[Map<String, Value>],
+    rows_end_time: i64,
+    start_time: Option<i64>,
+    group: Option<&AlertGroupState>,
+) -> Result<(String, String), anyhow::Error> {
+    let mut err_message = "".to_string();
+    let mut success_message = "".to_string();
+    let mut no_of_error = 0;
+    for dest in alert.destinations.iter() {
+        let dest = destinations::get_with_template(&alert.org_id, dest).await?;
+        match send_notification(alert, &dest, rows, rows_end_time, start_time, group).await {
+            Ok(resp) => {
+                success_message = format!("{success_message} destination {} {resp};", dest.name);
+            }
+            Err(e) => {
+                log::error!(
+                    "Error sending notification for {}/{}/{}/{} for destination {} err: {}",
+                    alert.org_id,
+                    alert.stream_type,
+                    alert.stream_name,
+                    alert.name,
+                    dest.name,
+                    e
+                );
+                no_of_error += 1;
+                err_message = format!(
+                    "{err_message} Error sending notification for destination {} err: {e};",
+                    dest.name
+                );
+            }
+        }
+    }
+    if no_of_error == alert.destinations.len() {
+        Err(anyhow::anyhow!(err_message))
+    } else {
+        Ok((success_message, err_message))
+    }
 }
 
 pub async fn send_notification(
@@ -441,41 +624,120 @@ pub async fn send_notification(
     rows: &[Map<String, Value>],
     rows_end_time: i64,
     start_time: Option<i64>,
+    group: Option<&AlertGroupState>,
 ) -> Result<String, anyhow::Error> {
+    let status = group.map_or(AlertStatus::Firing, |group| group.status);
     let rows_tpl_val = if alert.row_template.is_empty() {
         vec!["".to_string()]
     } else {
-        process_row_template(&alert.row_template, alert, rows)
+        process_row_template(&alert.row_template, alert, rows, status)
     };
     let msg: String = process_dest_template(
         &dest.template.body,
         alert,
         rows,
         &rows_tpl_val,
-        rows_end_time,
-        start_time,
+        (rows_end_time, start_time),
+        status,
     )
     .await;
 
-    let email_subject = if !dest.template.title.is_empty() {
+    let title = if !dest.template.title.is_empty() {
         process_dest_template(
             &dest.template.title,
             alert,
             rows,
             &rows_tpl_val,
-            rows_end_time,
-            start_time,
+            (rows_end_time, start_time),
+            status,
         )
         .await
-    } else {
+    } else if dest.destination_type == DestinationType::Email {
         dest.template.name.clone()
+    } else {
+        alert.name.clone()
     };
 
     match dest.destination_type {
         DestinationType::Http => send_http_notification(dest, msg.clone()).await,
-        DestinationType::Email => send_email_notification(&email_subject, dest, msg).await,
+        DestinationType::Email => send_email_notification(&title, dest, msg).await,
         DestinationType::Sns => send_sns_notification(&alert.name, dest, msg).await,
+        DestinationType::Slack => {
+            let payload = payloads::slack(alert, &title, &msg, status);
+            send_json_notification(dest, &dest.url, &payload).await
+        }
+        DestinationType::Teams => {
+            let payload = payloads::teams(alert, &title, &msg, status);
+            send_json_notification(dest, &dest.url, &payload).await
+        }
+        DestinationType::PagerDuty => {
+            // the events of a group share its fingerprint, so its incident gets resolved
+            let dedup_key = match group {
+                Some(group) => group.fingerprint.clone(),
+                None => format!("{}/{}", alert.org_id, grouping::alert_key(alert)),
+            };
+            let payload =
+                payloads::pagerduty(alert, dest, &dedup_key, (&title, &msg), status, rows.len());
+            let url = if dest.url.is_empty() {
+                PAGERDUTY_EVENTS_URL
+            } else {
+                &dest.url
+            };
+            send_json_notification(dest, url, &payload).await
+        }
+    }
+}
+
+/// Posts the JSON payload of a native destination to `url`, with the headers of the
+/// destination.
+pub async fn send_json_notification(
+    dest: &DestinationWithTemplate,
+    url: &str,
+    payload: &Value,
+) -> Result<String, anyhow::Error> {
+    let client = if dest.skip_tls_verify {
+        reqwest::Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()?
+    } else {
+        reqwest::Client::new()
+    };
+    let mut req = client.post(url::Url::parse(url)?);
+    if let Some(headers) = &dest.headers {
+        for (key, value) in headers.iter() {
+            if !key.is_empty() && !value.is_empty() && key.to_lowercase().trim() != "content-type" {
+                req = req.header(key, value);
+            }
+        }
+    }
+    let resp = req
+        .header("Content-type", "application/json")
+        .body(json::to_string(payload)?)
+        .send()
+        .await?;
+    let resp_status = resp.status();
+    let resp_body = resp.text().await?;
+    log::debug!(
+        "Alert sent to destination {} with status: {}, body: {:?}",
+        dest.name,
+        resp_status,
+        resp_body,
+    );
+    if !resp_status.is_success() {
+        log::error!(
+            "Alert {:?} notification failed with status: {}, body: {}",
+            dest.destination_type,
+            resp_status,
+            resp_body,
+        );
+        return Err(anyhow::anyhow!(
+            "sent error status: {}, err: {}",
+            resp_status,
+            resp_body
+        ));
     }
+
+    Ok(format!("sent status: {}, body: {}", resp_status, resp_body))
 }
 
 pub async fn send_http_notification(
@@ -611,7 +873,12 @@ pub async fn send_sns_notification(
     }
 }
 
-fn process_row_template(tpl: &String, alert: &Alert, rows: &[Map<String, Value>]) -> Vec<String> {
+fn process_row_template(
+    tpl: &String,
+    alert: &Alert,
+    rows: &[Map<String, Value>],
+    status: AlertStatus,
+) -> Vec<String> {
     let alert_type = if alert.is_real_time {
         "realtime"
     } else {
@@ -679,6 +946,7 @@ fn process_row_template(tpl: &String, alert: &Alert, rows: &[Map<String, Value>]
             .replace("{stream_name}", &alert.stream_name)
             .replace("{alert_name}", &alert.name)
             .replace("{alert_type}", alert_type)
+            .replace("{alert_status}", &status.to_string())
             .replace(
                 "{alert_period}",
                 &alert.trigger_condition.period.to_string(),
@@ -718,8 +986,8 @@ async fn process_dest_template(
     alert: &Alert,
     rows: &[Map<String, Value>],
     rows_tpl_val: &[String],
-    rows_end_time: i64,
-    start_time: Option<i64>,
+    (rows_end_time, start_time): (i64, Option<i64>),
+    status: AlertStatus,
 ) -> String {
     let cfg = get_config();
     // format values
@@ -875,6 +1143,7 @@ async fn process_dest_template(
         .replace("{stream_name}", &alert.stream_name)
         .replace("{alert_name}", &alert.name)
         .replace("{alert_type}", alert_type)
+        .replace("{alert_status}", &status.to_string())
         .replace(
             "{alert_period}",
             &alert.trigger_condition.period.to_string(),
@@ -1108,4 +1377,80 @@ mod tests {
         // alert name should not contain /
         assert!(ret.is_err());
     }
+
+    /// Accepts a single HTTP request and returns its body.
+    async fn mock_server(listener: tokio::net::TcpListener) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 1024];
+        let body = loop {
+            let n = stream.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+            let req = String::from_utf8_lossy(&buf).to_string();
+            if let Some((head, body)) = req.split_once("\r\n\r\n") {
+                let len = head
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().ok())?
+                    })
+                    .unwrap_or_default();
+                if body.len() >= len {
+                    break body.to_string();
+                }
+            }
+        };
+        stream
+            .write_all(b"HTTP/1.1 202 Accepted\r\ncontent-length: 2\r\n\r\n{}")
+            .await
+            .unwrap();
+        body
+    }
+
+    #[tokio::test]
+    async fn test_send_pagerduty_notification() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v2/enqueue", listener.local_addr().unwrap());
+        let server = tokio::spawn(mock_server(listener));
+
+        let alert = Alert {
+            name: "HighLatency".to_string(),
+            org_id: "default".to_string(),
+            stream_name: "k8s_logs".to_string(),
+            ..Default::default()
+        };
+        let dest = DestinationWithTemplate {
+            name: "oncall".to_string(),
+            url: url.clone(),
+            method: HTTPType::POST,
+            skip_tls_verify: false,
+            headers: None,
+            template: Default::default(),
+            emails: vec![],
+            destination_type: DestinationType::PagerDuty,
+            sns_topic_arn: None,
+            aws_region: None,
+            routing_key: Some("R0UT1NGK3Y".to_string()),
+            severity: None,
+        };
+        let payload = payloads::pagerduty(
+            &alert,
+            &dest,
+            "0123456789abcdef",
+            ("latency", "p99 is high"),
+            AlertStatus::Firing,
+            1,
+        );
+        let resp = send_json_notification(&dest, &url, &payload).await.unwrap();
+        assert!(resp.starts_with("sent status: 202"));
+
+        let body: Value = json::from_str(&server.await.unwrap()).unwrap();
+        assert_eq!(body["routing_key"], "R0UT1NGK3Y");
+        assert_eq!(body["event_action"], "trigger");
+        assert_eq!(body["dedup_key"], "0123456789abcdef");
+        assert_eq!(body["payload"]["severity"], "critical");
+    }
 }
@@ -22,7 +22,10 @@ use crate::{
         meta::authz::Authz,
         utils::auth::{is_ofga_unsupported, remove_ownership, set_ownership},
     },
-    service::db::{self, user},
+    service::{
+        alerts::payloads::PAGERDUTY_SEVERITIES,
+        db::{self, user},
+    },
 };
 
 pub async fn save(
@@ -82,6 +85,37 @@ pub async fn save(
                 ));
             }
         }
+        DestinationType::Slack | DestinationType::Teams => {
+            if destination.url.is_empty() {
+                return Err((
+                    http::StatusCode::BAD_REQUEST,
+                    anyhow::anyhow!("Webhook URL is required for Slack and Teams destinations"),
+                ));
+            }
+        }
+        DestinationType::PagerDuty => {
+            if destination
+                .routing_key
+                .as_ref()
+                .map_or(true, |key| key.is_empty())
+            {
+                return Err((
+                    http::StatusCode::BAD_REQUEST,
+                    anyhow::anyhow!("Routing key is required for PagerDuty destinations"),
+                ));
+            }
+            if let Some(severity) = destination.severity.as_ref() {
+                if !PAGERDUTY_SEVERITIES.contains(&severity.as_str()) {
+                    return Err((
+                        http::StatusCode::BAD_REQUEST,
+                        anyhow::anyhow!(
+                            "PagerDuty severity must be one of {}",
+                            PAGERDUTY_SEVERITIES.join(", ")
+                        ),
+                    ));
+                }
+            }
+        }
     }
 
     if !name.is_empty() {
@@ -0,0 +1,514 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Grouping, deduplication and inhibition of the alert notifications.
+//!
+//! The rows of an evaluation are split by the `group_by` labels of the alert, each group is
+//! identified by a fingerprint of the alert and its labels. The state of the groups is kept
+//! in the meta store, so a group is only notified once `group_wait` has passed, then again
+//! when its rows change (at most every `group_interval`) or every `repeat_interval`.
+//! A group is not notified at all while a firing group matches the source of one of the
+//! inhibition rules of the organization.
+//!
+//! A group is `pending` while it waits for `group_wait`, then `firing`, and `resolved` once
+//! it isn't part of the rows of an evaluation anymore, its state is then deleted.
+//!
+//! The states are only kept for the scheduled alerts with grouping. The other alerts are
+//! notified at every evaluation satisfying their condition, split by `group_by` for the
+//! realtime alerts, and can't be the source of an inhibition. The realtime alerts are
+//! evaluated at ingestion and don't touch the meta store.
+
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use config::{
+    get_config,
+    meta::{
+        alerts::{
+            alert::Alert,
+            grouping::{AlertGroupState, AlertGrouping},
+            history::AlertStatus,
+            inhibitions::InhibitionRule,
+        },
+        stream::StreamType,
+    },
+    utils::{
+        hash::{gxhash, Sum64},
+        json::{Map, Value},
+        time::{hour_micros, second_micros},
+    },
+};
+use hashbrown::HashMap;
+
+use crate::service::db;
+
+/// How long the states of the groups not resolved, e.g. of the disabled alerts, are kept
+/// after their expiry
+const STATE_RETENTION_HOURS: i64 = 24;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GroupAction {
+    Notify,
+    /// The group is new and waits for `group_wait`
+    Wait,
+    /// The group was notified recently
+    Duplicate,
+    /// The group is muted by the inhibition rule
+    Inhibited(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct NotificationGroup {
+    pub state: AlertGroupState,
+    pub rows: Vec<Map<String, Value>>,
+    pub rows_fingerprint: String,
+    pub action: GroupAction,
+    /// The status of the group before this evaluation, `None` for a new group
+    pub prev_status: Option<AlertStatus>,
+}
+
+/// Splits the rows of an alert evaluation into groups and decides which ones must be
+/// notified now. The states of the groups must be saved with [`save`] afterwards.
+pub async fn prepare(
+    alert: &Alert,
+    rows: &[Map<String, Value>],
+    now: i64,
+) -> Result<Vec<NotificationGroup>, anyhow::Error> {
+    let stateful = is_stateful(alert);
+    let rules = db::alerts::inhibitions::list(&alert.org_id)
+        .await?
+        .into_iter()
+        .filter(|rule| rule.enabled)
+        .collect::<Vec<_>>();
+    // listed once, when a rule targets one of the groups
+    let mut firing = None;
+
+    let alert_key = alert_key(alert);
+    let mut groups = Vec::new();
+    for (labels, rows) in group_rows(alert, rows) {
+        let fingerprint = fingerprint(&alert.org_id, &alert_key, &labels);
+        let prev = if stateful {
+            db::alerts::groups::get(&alert.org_id, &alert_key, &fingerprint)
+                .await?
+                .filter(|state| state.is_active(now))
+        } else {
+            None
+        };
+        let mut state = prev.clone().unwrap_or_else(|| AlertGroupState {
+            fingerprint,
+            alert: alert_key.clone(),
+            started_at: now,
+            ..Default::default()
+        });
+        state.labels = labels;
+        state.last_seen_at = now;
+        state.expires_at = now + expiry(alert);
+
+        let rows_fingerprint = rows_fingerprint(&rows);
+        let action = match inhibited_by(&alert.org_id, &rules, &mut firing, &state, now).await? {
+            Some(rule) => GroupAction::Inhibited(rule.name.clone()),
+            None => decide(
+                alert.grouping.as_ref().filter(|_| stateful),
+                prev.as_ref(),
+                &rows_fingerprint,
+                now,
+            ),
+        };
+        state.status = if action == GroupAction::Wait {
+            AlertStatus::Pending
+        } else {
+            AlertStatus::Firing
+        };
+        groups.push(NotificationGroup {
+            state,
+            rows,
+            rows_fingerprint,
+            action,
+            prev_status: prev.map(|prev| prev.status),
+        });
+    }
+    Ok(groups)
+}
+
+/// Saves the state of the group, nothing is saved for the alerts without state.
+pub async fn save(alert: &Alert, group: &NotificationGroup) -> Result<(), anyhow::Error> {
+    if !is_stateful(alert) {
+        return Ok(());
+    }
+    db::alerts::groups::set(&alert.org_id, &group.state).await
+}
+
+/// Whether the states of the groups of the alert are kept, only for the scheduled alerts
+/// with grouping.
+pub fn is_stateful(alert: &Alert) -> bool {
+    alert.grouping.is_some() && !alert.is_real_time
+}
+
+/// Deletes the states of the groups of the alert, e.g. when it's deleted.
+pub async fn clear(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    alert_name: &str,
+) -> Result<(), anyhow::Error> {
+    db::alerts::groups::delete_alert(org_id, &format!("{stream_type}/{stream_name}/{alert_name}"))
+        .await
+}
+
+/// Deletes the states of the groups which expired for `STATE_RETENTION_HOURS`, the groups of
+/// the alerts disabled or not evaluated anymore are never resolved.
+pub async fn cleanup() -> Result<usize, anyhow::Error> {
+    let expired_before = Utc::now().timestamp_micros() - hour_micros(STATE_RETENTION_HOURS);
+    let mut deleted = 0;
+    for (org_id, state) in db::alerts::groups::list_all().await? {
+        if state.expires_at >= expired_before {
+            continue;
+        }
+        db::alerts::groups::delete(&org_id, &state.alert, &state.fingerprint).await?;
+        deleted += 1;
+    }
+    Ok(deleted)
+}
+
+/// Returns the pending and firing groups of the alert which must be resolved, the ones
+/// not `seen` in the current evaluation. Only the alerts with a state can be resolved, see
+/// [`is_stateful`], the realtime alerts are never resolved.
+pub async fn resolvable(
+    alert: &Alert,
+    seen: &[String],
+) -> Result<Vec<AlertGroupState>, anyhow::Error> {
+    let Some(grouping) = alert.grouping.as_ref().filter(|_| is_stateful(alert)) else {
+        return Ok(vec![]);
+    };
+    let alert_key = alert_key(alert);
+    let states = if grouping.group_by.is_empty() {
+        // a single group, no need to list them
+        let fingerprint = fingerprint(&alert.org_id, &alert_key, &alert_labels(alert));
+        db::alerts::groups::get(&alert.org_id, &alert_key, &fingerprint)
+            .await?
+            .into_iter()
+            .collect()
+    } else {
+        db::alerts::groups::list_alert(&alert.org_id, &alert_key).await?
+    };
+    Ok(states
+        .into_iter()
+        .filter(|state| state.status != AlertStatus::Resolved && !seen.contains(&state.fingerprint))
+        .collect())
+}
+
+/// The row of the resolved notification of a group: its grouping labels, or all of its
+/// labels when the alert isn't grouped by any field.
+pub fn resolved_rows(alert: &Alert, state: &AlertGroupState) -> Vec<Map<String, Value>> {
+    let group_by = alert
+        .grouping
+        .as_ref()
+        .map(|grouping| grouping.group_by.as_slice())
+        .unwrap_or_default();
+    let row = state
+        .labels
+        .iter()
+        .filter(|(name, _)| group_by.is_empty() || group_by.contains(name))
+        .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+        .collect::<Map<_, _>>();
+    vec![row]
+}
+
+/// The key of the alert in the group states, `stream_type/stream_name/alert_name`.
+pub fn alert_key(alert: &Alert) -> String {
+    format!("{}/{}/{}", alert.stream_type, alert.stream_name, alert.name)
+}
+
+/// The labels of the alert shared by all of its groups.
+pub fn alert_labels(alert: &Alert) -> HashMap<String, String> {
+    let mut labels = alert.context_attributes.clone().unwrap_or_default();
+    labels.insert("alert_name".to_string(), alert.name.clone());
+    labels.insert("stream_type".to_string(), alert.stream_type.to_string());
+    labels.insert("stream_name".to_string(), alert.stream_name.clone());
+    labels
+}
+
+fn group_rows(
+    alert: &Alert,
+    rows: &[Map<String, Value>],
+) -> Vec<(HashMap<String, String>, Vec<Map<String, Value>>)> {
+    let labels = alert_labels(alert);
+    let group_by = match alert.grouping.as_ref() {
+        Some(grouping) if !grouping.group_by.is_empty() => &grouping.group_by,
+        _ => return vec![(labels, rows.to_vec())],
+    };
+
+    // BTreeMap keeps the notification order stable
+    let mut groups: BTreeMap<Vec<String>, Vec<Map<String, Value>>> = BTreeMap::new();
+    for row in rows {
+        let key = group_by
+            .iter()
+            .map(|name| match row.get(name) {
+                Some(value) => value_to_string(value),
+                None => labels.get(name).cloned().unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+        groups.entry(key).or_default().push(row.clone());
+    }
+    // the manual trigger of an alert has no rows, still notify it once
+    if groups.is_empty() {
+        groups.insert(vec![String::new(); group_by.len()], vec![]);
+    }
+    groups
+        .into_iter()
+        .map(|(values, rows)| {
+            let mut labels = labels.clone();
+            for (name, value) in group_by.iter().zip(values) {
+                labels.insert(name.clone(), value);
+            }
+            (labels, rows)
+        })
+        .collect()
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(v) => v.clone(),
+        Value::Null => String::new(),
+        v => v.to_string(),
+    }
+}
+
+fn fingerprint(org_id: &str, alert_key: &str, labels: &HashMap<String, String>) -> String {
+    let mut labels = labels.iter().collect::<Vec<_>>();
+    labels.sort();
+    let mut key = format!("{org_id}/{alert_key}");
+    for (name, value) in labels {
+        key.push_str(&format!("\n{name}={value}"));
+    }
+    format!("{:016x}", gxhash::new().sum64(&key))
+}
+
+/// Fingerprint of the series of the rows, only their string values are considered as the
+/// numbers and timestamps change at every evaluation.
+fn rows_fingerprint(rows: &[Map<String, Value>]) -> String {
+    let timestamp_col = &get_config().common.column_timestamp;
+    let mut series = rows
+        .iter()
+        .map(|row| {
+            let mut fields = row
+                .iter()
+                .filter(|(name, value)| *name != timestamp_col && value.is_string())
+                .map(|(name, value)| format!("{name}={}", value.as_str().unwrap_or_default()))
+                .collect::<Vec<_>>();
+            fields.sort();
+            fields.join(",")
+        })
+        .collect::<Vec<_>>();
+    series.sort();
+    series.dedup();
+    format!("{:016x}", gxhash::new().sum64(&series.join("\n")))
+}
+
+/// How long a group stays firing when it isn't seen again, two evaluations of the alert.
+fn expiry(alert: &Alert) -> i64 {
+    let interval = std::cmp::max(
+        alert.trigger_condition.frequency,
+        alert.trigger_condition.silence * 60,
+    );
+    second_micros(2 * std::cmp::max(interval, 60))
+}
+
+/// Returns the rule inhibiting the group, the firing groups are only listed when a rule
+/// targets the group.
+async fn inhibited_by<'a>(
+    org_id: &str,
+    rules: &'a [InhibitionRule],
+    firing: &mut Option<Vec<AlertGroupState>>,
+    target: &AlertGroupState,
+    now: i64,
+) -> Result<Option<&'a InhibitionRule>, anyhow::Error> {
+    let rules = rules
+        .iter()
+        .filter(|rule| rule.targets(&target.labels))
+        .collect::<Vec<_>>();
+    if rules.is_empty() {
+        return Ok(None);
+    }
+    if firing.is_none() {
+        *firing = Some(
+            db::alerts::groups::list(org_id)
+                .await?
+                .into_iter()
+                .filter(|state| state.status == AlertStatus::Firing && state.is_active(now))
+                .collect(),
+        );
+    }
+    let firing = firing.as_deref().unwrap_or_default();
+    Ok(rules.into_iter().find(|rule| {
+        firing.iter().any(|source| {
+            source.fingerprint != target.fingerprint
+                && rule.inhibits(&source.labels, &target.labels)
+        })
+    }))
+}
+
+fn decide(
+    grouping: Option<&AlertGrouping>,
+    prev: Option<&AlertGroupState>,
+    rows_fingerprint: &str,
+    now: i64,
+) -> GroupAction {
+    let Some(grouping) = grouping else {
+        return GroupAction::Notify;
+    };
+    match prev {
+        Some(prev) if prev.last_notified_at > 0 => {
+            let elapsed = now - prev.last_notified_at;
+            let interval = if prev.rows_fingerprint != rows_fingerprint {
+                grouping.group_interval
+            } else {
+                grouping.repeat_interval
+            };
+            if elapsed >= second_micros(interval) {
+                GroupAction::Notify
+            } else {
+                GroupAction::Duplicate
+            }
+        }
+        Some(prev) if now - prev.started_at >= second_micros(grouping.group_wait) => {
+            GroupAction::Notify
+        }
+        None if grouping.group_wait <= 0 => GroupAction::Notify,
+        _ => GroupAction::Wait,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(host: &str, value: i64) -> Map<String, Value> {
+        let mut row = Map::new();
+        row.insert("host".to_string(), Value::String(host.to_string()));
+        row.insert("value".to_string(), value.into());
+        row
+    }
+
+    #[test]
+    fn test_group_rows() {
+        let mut alert = Alert {
+            name: "HostDown".to_string(),
+            stream_name: "up".to_string(),
+            ..Default::default()
+        };
+        let rows = vec![row("a", 1), row("b", 1), row("a", 2)];
+        let groups = group_rows(&alert, &rows);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].1.len(), 3);
+        assert_eq!(groups[0].0.get("alert_name").unwrap(), "HostDown");
+
+        alert.grouping = Some(AlertGrouping {
+            group_by: vec!["host".to_string()],
+            ..Default::default()
+        });
+        let groups = group_rows(&alert, &rows);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].0.get("host").unwrap(), "a");
+        assert_eq!(groups[0].1.len(), 2);
+        assert_eq!(groups[1].0.get("host").unwrap(), "b");
+        assert_ne!(
+            fingerprint("default", "metrics/up/HostDown", &groups[0].0),
+            fingerprint("default", "metrics/up/HostDown", &groups[1].0)
+        );
+    }
+
+    #[test]
+    fn test_rows_fingerprint() {
+        // the values don't change the fingerprint, the series do
+        assert_eq!(
+            rows_fingerprint(&[row("a", 1), row("b", 1)]),
+            rows_fingerprint(&[row("b", 5), row("a", 3)])
+        );
+        assert_ne!(
+            rows_fingerprint(&[row("a", 1)]),
+            rows_fingerprint(&[row("a", 1), row("b", 1)])
+        );
+    }
+
+    #[test]
+    fn test_resolved_rows() {
+        let mut alert = Alert::default();
+        let state = AlertGroupState {
+            labels: HashMap::from([
+                ("alert_name".to_string(), "HostDown".to_string()),
+                ("host".to_string(), "a".to_string()),
+            ]),
+            ..Default::default()
+        };
+        // not grouped by any field, all the labels
+        let rows = resolved_rows(&alert, &state);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("alert_name").unwrap(), "HostDown");
+        assert_eq!(rows[0].get("host").unwrap(), "a");
+
+        alert.grouping = Some(AlertGrouping {
+            group_by: vec!["host".to_string()],
+            ..Default::default()
+        });
+        let rows = resolved_rows(&alert, &state);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("host").unwrap(), "a");
+        assert!(rows[0].get("alert_name").is_none());
+    }
+
+    #[test]
+    fn test_decide() {
+        let grouping = AlertGrouping {
+            group_by: vec![],
+            group_wait: 30,
+            group_interval: 300,
+            repeat_interval: 3600,
+        };
+        let now = second_micros(10000);
+        assert_eq!(decide(None, None, "x", now), GroupAction::Notify);
+        assert_eq!(decide(Some(&grouping), None, "x", now), GroupAction::Wait);
+
+        let mut prev = AlertGroupState {
+            started_at: now - second_micros(10),
+            ..Default::default()
+        };
+        assert_eq!(
+            decide(Some(&grouping), Some(&prev), "x", now),
+            GroupAction::Wait
+        );
+        prev.started_at = now - second_micros(30);
+        assert_eq!(
+            decide(Some(&grouping), Some(&prev), "x", now),
+            GroupAction::Notify
+        );
+
+        prev.last_notified_at = now - second_micros(600);
+        prev.rows_fingerprint = "x".to_string();
+        assert_eq!(
+            decide(Some(&grouping), Some(&prev), "x", now),
+            GroupAction::Duplicate
+        );
+        assert_eq!(
+            decide(Some(&grouping), Some(&prev), "y", now),
+            GroupAction::Notify
+        );
+        prev.last_notified_at = now - second_micros(3600);
+        assert_eq!(
+            decide(Some(&grouping), Some(&prev), "x", now),
+            GroupAction::Notify
+        );
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use config::meta::{
+    alerts::{alert::Alert, grouping::AlertGroupState, history::AlertHistoryEntry},
+    stream::StreamType,
+};
+use infra::table::alert_history;
+
+/// Records a change of state or a notification of a group of the alert, the errors are
+/// only logged as the history mustn't prevent the notifications.
+pub async fn record(alert: &Alert, state: &AlertGroupState, timestamp: i64, reason: String) {
+    let entry = AlertHistoryEntry {
+        timestamp,
+        alert_name: alert.name.clone(),
+        stream_type: alert.stream_type.to_string(),
+        stream_name: alert.stream_name.clone(),
+        fingerprint: state.fingerprint.clone(),
+        status: state.status,
+        labels: state.labels.clone(),
+        reason,
+    };
+    if let Err(e) = alert_history::add(&alert.org_id, &entry).await {
+        log::error!(
+            "Error recording the history of alert {}/{}/{}/{}: {e}",
+            alert.org_id,
+            alert.stream_type,
+            alert.stream_name,
+            alert.name
+        );
+    }
+}
+
+/// Lists the history of the alert, the most recent entries first.
+pub async fn list(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    alert_name: &str,
+    (start_time, end_time): (Option<i64>, Option<i64>),
+    size: Option<u64>,
+) -> Result<Vec<AlertHistoryEntry>, anyhow::Error> {
+    Ok(alert_history::list(
+        org_id,
+        &stream_type.to_string(),
+        stream_name,
+        alert_name,
+        (start_time, end_time),
+        size,
+    )
+    .await?)
+}
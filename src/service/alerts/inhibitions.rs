@@ -0,0 +1,83 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use actix_web::http;
+use config::meta::alerts::inhibitions::InhibitionRule;
+
+use crate::service::db;
+
+pub async fn save(
+    org_id: &str,
+    name: &str,
+    mut rule: InhibitionRule,
+    create: bool,
+) -> Result<(), anyhow::Error> {
+    if !name.is_empty() {
+        rule.name = name.to_owned();
+    }
+    rule.name = rule.name.trim().to_string();
+    if rule.name.is_empty() {
+        return Err(anyhow::anyhow!("Inhibition rule name is required"));
+    }
+    if rule.name.contains('/') {
+        return Err(anyhow::anyhow!("Inhibition rule name cannot contain '/'"));
+    }
+    // a rule without matchers would mute every alert as soon as one fires
+    if rule.source_matchers.is_empty() || rule.target_matchers.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Inhibition rule requires source and target matchers"
+        ));
+    }
+
+    match db::alerts::inhibitions::get(org_id, &rule.name).await? {
+        Some(_) => {
+            if create {
+                return Err(anyhow::anyhow!("Inhibition rule already exists"));
+            }
+        }
+        None => {
+            if !create {
+                return Err(anyhow::anyhow!("Inhibition rule not found"));
+            }
+        }
+    }
+    db::alerts::inhibitions::set(org_id, &rule).await
+}
+
+pub async fn get(org_id: &str, name: &str) -> Result<InhibitionRule, anyhow::Error> {
+    db::alerts::inhibitions::get(org_id, name)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Inhibition rule not found"))
+}
+
+pub async fn list(org_id: &str) -> Result<Vec<InhibitionRule>, anyhow::Error> {
+    db::alerts::inhibitions::list(org_id).await
+}
+
+pub async fn delete(org_id: &str, name: &str) -> Result<(), (http::StatusCode, anyhow::Error)> {
+    match db::alerts::inhibitions::get(org_id, name).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return Err((
+                http::StatusCode::NOT_FOUND,
+                anyhow::anyhow!("Inhibition rule not found {}", name),
+            ));
+        }
+        Err(e) => return Err((http::StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+    db::alerts::inhibitions::delete(org_id, name)
+        .await
+        .map_err(|e| (http::StatusCode::INTERNAL_SERVER_ERROR, e))
+}
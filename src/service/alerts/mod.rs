@@ -36,7 +36,14 @@ use crate::service::search as SearchService;
 pub mod alert;
 pub mod derived_streams;
 pub mod destinations;
+pub mod grouping;
+pub mod history;
+pub mod inhibitions;
+pub mod payloads;
+pub mod prom_rules;
+pub mod recording_rules;
 pub mod scheduler;
+pub mod silences;
 pub mod templates;
 
 #[async_trait]
@@ -0,0 +1,294 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The payloads of the native Slack, PagerDuty and Microsoft Teams destinations, built from
+//! the alert and its rendered template: the title of the template (or the alert name) is the
+//! headline and its body the message.
+
+use config::{
+    meta::alerts::{alert::Alert, destinations::DestinationWithTemplate, history::AlertStatus},
+    utils::json::{self, Value},
+};
+
+pub const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+pub const PAGERDUTY_SEVERITIES: [&str; 4] = ["critical", "error", "warning", "info"];
+
+/// Slack limits the text of a header block to 150 characters and of a section to 3000.
+const SLACK_HEADER_MAX_LEN: usize = 150;
+const SLACK_SECTION_MAX_LEN: usize = 3000;
+/// PagerDuty limits the summary of an event to 1024 characters.
+const PAGERDUTY_SUMMARY_MAX_LEN: usize = 1024;
+
+/// Message of a Slack incoming webhook, `text` is the fallback of the notifications.
+pub fn slack(alert: &Alert, title: &str, msg: &str, status: AlertStatus) -> Value {
+    let headline = headline(title, status);
+    let mut blocks = vec![json::json!({
+        "type": "header",
+        "text": {
+            "type": "plain_text",
+            "text": truncate(&headline, SLACK_HEADER_MAX_LEN),
+        }
+    })];
+    if !msg.trim().is_empty() {
+        blocks.push(json::json!({
+            "type": "section",
+            "text": {
+                "type": "mrkdwn",
+                "text": truncate(msg, SLACK_SECTION_MAX_LEN),
+            }
+        }));
+    }
+    blocks.push(json::json!({
+        "type": "context",
+        "elements": [{
+            "type": "mrkdwn",
+            "text": format!(
+                "*Organization:* {} | *Stream:* {}/{} | *Alert:* {}",
+                alert.org_id, alert.stream_type, alert.stream_name, alert.name
+            ),
+        }]
+    }));
+    json::json!({
+        "text": headline,
+        "blocks": blocks,
+    })
+}
+
+/// Message of a Microsoft Teams incoming webhook with an adaptive card.
+pub fn teams(alert: &Alert, title: &str, msg: &str, status: AlertStatus) -> Value {
+    let color = match status {
+        AlertStatus::Resolved => "Good",
+        _ => "Attention",
+    };
+    let mut body = vec![json::json!({
+        "type": "TextBlock",
+        "size": "Large",
+        "weight": "Bolder",
+        "color": color,
+        "wrap": true,
+        "text": headline(title, status),
+    })];
+    if !msg.trim().is_empty() {
+        body.push(json::json!({
+            "type": "TextBlock",
+            "wrap": true,
+            "text": msg,
+        }));
+    }
+    body.push(json::json!({
+        "type": "FactSet",
+        "facts": [
+            { "title": "Organization", "value": alert.org_id },
+            { "title": "Stream", "value": format!("{}/{}", alert.stream_type, alert.stream_name) },
+            { "title": "Alert", "value": alert.name },
+            { "title": "Status", "value": status.to_string() },
+        ]
+    }));
+    json::json!({
+        "type": "message",
+        "attachments": [{
+            "contentType": "application/vnd.microsoft.card.adaptive",
+            "contentUrl": null,
+            "content": {
+                "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                "type": "AdaptiveCard",
+                "version": "1.4",
+                "body": body,
+            }
+        }]
+    })
+}
+
+/// Event of the PagerDuty Events API v2. The events of a group of an alert share the
+/// `dedup_key`, so the incident opened when it fires is resolved with it.
+pub fn pagerduty(
+    alert: &Alert,
+    dest: &DestinationWithTemplate,
+    dedup_key: &str,
+    (title, msg): (&str, &str),
+    status: AlertStatus,
+    alert_count: usize,
+) -> Value {
+    let routing_key = dest.routing_key.as_deref().unwrap_or_default();
+    if status == AlertStatus::Resolved {
+        return json::json!({
+            "routing_key": routing_key,
+            "event_action": "resolve",
+            "dedup_key": dedup_key,
+        });
+    }
+    let mut custom_details = json::Map::new();
+    if let Some(attrs) = alert.context_attributes.as_ref() {
+        for (key, value) in attrs.iter() {
+            custom_details.insert(key.to_string(), value.clone().into());
+        }
+    }
+    custom_details.insert("alert_count".to_string(), alert_count.into());
+    if !msg.trim().is_empty() {
+        custom_details.insert("message".to_string(), msg.into());
+    }
+    json::json!({
+        "routing_key": routing_key,
+        "event_action": "trigger",
+        "dedup_key": dedup_key,
+        "payload": {
+            "summary": truncate(title, PAGERDUTY_SUMMARY_MAX_LEN),
+            "source": format!("{}/{}/{}", alert.org_id, alert.stream_type, alert.stream_name),
+            "severity": severity(alert, dest),
+            "component": alert.stream_name,
+            "group": alert.org_id,
+            "class": alert.name,
+            "custom_details": custom_details,
+        }
+    })
+}
+
+/// The `severity` context attribute of the alert, else the severity of the destination,
+/// else `critical`.
+fn severity<'a>(alert: &'a Alert, dest: &'a DestinationWithTemplate) -> &'a str {
+    alert
+        .context_attributes
+        .as_ref()
+        .and_then(|attrs| attrs.get("severity"))
+        .map(|v| v.as_str())
+        .filter(|v| PAGERDUTY_SEVERITIES.contains(v))
+        .or_else(|| {
+            dest.severity
+                .as_deref()
+                .filter(|v| PAGERDUTY_SEVERITIES.contains(v))
+        })
+        .unwrap_or("critical")
+}
+
+fn headline(title: &str, status: AlertStatus) -> String {
+    format!("[{}] {title}", status.to_string().to_uppercase())
+}
+
+fn truncate(s: &str, max_len: usize) -> String {
+    if s.chars().count() <= max_len {
+        return s.to_string();
+    }
+    let mut s = s.chars().take(max_len - 1).collect::<String>();
+    s.push('…');
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use hashbrown::HashMap;
+
+    use super::*;
+
+    fn alert() -> Alert {
+        Alert {
+            name: "HighLatency".to_string(),
+            org_id: "default".to_string(),
+            stream_name: "k8s_logs".to_string(),
+            context_attributes: Some(HashMap::from([(
+                "severity".to_string(),
+                "warning".to_string(),
+            )])),
+            ..Default::default()
+        }
+    }
+
+    fn pagerduty_dest() -> DestinationWithTemplate {
+        DestinationWithTemplate {
+            name: "oncall".to_string(),
+            url: "".to_string(),
+            method: Default::default(),
+            skip_tls_verify: false,
+            headers: None,
+            template: Default::default(),
+            emails: vec![],
+            destination_type: Default::default(),
+            sns_topic_arn: None,
+            aws_region: None,
+            routing_key: Some("R0UT1NGK3Y".to_string()),
+            severity: Some("error".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_slack() {
+        let payload = slack(&alert(), "latency", "p99 is *high*", AlertStatus::Firing);
+        assert_eq!(payload["text"], "[FIRING] latency");
+        assert_eq!(payload["blocks"][0]["text"]["text"], "[FIRING] latency");
+        assert_eq!(payload["blocks"][1]["text"]["text"], "p99 is *high*");
+        assert_eq!(payload["blocks"].as_array().unwrap().len(), 3);
+
+        let payload = slack(&alert(), &"x".repeat(200), "", AlertStatus::Resolved);
+        let header = payload["blocks"][0]["text"]["text"].as_str().unwrap();
+        assert!(header.starts_with("[RESOLVED] "));
+        assert_eq!(header.chars().count(), SLACK_HEADER_MAX_LEN);
+        assert_eq!(payload["blocks"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_teams() {
+        let payload = teams(&alert(), "latency", "p99 is high", AlertStatus::Resolved);
+        let card = &payload["attachments"][0]["content"];
+        assert_eq!(card["type"], "AdaptiveCard");
+        assert_eq!(card["body"][0]["text"], "[RESOLVED] latency");
+        assert_eq!(card["body"][0]["color"], "Good");
+        assert_eq!(card["body"][2]["facts"][3]["value"], "resolved");
+    }
+
+    #[test]
+    fn test_pagerduty() {
+        let mut alert = alert();
+        let dest = pagerduty_dest();
+        let payload = pagerduty(
+            &alert,
+            &dest,
+            "0123456789abcdef",
+            ("latency", "p99 is high"),
+            AlertStatus::Firing,
+            3,
+        );
+        assert_eq!(payload["routing_key"], "R0UT1NGK3Y");
+        assert_eq!(payload["event_action"], "trigger");
+        assert_eq!(payload["dedup_key"], "0123456789abcdef");
+        assert_eq!(payload["payload"]["severity"], "warning");
+        assert_eq!(payload["payload"]["custom_details"]["alert_count"], 3);
+
+        alert.context_attributes = None;
+        let payload = pagerduty(
+            &alert,
+            &dest,
+            "0123456789abcdef",
+            ("latency", ""),
+            AlertStatus::Firing,
+            3,
+        );
+        assert_eq!(payload["payload"]["severity"], "error");
+        assert!(payload["payload"]["custom_details"]
+            .get("message")
+            .is_none());
+
+        let payload = pagerduty(
+            &alert,
+            &dest,
+            "0123456789abcdef",
+            ("latency", ""),
+            AlertStatus::Resolved,
+            0,
+        );
+        assert_eq!(payload["event_action"], "resolve");
+        assert_eq!(payload["dedup_key"], "0123456789abcdef");
+        assert!(payload.get("payload").is_none());
+    }
+}
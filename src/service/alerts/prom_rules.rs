@@ -0,0 +1,322 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Imports the alerting rules of Prometheus rule files as PromQL alerts.
+
+use std::collections::HashSet;
+
+use config::meta::{
+    alerts::{
+        alert::Alert,
+        prom_rules::{
+            parse_duration, PromAlertImportResult, PromRule, PromRuleFile, PromRuleSkipped,
+        },
+        Condition, Operator, QueryCondition, QueryType, TriggerCondition,
+    },
+    dashboards::datetime_now,
+    stream::StreamType,
+};
+use promql_parser::parser::{self, token, Expr, NumberLiteral, ParenExpr, UnaryExpr};
+
+use crate::{
+    common::{meta::prom::NAME_LABEL, utils::auth::is_ofga_unsupported},
+    service::alerts::alert,
+};
+
+/// Imports the alerting rules of a Prometheus rule file into `org_id`, the recording rules
+/// of the file are ignored. Existing alerts with the same name are replaced.
+pub async fn import(
+    org_id: &str,
+    body: &[u8],
+    destinations: Vec<String>,
+    user_id: Option<&str>,
+) -> Result<PromAlertImportResult, anyhow::Error> {
+    if destinations.is_empty() {
+        return Err(anyhow::anyhow!("Alert destinations is required"));
+    }
+    let file: PromRuleFile = serde_yaml_ng::from_slice(body)
+        .map_err(|e| anyhow::anyhow!("Invalid Prometheus rule file: {e}"))?;
+    let (alerts, mut skipped) = to_alerts(file, &destinations);
+
+    let mut imported = Vec::with_capacity(alerts.len());
+    for (group, mut alert) in alerts {
+        alert.owner = user_id.map(|v| v.to_string());
+        alert.last_edited_by = alert.owner.clone();
+        alert.updated_at = Some(datetime_now());
+        let stream_name = alert.stream_name.clone();
+        let name = alert.name.clone();
+        let exists = match alert::get(org_id, StreamType::Metrics, &stream_name, &name).await {
+            Ok(v) => v.is_some(),
+            Err(e) => {
+                skipped.push(PromRuleSkipped {
+                    group,
+                    alert: name,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+        match alert::save(org_id, &stream_name, "", alert, !exists).await {
+            Ok(_) => imported.push(format!("{stream_name}/{name}")),
+            Err(e) => skipped.push(PromRuleSkipped {
+                group,
+                alert: name,
+                reason: e.to_string(),
+            }),
+        }
+    }
+    Ok(PromAlertImportResult { imported, skipped })
+}
+
+/// Maps the alerting rules of the file to alerts, along with their group name. The rules
+/// that cannot be expressed as an alert are returned with the reason.
+fn to_alerts(
+    file: PromRuleFile,
+    destinations: &[String],
+) -> (Vec<(String, Alert)>, Vec<PromRuleSkipped>) {
+    let mut alerts = Vec::new();
+    let mut skipped = Vec::new();
+    let mut names = HashSet::new();
+    for group in file.groups {
+        let frequency = match group.interval.as_deref().map(parse_duration) {
+            Some(Ok(v)) => v,
+            Some(Err(e)) => {
+                for rule in group.rules.iter().filter(|r| r.alert.is_some()) {
+                    skipped.push(PromRuleSkipped {
+                        group: group.name.clone(),
+                        alert: rule.alert.clone().unwrap_or_default(),
+                        reason: format!("invalid group interval: {e}"),
+                    });
+                }
+                continue;
+            }
+            // the default frequency is set when saving the alert
+            None => 0,
+        };
+        for rule in group.rules {
+            let Some(name) = rule.alert.clone() else {
+                continue;
+            };
+            match to_alert(rule, frequency, destinations) {
+                Ok(mut alert) => {
+                    // the same alert is often defined several times with different
+                    // thresholds, e.g. for the warning and critical severities
+                    let mut i = 1;
+                    while !names.insert((alert.stream_name.clone(), alert.name.clone())) {
+                        i += 1;
+                        alert.name = format!("{name}_{i}");
+                    }
+                    alerts.push((group.name.clone(), alert));
+                }
+                Err(reason) => skipped.push(PromRuleSkipped {
+                    group: group.name.clone(),
+                    alert: name,
+                    reason,
+                }),
+            }
+        }
+    }
+    (alerts, skipped)
+}
+
+fn to_alert(rule: PromRule, frequency: i64, destinations: &[String]) -> Result<Alert, String> {
+    let name = rule.alert.unwrap_or_default();
+    if name.is_empty() || name.contains('/') || is_ofga_unsupported(&name) {
+        return Err(format!("unsupported alert name: {name}"));
+    }
+    let expr = parser::parse(&rule.expr).map_err(|e| format!("invalid expression: {e}"))?;
+    let (query, operator, value) = split_condition(&expr).ok_or_else(|| {
+        "the expression should compare a query with a number, e.g. `up == 0`".to_string()
+    })?;
+    let stream_name = metric_name(&query)
+        .ok_or_else(|| "the expression does not select any metric".to_string())?;
+
+    // `for` is how long the condition must hold, at least a minute for the alert period
+    let period = match rule.for_duration.as_deref() {
+        Some(v) => parse_duration(v).map_err(|e| format!("invalid for duration: {e}"))?,
+        None => 0,
+    };
+    let period = std::cmp::max(1, (period + 59) / 60);
+
+    let description = rule
+        .annotations
+        .get("description")
+        .or_else(|| rule.annotations.get("summary"))
+        .cloned()
+        .unwrap_or_default();
+    let mut context_attributes = rule.labels;
+    context_attributes.extend(rule.annotations);
+
+    Ok(Alert {
+        name,
+        stream_type: StreamType::Metrics,
+        stream_name,
+        query_condition: QueryCondition {
+            query_type: QueryType::PromQL,
+            promql: Some(query.to_string()),
+            promql_condition: Some(Condition {
+                column: "value".to_string(),
+                operator,
+                value: value.into(),
+                ignore_case: false,
+            }),
+            ..Default::default()
+        },
+        trigger_condition: TriggerCondition {
+            period,
+            operator: Operator::GreaterThanEquals,
+            threshold: 1,
+            frequency,
+            ..Default::default()
+        },
+        destinations: destinations.to_vec(),
+        context_attributes: if context_attributes.is_empty() {
+            None
+        } else {
+            Some(context_attributes)
+        },
+        description,
+        enabled: true,
+        ..Default::default()
+    })
+}
+
+/// Splits `query > 10` into the query, the operator and the number. Comparisons with the
+/// `bool` modifier don't filter the series and are not supported.
+fn split_condition(expr: &Expr) -> Option<(Expr, Operator, f64)> {
+    let expr = unwrap_paren(expr);
+    let Expr::Binary(bin) = expr else {
+        return None;
+    };
+    if !bin.op.is_comparison_operator() || bin.return_bool() {
+        return None;
+    }
+    let (query, value, flipped) = match (number(&bin.lhs), number(&bin.rhs)) {
+        (None, Some(v)) => (bin.lhs.as_ref(), v, false),
+        (Some(v), None) => (bin.rhs.as_ref(), v, true),
+        _ => return None,
+    };
+    let operator = match (bin.op.id(), flipped) {
+        (token::T_EQLC, _) => Operator::EqualTo,
+        (token::T_NEQ, _) => Operator::NotEqualTo,
+        (token::T_GTR, false) | (token::T_LSS, true) => Operator::GreaterThan,
+        (token::T_GTE, false) | (token::T_LTE, true) => Operator::GreaterThanEquals,
+        (token::T_LSS, false) | (token::T_GTR, true) => Operator::LessThan,
+        (token::T_LTE, false) | (token::T_GTE, true) => Operator::LessThanEquals,
+        _ => return None,
+    };
+    Some((unwrap_paren(query).clone(), operator, value))
+}
+
+fn unwrap_paren(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Paren(ParenExpr { expr }) => unwrap_paren(expr),
+        _ => expr,
+    }
+}
+
+fn number(expr: &Expr) -> Option<f64> {
+    match unwrap_paren(expr) {
+        Expr::NumberLiteral(NumberLiteral { val }) => Some(*val),
+        Expr::Unary(UnaryExpr { expr }) => number(expr).map(|v| -v),
+        _ => None,
+    }
+}
+
+/// The first metric selected by the expression, the alert is attached to its stream.
+fn metric_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::VectorSelector(vs) => vs.name.clone().or_else(|| {
+            vs.matchers
+                .find_matchers(NAME_LABEL)
+                .first()
+                .map(|m| m.value.clone())
+        }),
+        Expr::MatrixSelector(ms) => metric_name(&Expr::VectorSelector(ms.vs.clone())),
+        Expr::Aggregate(agg) => metric_name(&agg.expr),
+        Expr::Unary(UnaryExpr { expr }) | Expr::Paren(ParenExpr { expr }) => metric_name(expr),
+        Expr::Subquery(sub) => metric_name(&sub.expr),
+        Expr::Binary(bin) => metric_name(&bin.lhs).or_else(|| metric_name(&bin.rhs)),
+        Expr::Call(call) => call.args.args.iter().find_map(|arg| metric_name(arg)),
+        Expr::NumberLiteral(_) | Expr::StringLiteral(_) | Expr::Extension(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_alerts() {
+        let file: PromRuleFile = serde_yaml_ng::from_str(
+            r#"
+groups:
+  - name: example
+    interval: 30s
+    rules:
+      - record: job:up:sum
+        expr: sum by (job) (up)
+      - alert: InstanceDown
+        expr: up == 0
+        for: 5m
+        labels:
+          severity: critical
+        annotations:
+          summary: "Instance {{ $labels.instance }} down"
+      - alert: HighLatency
+        expr: 0.5 < histogram_quantile(0.9, rate(http_request_duration_seconds_bucket[5m]))
+      - alert: HighLatency
+        expr: (histogram_quantile(0.9, rate(http_request_duration_seconds_bucket[5m]))) > 1
+      - alert: Absent
+        expr: absent(up)
+      - alert: Ratio
+        expr: rate(errors_total[5m]) > bool 0
+"#,
+        )
+        .unwrap();
+        let (alerts, skipped) = to_alerts(file, &["slack".to_string()]);
+        assert_eq!(alerts.len(), 3);
+        assert_eq!(skipped.len(), 2);
+        assert_eq!(skipped[0].alert, "Absent");
+        assert_eq!(skipped[1].alert, "Ratio");
+
+        let (group, alert) = &alerts[0];
+        assert_eq!(group, "example");
+        assert_eq!(alert.name, "InstanceDown");
+        assert_eq!(alert.stream_name, "up");
+        assert_eq!(alert.query_condition.query_type, QueryType::PromQL);
+        assert_eq!(alert.query_condition.promql.as_deref(), Some("up"));
+        let condition = alert.query_condition.promql_condition.as_ref().unwrap();
+        assert_eq!(condition.operator, Operator::EqualTo);
+        assert_eq!(condition.value, 0.0);
+        assert_eq!(alert.trigger_condition.period, 5);
+        assert_eq!(alert.trigger_condition.frequency, 30);
+        let attrs = alert.context_attributes.as_ref().unwrap();
+        assert_eq!(attrs.get("severity").unwrap(), "critical");
+        assert!(attrs.contains_key("summary"));
+        assert_eq!(alert.description, "Instance {{ $labels.instance }} down");
+
+        let (_, alert) = &alerts[1];
+        assert_eq!(alert.name, "HighLatency");
+        assert_eq!(alert.stream_name, "http_request_duration_seconds_bucket");
+        let condition = alert.query_condition.promql_condition.as_ref().unwrap();
+        assert_eq!(condition.operator, Operator::GreaterThan);
+        assert_eq!(condition.value, 0.5);
+        assert_eq!(alert.trigger_condition.period, 1);
+
+        let (_, alert) = &alerts[2];
+        assert_eq!(alert.name, "HighLatency_2");
+    }
+}
@@ -0,0 +1,407 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use actix_web::http;
+use chrono::Utc;
+use config::{
+    meta::{
+        alerts::{
+            prom_rules::{parse_duration, PromRuleFile},
+            recording_rules::{RecordingRule, RecordingRuleGroup},
+        },
+        stream::StreamType,
+    },
+    utils::{json, time::second_micros},
+};
+use once_cell::sync::Lazy;
+use proto::cluster_rpc;
+use regex::Regex;
+
+use crate::{
+    common::meta::prom::{NAME_LABEL, TYPE_LABEL, VALUE_LABEL},
+    service::{db, ingestion::ingestion_service, promql},
+};
+
+/// Default evaluation interval of a rule group, in seconds, same as Prometheus
+const DEFAULT_INTERVAL: i64 = 60;
+
+static RE_METRIC_NAME: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[a-zA-Z_:][a-zA-Z0-9_:]*$").unwrap());
+
+pub async fn save(
+    org_id: &str,
+    name: &str,
+    mut group: RecordingRuleGroup,
+    create: bool,
+) -> Result<(), anyhow::Error> {
+    if !name.is_empty() {
+        group.name = name.to_owned();
+    }
+    group.name = group.name.trim().to_string();
+    if group.name.is_empty() {
+        return Err(anyhow::anyhow!("Recording rule group name is required"));
+    }
+    if group.name.contains('/') {
+        return Err(anyhow::anyhow!(
+            "Recording rule group name cannot contain '/'"
+        ));
+    }
+    if group.interval <= 0 {
+        group.interval = DEFAULT_INTERVAL;
+    }
+    if group.rules.is_empty() {
+        return Err(anyhow::anyhow!("Recording rule group has no rules"));
+    }
+    for rule in group.rules.iter() {
+        validate_rule(rule)?;
+    }
+
+    match db::alerts::recording_rules::get(org_id, &group.name).await? {
+        Some(_) => {
+            if create {
+                return Err(anyhow::anyhow!("Recording rule group already exists"));
+            }
+        }
+        None => {
+            if !create {
+                return Err(anyhow::anyhow!("Recording rule group not found"));
+            }
+        }
+    }
+
+    db::alerts::recording_rules::set(org_id, &group).await?;
+
+    // schedule the evaluations
+    if !group.enabled {
+        // the trigger may not exist yet
+        if let Err(e) = db::scheduler::delete(
+            org_id,
+            db::scheduler::TriggerModule::RecordingRule,
+            &group.name,
+        )
+        .await
+        {
+            log::debug!("Delete trigger of recording rule group {}: {e}", group.name);
+        }
+        return Ok(());
+    }
+    let interval = second_micros(group.interval);
+    let now = Utc::now().timestamp_micros();
+    let trigger = db::scheduler::Trigger {
+        org: org_id.to_string(),
+        module: db::scheduler::TriggerModule::RecordingRule,
+        module_key: group.name.clone(),
+        // align the evaluations on the interval
+        next_run_at: now - now % interval + interval,
+        is_realtime: false,
+        is_silenced: false,
+        ..Default::default()
+    };
+    match db::scheduler::get(&trigger.org, trigger.module.clone(), &trigger.module_key).await {
+        Ok(_) => db::scheduler::update_trigger(trigger)
+            .await
+            .map_err(|_| anyhow::anyhow!("Trigger already exists, but failed to update")),
+        Err(_) => db::scheduler::push(trigger)
+            .await
+            .map_err(|e| anyhow::anyhow!("Error save recording rule trigger: {}", e)),
+    }
+}
+
+fn validate_rule(rule: &RecordingRule) -> Result<(), anyhow::Error> {
+    if !RE_METRIC_NAME.is_match(&rule.record) {
+        return Err(anyhow::anyhow!(
+            "Invalid metric name for recording rule: {}",
+            rule.record
+        ));
+    }
+    if let Err(e) = promql_parser::parser::parse(&rule.expr) {
+        return Err(anyhow::anyhow!(
+            "Invalid expression for recording rule {}: {}",
+            rule.record,
+            e
+        ));
+    }
+    for name in rule.labels.keys() {
+        if name == NAME_LABEL || !RE_METRIC_NAME.is_match(name) || name.contains(':') {
+            return Err(anyhow::anyhow!(
+                "Invalid label name for recording rule {}: {}",
+                rule.record,
+                name
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub async fn get(org_id: &str, name: &str) -> Result<RecordingRuleGroup, anyhow::Error> {
+    db::alerts::recording_rules::get(org_id, name)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Recording rule group not found"))
+}
+
+pub async fn list(org_id: &str) -> Result<Vec<RecordingRuleGroup>, anyhow::Error> {
+    db::alerts::recording_rules::list(org_id).await
+}
+
+pub async fn delete(org_id: &str, name: &str) -> Result<(), (http::StatusCode, anyhow::Error)> {
+    match db::alerts::recording_rules::get(org_id, name).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return Err((
+                http::StatusCode::NOT_FOUND,
+                anyhow::anyhow!("Recording rule group not found {}", name),
+            ));
+        }
+        Err(e) => return Err((http::StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+    if let Err(e) =
+        db::scheduler::delete(org_id, db::scheduler::TriggerModule::RecordingRule, name).await
+    {
+        log::error!("Failed to delete the trigger of recording rule group {name}: {e}");
+    }
+    db::alerts::recording_rules::delete(org_id, name)
+        .await
+        .map_err(|e| (http::StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+/// Imports the recording rules of a Prometheus rule file, existing groups with the same
+/// name are replaced. Returns the names of the imported groups.
+pub async fn import(org_id: &str, body: &[u8]) -> Result<Vec<String>, anyhow::Error> {
+    let groups = parse_rule_file(body)?;
+    let mut names = Vec::with_capacity(groups.len());
+    for group in groups {
+        let exists = db::alerts::recording_rules::get(org_id, &group.name)
+            .await?
+            .is_some();
+        let name = group.name.clone();
+        save(org_id, "", group, !exists)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to import rule group {name}: {e}"))?;
+        names.push(name);
+    }
+    Ok(names)
+}
+
+/// Collects the recording rules of a Prometheus rule file, the alerting rules are skipped.
+fn parse_rule_file(body: &[u8]) -> Result<Vec<RecordingRuleGroup>, anyhow::Error> {
+    let file: PromRuleFile = serde_yaml_ng::from_slice(body)
+        .map_err(|e| anyhow::anyhow!("Invalid Prometheus rule file: {e}"))?;
+    let mut groups = Vec::with_capacity(file.groups.len());
+    for group in file.groups {
+        let rules = group
+            .rules
+            .into_iter()
+            .filter_map(|rule| {
+                rule.record.map(|record| RecordingRule {
+                    record,
+                    expr: rule.expr,
+                    labels: rule.labels,
+                })
+            })
+            .collect::<Vec<_>>();
+        if rules.is_empty() {
+            continue;
+        }
+        let interval = match group.interval.as_deref() {
+            Some(v) => parse_duration(v).map_err(|e| anyhow::anyhow!(e))?,
+            None => DEFAULT_INTERVAL,
+        };
+        groups.push(RecordingRuleGroup {
+            name: group.name,
+            interval,
+            rules,
+            ..Default::default()
+        });
+    }
+    Ok(groups)
+}
+
+/// Evaluates every rule of the group at `eval_time` and writes the results as new
+/// series through the metrics ingestion.
+pub async fn evaluate(
+    org_id: &str,
+    group: &RecordingRuleGroup,
+    eval_time: i64,
+) -> Result<(), anyhow::Error> {
+    for rule in group.rules.iter() {
+        let req = promql::MetricsQueryRequest {
+            query: rule.expr.clone(),
+            start: eval_time,
+            end: eval_time,
+            step: promql::micros(promql::DEFAULT_LOOKBACK),
+        };
+        let value = promql::search::search(org_id, &req, 0, "")
+            .await
+            .map_err(|e| anyhow::anyhow!("Error evaluating rule {}: {e}", rule.record))?;
+        let records = to_records(rule, value, eval_time);
+        if records.is_empty() {
+            continue;
+        }
+        let req = cluster_rpc::IngestionRequest {
+            org_id: org_id.to_string(),
+            stream_name: rule.record.clone(),
+            stream_type: cluster_rpc::StreamType::from(StreamType::Metrics).into(),
+            data: Some(cluster_rpc::IngestionData::from(records)),
+            ingestion_type: Some(cluster_rpc::IngestionType::Json.into()),
+        };
+        match ingestion_service::ingest(req).await {
+            Ok(resp) if resp.status_code == 200 => {}
+            error => {
+                let err = error.map_or_else(|e| e.to_string(), |resp| resp.message);
+                return Err(anyhow::anyhow!(
+                    "Error ingesting the result of rule {}: {err}",
+                    rule.record
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Turns the result of an instant query into metrics records, named after the rule and
+/// stamped with the evaluation time as Prometheus does.
+fn to_records(
+    rule: &RecordingRule,
+    value: promql::value::Value,
+    eval_time: i64,
+) -> Vec<json::Value> {
+    let samples = match value {
+        promql::value::Value::Vector(v) => v
+            .into_iter()
+            .map(|v| (v.labels, v.sample.value))
+            .collect::<Vec<_>>(),
+        promql::value::Value::Matrix(v) => v
+            .into_iter()
+            .filter_map(|v| v.samples.last().map(|s| (v.labels.clone(), s.value)))
+            .collect(),
+        promql::value::Value::Instant(v) => vec![(v.labels, v.sample.value)],
+        promql::value::Value::Sample(v) => vec![(vec![], v.value)],
+        promql::value::Value::Float(v) => vec![(vec![], v)],
+        _ => vec![],
+    };
+
+    let timestamp_column = config::get_config().common.column_timestamp.clone();
+    samples
+        .into_iter()
+        // NaN and Inf can't be represented in json
+        .filter(|(_, value)| value.is_finite())
+        .map(|(labels, value)| {
+            let mut record = json::Map::with_capacity(labels.len() + rule.labels.len() + 4);
+            for label in labels.iter() {
+                record.insert(label.name.clone(), label.value.clone().into());
+            }
+            for (name, value) in rule.labels.iter() {
+                record.insert(name.clone(), value.clone().into());
+            }
+            record.insert(NAME_LABEL.to_string(), rule.record.clone().into());
+            record.insert(TYPE_LABEL.to_string(), "gauge".into());
+            record.insert(VALUE_LABEL.to_string(), value.into());
+            record.insert(timestamp_column.clone(), eval_time.into());
+            json::Value::Object(record)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::service::promql::value::{InstantValue, Label, Sample};
+
+    #[test]
+    fn test_parse_rule_file() {
+        let body = r#"
+groups:
+  - name: example
+    interval: 30s
+    rules:
+      - record: job:http_requests:rate5m
+        expr: sum by (job) (rate(http_requests_total[5m]))
+        labels:
+          team: infra
+      - alert: HighErrorRate
+        expr: job:http_requests:rate5m > 100
+        for: 10m
+  - name: alerts_only
+    rules:
+      - alert: InstanceDown
+        expr: up == 0
+"#;
+        let groups = parse_rule_file(body.as_bytes()).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name, "example");
+        assert_eq!(groups[0].interval, 30);
+        assert_eq!(groups[0].rules.len(), 1);
+        assert_eq!(groups[0].rules[0].record, "job:http_requests:rate5m");
+        assert_eq!(groups[0].rules[0].labels.get("team").unwrap(), "infra");
+        assert!(validate_rule(&groups[0].rules[0]).is_ok());
+
+        assert!(parse_rule_file(b"groups: [").is_err());
+    }
+
+    #[test]
+    fn test_validate_rule() {
+        let mut rule = RecordingRule {
+            record: "job:up:sum".to_string(),
+            expr: "sum by (job) (up)".to_string(),
+            ..Default::default()
+        };
+        assert!(validate_rule(&rule).is_ok());
+        rule.record = "1job".to_string();
+        assert!(validate_rule(&rule).is_err());
+        rule.record = "job:up:sum".to_string();
+        rule.expr = "sum by (job) (up".to_string();
+        assert!(validate_rule(&rule).is_err());
+    }
+
+    #[test]
+    fn test_to_records() {
+        let rule = RecordingRule {
+            record: "job:up:sum".to_string(),
+            expr: "sum by (job) (up)".to_string(),
+            labels: [("env".to_string(), "prod".to_string())]
+                .into_iter()
+                .collect(),
+        };
+        let value = promql::value::Value::Vector(vec![
+            InstantValue {
+                labels: vec![
+                    Arc::new(Label::new("__name__", "up")),
+                    Arc::new(Label::new("job", "api")),
+                ],
+                sample: Sample {
+                    timestamp: 0,
+                    value: 3.0,
+                },
+            },
+            InstantValue {
+                labels: vec![Arc::new(Label::new("job", "db"))],
+                sample: Sample {
+                    timestamp: 0,
+                    value: f64::NAN,
+                },
+            },
+        ]);
+        let records = to_records(&rule, value, 1000);
+        assert_eq!(records.len(), 1);
+        let record = records[0].as_object().unwrap();
+        assert_eq!(record.get(NAME_LABEL).unwrap(), "job:up:sum");
+        assert_eq!(record.get(TYPE_LABEL).unwrap(), "gauge");
+        assert_eq!(record.get("job").unwrap(), "api");
+        assert_eq!(record.get("env").unwrap(), "prod");
+        assert_eq!(record.get(VALUE_LABEL).unwrap().as_f64(), Some(3.0));
+    }
+}
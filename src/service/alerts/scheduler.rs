@@ -39,6 +39,7 @@ use crate::service::{
     alerts::{
         alert::{get_alert_start_end_time, get_row_column_map, AlertExt},
         derived_streams::DerivedStreamExt,
+        recording_rules,
     },
     dashboards::reports::SendReport,
     db::{self, scheduler::ScheduledTriggerData},
@@ -85,6 +86,9 @@ pub async fn handle_triggers(trigger: db::scheduler::Trigger) -> Result<(), anyh
         db::scheduler::TriggerModule::DerivedStream => {
             handle_derived_stream_triggers(trigger).await
         }
+        db::scheduler::TriggerModule::RecordingRule => {
+            handle_recording_rule_triggers(trigger).await
+        }
     }
 }
 
@@ -377,7 +381,14 @@ async fn handle_alert_triggers(trigger: db::scheduler::Trigger) -> Result<(), an
         }
         _ => 0,
     };
-    if ret.is_some() && alert.trigger_condition.silence > 0 {
+    // Silences only suppress the notification, the alert is still evaluated and its
+    // `last_satisfied_at` updated
+    let silenced_by = if ret.is_some() {
+        super::silences::find_active(&alert, now).await
+    } else {
+        None
+    };
+    if ret.is_some() && silenced_by.is_none() && alert.trigger_condition.silence > 0 {
         if alert.trigger_condition.frequency_type == FrequencyType::Cron {
             let schedule = Schedule::from_str(&alert.trigger_condition.cron)?;
             let silence =
@@ -434,8 +445,26 @@ async fn handle_alert_triggers(trigger: db::scheduler::Trigger) -> Result<(), an
     } else {
         None
     };
-    // send notification
-    if let Some(data) = ret {
+    if let Some(silence) = silenced_by {
+        log::info!(
+            "Alert notification suppressed by silence {}, org: {}, module_key: {}",
+            silence.id,
+            &new_trigger.org,
+            &new_trigger.module_key
+        );
+        trigger_data_stream.status = TriggerDataStatus::Silenced;
+        trigger_data_stream.success_response =
+            Some(format!("notification suppressed by silence {}", silence.id));
+        trigger_data_stream.end_time = end_time;
+        trigger_data.period_end_time = if should_store_last_end_time {
+            Some(end_time)
+        } else {
+            None
+        };
+        new_trigger.data = json::to_string(&trigger_data).unwrap();
+        db::scheduler::update_trigger(new_trigger).await?;
+    } else if let Some(data) = ret {
+        // send notification
         let vars = get_row_column_map(&data);
         // Multi-time range alerts can have multiple time ranges, hence only
         // use the main start_time (now - period) and end_time (now) for the alert evaluation.
@@ -455,7 +484,7 @@ async fn handle_alert_triggers(trigger: db::scheduler::Trigger) -> Result<(), an
         );
         trigger_data_stream.start_time = alert_start_time;
         trigger_data_stream.end_time = alert_end_time;
-        match alert.send_notification(&data, end_time, start_time).await {
+        match alert.notify(&data, end_time, start_time).await {
             Ok((success_msg, err_msg)) => {
                 let success_msg = success_msg.trim().to_owned();
                 let err_msg = err_msg.trim().to_owned();
@@ -556,6 +585,13 @@ async fn handle_alert_triggers(trigger: db::scheduler::Trigger) -> Result<(), an
         };
         trigger_data_stream.end_time = end_time;
         trigger_data_stream.status = TriggerDataStatus::ConditionNotSatisfied;
+        if let Err(e) = alert.resolve().await {
+            log::error!(
+                "Error resolving alert groups, org: {}, module_key: {}, err: {e}",
+                &org_id,
+                &trigger.module_key
+            );
+        }
     }
 
     // Check if the alert has been disabled in the mean time
@@ -1112,3 +1148,114 @@ async fn handle_derived_stream_triggers(
 
     Ok(())
 }
+
+async fn handle_recording_rule_triggers(
+    trigger: db::scheduler::Trigger,
+) -> Result<(), anyhow::Error> {
+    log::debug!(
+        "Inside handle_recording_rule_triggers processing trigger: {}",
+        trigger.module_key
+    );
+    let (_, max_retries) = get_scheduler_max_retries();
+
+    // module_key format: group_name
+    let org_id = &trigger.org;
+    let group_name = &trigger.module_key;
+    let Some(group) = db::alerts::recording_rules::get(org_id, group_name).await? else {
+        log::warn!(
+            "Recording rule group associated with trigger not found: {}/{}. Deleting this trigger",
+            org_id,
+            group_name
+        );
+        db::scheduler::delete(
+            org_id,
+            db::scheduler::TriggerModule::RecordingRule,
+            group_name,
+        )
+        .await?;
+        return Ok(());
+    };
+    if !group.enabled {
+        // the trigger will be added back when the group is enabled again
+        db::scheduler::delete(
+            org_id,
+            db::scheduler::TriggerModule::RecordingRule,
+            group_name,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    // evaluate at the scheduled time so that the recorded samples are evenly spaced, and
+    // skip the evaluations missed while the scheduler was behind
+    let eval_time = trigger.next_run_at;
+    let interval = second_micros(group.interval);
+    let now = Utc::now().timestamp_micros();
+    let mut new_trigger = db::scheduler::Trigger {
+        next_run_at: eval_time + interval,
+        is_silenced: false,
+        status: db::scheduler::TriggerStatus::Waiting,
+        retries: 0,
+        ..trigger.clone()
+    };
+    if new_trigger.next_run_at <= now {
+        new_trigger.next_run_at = now - now % interval + interval;
+    }
+
+    let mut trigger_data_stream = TriggerData {
+        _timestamp: trigger.start_time.unwrap_or_default(),
+        org: trigger.org.clone(),
+        module: TriggerDataType::RecordingRule,
+        key: trigger.module_key.clone(),
+        next_run_at: new_trigger.next_run_at,
+        is_realtime: trigger.is_realtime,
+        is_silenced: trigger.is_silenced,
+        status: TriggerDataStatus::Completed,
+        start_time: eval_time,
+        end_time: eval_time,
+        retries: trigger.retries,
+        error: None,
+        success_response: None,
+        is_partial: None,
+        delay_in_secs: Some(Duration::microseconds(now - eval_time).num_seconds()),
+        evaluation_took_in_secs: None,
+    };
+
+    let evaluation_took = Instant::now();
+    let ret = recording_rules::evaluate(org_id, &group, eval_time).await;
+    trigger_data_stream.evaluation_took_in_secs = Some(evaluation_took.elapsed().as_secs_f64());
+    match ret {
+        Ok(_) => {
+            db::scheduler::update_trigger(new_trigger).await?;
+        }
+        Err(e) => {
+            log::error!(
+                "Error evaluating recording rule group {}/{}: {}",
+                org_id,
+                group_name,
+                e
+            );
+            trigger_data_stream.status = TriggerDataStatus::Failed;
+            trigger_data_stream.error = Some(e.to_string());
+            if trigger.retries + 1 >= max_retries {
+                // It has been tried the maximum time, move on to the next evaluation
+                db::scheduler::update_trigger(new_trigger).await?;
+            } else {
+                // Otherwise, update its status only
+                db::scheduler::update_status(
+                    org_id,
+                    db::scheduler::TriggerModule::RecordingRule,
+                    group_name,
+                    db::scheduler::TriggerStatus::Waiting,
+                    trigger.retries + 1,
+                )
+                .await?;
+            }
+        }
+    }
+
+    // publish the triggers as stream
+    publish_triggers_usage(trigger_data_stream).await;
+
+    Ok(())
+}
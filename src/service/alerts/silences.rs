@@ -0,0 +1,188 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+
+use actix_web::http;
+use chrono::{Duration, FixedOffset, TimeZone, Utc};
+use config::{
+    ider,
+    meta::alerts::{alert::Alert, silences::Silence},
+};
+use cron::Schedule;
+
+use crate::service::{alerts::grouping::alert_labels, db};
+
+pub async fn create(org_id: &str, mut silence: Silence) -> Result<Silence, anyhow::Error> {
+    if silence.matchers.is_empty() {
+        return Err(anyhow::anyhow!("Silence requires at least one matcher"));
+    }
+    if silence.starts_at <= 0 {
+        silence.starts_at = Utc::now().timestamp_micros();
+    }
+    if silence.ends_at <= silence.starts_at {
+        return Err(anyhow::anyhow!("Silence should end after it starts"));
+    }
+    if let Some(cron) = silence.cron.as_ref() {
+        if let Err(e) = Schedule::from_str(cron) {
+            return Err(anyhow::anyhow!("Invalid cron expression for silence: {e}"));
+        }
+        if silence.duration <= 0 {
+            return Err(anyhow::anyhow!(
+                "Recurring silence requires a duration in minutes"
+            ));
+        }
+        if FixedOffset::east_opt(silence.tz_offset * 60).is_none() {
+            return Err(anyhow::anyhow!("Invalid timezone offset for silence"));
+        }
+    }
+    silence.id = ider::uuid();
+    silence.created_at = Utc::now().timestamp_micros();
+    db::alerts::silences::set(org_id, &silence).await?;
+    Ok(silence)
+}
+
+pub async fn get(org_id: &str, id: &str) -> Result<Silence, anyhow::Error> {
+    db::alerts::silences::get(org_id, id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Silence not found"))
+}
+
+/// Lists the silences of the organization, only the ones not expired yet with
+/// `active_only`.
+pub async fn list(org_id: &str, active_only: bool) -> Result<Vec<Silence>, anyhow::Error> {
+    let now = Utc::now().timestamp_micros();
+    Ok(db::alerts::silences::list(org_id)
+        .await?
+        .into_iter()
+        .filter(|silence| !active_only || silence.ends_at > now)
+        .collect())
+}
+
+/// Ends the silence now, it is kept for the record.
+pub async fn expire(org_id: &str, id: &str) -> Result<(), (http::StatusCode, anyhow::Error)> {
+    let mut silence = match db::alerts::silences::get(org_id, id).await {
+        Ok(Some(silence)) => silence,
+        Ok(None) => {
+            return Err((
+                http::StatusCode::NOT_FOUND,
+                anyhow::anyhow!("Silence not found {}", id),
+            ));
+        }
+        Err(e) => return Err((http::StatusCode::INTERNAL_SERVER_ERROR, e)),
+    };
+    let now = Utc::now().timestamp_micros();
+    if silence.ends_at <= now {
+        return Ok(());
+    }
+    silence.ends_at = std::cmp::max(now, silence.starts_at);
+    db::alerts::silences::set(org_id, &silence)
+        .await
+        .map_err(|e| (http::StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+pub async fn delete(org_id: &str, id: &str) -> Result<(), (http::StatusCode, anyhow::Error)> {
+    match db::alerts::silences::get(org_id, id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return Err((
+                http::StatusCode::NOT_FOUND,
+                anyhow::anyhow!("Silence not found {}", id),
+            ));
+        }
+        Err(e) => return Err((http::StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+    db::alerts::silences::delete(org_id, id)
+        .await
+        .map_err(|e| (http::StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+/// Returns the first silence matching the alert which is active at `now`.
+pub async fn find_active(alert: &Alert, now: i64) -> Option<Silence> {
+    let silences = match db::alerts::silences::list(&alert.org_id).await {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("Error listing the silences of org {}: {e}", alert.org_id);
+            return None;
+        }
+    };
+    let labels = alert_labels(alert);
+    silences
+        .into_iter()
+        .find(|silence| silence.matches(&labels) && is_active(silence, now))
+}
+
+fn is_active(silence: &Silence, now: i64) -> bool {
+    if !silence.is_within(now) {
+        return false;
+    }
+    let Some(cron) = silence.cron.as_ref() else {
+        return true;
+    };
+    let Ok(schedule) = Schedule::from_str(cron) else {
+        return false;
+    };
+    let Some(tz_offset) = FixedOffset::east_opt(silence.tz_offset * 60) else {
+        return false;
+    };
+    // active when the window of an occurrence contains now, i.e. an occurrence happened in
+    // the last `duration` minutes
+    let window_start = now
+        - Duration::try_minutes(silence.duration)
+            .unwrap()
+            .num_microseconds()
+            .unwrap();
+    let window_start = tz_offset.timestamp_nanos(window_start * 1000);
+    schedule
+        .after(&window_start)
+        .next()
+        .is_some_and(|occurrence| occurrence.timestamp_micros() <= now)
+}
+
+#[cfg(test)]
+mod tests {
+    use hashbrown::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_is_active() {
+        // 2024-01-01T00:00:00Z is a Monday
+        let day = Duration::try_days(1).unwrap().num_microseconds().unwrap();
+        let hour = Duration::try_hours(1).unwrap().num_microseconds().unwrap();
+        let monday = 1704067200000000;
+        let mut silence = Silence {
+            matchers: HashMap::from([("alert_name".to_string(), "a".to_string())]),
+            starts_at: monday,
+            ends_at: monday + 7 * day,
+            ..Default::default()
+        };
+        assert!(is_active(&silence, monday + day));
+        assert!(!is_active(&silence, monday + 7 * day));
+
+        // every day from 02:00 to 04:00
+        silence.cron = Some("0 0 2 * * *".to_string());
+        silence.duration = 120;
+        assert!(!is_active(&silence, monday + day + hour));
+        assert!(is_active(&silence, monday + day + 2 * hour));
+        assert!(is_active(&silence, monday + day + 3 * hour));
+        assert!(!is_active(&silence, monday + day + 4 * hour));
+
+        // the same window in UTC+1
+        silence.tz_offset = 60;
+        assert!(is_active(&silence, monday + day + hour));
+        assert!(!is_active(&silence, monday + day + 3 * hour));
+    }
+}
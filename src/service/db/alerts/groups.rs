@@ -0,0 +1,103 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The notification state of the alert groups, shared by all the alert managers through
+//! the meta store. The states are stored by alert, `/alert_groups/{org_id}/{alert_key}/
+//! {fingerprint}`, and only for the scheduled alerts with grouping.
+
+use config::{meta::alerts::grouping::AlertGroupState, utils::json};
+
+use crate::service::db;
+
+pub async fn get(
+    org_id: &str,
+    alert_key: &str,
+    fingerprint: &str,
+) -> Result<Option<AlertGroupState>, anyhow::Error> {
+    let key = format!("/alert_groups/{org_id}/{alert_key}/{fingerprint}");
+    Ok(db::get(&key)
+        .await
+        .ok()
+        .and_then(|val| json::from_slice(&val).ok()))
+}
+
+pub async fn set(org_id: &str, state: &AlertGroupState) -> Result<(), anyhow::Error> {
+    let key = format!(
+        "/alert_groups/{org_id}/{}/{}",
+        state.alert, state.fingerprint
+    );
+    Ok(db::put(
+        &key,
+        json::to_vec(state).unwrap().into(),
+        db::NO_NEED_WATCH,
+        None,
+    )
+    .await?)
+}
+
+pub async fn delete(org_id: &str, alert_key: &str, fingerprint: &str) -> Result<(), anyhow::Error> {
+    let key = format!("/alert_groups/{org_id}/{alert_key}/{fingerprint}");
+    Ok(db::delete_if_exists(&key, false, db::NO_NEED_WATCH).await?)
+}
+
+/// Deletes the states of all the groups of the alert.
+pub async fn delete_alert(org_id: &str, alert_key: &str) -> Result<(), anyhow::Error> {
+    let key = format!("/alert_groups/{org_id}/{alert_key}/");
+    Ok(db::delete_if_exists(&key, true, db::NO_NEED_WATCH).await?)
+}
+
+/// Lists the states of the groups of all the alerts of the organization.
+pub async fn list(org_id: &str) -> Result<Vec<AlertGroupState>, anyhow::Error> {
+    list_values(&format!("/alert_groups/{org_id}/")).await
+}
+
+/// Lists the states of the groups of all the organizations, with their organization.
+pub async fn list_all() -> Result<Vec<(String, AlertGroupState)>, anyhow::Error> {
+    let key = "/alert_groups/";
+    let mut items = Vec::new();
+    for (item_key, item_value) in db::list(key).await? {
+        let Some((org_id, _)) = item_key
+            .strip_prefix(key)
+            .and_then(|item_key| item_key.split_once('/'))
+        else {
+            continue;
+        };
+        match json::from_slice(&item_value) {
+            Ok(v) => items.push((org_id.to_string(), v)),
+            Err(e) => log::error!("Error parsing alert group state: {e}"),
+        }
+    }
+    Ok(items)
+}
+
+/// Lists the states of the groups of the alert.
+pub async fn list_alert(
+    org_id: &str,
+    alert_key: &str,
+) -> Result<Vec<AlertGroupState>, anyhow::Error> {
+    list_values(&format!("/alert_groups/{org_id}/{alert_key}/")).await
+}
+
+async fn list_values(key: &str) -> Result<Vec<AlertGroupState>, anyhow::Error> {
+    let ret = db::list_values(key).await?;
+    let mut items = Vec::with_capacity(ret.len());
+    for item_value in ret {
+        match json::from_slice(&item_value) {
+            Ok(v) => items.push(v),
+            Err(e) => log::error!("Error parsing alert group state: {e}"),
+        }
+    }
+    Ok(items)
+}
@@ -15,5 +15,9 @@
 
 pub mod alert;
 pub mod destinations;
+pub mod groups;
+pub mod inhibitions;
 pub mod realtime_triggers;
+pub mod recording_rules;
+pub mod silences;
 pub mod templates;
@@ -0,0 +1,133 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use config::{meta::alerts::silences::Silence, utils::json};
+use itertools::Itertools;
+
+use crate::{common::infra::config::ALERTS_SILENCES, service::db};
+
+pub async fn get(org_id: &str, id: &str) -> Result<Option<Silence>, anyhow::Error> {
+    let map_key = format!("{org_id}/{id}");
+    if let Some(v) = ALERTS_SILENCES.get(&map_key) {
+        return Ok(Some(v.value().clone()));
+    }
+    let key = format!("/alert_silences/{org_id}/{id}");
+    Ok(db::get(&key)
+        .await
+        .ok()
+        .map(|val| json::from_slice(&val).unwrap()))
+}
+
+pub async fn set(org_id: &str, silence: &Silence) -> Result<(), anyhow::Error> {
+    let key = format!("/alert_silences/{org_id}/{}", silence.id);
+    Ok(db::put(
+        &key,
+        json::to_vec(silence).unwrap().into(),
+        db::NEED_WATCH,
+        None,
+    )
+    .await?)
+}
+
+pub async fn delete(org_id: &str, id: &str) -> Result<(), anyhow::Error> {
+    let key = format!("/alert_silences/{org_id}/{id}");
+    Ok(db::delete(&key, false, db::NEED_WATCH, None).await?)
+}
+
+pub async fn list(org_id: &str) -> Result<Vec<Silence>, anyhow::Error> {
+    let cache = ALERTS_SILENCES.clone();
+    if !cache.is_empty() {
+        return Ok(cache
+            .iter()
+            .filter_map(|silence| {
+                silence
+                    .key()
+                    .starts_with(&format!("{org_id}/"))
+                    .then(|| silence.value().clone())
+            })
+            .sorted_by(|a, b| a.starts_at.cmp(&b.starts_at))
+            .collect());
+    }
+
+    let key = format!("/alert_silences/{org_id}/");
+    let ret = db::list_values(key.as_str()).await?;
+    let mut items = Vec::new();
+    for item_value in ret {
+        let json_val: Silence = json::from_slice(&item_value).unwrap();
+        items.push(json_val);
+    }
+    items.sort_by(|a, b| a.starts_at.cmp(&b.starts_at));
+    Ok(items)
+}
+
+pub async fn watch() -> Result<(), anyhow::Error> {
+    let key = "/alert_silences/";
+    let cluster_coordinator = db::get_coordinator().await;
+    let mut events = cluster_coordinator.watch(key).await?;
+    let events = Arc::get_mut(&mut events).unwrap();
+    log::info!("Start watching alert silences");
+    loop {
+        let ev = match events.recv().await {
+            Some(ev) => ev,
+            None => {
+                log::error!("watch_alert_silences: event channel closed");
+                break;
+            }
+        };
+        match ev {
+            db::Event::Put(ev) => {
+                let item_key = ev.key.strip_prefix(key).unwrap();
+                let item_value: Silence = if config::get_config().common.meta_store_external {
+                    match db::get(&ev.key).await {
+                        Ok(val) => match json::from_slice(&val) {
+                            Ok(val) => val,
+                            Err(e) => {
+                                log::error!("Error getting value: {}", e);
+                                continue;
+                            }
+                        },
+                        Err(e) => {
+                            log::error!("Error getting value: {}", e);
+                            continue;
+                        }
+                    }
+                } else {
+                    json::from_slice(&ev.value.unwrap()).unwrap()
+                };
+                ALERTS_SILENCES.insert(item_key.to_owned(), item_value);
+            }
+            db::Event::Delete(ev) => {
+                let item_key = ev.key.strip_prefix(key).unwrap();
+                ALERTS_SILENCES.remove(item_key);
+            }
+            db::Event::Empty => {}
+        }
+    }
+    Ok(())
+}
+
+pub async fn cache() -> Result<(), anyhow::Error> {
+    let key = "/alert_silences/";
+    let ret = db::list(key).await?;
+    for (item_key, item_value) in ret {
+        let item_key = item_key.strip_prefix(key).unwrap();
+        let json_val: Silence = json::from_slice(&item_value).unwrap();
+        ALERTS_SILENCES.insert(item_key.to_owned(), json_val);
+    }
+    log::info!("Alert silences Cached");
+    Ok(())
+}
@@ -0,0 +1,72 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use config::utils::json;
+use infra::errors::{DbError, Error};
+use serde::{Deserialize, Serialize};
+
+use crate::service::db;
+
+const FLIGHT_SQL_SESSIONS_KEY: &str = "/flight_sql_sessions/";
+
+/// Session opened by a handshake of an Arrow Flight SQL client, it's shared by the queriers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub org_id: String,
+    pub user_id: String,
+    /// Expiry time in microseconds
+    pub expires_at: i64,
+}
+
+pub async fn get(token: &str) -> Result<Option<Session>, anyhow::Error> {
+    let key = format!("{FLIGHT_SQL_SESSIONS_KEY}{token}");
+    match db::get(&key).await {
+        Ok(val) => Ok(Some(json::from_slice(&val)?)),
+        Err(Error::DbError(DbError::KeyNotExists(_))) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn set(token: &str, session: &Session) -> Result<(), anyhow::Error> {
+    let key = format!("{FLIGHT_SQL_SESSIONS_KEY}{token}");
+    Ok(db::put(
+        &key,
+        json::to_vec(session).unwrap().into(),
+        db::NO_NEED_WATCH,
+        None,
+    )
+    .await?)
+}
+
+pub async fn delete(token: &str) -> Result<(), anyhow::Error> {
+    let key = format!("{FLIGHT_SQL_SESSIONS_KEY}{token}");
+    Ok(db::delete(&key, false, db::NO_NEED_WATCH, None).await?)
+}
+
+/// Lists the sessions by token.
+pub async fn list() -> Result<Vec<(String, Session)>, anyhow::Error> {
+    let mut items = Vec::new();
+    for (key, val) in db::list(FLIGHT_SQL_SESSIONS_KEY).await? {
+        let token = key
+            .strip_prefix(FLIGHT_SQL_SESSIONS_KEY)
+            .unwrap()
+            .to_string();
+        match json::from_slice::<Session>(&val) {
+            Ok(session) => items.push((token, session)),
+            Err(e) => log::error!("[FLIGHT_SQL] Invalid session: {}", e),
+        }
+    }
+    Ok(items)
+}
@@ -27,6 +27,7 @@ pub mod compact;
 pub mod dashboards;
 pub mod enrichment_table;
 pub mod file_list;
+pub mod flight_sql_session;
 pub mod folders;
 pub mod functions;
 pub mod instance;
@@ -36,9 +37,11 @@ pub mod metrics;
 pub mod ofga;
 pub mod organization;
 pub mod pipeline;
+pub mod quota;
 pub mod saved_view;
 pub mod scheduler;
 pub mod schema;
+pub mod search_job;
 pub mod session;
 pub mod short_url;
 pub mod syslog;
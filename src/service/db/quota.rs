@@ -0,0 +1,149 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{collections::HashMap, sync::Arc};
+
+use config::{meta::quota::IngestionQuota, utils::json};
+use infra::errors::{DbError, Error};
+use serde::{Deserialize, Serialize};
+
+use crate::{common::infra::config::INGESTION_QUOTAS, service::db};
+
+pub async fn get(org_id: &str) -> Result<Option<IngestionQuota>, anyhow::Error> {
+    if let Some(v) = INGESTION_QUOTAS.get(org_id) {
+        return Ok(Some(v.value().clone()));
+    }
+    let key = format!("/ingestion_quota/{org_id}");
+    Ok(db::get(&key)
+        .await
+        .ok()
+        .map(|val| json::from_slice(&val).unwrap()))
+}
+
+pub async fn set(org_id: &str, quota: &IngestionQuota) -> Result<(), anyhow::Error> {
+    let key = format!("/ingestion_quota/{org_id}");
+    Ok(db::put(
+        &key,
+        json::to_vec(quota).unwrap().into(),
+        db::NEED_WATCH,
+        None,
+    )
+    .await?)
+}
+
+pub async fn delete(org_id: &str) -> Result<(), anyhow::Error> {
+    let key = format!("/ingestion_quota/{org_id}");
+    Ok(db::delete(&key, false, db::NEED_WATCH, None).await?)
+}
+
+const DAILY_USAGE_KEY: &str = "/ingestion_quota_usage/";
+
+/// The bytes ingested during a day by an ingester, key is `org_id` for the quota of the
+/// organization and `org_id/stream_type/stream_name` for the quota of a stream.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct DailyUsage {
+    /// Days since epoch
+    pub day: i64,
+    pub bytes: HashMap<String, u64>,
+}
+
+pub async fn get_daily_usage(node: &str) -> Result<Option<DailyUsage>, anyhow::Error> {
+    let key = format!("{DAILY_USAGE_KEY}{node}");
+    match db::get(&key).await {
+        Ok(val) => Ok(Some(json::from_slice(&val)?)),
+        Err(Error::DbError(DbError::KeyNotExists(_))) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn set_daily_usage(node: &str, usage: &DailyUsage) -> Result<(), anyhow::Error> {
+    let key = format!("{DAILY_USAGE_KEY}{node}");
+    Ok(db::put(&key, json::to_vec(usage)?.into(), db::NO_NEED_WATCH, None).await?)
+}
+
+pub async fn delete_daily_usage(node: &str) -> Result<(), anyhow::Error> {
+    let key = format!("{DAILY_USAGE_KEY}{node}");
+    Ok(db::delete(&key, false, db::NO_NEED_WATCH, None).await?)
+}
+
+/// Returns the daily usage of every ingester, key is the node name.
+pub async fn list_daily_usages() -> Result<Vec<(String, DailyUsage)>, anyhow::Error> {
+    let ret = db::list(DAILY_USAGE_KEY).await?;
+    let mut usages = Vec::with_capacity(ret.len());
+    for (item_key, item_value) in ret {
+        let node = item_key.strip_prefix(DAILY_USAGE_KEY).unwrap();
+        usages.push((node.to_string(), json::from_slice(&item_value)?));
+    }
+    Ok(usages)
+}
+
+pub async fn watch() -> Result<(), anyhow::Error> {
+    let key = "/ingestion_quota/";
+    let cluster_coordinator = db::get_coordinator().await;
+    let mut events = cluster_coordinator.watch(key).await?;
+    let events = Arc::get_mut(&mut events).unwrap();
+    log::info!("Start watching ingestion quotas");
+    loop {
+        let ev = match events.recv().await {
+            Some(ev) => ev,
+            None => {
+                log::error!("watch_ingestion_quotas: event channel closed");
+                break;
+            }
+        };
+        match ev {
+            db::Event::Put(ev) => {
+                let item_key = ev.key.strip_prefix(key).unwrap();
+                let item_value: IngestionQuota = if config::get_config().common.meta_store_external
+                {
+                    match db::get(&ev.key).await {
+                        Ok(val) => match json::from_slice(&val) {
+                            Ok(val) => val,
+                            Err(e) => {
+                                log::error!("Error getting value: {}", e);
+                                continue;
+                            }
+                        },
+                        Err(e) => {
+                            log::error!("Error getting value: {}", e);
+                            continue;
+                        }
+                    }
+                } else {
+                    json::from_slice(&ev.value.unwrap()).unwrap()
+                };
+                INGESTION_QUOTAS.insert(item_key.to_owned(), item_value);
+            }
+            db::Event::Delete(ev) => {
+                let item_key = ev.key.strip_prefix(key).unwrap();
+                INGESTION_QUOTAS.remove(item_key);
+            }
+            db::Event::Empty => {}
+        }
+    }
+    Ok(())
+}
+
+pub async fn cache() -> Result<(), anyhow::Error> {
+    let key = "/ingestion_quota/";
+    let ret = db::list(key).await?;
+    for (item_key, item_value) in ret {
+        let item_key = item_key.strip_prefix(key).unwrap();
+        let json_val: IngestionQuota = json::from_slice(&item_value).unwrap();
+        INGESTION_QUOTAS.insert(item_key.to_owned(), json_val);
+    }
+    log::info!("Ingestion quotas Cached");
+    Ok(())
+}
@@ -0,0 +1,86 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use bytes::Bytes;
+use config::{meta::search_job::SearchJob, utils::json};
+
+use crate::service::db;
+
+const SEARCH_JOBS_KEY: &str = "/search_jobs/";
+
+pub async fn get(org_id: &str, id: &str) -> Result<Option<SearchJob>, anyhow::Error> {
+    let key = format!("{SEARCH_JOBS_KEY}{org_id}/{id}");
+    match db::get(&key).await {
+        Ok(val) => Ok(Some(json::from_slice(&val)?)),
+        Err(_) => Ok(None),
+    }
+}
+
+pub async fn set(job: &SearchJob) -> Result<(), anyhow::Error> {
+    let key = format!("{SEARCH_JOBS_KEY}{}/{}", job.org_id, job.id);
+    Ok(db::put(
+        &key,
+        json::to_vec(job).unwrap().into(),
+        db::NO_NEED_WATCH,
+        None,
+    )
+    .await?)
+}
+
+/// Saves the job only if the saved one is still active, in the same transaction, so that a
+/// cancellation is never overwritten. Returns false when the job isn't active anymore.
+pub async fn set_if_active(job: &SearchJob) -> Result<bool, anyhow::Error> {
+    let key = format!("{SEARCH_JOBS_KEY}{}/{}", job.org_id, job.id);
+    let value: Bytes = json::to_vec(job).unwrap().into();
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let db = infra::db::get_db().await;
+    db.get_for_update(
+        &key,
+        db::NO_NEED_WATCH,
+        None,
+        Box::new(move |saved| {
+            let is_active = saved
+                .and_then(|v| json::from_slice::<SearchJob>(&v).ok())
+                .is_some_and(|v| v.status.is_active());
+            let _ = tx.send(is_active);
+            Ok(is_active.then_some((Some(value), None)))
+        }),
+    )
+    .await?;
+    Ok(rx.await.unwrap_or_default())
+}
+
+pub async fn delete(org_id: &str, id: &str) -> Result<(), anyhow::Error> {
+    let key = format!("{SEARCH_JOBS_KEY}{org_id}/{id}");
+    Ok(db::delete(&key, false, db::NO_NEED_WATCH, None).await?)
+}
+
+/// Lists the search jobs of an organization, or of all of them when `org_id` is empty.
+pub async fn list(org_id: &str) -> Result<Vec<SearchJob>, anyhow::Error> {
+    let key = if org_id.is_empty() {
+        SEARCH_JOBS_KEY.to_string()
+    } else {
+        format!("{SEARCH_JOBS_KEY}{org_id}/")
+    };
+    let mut items = Vec::new();
+    for item_value in db::list_values(&key).await? {
+        match json::from_slice::<SearchJob>(&item_value) {
+            Ok(job) => items.push(job),
+            Err(e) => log::error!("[SEARCH_JOB] Invalid search job: {}", e),
+        }
+    }
+    items.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(items)
+}
@@ -55,11 +55,16 @@ use crate::{
         meta::{ingestion::IngestionRequest, stream::SchemaRecords},
         utils::functions::get_vrl_compiler_config,
     },
-    service::{alerts::alert::AlertExt, db, logs::bulk::TRANSFORM_FAILED},
+    service::{
+        alerts::{alert::AlertExt, silences},
+        db,
+        logs::bulk::TRANSFORM_FAILED,
+    },
 };
 
 pub mod grpc;
 pub mod ingestion_service;
+pub mod quota;
 
 pub type TriggerAlertData = Vec<(Alert, Vec<Map<String, Value>>)>;
 
@@ -245,7 +250,21 @@ pub async fn evaluate_trigger(triggers: TriggerAlertData) {
             delay_in_secs: None,
             evaluation_took_in_secs: None,
         };
-        match alert.send_notification(val, now, None).await {
+        if let Some(silence) = silences::find_active(alert, now).await {
+            log::info!(
+                "Realtime alert {}/{} notification suppressed by silence {}",
+                &alert.org_id,
+                module_key,
+                silence.id
+            );
+            trigger_data_stream.status = TriggerDataStatus::Silenced;
+            trigger_data_stream.success_response =
+                Some(format!("notification suppressed by silence {}", silence.id));
+            trigger_data_stream.end_time = Utc::now().timestamp_micros();
+            trigger_usage_reports.push(trigger_data_stream);
+            continue;
+        }
+        match alert.notify(val, now, None).await {
             Err(e) => {
                 log::error!("Failed to send notification: {}", e);
                 trigger_data_stream.status = TriggerDataStatus::Failed;
@@ -419,6 +438,9 @@ pub fn check_ingestion_allowed(org_id: &str, stream_name: Option<&str>) -> Resul
         return Err(anyhow!("Quota exceeded for this organization [{}]", org_id));
     }
 
+    // check the ingestion quotas
+    quota::check(org_id, StreamType::Logs, stream_name)?;
+
     // check if we are allowed to ingest
     if let Some(stream_name) = stream_name {
         if db::compact::retention::is_deleting_stream(org_id, StreamType::Logs, stream_name, None) {
@@ -0,0 +1,701 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per organization and per stream ingestion quotas.
+//!
+//! The usage is tracked in memory by each ingester and shared with the others through the
+//! meta store, which makes the quotas approximate:
+//! - the rates are split evenly between the online ingesters, so they assume the requests are
+//!   balanced between them,
+//! - the daily usage of every ingester is saved every `SYNC_INTERVAL_SECS` and added to the one of
+//!   the others, so the cluster can go over the daily quota by the traffic of the last interval,
+//!   and lose it if an ingester restarts before saving it.
+//!
+//! The requests are admitted as long as the quota has not been exhausted yet and their usage is
+//! accounted once written, which lets one request go over the limit and delays the next ones
+//! accordingly.
+//!
+//! The rejections are counted in memory and reported in the usage stream every minute.
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use actix_web::{http, HttpResponse};
+use chrono::Utc;
+use config::{
+    cluster::LOCAL_NODE,
+    meta::{
+        quota::{IngestionQuota, QuotaKind, QuotaLimits},
+        stream::StreamType,
+    },
+    RwHashMap, SIZE_IN_GB,
+};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use tokio::time;
+
+use crate::{
+    common::{
+        infra::{cluster, config::INGESTION_QUOTAS},
+        meta::stream::SchemaRecords,
+    },
+    service::{
+        db::{self, quota::DailyUsage},
+        self_reporting::report_quota_rejection,
+    },
+};
+
+const MICROS_PER_SEC: i64 = 1_000_000;
+const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SEC;
+
+/// Usage of the organizations, key is `org_id`, and of the streams, key is
+/// `org_id/stream_type/stream_name`.
+static USAGES: Lazy<RwHashMap<String, Usage>> = Lazy::new(Default::default);
+
+/// Daily usage of the other ingesters, same keys as `USAGES`.
+static OTHERS_DAILY_USAGE: Lazy<RwLock<DailyUsage>> = Lazy::new(Default::default);
+
+/// Number of online ingesters the rates are split between.
+static ONLINE_INGESTERS: AtomicUsize = AtomicUsize::new(1);
+
+/// Rejections not reported yet, key is `org_id/stream_type/stream_name` with an empty
+/// `stream_name` for the quota of the organization.
+static REJECTIONS: Lazy<RwHashMap<String, Rejection>> = Lazy::new(Default::default);
+
+const REPORT_INTERVAL_SECS: u64 = 60;
+const SYNC_INTERVAL_SECS: u64 = 10;
+
+/// An ingestion request rejected because a quota was exhausted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuotaExceeded {
+    pub org_id: String,
+    pub stream_type: StreamType,
+    /// Set when the quota of the stream was exhausted, not the one of the organization
+    pub stream_name: Option<String>,
+    pub kind: QuotaKind,
+    /// Seconds to wait before the quota allows ingesting again
+    pub retry_after: u64,
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.stream_name {
+            Some(stream_name) => write!(
+                f,
+                "Ingestion quota [{}] exceeded for stream [{}/{}] of organization [{}], retry after {} seconds",
+                self.kind, self.stream_type, stream_name, self.org_id, self.retry_after
+            ),
+            None => write!(
+                f,
+                "Ingestion quota [{}] exceeded for organization [{}], retry after {} seconds",
+                self.kind, self.org_id, self.retry_after
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+impl QuotaExceeded {
+    /// The HTTP response of the rejected request, 429 with a `Retry-After` header.
+    pub fn http_response(&self) -> HttpResponse {
+        HttpResponse::TooManyRequests()
+            .insert_header((http::header::RETRY_AFTER, self.retry_after.to_string()))
+            .json(crate::common::meta::http::HttpResponse::error(
+                http::StatusCode::TOO_MANY_REQUESTS.into(),
+                self.to_string(),
+            ))
+    }
+
+    /// The gRPC status of the rejected request, `RESOURCE_EXHAUSTED` with a `retry-after`
+    /// metadata.
+    pub fn grpc_status(&self) -> tonic::Status {
+        let mut status = tonic::Status::resource_exhausted(self.to_string());
+        if let Ok(v) = self.retry_after.to_string().parse() {
+            status.metadata_mut().insert("retry-after", v);
+        }
+        status
+    }
+}
+
+pub async fn get(org_id: &str) -> Result<IngestionQuota, anyhow::Error> {
+    db::quota::get(org_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Ingestion quota not found for organization {org_id}"))
+}
+
+pub async fn set(org_id: &str, quota: IngestionQuota) -> Result<IngestionQuota, anyhow::Error> {
+    validate(&quota)?;
+    db::quota::set(org_id, &quota).await?;
+    Ok(quota)
+}
+
+pub async fn delete(org_id: &str) -> Result<(), (http::StatusCode, anyhow::Error)> {
+    match db::quota::get(org_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return Err((
+                http::StatusCode::NOT_FOUND,
+                anyhow::anyhow!("Ingestion quota not found for organization {org_id}"),
+            ));
+        }
+        Err(e) => return Err((http::StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+    db::quota::delete(org_id)
+        .await
+        .map_err(|e| (http::StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+fn validate(quota: &IngestionQuota) -> Result<(), anyhow::Error> {
+    validate_limits(&quota.limits)?;
+    let mut streams = std::collections::HashSet::new();
+    for stream in quota.streams.iter() {
+        if stream.stream_name.is_empty() {
+            return Err(anyhow::anyhow!("Stream quota requires a stream name"));
+        }
+        if stream.limits.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Stream quota [{}/{}] requires at least one limit",
+                stream.stream_type,
+                stream.stream_name
+            ));
+        }
+        if !streams.insert((stream.stream_type, stream.stream_name.as_str())) {
+            return Err(anyhow::anyhow!(
+                "Duplicate quota for stream [{}/{}]",
+                stream.stream_type,
+                stream.stream_name
+            ));
+        }
+        validate_limits(&stream.limits)?;
+    }
+    Ok(())
+}
+
+fn validate_limits(limits: &QuotaLimits) -> Result<(), anyhow::Error> {
+    if limits.bytes_per_sec == Some(0) {
+        return Err(anyhow::anyhow!("bytes_per_sec should be a positive value"));
+    }
+    if limits.events_per_sec == Some(0) {
+        return Err(anyhow::anyhow!("events_per_sec should be a positive value"));
+    }
+    if limits
+        .daily_gb
+        .is_some_and(|daily_gb| !daily_gb.is_finite() || daily_gb <= 0.0)
+    {
+        return Err(anyhow::anyhow!("daily_gb should be a positive value"));
+    }
+    Ok(())
+}
+
+/// Checks that neither the organization nor the stream, when given, exhausted its
+/// quota. The rejections are reported in the usage stream.
+pub fn check(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: Option<&str>,
+) -> Result<(), QuotaExceeded> {
+    let Some(quota) = INGESTION_QUOTAS.get(org_id) else {
+        return Ok(());
+    };
+    let now = Utc::now().timestamp_micros();
+    let mut exceeded =
+        check_usage(org_id, &quota.limits, now).map(|(kind, retry_after)| QuotaExceeded {
+            org_id: org_id.to_string(),
+            stream_type,
+            stream_name: None,
+            kind,
+            retry_after,
+        });
+    if exceeded.is_none() {
+        if let Some(stream_name) = stream_name {
+            if let Some(limits) = quota.stream_limits(stream_type, stream_name) {
+                let key = format!("{org_id}/{stream_type}/{stream_name}");
+                exceeded =
+                    check_usage(&key, limits, now).map(|(kind, retry_after)| QuotaExceeded {
+                        org_id: org_id.to_string(),
+                        stream_type,
+                        stream_name: Some(stream_name.to_string()),
+                        kind,
+                        retry_after,
+                    });
+            }
+        }
+    }
+    drop(quota);
+
+    match exceeded {
+        Some(e) => {
+            record_rejection(&e, now);
+            Err(e)
+        }
+        None => Ok(()),
+    }
+}
+
+/// Checks the quotas of all the streams of a request, see [`check`].
+pub fn check_streams<S: AsRef<str>>(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_names: impl IntoIterator<Item = S>,
+) -> Result<(), QuotaExceeded> {
+    if !INGESTION_QUOTAS.contains_key(org_id) {
+        return Ok(());
+    }
+    for stream_name in stream_names {
+        check(org_id, stream_type, Some(stream_name.as_ref()))?;
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+struct Rejection {
+    org_id: String,
+    stream_type: StreamType,
+    stream_name: String,
+    /// The reason of the last rejection
+    reason: String,
+    count: i64,
+    last_at: i64,
+}
+
+fn record_rejection(e: &QuotaExceeded, now: i64) {
+    let stream_name = e.stream_name.clone().unwrap_or_default();
+    let key = format!("{}/{}/{stream_name}", e.org_id, e.stream_type);
+    let mut rejection = REJECTIONS.entry(key).or_insert_with(|| Rejection {
+        org_id: e.org_id.clone(),
+        stream_type: e.stream_type,
+        stream_name,
+        reason: String::new(),
+        count: 0,
+        last_at: now,
+    });
+    rejection.reason = e.to_string();
+    rejection.count += 1;
+    rejection.last_at = now;
+}
+
+/// Shares the usage with the other ingesters and reports the rejections counted since the last
+/// run in the usage stream.
+pub async fn run() {
+    if !LOCAL_NODE.is_ingester() {
+        return;
+    }
+    if let Err(e) = load_daily_usage().await {
+        log::error!("[QUOTA] Error while loading the daily usage: {}", e);
+    }
+    tokio::join!(run_sync(), run_report());
+}
+
+async fn run_sync() {
+    let mut interval = time::interval(time::Duration::from_secs(SYNC_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        if let Err(e) = sync_usage().await {
+            log::error!("[QUOTA] Error while sharing the usage: {}", e);
+        }
+    }
+}
+
+async fn run_report() {
+    let mut interval = time::interval(time::Duration::from_secs(REPORT_INTERVAL_SECS));
+    interval.tick().await; // trigger the first run
+    loop {
+        interval.tick().await;
+        report_rejections().await;
+    }
+}
+
+/// Restores the daily usage saved by this ingester before a restart.
+async fn load_daily_usage() -> Result<(), anyhow::Error> {
+    let day = Utc::now().timestamp_micros() / MICROS_PER_DAY;
+    let Some(saved) = db::quota::get_daily_usage(&LOCAL_NODE.name).await? else {
+        return Ok(());
+    };
+    if saved.day != day {
+        return Ok(());
+    }
+    for (key, bytes) in saved.bytes {
+        let mut usage = USAGES.entry(key).or_default();
+        if usage.day != day {
+            usage.day = day;
+            usage.daily_bytes = 0;
+        }
+        usage.daily_bytes += bytes;
+    }
+    Ok(())
+}
+
+/// Saves the daily usage of this ingester and loads the ones of the others.
+async fn sync_usage() -> Result<(), anyhow::Error> {
+    if let Some(nodes) = cluster::get_cached_online_ingester_nodes().await {
+        ONLINE_INGESTERS.store(nodes.len().max(1), Ordering::Relaxed);
+    }
+
+    let day = Utc::now().timestamp_micros() / MICROS_PER_DAY;
+    let bytes = USAGES
+        .iter()
+        .filter(|usage| usage.day == day && usage.daily_bytes > 0)
+        .map(|usage| (usage.key().clone(), usage.daily_bytes))
+        .collect();
+    db::quota::set_daily_usage(&LOCAL_NODE.name, &DailyUsage { day, bytes }).await?;
+
+    let mut others = DailyUsage {
+        day,
+        bytes: HashMap::new(),
+    };
+    for (node, usage) in db::quota::list_daily_usages().await? {
+        if node == LOCAL_NODE.name {
+            continue;
+        }
+        if usage.day == day {
+            for (key, bytes) in usage.bytes {
+                *others.bytes.entry(key).or_default() += bytes;
+            }
+        } else if usage.day < day - 1 {
+            // the ingester is gone for a while
+            db::quota::delete_daily_usage(&node).await?;
+        }
+    }
+    *OTHERS_DAILY_USAGE.write() = others;
+    Ok(())
+}
+
+/// The bytes ingested today by the other ingesters.
+fn others_daily_bytes(key: &str, now: i64) -> u64 {
+    let others = OTHERS_DAILY_USAGE.read();
+    if others.day != now / MICROS_PER_DAY {
+        return 0;
+    }
+    others.bytes.get(key).copied().unwrap_or_default()
+}
+
+/// The limits of this ingester, the rates are split between the online ingesters.
+fn node_limits(limits: &QuotaLimits, ingesters: usize) -> QuotaLimits {
+    let split = |rate: u64| rate.div_ceil(ingesters.max(1) as u64);
+    QuotaLimits {
+        bytes_per_sec: limits.bytes_per_sec.map(split),
+        events_per_sec: limits.events_per_sec.map(split),
+        daily_gb: limits.daily_gb,
+    }
+}
+
+async fn report_rejections() {
+    let keys = REJECTIONS
+        .iter()
+        .map(|entry| entry.key().clone())
+        .collect::<Vec<_>>();
+    for key in keys {
+        let Some((_, rejection)) = REJECTIONS.remove(&key) else {
+            continue;
+        };
+        report_quota_rejection(
+            &rejection.org_id,
+            rejection.stream_type,
+            &rejection.stream_name,
+            rejection.reason,
+            rejection.count,
+            rejection.last_at,
+        )
+        .await;
+    }
+}
+
+/// Accounts the records about to be written to the stream in its quota and the one of
+/// its organization.
+pub fn consume(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    buf: &HashMap<String, SchemaRecords>,
+) {
+    let Some(quota) = INGESTION_QUOTAS.get(org_id) else {
+        return;
+    };
+    let (bytes, events) = buf.values().fold((0, 0), |(bytes, events), entry| {
+        (
+            bytes + entry.records_size as u64,
+            events + entry.records.len() as u64,
+        )
+    });
+    if bytes == 0 && events == 0 {
+        return;
+    }
+    let now = Utc::now().timestamp_micros();
+    let ingesters = ONLINE_INGESTERS.load(Ordering::Relaxed);
+    if !quota.limits.is_empty() {
+        USAGES.entry(org_id.to_string()).or_default().consume(
+            &node_limits(&quota.limits, ingesters),
+            bytes,
+            events,
+            now,
+        );
+    }
+    if let Some(limits) = quota.stream_limits(stream_type, stream_name) {
+        USAGES
+            .entry(format!("{org_id}/{stream_type}/{stream_name}"))
+            .or_default()
+            .consume(&node_limits(limits, ingesters), bytes, events, now);
+    }
+}
+
+fn check_usage(key: &str, limits: &QuotaLimits, now: i64) -> Option<(QuotaKind, u64)> {
+    if limits.is_empty() {
+        return None;
+    }
+    let limits = node_limits(limits, ONLINE_INGESTERS.load(Ordering::Relaxed));
+    let others_daily_bytes = others_daily_bytes(key, now);
+    match USAGES.get_mut(key) {
+        Some(mut usage) => usage.check(&limits, now, others_daily_bytes),
+        // nothing ingested by this ingester yet, only the daily usage of the others counts
+        None => Usage::default().check(&limits, now, others_daily_bytes),
+    }
+}
+
+#[derive(Debug, Default)]
+struct Usage {
+    bytes: Bucket,
+    events: Bucket,
+    /// Days since epoch of `daily_bytes`
+    day: i64,
+    daily_bytes: u64,
+}
+
+impl Usage {
+    fn consume(&mut self, limits: &QuotaLimits, bytes: u64, events: u64, now: i64) {
+        if let Some(rate) = limits.bytes_per_sec {
+            self.bytes.consume(rate, bytes, now);
+        }
+        if let Some(rate) = limits.events_per_sec {
+            self.events.consume(rate, events, now);
+        }
+        let day = now / MICROS_PER_DAY;
+        if self.day != day {
+            self.day = day;
+            self.daily_bytes = 0;
+        }
+        self.daily_bytes += bytes;
+    }
+
+    /// Returns the exhausted quota and the seconds until it allows ingesting again, the daily
+    /// quota includes the bytes ingested today by the other ingesters.
+    fn check(
+        &mut self,
+        limits: &QuotaLimits,
+        now: i64,
+        others_daily_bytes: u64,
+    ) -> Option<(QuotaKind, u64)> {
+        if let Some(daily_gb) = limits.daily_gb {
+            let day = now / MICROS_PER_DAY;
+            let daily_bytes = if self.day == day { self.daily_bytes } else { 0 };
+            if (daily_bytes + others_daily_bytes) as f64 >= daily_gb * SIZE_IN_GB as f64 {
+                let next_day = (day + 1) * MICROS_PER_DAY;
+                return Some((QuotaKind::DailyGb, secs_ceil(next_day - now)));
+            }
+        }
+        if let Some(rate) = limits.bytes_per_sec {
+            if let Some(retry_after) = self.bytes.retry_after(rate, now) {
+                return Some((QuotaKind::BytesPerSec, retry_after));
+            }
+        }
+        if let Some(rate) = limits.events_per_sec {
+            if let Some(retry_after) = self.events.retry_after(rate, now) {
+                return Some((QuotaKind::EventsPerSec, retry_after));
+            }
+        }
+        None
+    }
+}
+
+/// Token bucket refilled at `rate` tokens per second holding up to one second of tokens,
+/// the tokens can go negative when a request consumes more than available.
+#[derive(Debug, Default)]
+struct Bucket {
+    tokens: f64,
+    updated_at: i64,
+}
+
+impl Bucket {
+    fn refill(&mut self, rate: u64, now: i64) {
+        let rate = rate as f64;
+        if self.updated_at == 0 {
+            self.tokens = rate;
+        } else if now > self.updated_at {
+            let elapsed = (now - self.updated_at) as f64 / MICROS_PER_SEC as f64;
+            self.tokens = (self.tokens + rate * elapsed).min(rate);
+        }
+        self.updated_at = std::cmp::max(self.updated_at, now);
+    }
+
+    fn consume(&mut self, rate: u64, amount: u64, now: i64) {
+        self.refill(rate, now);
+        self.tokens -= amount as f64;
+    }
+
+    fn retry_after(&mut self, rate: u64, now: i64) -> Option<u64> {
+        self.refill(rate, now);
+        if self.tokens >= 0.0 {
+            return None;
+        }
+        if rate == 0 {
+            return Some(1);
+        }
+        Some(((-self.tokens / rate as f64).ceil() as u64).max(1))
+    }
+}
+
+fn secs_ceil(micros: i64) -> u64 {
+    std::cmp::max(1, (micros + MICROS_PER_SEC - 1) / MICROS_PER_SEC) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use config::meta::quota::StreamQuota;
+
+    use super::*;
+
+    #[test]
+    fn test_bucket() {
+        let now = 1_700_000_000 * MICROS_PER_SEC;
+        let mut bucket = Bucket::default();
+        assert_eq!(bucket.retry_after(100, now), None);
+        bucket.consume(100, 100, now);
+        assert_eq!(bucket.retry_after(100, now), None);
+        // a request is admitted even if it goes over the limit
+        bucket.consume(100, 250, now);
+        assert_eq!(bucket.retry_after(100, now), Some(3));
+        assert_eq!(bucket.retry_after(100, now + 2 * MICROS_PER_SEC), Some(1));
+        assert_eq!(bucket.retry_after(100, now + 3 * MICROS_PER_SEC), None);
+        // no more than one second of tokens is kept
+        assert_eq!(bucket.retry_after(100, now + 60 * MICROS_PER_SEC), None);
+        bucket.consume(100, 101, now + 60 * MICROS_PER_SEC);
+        assert_eq!(bucket.retry_after(100, now + 60 * MICROS_PER_SEC), Some(1));
+    }
+
+    #[test]
+    fn test_usage_check() {
+        let day = 19_000 * MICROS_PER_DAY;
+        let limits = QuotaLimits {
+            bytes_per_sec: Some(SIZE_IN_GB as u64),
+            events_per_sec: Some(10),
+            daily_gb: Some(1.0),
+        };
+        let mut usage = Usage::default();
+        assert_eq!(usage.check(&limits, day, 0), None);
+
+        usage.consume(&limits, 100, 20, day);
+        assert_eq!(
+            usage.check(&limits, day, 0),
+            Some((QuotaKind::EventsPerSec, 1))
+        );
+        assert_eq!(usage.check(&limits, day + MICROS_PER_SEC, 0), None);
+
+        // the daily usage of the other ingesters counts
+        let (kind, _) = usage
+            .check(&limits, day + MICROS_PER_SEC, SIZE_IN_GB as u64 - 100)
+            .unwrap();
+        assert_eq!(kind, QuotaKind::DailyGb);
+
+        // the daily quota is reset at midnight
+        usage.consume(&limits, SIZE_IN_GB as u64, 0, day + 2 * MICROS_PER_SEC);
+        let (kind, retry_after) = usage
+            .check(&limits, day + 3600 * MICROS_PER_SEC, 0)
+            .unwrap();
+        assert_eq!(kind, QuotaKind::DailyGb);
+        assert_eq!(retry_after, 86_400 - 3600);
+        assert_eq!(usage.check(&limits, day + MICROS_PER_DAY, 0), None);
+    }
+
+    #[test]
+    fn test_node_limits() {
+        let limits = QuotaLimits {
+            bytes_per_sec: Some(1000),
+            events_per_sec: Some(10),
+            daily_gb: Some(1.0),
+        };
+        assert_eq!(node_limits(&limits, 1), limits);
+        assert_eq!(
+            node_limits(&limits, 3),
+            QuotaLimits {
+                bytes_per_sec: Some(334),
+                events_per_sec: Some(4),
+                daily_gb: Some(1.0),
+            }
+        );
+        assert_eq!(node_limits(&limits, 0), limits);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut quota = IngestionQuota {
+            limits: QuotaLimits {
+                daily_gb: Some(10.0),
+                ..Default::default()
+            },
+            streams: vec![StreamQuota {
+                stream_type: StreamType::Logs,
+                stream_name: "k8s".to_string(),
+                limits: QuotaLimits {
+                    events_per_sec: Some(100),
+                    ..Default::default()
+                },
+            }],
+        };
+        assert!(validate(&quota).is_ok());
+
+        quota.streams.push(quota.streams[0].clone());
+        assert!(validate(&quota).is_err());
+        quota.streams.pop();
+
+        quota.streams[0].limits.events_per_sec = Some(0);
+        assert!(validate(&quota).is_err());
+        quota.streams[0].limits.events_per_sec = None;
+        assert!(validate(&quota).is_err());
+
+        quota.streams.clear();
+        quota.limits.daily_gb = Some(-1.0);
+        assert!(validate(&quota).is_err());
+    }
+
+    #[test]
+    fn test_quota_exceeded() {
+        let e = QuotaExceeded {
+            org_id: "default".to_string(),
+            stream_type: StreamType::Logs,
+            stream_name: Some("k8s".to_string()),
+            kind: QuotaKind::BytesPerSec,
+            retry_after: 2,
+        };
+        let resp = e.http_response();
+        assert_eq!(resp.status(), http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(resp.headers().get(http::header::RETRY_AFTER).unwrap(), "2");
+        let status = e.grpc_status();
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        assert_eq!(status.metadata().get("retry-after").unwrap(), "2");
+        assert!(anyhow::Error::from(e.clone())
+            .downcast_ref::<QuotaExceeded>()
+            .is_some());
+
+        // the rejections are counted until reported
+        let now = Utc::now().timestamp_micros();
+        record_rejection(&e, now);
+        record_rejection(&e, now + 1);
+        let rejection = REJECTIONS.get("default/logs/k8s").unwrap();
+        assert_eq!(rejection.count, 2);
+        assert_eq!(rejection.last_at, now + 1);
+    }
+}
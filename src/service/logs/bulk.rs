@@ -31,13 +31,17 @@ use config::{
     utils::{flatten, json, time::parse_timestamp_micro_from_value},
     BLOCKED_STREAMS, ID_COL_NAME, ORIGINAL_DATA_COL_NAME,
 };
+use serde::Deserialize;
 
 use super::{ingestion_log_enabled, log_failed_record};
 use crate::{
-    common::meta::ingestion::{BulkResponse, BulkResponseError, BulkResponseItem, IngestionStatus},
+    common::meta::ingestion::{
+        BulkResponse, BulkResponseError, BulkResponseItem, HecMetadata, HecResponse,
+        IngestionStatus,
+    },
     service::{
         format_stream_name,
-        ingestion::check_ingestion_allowed,
+        ingestion::{check_ingestion_allowed, quota::QuotaExceeded},
         pipeline::batch_execution::{ExecutablePipeline, ExecutablePipelineBulkInputs},
         schema::get_upto_discard_error,
     },
@@ -53,6 +57,64 @@ pub async fn ingest(
     org_id: &str,
     body: web::Bytes,
     user_email: &str,
+) -> Result<BulkResponse, anyhow::Error> {
+    ingest_documents(
+        thread_id,
+        org_id,
+        parse_bulk_documents(&body),
+        user_email,
+        UsageType::Bulk,
+        "/api/org/ingest/logs/_bulk",
+    )
+    .await
+}
+
+/// A document of a bulk request with the action and the stream it was sent with.
+pub struct BulkDocument {
+    pub action: String,
+    pub stream_name: String,
+    pub doc_id: Option<String>,
+    pub value: json::Value,
+}
+
+/// Parses the ndjson body of an ES `_bulk` request, each document is an action line followed by
+/// a data line.
+fn parse_bulk_documents(body: &[u8]) -> impl Iterator<Item = Result<BulkDocument>> + '_ {
+    let mut values = BufReader::new(body).lines().filter_map(|line| match line {
+        Ok(line) if line.is_empty() => None,
+        Ok(line) => Some(json::from_slice::<json::Value>(line.as_bytes()).map_err(|e| e.into())),
+        Err(e) => Some(Err(e.into())),
+    });
+    std::iter::from_fn(move || loop {
+        let value = match values.next()? {
+            Ok(v) => v,
+            Err(e) => return Some(Err(e)),
+        };
+        // check bulk operate
+        let Some((action, stream_name, doc_id)) = super::parse_bulk_index(&value) else {
+            continue; // skip
+        };
+        return match values.next()? {
+            Ok(value) => Some(Ok(BulkDocument {
+                action,
+                stream_name,
+                doc_id,
+                value,
+            })),
+            Err(e) => Some(Err(e)),
+        };
+    })
+}
+
+/// Ingests the documents of a bulk request into the streams they were sent with, `endpoint` is
+/// the label of the request metrics.
+pub async fn ingest_documents(
+    thread_id: usize,
+    org_id: &str,
+    documents: impl IntoIterator<Item = Result<BulkDocument>>,
+    user_email: &str,
+    usage_type: UsageType,
+    endpoint: &str,
 ) -> Result<BulkResponse, anyhow::Error> {
     let start = std::time::Instant::now();
     let started_at = Utc::now().timestamp_micros();
@@ -86,206 +148,188 @@ pub async fn ingest(
     let mut streams_need_original_set: HashSet<String> = HashSet::new();
 
     let mut json_data_by_stream = HashMap::new();
-    let mut next_line_is_data = false;
-    let reader = BufReader::new(body.as_ref());
-    for line in reader.lines() {
-        let line = line?;
-        if line.is_empty() {
-            continue;
+    for document in documents {
+        let document = document?;
+        let mut value = document.value;
+        (action, stream_name, doc_id) = (document.action, document.stream_name, document.doc_id);
+
+        if !cfg.common.skip_formatting_stream_name {
+            stream_name = format_stream_name(&stream_name);
         }
 
-        let mut value: json::Value = json::from_slice(line.as_bytes())?;
+        // skip blocked streams
+        let key = format!("{org_id}/{}/{stream_name}", StreamType::Logs);
+        if BLOCKED_STREAMS.contains(&key) {
+            // print warning only once
+            blocked_stream_warnings.entry(key).or_insert_with(|| {
+                log::warn!("stream [{stream_name}] is blocked from ingestion");
+                true
+            });
+            continue; // skip
+        }
 
-        if !next_line_is_data {
-            // check bulk operate
-            let ret = super::parse_bulk_index(&value);
-            if ret.is_none() {
-                continue; // skip
+        let mut streams = vec![StreamParams {
+            org_id: org_id.to_owned().into(),
+            stream_type: StreamType::Logs,
+            stream_name: stream_name.to_owned().into(),
+        }];
+
+        // Start retrieve associated pipeline and initialize ExecutablePipeline
+        if !stream_executable_pipelines.contains_key(&stream_name) {
+            let exec_pl_option = crate::service::ingestion::get_stream_executable_pipeline(
+                org_id,
+                &stream_name,
+                &StreamType::Logs,
+            )
+            .await;
+            if let Some(exec_pl) = &exec_pl_option {
+                let pl_destinations = exec_pl.get_all_destination_streams();
+                streams.extend(pl_destinations);
             }
-            (action, stream_name, doc_id) = ret.unwrap();
+            stream_executable_pipelines.insert(stream_name.clone(), exec_pl_option);
+        }
+        // End pipeline params construction
 
-            if !cfg.common.skip_formatting_stream_name {
-                stream_name = format_stream_name(&stream_name);
+        crate::service::ingestion::get_uds_and_original_data_streams(
+            &streams,
+            &mut user_defined_schema_map,
+            &mut streams_need_original_set,
+        )
+        .await;
+
+        // store a copy of original data before it's being transformed and/or flattened, when
+        // 1. original data is not an object -> won't be flattened.
+        let original_data = if value.is_object() {
+            // 2. current stream does not have pipeline
+            if stream_executable_pipelines
+                .get(&stream_name)
+                .unwrap()
+                .is_none()
+            {
+                // current stream requires original
+                streams_need_original_set
+                    .contains(&stream_name)
+                    .then_some(value.to_string())
+            } else {
+                // 3. with pipeline, storing original as long as streams_need_original_set is not
+                //    empty
+                // because not sure the pipeline destinations
+                (!streams_need_original_set.is_empty()).then_some(value.to_string())
             }
+        } else {
+            None // `item` won't be flattened, no need to store original
+        };
+
+        if stream_executable_pipelines
+            .get(&stream_name)
+            .unwrap()
+            .is_some()
+        {
+            // current stream has pipeline. buff the record for batch processing later
+            let inputs = stream_pipeline_inputs
+                .entry(stream_name.clone())
+                .or_default();
+            inputs.add_input(value, doc_id.to_owned(), original_data);
+        } else {
+            // JSON Flattening
+            value = flatten::flatten_with_level(value, cfg.limit.ingest_flatten_level)?;
+
+            // get json object
+            let mut local_val = match value.take() {
+                json::Value::Object(v) => v,
+                _ => unreachable!(),
+            };
 
-            // skip blocked streams
-            let key = format!("{org_id}/{}/{stream_name}", StreamType::Logs);
-            if BLOCKED_STREAMS.contains(&key) {
-                // print warning only once
-                blocked_stream_warnings.entry(key).or_insert_with(|| {
-                    log::warn!("stream [{stream_name}] is blocked from ingestion");
-                    true
-                });
-                continue; // skip
+            // set _id
+            if let Some(doc_id) = &doc_id {
+                local_val.insert("_id".to_string(), json::Value::String(doc_id.to_owned()));
             }
 
-            let mut streams = vec![StreamParams {
-                org_id: org_id.to_owned().into(),
-                stream_type: StreamType::Logs,
-                stream_name: stream_name.to_owned().into(),
-            }];
+            if let Some(fields) = user_defined_schema_map.get(&stream_name) {
+                local_val = crate::service::logs::refactor_map(local_val, fields);
+            }
 
-            // Start retrieve associated pipeline and initialize ExecutablePipeline
-            if !stream_executable_pipelines.contains_key(&stream_name) {
-                let exec_pl_option = crate::service::ingestion::get_stream_executable_pipeline(
+            // add `_original` and '_record_id` if required by StreamSettings
+            if streams_need_original_set.contains(&stream_name) && original_data.is_some() {
+                local_val.insert(
+                    ORIGINAL_DATA_COL_NAME.to_string(),
+                    original_data.unwrap().into(),
+                );
+                let record_id = crate::service::ingestion::generate_record_id(
                     org_id,
                     &stream_name,
                     &StreamType::Logs,
-                )
-                .await;
-                if let Some(exec_pl) = &exec_pl_option {
-                    let pl_destinations = exec_pl.get_all_destination_streams();
-                    streams.extend(pl_destinations);
-                }
-                stream_executable_pipelines.insert(stream_name.clone(), exec_pl_option);
+                );
+                local_val.insert(
+                    ID_COL_NAME.to_string(),
+                    json::Value::String(record_id.to_string()),
+                );
             }
-            // End pipeline params construction
 
-            crate::service::ingestion::get_uds_and_original_data_streams(
-                &streams,
-                &mut user_defined_schema_map,
-                &mut streams_need_original_set,
-            )
-            .await;
-
-            next_line_is_data = true;
-        } else {
-            next_line_is_data = false;
-
-            // store a copy of original data before it's being transformed and/or flattened, when
-            // 1. original data is not an object -> won't be flattened.
-            let original_data = if value.is_object() {
-                // 2. current stream does not have pipeline
-                if stream_executable_pipelines
-                    .get(&stream_name)
-                    .unwrap()
-                    .is_none()
-                {
-                    // current stream requires original
-                    streams_need_original_set
-                        .contains(&stream_name)
-                        .then_some(value.to_string())
-                } else {
-                    // 3. with pipeline, storing original as long as streams_need_original_set is
-                    //    not empty
-                    // because not sure the pipeline destinations
-                    (!streams_need_original_set.is_empty()).then_some(value.to_string())
-                }
-            } else {
-                None // `item` won't be flattened, no need to store original
+            // handle timestamp
+            let timestamp = match local_val.get(&cfg.common.column_timestamp) {
+                Some(v) => match parse_timestamp_micro_from_value(v) {
+                    Ok(t) => t,
+                    Err(_e) => {
+                        bulk_res.errors = true;
+                        metrics::INGEST_ERRORS
+                            .with_label_values(&[
+                                org_id,
+                                StreamType::Logs.to_string().as_str(),
+                                &stream_name,
+                                TS_PARSE_FAILED,
+                            ])
+                            .inc();
+                        log_failed_record(log_ingestion_errors, &value, TS_PARSE_FAILED);
+                        add_record_status(
+                            stream_name.clone(),
+                            &doc_id,
+                            action.clone(),
+                            Some(value),
+                            &mut bulk_res,
+                            Some(TS_PARSE_FAILED.to_string()),
+                            Some(TS_PARSE_FAILED.to_string()),
+                        );
+                        continue;
+                    }
+                },
+                None => Utc::now().timestamp_micros(),
             };
 
-            if stream_executable_pipelines
-                .get(&stream_name)
-                .unwrap()
-                .is_some()
-            {
-                // current stream has pipeline. buff the record for batch processing later
-                let inputs = stream_pipeline_inputs
-                    .entry(stream_name.clone())
-                    .or_default();
-                inputs.add_input(value, doc_id.to_owned(), original_data);
-            } else {
-                // JSON Flattening
-                value = flatten::flatten_with_level(value, cfg.limit.ingest_flatten_level)?;
-
-                // get json object
-                let mut local_val = match value.take() {
-                    json::Value::Object(v) => v,
-                    _ => unreachable!(),
-                };
-
-                // set _id
-                if let Some(doc_id) = &doc_id {
-                    local_val.insert("_id".to_string(), json::Value::String(doc_id.to_owned()));
-                }
-
-                if let Some(fields) = user_defined_schema_map.get(&stream_name) {
-                    local_val = crate::service::logs::refactor_map(local_val, fields);
-                }
-
-                // add `_original` and '_record_id` if required by StreamSettings
-                if streams_need_original_set.contains(&stream_name) && original_data.is_some() {
-                    local_val.insert(
-                        ORIGINAL_DATA_COL_NAME.to_string(),
-                        original_data.unwrap().into(),
-                    );
-                    let record_id = crate::service::ingestion::generate_record_id(
+            // check ingestion time
+            if timestamp < min_ts {
+                bulk_res.errors = true;
+                let failure_reason = Some(get_upto_discard_error().to_string());
+                metrics::INGEST_ERRORS
+                    .with_label_values(&[
                         org_id,
+                        StreamType::Logs.to_string().as_str(),
                         &stream_name,
-                        &StreamType::Logs,
-                    );
-                    local_val.insert(
-                        ID_COL_NAME.to_string(),
-                        json::Value::String(record_id.to_string()),
-                    );
-                }
-
-                // handle timestamp
-                let timestamp = match local_val.get(&cfg.common.column_timestamp) {
-                    Some(v) => match parse_timestamp_micro_from_value(v) {
-                        Ok(t) => t,
-                        Err(_e) => {
-                            bulk_res.errors = true;
-                            metrics::INGEST_ERRORS
-                                .with_label_values(&[
-                                    org_id,
-                                    StreamType::Logs.to_string().as_str(),
-                                    &stream_name,
-                                    TS_PARSE_FAILED,
-                                ])
-                                .inc();
-                            log_failed_record(log_ingestion_errors, &value, TS_PARSE_FAILED);
-                            add_record_status(
-                                stream_name.clone(),
-                                &doc_id,
-                                action.clone(),
-                                Some(value),
-                                &mut bulk_res,
-                                Some(TS_PARSE_FAILED.to_string()),
-                                Some(TS_PARSE_FAILED.to_string()),
-                            );
-                            continue;
-                        }
-                    },
-                    None => Utc::now().timestamp_micros(),
-                };
-
-                // check ingestion time
-                if timestamp < min_ts {
-                    bulk_res.errors = true;
-                    let failure_reason = Some(get_upto_discard_error().to_string());
-                    metrics::INGEST_ERRORS
-                        .with_label_values(&[
-                            org_id,
-                            StreamType::Logs.to_string().as_str(),
-                            &stream_name,
-                            TS_PARSE_FAILED,
-                        ])
-                        .inc();
-                    log_failed_record(log_ingestion_errors, &value, TS_PARSE_FAILED);
-                    add_record_status(
-                        stream_name.clone(),
-                        &doc_id,
-                        action.clone(),
-                        Some(value),
-                        &mut bulk_res,
-                        Some(TS_PARSE_FAILED.to_string()),
-                        failure_reason,
-                    );
-                    continue;
-                }
-                local_val.insert(
-                    cfg.common.column_timestamp.clone(),
-                    json::Value::Number(timestamp.into()),
+                        TS_PARSE_FAILED,
+                    ])
+                    .inc();
+                log_failed_record(log_ingestion_errors, &value, TS_PARSE_FAILED);
+                add_record_status(
+                    stream_name.clone(),
+                    &doc_id,
+                    action.clone(),
+                    Some(value),
+                    &mut bulk_res,
+                    Some(TS_PARSE_FAILED.to_string()),
+                    failure_reason,
                 );
-
-                let (ts_data, fn_num) = json_data_by_stream
-                    .entry(stream_name.clone())
-                    .or_insert((Vec::new(), None));
-                ts_data.push((timestamp, local_val));
-                *fn_num = Some(0); // no pl -> no func
+                continue;
             }
+            local_val.insert(
+                cfg.common.column_timestamp.clone(),
+                json::Value::Number(timestamp.into()),
+            );
+
+            let (ts_data, fn_num) = json_data_by_stream
+                .entry(stream_name.clone())
+                .or_insert((Vec::new(), None));
+            ts_data.push((timestamp, local_val));
+            *fn_num = Some(0); // no pl -> no func
         }
     }
 
@@ -464,7 +508,7 @@ pub async fn ingest(
             org_id,
             user_email,
             (started_at, &start),
-            UsageType::Bulk,
+            usage_type,
             &mut status,
             json_data_by_stream,
         )
@@ -475,6 +519,7 @@ pub async fn ingest(
         bulk_res.took = start.elapsed().as_millis();
         match write_result {
             Ok(()) => ("200", bulk_res),
+            Err(e) if e.is::<QuotaExceeded>() => return Err(e),
             Err(e) => {
                 log::error!("Error while writing logs: {}", e);
                 bulk_res.errors = true;
@@ -487,7 +532,7 @@ pub async fn ingest(
     let took_time = start.elapsed().as_secs_f64();
     metrics::HTTP_RESPONSE_TIME
         .with_label_values(&[
-            "/api/org/ingest/logs/_bulk",
+            endpoint,
             metric_rpt_status_code,
             org_id,
             "",
@@ -496,7 +541,7 @@ pub async fn ingest(
         .observe(took_time);
     metrics::HTTP_INCOMING_REQUESTS
         .with_label_values(&[
-            "/api/org/ingest/logs/_bulk",
+            endpoint,
             metric_rpt_status_code,
             org_id,
             "",
@@ -507,6 +552,256 @@ pub async fn ingest(
     Ok(response_body)
 }
 
+/// Field of the records holding the Splunk HEC events which are not json objects
+pub const HEC_EVENT_FIELD: &str = "message";
+
+/// Splunk HTTP Event Collector compatible ingestion, the events are ingested into the stream
+/// named by their `index`. The events of the `/raw` endpoint (`is_raw`) are the lines of the body.
+pub async fn ingest_hec(
+    thread_id: usize,
+    org_id: &str,
+    body: web::Bytes,
+    is_raw: bool,
+    metadata: HecMetadata,
+    user_email: &str,
+) -> Result<HecResponse, anyhow::Error> {
+    let records = if is_raw {
+        parse_hec_raw(&body, metadata)
+    } else {
+        match parse_hec_events(&body, &metadata) {
+            Ok(records) => records,
+            Err(e) => return Ok(e),
+        }
+    };
+    if records.is_empty() {
+        return Ok(HecResponse::error(HecResponse::NO_DATA, "No data", None));
+    }
+
+    // the events too old are rejected alone, like the invalid ones of the bulk requests
+    let total = records.len();
+    let min_ts = (Utc::now()
+        - Duration::try_hours(get_config().limit.ingest_allowed_upto).unwrap())
+    .timestamp_micros();
+    let (records, rejected) = reject_too_old_hec_events(records, min_ts);
+    if records.is_empty() {
+        return Ok(HecResponse::error(
+            HecResponse::INVALID_DATA_FORMAT,
+            format!("Invalid data format: {}", get_upto_discard_error()),
+            rejected.first().copied(),
+        ));
+    }
+
+    let documents = records.into_iter().map(|(stream_name, value)| {
+        Ok(BulkDocument {
+            action: "index".to_string(),
+            stream_name,
+            doc_id: None,
+            value: json::Value::Object(value),
+        })
+    });
+    let bulk_res = ingest_documents(
+        thread_id,
+        org_id,
+        documents,
+        user_email,
+        UsageType::Hec,
+        "/api/org/services/collector",
+    )
+    .await?;
+    if !bulk_res.errors && rejected.is_empty() {
+        return Ok(HecResponse::success());
+    }
+
+    // the failures of the ingestion, e.g. of a pipeline, aren't bound to an event
+    let errors = bulk_res
+        .items
+        .iter()
+        .flat_map(|item| item.values())
+        .filter_map(|item| item.error.as_ref())
+        .collect::<Vec<_>>();
+    let reason = errors
+        .first()
+        .map(|e| e.reason.clone())
+        .unwrap_or_else(|| get_upto_discard_error().to_string());
+    let failed = rejected.len() + errors.len();
+    if failed >= total {
+        return Ok(HecResponse::error(
+            HecResponse::INVALID_DATA_FORMAT,
+            format!("Invalid data format: {reason}"),
+            rejected.first().copied(),
+        ));
+    }
+    Ok(HecResponse::partial_success(
+        format!("Partial success, {failed} of {total} events failed: {reason}"),
+        rejected,
+    ))
+}
+
+/// Splits the events older than `min_ts` from the others, returns the events kept and the
+/// indexes of the events rejected.
+fn reject_too_old_hec_events(
+    records: Vec<(String, json::Map<String, json::Value>)>,
+    min_ts: i64,
+) -> (Vec<(String, json::Map<String, json::Value>)>, Vec<usize>) {
+    let ts_field = &get_config().common.column_timestamp;
+    let mut kept = Vec::with_capacity(records.len());
+    let mut rejected = Vec::new();
+    for (i, (stream_name, record)) in records.into_iter().enumerate() {
+        let too_old = record
+            .get(ts_field)
+            .and_then(|v| v.as_i64())
+            .is_some_and(|ts| ts < min_ts);
+        if too_old {
+            rejected.push(i);
+        } else {
+            kept.push((stream_name, record));
+        }
+    }
+    (kept, rejected)
+}
+
+#[derive(Deserialize)]
+struct HecEvent {
+    #[serde(default)]
+    event: Option<json::Value>,
+    #[serde(default)]
+    time: Option<json::Value>,
+    #[serde(default)]
+    fields: Option<json::Map<String, json::Value>>,
+    #[serde(flatten)]
+    metadata: HecMetadata,
+}
+
+/// Parses the body of a `/services/collector/event` request, a sequence of json objects having
+/// the event and its metadata, e.g.
+/// `{"time": 1426279439.123, "index": "k8s", "host": "web-01", "event": "a line", "fields": {}}`.
+fn parse_hec_events(
+    body: &[u8],
+    defaults: &HecMetadata,
+) -> Result<Vec<(String, json::Map<String, json::Value>)>, HecResponse> {
+    let cfg = get_config();
+    let mut records = Vec::new();
+    for (i, event) in json::Deserializer::from_slice(body)
+        .into_iter::<HecEvent>()
+        .enumerate()
+    {
+        let invalid_data_format = || {
+            HecResponse::error(
+                HecResponse::INVALID_DATA_FORMAT,
+                "Invalid data format",
+                Some(i),
+            )
+        };
+        let event = event.map_err(|_| invalid_data_format())?;
+        let mut record = match event.event {
+            None | Some(json::Value::Null) => {
+                return Err(HecResponse::error(
+                    HecResponse::EVENT_FIELD_REQUIRED,
+                    "Event field is required",
+                    Some(i),
+                ));
+            }
+            Some(json::Value::String(v)) if v.is_empty() => {
+                return Err(HecResponse::error(
+                    HecResponse::EVENT_FIELD_BLANK,
+                    "Event field cannot be blank",
+                    Some(i),
+                ));
+            }
+            Some(json::Value::Object(v)) => v,
+            Some(v) => json::Map::from_iter([(HEC_EVENT_FIELD.to_string(), v)]),
+        };
+
+        let HecMetadata {
+            index,
+            host,
+            source,
+            sourcetype,
+        } = event.metadata;
+        set_hec_metadata(
+            &mut record,
+            HecMetadata {
+                index: None,
+                host: host.or_else(|| defaults.host.clone()),
+                source: source.or_else(|| defaults.source.clone()),
+                sourcetype: sourcetype.or_else(|| defaults.sourcetype.clone()),
+            },
+        );
+        // the indexed fields
+        record.extend(event.fields.unwrap_or_default());
+        if let Some(time) = event.time {
+            let timestamp = parse_hec_time(&time).ok_or_else(invalid_data_format)?;
+            record.insert(
+                cfg.common.column_timestamp.clone(),
+                json::Value::Number(timestamp.into()),
+            );
+        }
+
+        let stream_name = index
+            .or_else(|| defaults.index.clone())
+            .unwrap_or_else(|| "default".to_string());
+        records.push((stream_name, record));
+    }
+    Ok(records)
+}
+
+/// Parses the body of a `/services/collector/raw` request, each line is an event.
+fn parse_hec_raw(
+    body: &[u8],
+    metadata: HecMetadata,
+) -> Vec<(String, json::Map<String, json::Value>)> {
+    let stream_name = metadata
+        .index
+        .clone()
+        .unwrap_or_else(|| "default".to_string());
+    String::from_utf8_lossy(body)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut record = json::Map::from_iter([(
+                HEC_EVENT_FIELD.to_string(),
+                json::Value::String(line.to_string()),
+            )]);
+            set_hec_metadata(&mut record, metadata.clone());
+            (stream_name.clone(), record)
+        })
+        .collect()
+}
+
+fn set_hec_metadata(record: &mut json::Map<String, json::Value>, metadata: HecMetadata) {
+    let fields = [
+        ("host", metadata.host),
+        ("source", metadata.source),
+        ("sourcetype", metadata.sourcetype),
+    ];
+    for (name, value) in fields {
+        if let Some(value) = value {
+            record.insert(name.to_string(), json::Value::String(value));
+        }
+    }
+}
+
+/// Parses the time of a HEC event, in seconds since the epoch with optional decimals, to
+/// microseconds.
+fn parse_hec_time(time: &json::Value) -> Option<i64> {
+    match time {
+        json::Value::Number(v) => match v.as_i64() {
+            Some(v) => Some(v * 1_000_000),
+            None => v.as_f64().map(|v| (v * 1_000_000.0).round() as i64),
+        },
+        json::Value::String(v) => {
+            let (secs, decimals) = v.trim().split_once('.').unwrap_or((v.trim(), ""));
+            let secs = secs.parse::<i64>().ok()?;
+            if !decimals.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let decimals = format!("{:0<6}", &decimals[..decimals.len().min(6)]);
+            Some(secs * 1_000_000 + decimals.parse::<i64>().ok()?)
+        }
+        _ => None,
+    }
+}
+
 pub fn add_record_status(
     stream_name: String,
     doc_id: &Option<String>,
@@ -584,4 +879,113 @@ mod tests {
         );
         assert!(bulk_res.items.len() == 1);
     }
+
+    #[test]
+    fn test_parse_bulk_documents() {
+        let body = r#"{"index": {"_index": "olympics", "_id": "1"}}
+{"athlete": "CHASAPIS, Spiridon"}
+
+{"foo": {}}
+{"create": {"_index": "games"}}
+{"city": "BER"}
+"#;
+        let documents = parse_bulk_documents(body.as_bytes())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[0].action, "index");
+        assert_eq!(documents[0].stream_name, "olympics");
+        assert_eq!(documents[0].doc_id, Some("1".to_string()));
+        assert_eq!(documents[0].value["athlete"], "CHASAPIS, Spiridon");
+        assert_eq!(documents[1].stream_name, "games");
+        assert_eq!(documents[1].value["city"], "BER");
+
+        assert!(
+            parse_bulk_documents(b"{\"index\": {\"_index\": \"a\"}}\nnot json").any(|d| d.is_err())
+        );
+    }
+
+    #[test]
+    fn test_parse_hec_events() {
+        let body = r#"{"time": 1426279439.5, "host": "web-01", "index": "k8s", "event": "a line"}
+{"time": "1426279439.123456", "event": {"level": "info", "msg": "hello"}, "fields": {"env": "prod"}}"#;
+        let defaults = HecMetadata {
+            sourcetype: Some("access_log".to_string()),
+            ..Default::default()
+        };
+        let records = parse_hec_events(body.as_bytes(), &defaults).unwrap();
+        let ts_field = get_config().common.column_timestamp.clone();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].0, "k8s");
+        assert_eq!(records[0].1[HEC_EVENT_FIELD], "a line");
+        assert_eq!(records[0].1["host"], "web-01");
+        assert_eq!(records[0].1["sourcetype"], "access_log");
+        assert_eq!(records[0].1[&ts_field], 1426279439500000_i64);
+        assert_eq!(records[1].0, "default");
+        assert_eq!(records[1].1["level"], "info");
+        assert_eq!(records[1].1["env"], "prod");
+        assert_eq!(records[1].1[&ts_field], 1426279439123456_i64);
+
+        let err = parse_hec_events(br#"{"event": "a"}{"host": "b"}"#, &defaults).unwrap_err();
+        assert_eq!(err.code, HecResponse::EVENT_FIELD_REQUIRED);
+        assert_eq!(err.invalid_event_number, Some(1));
+        let err = parse_hec_events(br#"{"event": ""}"#, &defaults).unwrap_err();
+        assert_eq!(err.code, HecResponse::EVENT_FIELD_BLANK);
+        let err = parse_hec_events(br#"{"event": "a", "time": "x"}"#, &defaults).unwrap_err();
+        assert_eq!(err.code, HecResponse::INVALID_DATA_FORMAT);
+    }
+
+    #[test]
+    fn test_reject_too_old_hec_events() {
+        let ts_field = get_config().common.column_timestamp.clone();
+        let record = |ts: i64| {
+            (
+                "default".to_string(),
+                json::Map::from_iter([(ts_field.clone(), json::Value::Number(ts.into()))]),
+            )
+        };
+        let records = vec![
+            record(100),
+            record(10),
+            ("default".to_string(), json::Map::new()),
+            record(5),
+        ];
+        let (kept, rejected) = reject_too_old_hec_events(records, 50);
+        assert_eq!(kept.len(), 2);
+        assert!(kept[1].1.is_empty());
+        assert_eq!(rejected, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_parse_hec_raw() {
+        let metadata = HecMetadata {
+            index: Some("k8s".to_string()),
+            host: Some("web-01".to_string()),
+            ..Default::default()
+        };
+        let records = parse_hec_raw(b"first line\n\nsecond line\n", metadata);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].0, "k8s");
+        assert_eq!(records[0].1[HEC_EVENT_FIELD], "first line");
+        assert_eq!(records[1].1[HEC_EVENT_FIELD], "second line");
+        assert_eq!(records[1].1["host"], "web-01");
+    }
+
+    #[test]
+    fn test_parse_hec_time() {
+        assert_eq!(
+            parse_hec_time(&json::json!(1426279439)),
+            Some(1426279439000000)
+        );
+        assert_eq!(
+            parse_hec_time(&json::json!("1426279439.1")),
+            Some(1426279439100000)
+        );
+        assert_eq!(
+            parse_hec_time(&json::json!("1426279439")),
+            Some(1426279439000000)
+        );
+        assert_eq!(parse_hec_time(&json::json!("1426279439.x")), None);
+        assert_eq!(parse_hec_time(&json::json!(true)), None);
+    }
 }
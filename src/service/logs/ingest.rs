@@ -48,8 +48,10 @@ use crate::{
         StreamStatus,
     },
     service::{
-        format_stream_name, get_formatted_stream_name, ingestion::check_ingestion_allowed,
-        logs::bulk::TRANSFORM_FAILED, schema::get_upto_discard_error,
+        format_stream_name, get_formatted_stream_name,
+        ingestion::{check_ingestion_allowed, quota::QuotaExceeded},
+        logs::bulk::TRANSFORM_FAILED,
+        schema::get_upto_discard_error,
     },
 };
 
@@ -141,6 +143,16 @@ pub async fn ingest(
             UsageType::RUM,
             IngestionData::Multi(req),
         ),
+        IngestionRequest::Loki(req) => (
+            "/api/org/loki/api/v1/push",
+            UsageType::Loki,
+            IngestionData::JSON(req),
+        ),
+        IngestionRequest::Kafka(req) => (
+            "/api/org/ingest/logs/_kafka",
+            UsageType::Kafka,
+            IngestionData::JSON(req),
+        ),
         IngestionRequest::Usage(req) => {
             // no need to report usage for usage data
             need_usage_report = false;
@@ -376,6 +388,9 @@ pub async fn ingest(
         };
         match write_result {
             Ok(()) => ("200", stream_status),
+            Err(e) if e.is::<QuotaExceeded>() => return Err(e),
+            // the Kafka consumer retries the batch, its offsets mustn't be committed
+            Err(e) if usage_type == UsageType::Kafka => return Err(e),
             Err(e) => {
                 log::error!("Error while writing logs: {}", e);
                 ("500", stream_status)
@@ -0,0 +1,206 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ingestion of the messages consumed from Kafka topics, see `job::kafka_consumer`.
+
+use anyhow::{anyhow, Result};
+use config::utils::json;
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+use prost::Message;
+
+use crate::common::meta::ingestion::IngestionRequest;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayloadFormat {
+    /// A json object or an array of json objects per message
+    Json,
+    /// An OTLP `ExportLogsServiceRequest` protobuf per message
+    Otlp,
+}
+
+/// Stream the messages of a topic are ingested into.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TopicMapping {
+    pub topic: String,
+    pub org_id: String,
+    pub stream_name: String,
+    pub format: PayloadFormat,
+}
+
+/// Parses the comma separated `<topic>=<org>/<stream>[:json|otlp]` mappings of
+/// `ZO_KAFKA_TOPICS`.
+pub fn parse_topic_mappings(topics: &str) -> Result<Vec<TopicMapping>> {
+    topics
+        .split(',')
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|mapping| {
+            let invalid = || anyhow!("Invalid topic mapping: {mapping}");
+            let (topic, target) = mapping.split_once('=').ok_or_else(invalid)?;
+            let (target, format) = match target.split_once(':') {
+                Some((target, "json")) => (target, PayloadFormat::Json),
+                Some((target, "otlp")) => (target, PayloadFormat::Otlp),
+                Some(_) => return Err(invalid()),
+                None => (target, PayloadFormat::Json),
+            };
+            let (org_id, stream_name) = target.split_once('/').ok_or_else(invalid)?;
+            let (topic, org_id, stream_name) = (topic.trim(), org_id.trim(), stream_name.trim());
+            if topic.is_empty() || org_id.is_empty() || stream_name.is_empty() {
+                return Err(invalid());
+            }
+            Ok(TopicMapping {
+                topic: topic.to_string(),
+                org_id: org_id.to_string(),
+                stream_name: stream_name.to_string(),
+                format,
+            })
+        })
+        .collect()
+}
+
+/// Ingests the payloads of a batch of messages of a topic, the payloads which can't be decoded
+/// and the records rejected alone, e.g. too old, are skipped. An error, e.g. an exhausted
+/// quota, means the batch must be retried.
+pub async fn ingest(mapping: &TopicMapping, payloads: &[&[u8]]) -> Result<()> {
+    match mapping.format {
+        PayloadFormat::Json => {
+            let mut records = Vec::new();
+            for payload in payloads {
+                match decode_json(payload) {
+                    Ok(v) => records.extend(v),
+                    Err(e) => log::error!(
+                        "[KAFKA] Skipping invalid json message of topic {}: {}",
+                        mapping.topic,
+                        e
+                    ),
+                }
+            }
+            if records.is_empty() {
+                return Ok(());
+            }
+            super::ingest::ingest(
+                0,
+                &mapping.org_id,
+                &mapping.stream_name,
+                IngestionRequest::Kafka(&records),
+                "",
+                None,
+            )
+            .await?;
+        }
+        PayloadFormat::Otlp => {
+            let mut request = ExportLogsServiceRequest::default();
+            for payload in payloads {
+                match ExportLogsServiceRequest::decode(*payload) {
+                    Ok(v) => request.resource_logs.extend(v.resource_logs),
+                    Err(e) => log::error!(
+                        "[KAFKA] Skipping invalid protobuf message of topic {}: {}",
+                        mapping.topic,
+                        e
+                    ),
+                }
+            }
+            if request.resource_logs.is_empty() {
+                return Ok(());
+            }
+            let resp = super::otlp_grpc::handle_grpc_request(
+                0,
+                &mapping.org_id,
+                request,
+                false,
+                Some(&mapping.stream_name),
+                "",
+            )
+            .await?;
+            // the records rejected alone are reported as a partial success, the request failed
+            // when the records couldn't be written
+            if resp.status().is_server_error() {
+                return Err(anyhow!(
+                    "failed to write the records, status: {}",
+                    resp.status()
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn decode_json(payload: &[u8]) -> Result<Vec<json::Value>> {
+    match json::from_slice(payload)? {
+        json::Value::Array(values) => values
+            .into_iter()
+            .map(|v| match v {
+                json::Value::Object(_) => Ok(v),
+                _ => Err(anyhow!("the records should be json objects")),
+            })
+            .collect(),
+        v @ json::Value::Object(_) => Ok(vec![v]),
+        _ => Err(anyhow!("the records should be json objects")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_topic_mappings() {
+        assert_eq!(
+            parse_topic_mappings("app-logs=default/app, otel.logs=acme/otel:otlp,").unwrap(),
+            vec![
+                TopicMapping {
+                    topic: "app-logs".to_string(),
+                    org_id: "default".to_string(),
+                    stream_name: "app".to_string(),
+                    format: PayloadFormat::Json,
+                },
+                TopicMapping {
+                    topic: "otel.logs".to_string(),
+                    org_id: "acme".to_string(),
+                    stream_name: "otel".to_string(),
+                    format: PayloadFormat::Otlp,
+                },
+            ]
+        );
+        assert!(parse_topic_mappings("").unwrap().is_empty());
+        assert!(parse_topic_mappings("app-logs").is_err());
+        assert!(parse_topic_mappings("app-logs=default").is_err());
+        assert!(parse_topic_mappings("app-logs=default/app:avro").is_err());
+        assert!(parse_topic_mappings("=default/app").is_err());
+    }
+
+    #[test]
+    fn test_decode_json() {
+        assert_eq!(decode_json(br#"{"a": 1}"#).unwrap().len(), 1);
+        assert_eq!(decode_json(br#"[{"a": 1}, {"a": 2}]"#).unwrap().len(), 2);
+        assert!(decode_json(br#"[{"a": 1}, 2]"#).is_err());
+        assert!(decode_json(b"not json").is_err());
+        assert!(decode_json(b"\"a\"").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ingest_skips_invalid_payloads() {
+        let mut mapping = parse_topic_mappings("app-logs=default/app")
+            .unwrap()
+            .remove(0);
+        // nothing is decoded so nothing is ingested
+        assert!(ingest(&mapping, &[b"not json".as_slice(), b"1"])
+            .await
+            .is_ok());
+        mapping.format = PayloadFormat::Otlp;
+        assert!(ingest(&mapping, &[b"\xff\xff".as_slice()]).await.is_ok());
+        assert!(ingest(&mapping, &[]).await.is_ok());
+    }
+}
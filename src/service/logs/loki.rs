@@ -0,0 +1,282 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Loki push API, the entries of the Loki streams are ingested as records having the labels
+//! of their stream and their structured metadata as fields.
+
+use actix_web::web;
+use anyhow::{anyhow, Result};
+use config::{get_config, utils::json};
+use prost::Message;
+use proto::loki_rpc;
+
+use crate::common::meta::ingestion::{IngestionRequest, IngestionResponse};
+
+/// Field of the records holding the log line
+pub const LOKI_LINE_FIELD: &str = "message";
+
+pub async fn ingest(
+    thread_id: usize,
+    org_id: &str,
+    in_stream_name: Option<&str>,
+    body: web::Bytes,
+    is_protobuf: bool,
+    user_email: &str,
+) -> Result<IngestionResponse> {
+    let records = if is_protobuf {
+        decode_protobuf(&body)?
+    } else {
+        decode_json(&body)?
+    };
+    super::ingest::ingest(
+        thread_id,
+        org_id,
+        in_stream_name.unwrap_or("default"),
+        IngestionRequest::Loki(&records),
+        user_email,
+        None,
+    )
+    .await
+}
+
+/// Decodes a snappy compressed protobuf push request.
+fn decode_protobuf(body: &[u8]) -> Result<Vec<json::Value>> {
+    let decoded = snap::raw::Decoder::new()
+        .decompress_vec(body)
+        .map_err(|e| anyhow!("Invalid snappy compressed data: {}", e.to_string()))?;
+    let request = loki_rpc::PushRequest::decode(bytes::Bytes::from(decoded))
+        .map_err(|e| anyhow!("Invalid protobuf: {}", e.to_string()))?;
+
+    let mut records = Vec::new();
+    for stream in request.streams {
+        let labels = parse_labels(&stream.labels)?;
+        for entry in stream.entries {
+            let timestamp = entry
+                .timestamp
+                .map(|ts| ts.seconds * 1_000_000 + ts.nanos as i64 / 1_000)
+                .unwrap_or_default();
+            let metadata = entry
+                .structured_metadata
+                .into_iter()
+                .map(|label| (label.name, label.value));
+            records.push(new_record(&labels, metadata, timestamp, entry.line));
+        }
+    }
+    Ok(records)
+}
+
+/// Decodes a json push request, the values of the streams are
+/// `[<unix epoch in nanoseconds>, <log line>, <optional structured metadata>]`.
+fn decode_json(body: &[u8]) -> Result<Vec<json::Value>> {
+    let request: json::Value = json::from_slice(body)?;
+    let Some(streams) = request.get("streams").and_then(|v| v.as_array()) else {
+        return Err(anyhow!("Invalid push request: streams not found"));
+    };
+
+    let mut records = Vec::new();
+    for stream in streams {
+        let labels: Vec<(String, String)> = match stream.get("stream") {
+            Some(json::Value::Object(labels)) => labels
+                .iter()
+                .map(|(name, value)| (name.to_string(), json_to_string(value)))
+                .collect(),
+            Some(_) => return Err(anyhow!("Invalid push request: stream should be an object")),
+            None => vec![],
+        };
+        let Some(values) = stream.get("values").and_then(|v| v.as_array()) else {
+            return Err(anyhow!("Invalid push request: values not found"));
+        };
+        for value in values {
+            let entry = value.as_array().map(|v| v.as_slice()).unwrap_or_default();
+            if entry.len() < 2 {
+                return Err(anyhow!(
+                    "Invalid push request: entry should be [timestamp, line]"
+                ));
+            }
+            let timestamp = match &entry[0] {
+                json::Value::String(v) => v.parse::<i64>().ok(),
+                json::Value::Number(v) => v.as_i64(),
+                _ => None,
+            }
+            .ok_or_else(|| anyhow!("Invalid push request: timestamp should be in nanoseconds"))?;
+            let line = json_to_string(&entry[1]);
+            let metadata = entry
+                .get(2)
+                .and_then(|v| v.as_object())
+                .map(|metadata| {
+                    metadata
+                        .iter()
+                        .map(|(name, value)| (name.to_string(), json_to_string(value)))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            records.push(new_record(&labels, metadata, timestamp / 1_000, line));
+        }
+    }
+    Ok(records)
+}
+
+fn new_record(
+    labels: &[(String, String)],
+    metadata: impl IntoIterator<Item = (String, String)>,
+    timestamp: i64,
+    line: String,
+) -> json::Value {
+    let mut record = json::Map::with_capacity(labels.len() + 2);
+    for (name, value) in labels {
+        record.insert(name.to_string(), json::Value::String(value.to_string()));
+    }
+    for (name, value) in metadata {
+        record.insert(name, json::Value::String(value));
+    }
+    if timestamp > 0 {
+        record.insert(
+            get_config().common.column_timestamp.clone(),
+            json::Value::Number(timestamp.into()),
+        );
+    }
+    record.insert(LOKI_LINE_FIELD.to_string(), json::Value::String(line));
+    json::Value::Object(record)
+}
+
+fn json_to_string(value: &json::Value) -> String {
+    match value {
+        json::Value::String(v) => v.to_string(),
+        v => v.to_string(),
+    }
+}
+
+/// Parses labels in the Prometheus format, e.g. `{job="varlogs", env="prod"}`.
+fn parse_labels(labels: &str) -> Result<Vec<(String, String)>> {
+    let labels = labels.trim();
+    let Some(inner) = labels.strip_prefix('{').and_then(|v| v.strip_suffix('}')) else {
+        return Err(anyhow!("Invalid stream labels: {labels}"));
+    };
+
+    let mut ret = Vec::new();
+    let mut chars = inner.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ',').is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+        let mut name = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_' || *c == '.') {
+            name.push(c);
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if name.is_empty() || chars.next() != Some('=') {
+            return Err(anyhow!("Invalid stream labels: {labels}"));
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.next() != Some('"') {
+            return Err(anyhow!("Invalid stream labels: {labels}"));
+        }
+        let mut value = String::new();
+        loop {
+            match chars.next() {
+                Some('"') => break,
+                Some('\\') => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(c) => value.push(c),
+                    None => return Err(anyhow!("Invalid stream labels: {labels}")),
+                },
+                Some(c) => value.push(c),
+                None => return Err(anyhow!("Invalid stream labels: {labels}")),
+            }
+        }
+        ret.push((name, value));
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_labels() {
+        assert_eq!(
+            parse_labels(r#"{job="varlogs", filename="/var/log/a \"b\".log",env="prod"}"#).unwrap(),
+            vec![
+                ("job".to_string(), "varlogs".to_string()),
+                ("filename".to_string(), "/var/log/a \"b\".log".to_string()),
+                ("env".to_string(), "prod".to_string()),
+            ]
+        );
+        assert!(parse_labels("{}").unwrap().is_empty());
+        assert!(parse_labels(r#"{job="varlogs""#).is_err());
+        assert!(parse_labels(r#"{job=varlogs}"#).is_err());
+    }
+
+    #[test]
+    fn test_decode_json() {
+        let body = r#"{"streams": [{
+            "stream": {"job": "varlogs", "level": "info"},
+            "values": [
+                ["1700000000123456789", "first line"],
+                ["1700000001000000000", "second line", {"trace_id": "abc"}]
+            ]
+        }]}"#;
+        let records = decode_json(body.as_bytes()).unwrap();
+        assert_eq!(records.len(), 2);
+        let ts_field = get_config().common.column_timestamp.clone();
+        assert_eq!(records[0]["job"], "varlogs");
+        assert_eq!(records[0]["level"], "info");
+        assert_eq!(records[0][LOKI_LINE_FIELD], "first line");
+        assert_eq!(records[0][&ts_field], 1700000000123456_i64);
+        assert_eq!(records[1]["trace_id"], "abc");
+        assert_eq!(records[1][&ts_field], 1700000001000000_i64);
+
+        assert!(decode_json(br#"{"streams": [{"values": [["x", "line"]]}]}"#).is_err());
+        assert!(decode_json(br#"{"foo": []}"#).is_err());
+    }
+
+    #[test]
+    fn test_decode_protobuf() {
+        let request = loki_rpc::PushRequest {
+            streams: vec![loki_rpc::StreamAdapter {
+                labels: r#"{job="varlogs"}"#.to_string(),
+                entries: vec![loki_rpc::EntryAdapter {
+                    timestamp: Some(loki_rpc::Timestamp {
+                        seconds: 1700000000,
+                        nanos: 123456789,
+                    }),
+                    line: "a line".to_string(),
+                    structured_metadata: vec![loki_rpc::LabelPairAdapter {
+                        name: "trace_id".to_string(),
+                        value: "abc".to_string(),
+                    }],
+                }],
+                hash: 0,
+            }],
+        };
+        let body = snap::raw::Encoder::new()
+            .compress_vec(&request.encode_to_vec())
+            .unwrap();
+        let records = decode_protobuf(&body).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["job"], "varlogs");
+        assert_eq!(records[0]["trace_id"], "abc");
+        assert_eq!(records[0][LOKI_LINE_FIELD], "a line");
+        assert_eq!(
+            records[0][&get_config().common.column_timestamp],
+            1700000000123456_i64
+        );
+
+        assert!(decode_protobuf(b"not snappy").is_err());
+    }
+}
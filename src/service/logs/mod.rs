@@ -41,7 +41,7 @@ use infra::schema::{unwrap_partition_time_level, SchemaCache};
 
 use super::{
     db::organization::get_org_setting,
-    ingestion::{evaluate_trigger, write_file, TriggerAlertData},
+    ingestion::{evaluate_trigger, quota, write_file, TriggerAlertData},
     metadata::{distinct_values::DvItem, write, MetadataItem, MetadataType},
     schema::stream_schema_exists,
 };
@@ -55,6 +55,8 @@ use crate::{
 
 pub mod bulk;
 pub mod ingest;
+pub mod kafka;
+pub mod loki;
 pub mod otlp_grpc;
 pub mod otlp_http;
 pub mod syslog;
@@ -196,6 +198,10 @@ async fn write_logs_by_stream(
     status: &mut IngestionStatus,
     json_data_by_stream: HashMap<String, O2IngestJsonData>,
 ) -> Result<()> {
+    // the request is rejected as a whole when the quota of one of its streams is exhausted, so
+    // that it can be retried without writing twice the other streams
+    quota::check_streams(org_id, StreamType::Logs, json_data_by_stream.keys())?;
+
     for (stream_name, (json_data, fn_num)) in json_data_by_stream {
         // check if we are allowed to ingest
         if db::compact::retention::is_deleting_stream(org_id, StreamType::Logs, &stream_name, None)
@@ -495,6 +501,7 @@ async fn write_logs(
         stream_name,
     )
     .await;
+    quota::consume(org_id, StreamType::Logs, stream_name, &write_buf);
     let req_stats = write_file(&writer, stream_name, write_buf).await;
     if let Err(e) = writer.sync().await {
         log::error!("ingestion error while syncing writer: {}", e);
@@ -44,6 +44,7 @@ use crate::{
         ingestion::{
             check_ingestion_allowed,
             grpc::{get_val, get_val_with_type_retained},
+            quota::QuotaExceeded,
         },
         logs::bulk::TRANSFORM_FAILED,
         schema::get_upto_discard_error,
@@ -377,6 +378,7 @@ pub async fn handle_grpc_request(
             res.encode(&mut out).expect("Out of memory");
             ("200", out)
         }
+        Err(e) if e.is::<QuotaExceeded>() => return Err(e),
         Err(e) => {
             log::error!("Error while writing logs: {}", e);
             stream_status.status = match status {
@@ -44,7 +44,7 @@ use crate::{
     handler::http::request::CONTENT_TYPE_JSON,
     service::{
         format_stream_name,
-        ingestion::{check_ingestion_allowed, get_val_for_attr},
+        ingestion::{check_ingestion_allowed, get_val_for_attr, quota::QuotaExceeded},
         logs::bulk::TRANSFORM_FAILED,
         schema::get_upto_discard_error,
     },
@@ -73,6 +73,9 @@ pub async fn logs_proto_handler(
     {
         Ok(res) => Ok(res),
         Err(e) => {
+            if let Some(e) = e.downcast_ref::<QuotaExceeded>() {
+                return Ok(e.http_response());
+            }
             log::error!("error while handling request: {}", e);
             Ok(
                 HttpResponse::InternalServerError().json(MetaHttpResponse::error(
@@ -509,6 +512,7 @@ pub async fn logs_json_handler(
             res.encode(&mut out).expect("Out of memory");
             ("200", out)
         }
+        Err(e) if e.is::<QuotaExceeded>() => return Err(e),
         Err(e) => {
             log::error!("Error while writing logs: {}", e);
             stream_status.status = match status {
@@ -46,7 +46,9 @@ use crate::{
         },
     },
     service::{
-        format_stream_name, ingestion::check_ingestion_allowed, logs::bulk::TRANSFORM_FAILED,
+        format_stream_name,
+        ingestion::{check_ingestion_allowed, quota::QuotaExceeded},
+        logs::bulk::TRANSFORM_FAILED,
     },
 };
 
@@ -76,6 +78,9 @@ pub async fn ingest(msg: &str, addr: SocketAddr) -> Result<HttpResponse> {
     // check stream
     let stream_name = format_stream_name(in_stream_name);
     if let Err(e) = check_ingestion_allowed(org_id, Some(&stream_name)) {
+        if let Some(e) = e.downcast_ref::<QuotaExceeded>() {
+            return Ok(e.http_response());
+        }
         return Ok(
             HttpResponse::InternalServerError().json(MetaHttpResponse::error(
                 http::StatusCode::INTERNAL_SERVER_ERROR.into(),
@@ -338,6 +343,9 @@ pub async fn ingest(msg: &str, addr: SocketAddr) -> Result<HttpResponse> {
         match write_result {
             Ok(_) => ("200", stream_status),
             Err(e) => {
+                if let Some(e) = e.downcast_ref::<QuotaExceeded>() {
+                    return Ok(e.http_response());
+                }
                 log::error!("Error while writing logs: {}", e);
                 ("500", stream_status)
             }
@@ -20,9 +20,12 @@ use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use tokio::try_join;
 
-use crate::service::metadata::{distinct_values::DvItem, trace_list_index::TraceListItem};
+use crate::service::metadata::{
+    distinct_values::DvItem, service_graph::SgItem, trace_list_index::TraceListItem,
+};
 
 pub mod distinct_values;
+pub mod service_graph;
 pub mod trace_list_index;
 
 static METADATA_MANAGER: Lazy<MetadataManager> = Lazy::new(MetadataManager::new);
@@ -31,11 +34,13 @@ static METADATA_MANAGER: Lazy<MetadataManager> = Lazy::new(MetadataManager::new)
 pub enum MetadataItem {
     TraceListIndexer(TraceListItem),
     DistinctValues(DvItem),
+    ServiceGraph(SgItem),
 }
 
 pub enum MetadataType {
     TraceListIndexer,
     DistinctValues,
+    ServiceGraph,
 }
 
 pub struct MetadataManager {}
@@ -65,7 +70,15 @@ impl MetadataManager {
     pub async fn close(&self) -> infra::errors::Result<()> {
         match try_join!(
             trace_list_index::INSTANCE.stop(),
-            distinct_values::INSTANCE.stop()
+            distinct_values::INSTANCE.stop(),
+            async {
+                // the service graph isn't started when it's disabled
+                if config::get_config().common.traces_service_graph_enabled {
+                    service_graph::INSTANCE.stop().await
+                } else {
+                    Ok(())
+                }
+            }
         ) {
             Ok(_) => {}
             Err(e) => {
@@ -85,6 +98,7 @@ pub async fn write(
     match mt {
         MetadataType::TraceListIndexer => trace_list_index::INSTANCE.write(org_id, data).await,
         MetadataType::DistinctValues => distinct_values::INSTANCE.write(org_id, data).await,
+        MetadataType::ServiceGraph => service_graph::INSTANCE.write(org_id, data).await,
     }
 }
 
@@ -0,0 +1,628 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use arrow_schema::{DataType, Field, Schema};
+use config::{
+    get_config,
+    meta::{search, stream::StreamType},
+    metrics::SPAN_METRICS_BUCKET,
+    utils::{json, schema_ext::SchemaExt},
+    FxIndexMap,
+};
+use infra::{
+    errors::{Error, Result},
+    schema::unwrap_partition_time_level,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{mpsc, RwLock},
+    time,
+};
+
+use crate::{
+    common::meta::{
+        stream::SchemaRecords,
+        traces::{ServiceGraph, ServiceGraphEdge, ServiceGraphNode},
+    },
+    service,
+    service::{
+        ingestion,
+        metadata::{Metadata, MetadataItem},
+    },
+};
+
+const CHANNEL_SIZE: usize = 10240;
+const STREAM_NAME: &str = "service_graph";
+const MAX_EDGES: i64 = 10000;
+// the last bucket collects the durations greater than the last bound
+const BUCKETS_NUM: usize = SPAN_METRICS_BUCKET.len() + 1;
+
+pub(crate) static INSTANCE: Lazy<ServiceGraphIndex> = Lazy::new(ServiceGraphIndex::new);
+
+type MemTable = FxIndexMap<(String, String), StreamGraph>;
+
+pub struct ServiceGraphIndex {
+    channel: Arc<mpsc::Sender<SgEvent>>,
+    shutdown: Arc<AtomicBool>,
+    mem_table: Arc<RwLock<MemTable>>,
+}
+
+/// One span of a traces stream, reduced to what is needed to pair it with its parent.
+#[derive(Debug, Default, Eq, Hash, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SgItem {
+    pub stream_name: String,
+    pub trace_id: String,
+    pub span_id: String,
+    pub parent_span_id: String,
+    pub service_name: String,
+    pub is_error: bool,
+    /// microseconds
+    pub duration: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct EdgeStats {
+    request_count: u64,
+    error_count: u64,
+    duration_sum: u64,
+    buckets: [u64; BUCKETS_NUM],
+}
+
+impl EdgeStats {
+    fn add(&mut self, item: &SgItem) {
+        self.request_count += 1;
+        if item.is_error {
+            self.error_count += 1;
+        }
+        self.duration_sum += item.duration;
+        let duration_ms = item.duration as f64 / 1000.0;
+        let idx = SPAN_METRICS_BUCKET
+            .iter()
+            .position(|bound| duration_ms <= *bound)
+            .unwrap_or(SPAN_METRICS_BUCKET.len());
+        self.buckets[idx] += 1;
+    }
+}
+
+/// Spans of one stream waiting to be paired, and the edges found since the last flush.
+///
+/// Spans are kept for one more flush interval after the one they arrived in, so that a
+/// parent and a child ingested in different requests can still be paired.
+#[derive(Debug, Default)]
+struct StreamGraph {
+    generation: u64,
+    // (trace_id, span_id) -> (service_name, generation)
+    spans: HashMap<(String, String), (String, u64)>,
+    // (trace_id, parent_span_id) -> children arrived before their parent
+    waiting: HashMap<(String, String), Vec<(SgItem, u64)>>,
+    // (client_service, server_service) -> stats
+    edges: FxIndexMap<(String, String), EdgeStats>,
+}
+
+impl StreamGraph {
+    fn add_span(&mut self, item: SgItem, max_items: usize) {
+        let generation = self.generation;
+        if let Some(children) = self
+            .waiting
+            .remove(&(item.trace_id.clone(), item.span_id.clone()))
+        {
+            for (child, _) in children {
+                self.add_edge(&item.service_name, &child);
+            }
+        }
+
+        if !item.parent_span_id.is_empty() {
+            let parent_key = (item.trace_id.clone(), item.parent_span_id.clone());
+            if let Some((parent_service, _)) = self.spans.get(&parent_key) {
+                let parent_service = parent_service.clone();
+                self.add_edge(&parent_service, &item);
+            } else if self.spans.len() + self.waiting.len() < max_items {
+                self.waiting
+                    .entry(parent_key)
+                    .or_default()
+                    .push((item.clone(), generation));
+            }
+        }
+
+        if self.spans.len() + self.waiting.len() < max_items {
+            self.spans.insert(
+                (item.trace_id, item.span_id),
+                (item.service_name, generation),
+            );
+        }
+    }
+
+    // an edge is only recorded when a call crosses the service boundary, the latency and
+    // the status of the call are the ones of the server (child) span
+    fn add_edge(&mut self, client_service: &str, child: &SgItem) {
+        if client_service == child.service_name {
+            return;
+        }
+        self.edges
+            .entry((client_service.to_string(), child.service_name.clone()))
+            .or_default()
+            .add(child);
+    }
+
+    fn take_edges(&mut self) -> FxIndexMap<(String, String), EdgeStats> {
+        let edges = std::mem::take(&mut self.edges);
+        let expired = self.generation;
+        self.spans
+            .retain(|_, (_, generation)| *generation >= expired);
+        self.waiting.retain(|_, children| {
+            children.retain(|(_, generation)| *generation >= expired);
+            !children.is_empty()
+        });
+        self.generation += 1;
+        edges
+    }
+
+    fn is_empty(&self) -> bool {
+        self.spans.is_empty() && self.waiting.is_empty() && self.edges.is_empty()
+    }
+}
+
+#[derive(Debug)]
+enum SgEventType {
+    Add,
+    Shutdown,
+}
+
+#[derive(Debug)]
+struct SgEvent {
+    org_id: String,
+    items: Vec<SgItem>,
+    ev_type: SgEventType,
+}
+
+impl SgEvent {
+    pub fn new(org_id: &str, items: Vec<SgItem>) -> Self {
+        Self {
+            org_id: org_id.to_string(),
+            items,
+            ev_type: SgEventType::Add,
+        }
+    }
+    pub fn shutdown() -> Self {
+        Self {
+            org_id: String::from(""),
+            items: vec![],
+            ev_type: SgEventType::Shutdown,
+        }
+    }
+}
+
+impl Default for ServiceGraphIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ServiceGraphIndex {
+    pub fn new() -> Self {
+        tokio::task::spawn(async move { run_flush().await });
+        Self {
+            channel: handle_channel(),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            mem_table: Arc::new(RwLock::new(FxIndexMap::default())),
+        }
+    }
+}
+
+fn handle_channel() -> Arc<mpsc::Sender<SgEvent>> {
+    let (tx, mut rx) = mpsc::channel::<SgEvent>(CHANNEL_SIZE);
+    tokio::task::spawn(async move {
+        let max_items = get_config().common.traces_service_graph_max_items;
+        loop {
+            let event = match rx.recv().await {
+                Some(v) => v,
+                None => {
+                    log::info!("[SERVICE_GRAPH] event channel closed");
+                    break;
+                }
+            };
+            if let SgEventType::Shutdown = event.ev_type {
+                if let Err(e) = INSTANCE.flush().await {
+                    log::error!("[SERVICE_GRAPH] flush error: {}", e);
+                }
+                INSTANCE.shutdown.store(true, Ordering::Release);
+                break;
+            }
+            let mut mem_table = INSTANCE.mem_table.write().await;
+            for item in event.items {
+                mem_table
+                    .entry((event.org_id.clone(), item.stream_name.clone()))
+                    .or_default()
+                    .add_span(item, max_items);
+            }
+        }
+        log::info!("[SERVICE_GRAPH] event loop exit");
+    });
+    Arc::new(tx)
+}
+
+impl Metadata for ServiceGraphIndex {
+    fn generate_schema(&self) -> Arc<Schema> {
+        let mut fields = vec![
+            Field::new(
+                get_config().common.column_timestamp.as_str(),
+                DataType::Int64,
+                false,
+            ),
+            Field::new("stream_name", DataType::Utf8, false),
+            Field::new("client_service", DataType::Utf8, false),
+            Field::new("server_service", DataType::Utf8, false),
+            Field::new("request_count", DataType::Int64, false),
+            Field::new("error_count", DataType::Int64, false),
+            Field::new("duration_sum", DataType::Int64, false),
+        ];
+        for i in 0..BUCKETS_NUM {
+            fields.push(Field::new(bucket_field(i), DataType::Int64, false));
+        }
+        Arc::new(Schema::new(fields))
+    }
+
+    async fn write(&self, org_id: &str, data: Vec<MetadataItem>) -> Result<()> {
+        let items = data
+            .into_iter()
+            .filter_map(|item| match item {
+                MetadataItem::ServiceGraph(item) => Some(item),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if items.is_empty() {
+            return Ok(());
+        }
+        self.channel
+            .send(SgEvent::new(org_id, items))
+            .await
+            .map_err(|v| Error::Message(v.to_string()))
+    }
+
+    async fn flush(&self) -> Result<()> {
+        let mut mem_table = self.mem_table.write().await;
+        let mut new_table: FxIndexMap<String, Vec<(String, String, String, EdgeStats)>> =
+            FxIndexMap::default();
+        for ((org_id, stream_name), graph) in mem_table.iter_mut() {
+            let edges = graph.take_edges();
+            if edges.is_empty() {
+                continue;
+            }
+            let entry = new_table.entry(org_id.clone()).or_default();
+            for ((client, server), stats) in edges {
+                entry.push((stream_name.clone(), client, server, stats));
+            }
+        }
+        mem_table.retain(|_, graph| !graph.is_empty());
+        drop(mem_table);
+
+        // write to wal
+        let timestamp = chrono::Utc::now().timestamp_micros();
+        let schema = self.generate_schema();
+        let schema_key = schema.hash_key();
+        for (org_id, edges) in new_table {
+            // check for schema
+            let db_schema = infra::schema::get(&org_id, STREAM_NAME, StreamType::Metadata)
+                .await
+                .unwrap();
+            let mut _is_new = false;
+            if db_schema.fields().is_empty() {
+                _is_new = true;
+                let schema = schema.as_ref().clone();
+                if let Err(e) = service::db::schema::merge(
+                    &org_id,
+                    STREAM_NAME,
+                    StreamType::Metadata,
+                    &schema,
+                    Some(timestamp),
+                )
+                .await
+                {
+                    log::error!("[SERVICE_GRAPH] error while setting schema: {}", e);
+                }
+            }
+
+            let mut buf: HashMap<String, SchemaRecords> = HashMap::new();
+            for (stream_name, client_service, server_service, stats) in edges {
+                let mut data = json::Map::new();
+                data.insert(
+                    get_config().common.column_timestamp.clone(),
+                    json::Value::Number(timestamp.into()),
+                );
+                data.insert("stream_name".to_string(), stream_name.into());
+                data.insert("client_service".to_string(), client_service.into());
+                data.insert("server_service".to_string(), server_service.into());
+                data.insert("request_count".to_string(), stats.request_count.into());
+                data.insert("error_count".to_string(), stats.error_count.into());
+                data.insert("duration_sum".to_string(), stats.duration_sum.into());
+                for (i, count) in stats.buckets.iter().enumerate() {
+                    data.insert(bucket_field(i), (*count).into());
+                }
+                let hour_key = ingestion::get_write_partition_key(
+                    timestamp,
+                    &vec![],
+                    unwrap_partition_time_level(None, StreamType::Metadata),
+                    &data,
+                    Some(&schema_key),
+                );
+                let data = json::Value::Object(data);
+                let data_size = json::to_vec(&data).unwrap_or_default().len();
+
+                let hour_buf = buf.entry(hour_key).or_insert_with(|| SchemaRecords {
+                    schema_key: schema_key.clone(),
+                    schema: schema.clone(),
+                    records: vec![],
+                    records_size: 0,
+                });
+                hour_buf.records.push(Arc::new(data));
+                hour_buf.records_size += data_size;
+            }
+
+            let writer =
+                ingester::get_writer(0, &org_id, &StreamType::Metadata.to_string(), STREAM_NAME)
+                    .await;
+            _ = ingestion::write_file(&writer, STREAM_NAME, buf).await;
+            if let Err(e) = writer.sync().await {
+                log::error!("[SERVICE_GRAPH] error while syncing writer: {}", e);
+            }
+            #[cfg(feature = "enterprise")]
+            {
+                use o2_enterprise::enterprise::{
+                    common::infra::config::get_config as get_o2_config,
+                    openfga::authorizer::authz::set_ownership_if_not_exists,
+                };
+
+                // set ownership only in the first time
+                if _is_new && get_o2_config().openfga.enabled {
+                    set_ownership_if_not_exists(
+                        &org_id,
+                        &format!("{}:{}", StreamType::Metadata, STREAM_NAME),
+                    )
+                    .await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<()> {
+        let tx = self.channel.clone();
+        tx.send(SgEvent::shutdown())
+            .await
+            .map_err(|e| Error::Message(e.to_string()))?;
+        let mut i = 0;
+        while i < 10 {
+            if self.shutdown.load(Ordering::Relaxed) {
+                break;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            log::info!("[SERVICE_GRAPH] shutting down");
+            i += 1;
+        }
+        Ok(())
+    }
+}
+
+async fn run_flush() {
+    let mut interval = time::interval(time::Duration::from_secs(
+        get_config().common.traces_service_graph_flush_interval,
+    ));
+    interval.tick().await; // trigger the first run
+    loop {
+        interval.tick().await;
+        if let Err(e) = INSTANCE.flush().await {
+            log::error!("[SERVICE_GRAPH] error flush data to wal: {}", e);
+        }
+    }
+}
+
+fn bucket_field(idx: usize) -> String {
+    format!("duration_bucket_{idx}")
+}
+
+/// Aggregates the edges recorded for `stream_name` in `time_range` into a [ServiceGraph].
+pub async fn get_service_graph(
+    trace_id: &str,
+    org_id: &str,
+    stream_name: &str,
+    time_range: (i64, i64),
+) -> Result<ServiceGraph> {
+    let buckets = (0..BUCKETS_NUM)
+        .map(|i| format!("sum({0}) AS {0}", bucket_field(i)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let req = search::Request {
+        query: search::Query {
+            sql: format!(
+                "SELECT client_service, server_service, sum(request_count) AS request_count, sum(error_count) AS error_count, sum(duration_sum) AS duration_sum, {buckets} FROM \"{STREAM_NAME}\" WHERE stream_name = '{stream_name}' GROUP BY client_service, server_service"
+            ),
+            size: MAX_EDGES,
+            start_time: time_range.0,
+            end_time: time_range.1,
+            ..Default::default()
+        },
+        encoding: search::RequestEncoding::Empty,
+        regions: vec![],
+        clusters: vec![],
+        timeout: 0,
+        search_type: None,
+        search_event_context: None,
+    };
+    let resp =
+        crate::service::search::search(trace_id, org_id, StreamType::Metadata, None, &req).await?;
+
+    let window_secs = ((time_range.1 - time_range.0) as f64 / 1_000_000.0).max(1.0);
+    let get_u64 = |hit: &json::Value, key: &str| {
+        hit.get(key)
+            .and_then(|v| v.as_f64())
+            .map(|v| v.max(0.0) as u64)
+            .unwrap_or_default()
+    };
+    let mut nodes: BTreeMap<String, ServiceGraphNode> = BTreeMap::new();
+    let mut edges = Vec::with_capacity(resp.hits.len());
+    for hit in resp.hits.iter() {
+        let client_service = hit
+            .get("client_service")
+            .map(json::get_string_value)
+            .unwrap_or_default();
+        let server_service = hit
+            .get("server_service")
+            .map(json::get_string_value)
+            .unwrap_or_default();
+        let request_count = get_u64(hit, "request_count");
+        let error_count = get_u64(hit, "error_count");
+        let mut buckets = [0; BUCKETS_NUM];
+        for (i, bucket) in buckets.iter_mut().enumerate() {
+            *bucket = get_u64(hit, &bucket_field(i));
+        }
+
+        nodes
+            .entry(client_service.clone())
+            .or_insert_with(|| ServiceGraphNode {
+                service_name: client_service.clone(),
+                ..Default::default()
+            });
+        let node = nodes
+            .entry(server_service.clone())
+            .or_insert_with(|| ServiceGraphNode {
+                service_name: server_service.clone(),
+                ..Default::default()
+            });
+        node.request_count += request_count;
+        node.error_count += error_count;
+
+        edges.push(ServiceGraphEdge {
+            client_service,
+            server_service,
+            request_count,
+            error_count,
+            request_rate: request_count as f64 / window_secs,
+            error_rate: if request_count > 0 {
+                error_count as f64 / request_count as f64
+            } else {
+                0.0
+            },
+            p50_duration: bucket_quantile(0.5, &buckets),
+            p95_duration: bucket_quantile(0.95, &buckets),
+            p99_duration: bucket_quantile(0.99, &buckets),
+        });
+    }
+    edges.sort_by(|a, b| {
+        (&a.client_service, &a.server_service).cmp(&(&b.client_service, &b.server_service))
+    });
+
+    Ok(ServiceGraph {
+        nodes: nodes.into_values().collect(),
+        edges,
+    })
+}
+
+// estimates the quantile in milliseconds by linear interpolation inside the bucket it
+// falls in, the same way as the PromQL `histogram_quantile` function
+fn bucket_quantile(q: f64, buckets: &[u64; BUCKETS_NUM]) -> f64 {
+    let total = buckets.iter().sum::<u64>();
+    if total == 0 {
+        return 0.0;
+    }
+    let rank = q * total as f64;
+    let mut cumulative = 0;
+    for (i, count) in buckets.iter().enumerate() {
+        if *count == 0 || ((cumulative + count) as f64) < rank {
+            cumulative += count;
+            continue;
+        }
+        let lower = if i == 0 {
+            0.0
+        } else {
+            SPAN_METRICS_BUCKET[i - 1]
+        };
+        // nothing is known about the upper bound of the last bucket
+        let Some(upper) = SPAN_METRICS_BUCKET.get(i) else {
+            return lower;
+        };
+        return lower + (upper - lower) * (rank - cumulative as f64) / *count as f64;
+    }
+    SPAN_METRICS_BUCKET[SPAN_METRICS_BUCKET.len() - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(span_id: &str, parent: &str, service: &str, is_error: bool) -> SgItem {
+        SgItem {
+            stream_name: "default".to_string(),
+            trace_id: "t1".to_string(),
+            span_id: span_id.to_string(),
+            parent_span_id: parent.to_string(),
+            service_name: service.to_string(),
+            is_error,
+            duration: 3000,
+        }
+    }
+
+    #[test]
+    fn test_stream_graph_edges() {
+        let mut graph = StreamGraph::default();
+        // the child of "a" arrives before its parent
+        graph.add_span(span("b", "a", "api", false), 100);
+        graph.add_span(span("a", "", "frontend", false), 100);
+        graph.add_span(span("c", "b", "api", false), 100);
+        graph.add_span(span("d", "c", "db", true), 100);
+        graph.add_span(span("e", "x", "db", false), 100);
+
+        let edges = graph.take_edges();
+        assert_eq!(edges.len(), 2);
+        let stats = edges
+            .get(&("frontend".to_string(), "api".to_string()))
+            .unwrap();
+        assert_eq!(stats.request_count, 1);
+        assert_eq!(stats.error_count, 0);
+        // 3ms falls in the (1, 5] bucket
+        assert_eq!(stats.buckets[3], 1);
+        let stats = edges.get(&("api".to_string(), "db".to_string())).unwrap();
+        assert_eq!(stats.error_count, 1);
+
+        // the parent of "e" arrives one flush later
+        graph.add_span(span("x", "", "api", false), 100);
+        assert_eq!(graph.take_edges().len(), 1);
+        assert!(!graph.is_empty());
+        // spans expire one flush after the one they arrived in
+        graph.take_edges();
+        assert!(graph.is_empty());
+    }
+
+    #[test]
+    fn test_bucket_quantile() {
+        let mut buckets = [0; BUCKETS_NUM];
+        assert_eq!(bucket_quantile(0.5, &buckets), 0.0);
+        // 10 requests in (10, 20]
+        buckets[5] = 10;
+        assert_eq!(bucket_quantile(0.5, &buckets), 15.0);
+        assert!((bucket_quantile(0.99, &buckets) - 19.9).abs() < 1e-9);
+        // the last bucket has no upper bound
+        buckets[BUCKETS_NUM - 1] = 90;
+        assert_eq!(bucket_quantile(0.99, &buckets), 60000.0);
+    }
+}
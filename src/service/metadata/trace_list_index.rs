@@ -211,6 +211,7 @@ impl TraceListIndex {
                 defined_schema_fields: None,
                 store_original_data: false,
                 approx_partition: false,
+                span_metrics_enabled: false,
             };
 
             stream::save_stream_settings(org_id, STREAM_NAME, StreamType::Metadata, settings)
@@ -223,6 +224,48 @@ impl TraceListIndex {
     }
 }
 
+/// Returns the min and max `_timestamp` of the spans of `trace_id` recorded in the trace list
+/// index within `time_range`, `None` if the trace is not indexed.
+pub async fn get_trace_time_range(
+    org_id: &str,
+    stream_name: &str,
+    trace_id: &str,
+    time_range: (i64, i64),
+) -> infra::errors::Result<Option<(i64, i64)>> {
+    let cfg = get_config();
+    let stream_name = stream_name.replace('\'', "''");
+    let trace_id = trace_id.replace('\'', "''");
+    let req = config::meta::search::Request {
+        query: config::meta::search::Query {
+            sql: format!(
+                "SELECT min({ts}) AS start_time, max({ts}) AS end_time FROM \"{STREAM_NAME}\" WHERE stream_name = '{stream_name}' AND trace_id = '{trace_id}'",
+                ts = cfg.common.column_timestamp
+            ),
+            size: 1,
+            start_time: time_range.0,
+            end_time: time_range.1,
+            ..Default::default()
+        },
+        encoding: config::meta::search::RequestEncoding::Empty,
+        regions: vec![],
+        clusters: vec![],
+        timeout: 0,
+        search_type: None,
+        search_event_context: None,
+    };
+    let resp = crate::service::search::search("", org_id, StreamType::Metadata, None, &req).await?;
+    let Some(hit) = resp.hits.first() else {
+        return Ok(None);
+    };
+    match (
+        hit.get("start_time").and_then(|v| v.as_i64()),
+        hit.get("end_time").and_then(|v| v.as_i64()),
+    ) {
+        (Some(start_time), Some(end_time)) => Ok(Some((start_time, end_time))),
+        _ => Ok(None),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{collections::HashMap, sync::Arc};
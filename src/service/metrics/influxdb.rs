@@ -0,0 +1,332 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! InfluxDB line protocol ingestion, every numeric field of a point is a gauge series named
+//! `<measurement>_<field>` having the tags of the point as labels, as Telegraf's prometheus
+//! output does. String fields are not ingested.
+
+use actix_web::web;
+use anyhow::{anyhow, Result};
+use config::{
+    meta::self_reporting::usage::UsageType,
+    utils::json::{self, Map, Value},
+};
+
+use super::format_label_name;
+use crate::common::meta::{
+    ingestion::IngestionResponse,
+    prom::{NAME_LABEL, TYPE_LABEL, VALUE_LABEL},
+};
+
+pub async fn ingest(
+    org_id: &str,
+    body: web::Bytes,
+    precision: Option<&str>,
+) -> Result<IngestionResponse> {
+    let multiplier = precision_multiplier(precision)?;
+    let lines = std::str::from_utf8(&body)?;
+    let mut records = Vec::new();
+    for (i, line) in lines.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let point = parse_line(line).map_err(|e| anyhow!("line {}: {e}", i + 1))?;
+        records.extend(point.into_records(multiplier));
+    }
+    super::json::ingest_records(
+        org_id,
+        records,
+        UsageType::InfluxDB,
+        "/api/org/influxdb/api/v2/write",
+    )
+    .await
+}
+
+/// Returns the multiplier converting the timestamps of the given precision to microseconds, a
+/// negative one is a divisor.
+fn precision_multiplier(precision: Option<&str>) -> Result<i64> {
+    match precision.unwrap_or("ns") {
+        "ns" | "n" => Ok(-1_000),
+        "us" | "u" => Ok(1),
+        "ms" => Ok(1_000),
+        "s" => Ok(1_000_000),
+        "m" => Ok(60_000_000),
+        "h" => Ok(3_600_000_000),
+        v => Err(anyhow!("invalid precision: {v}")),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FieldValue {
+    Float(f64),
+    Int(i64),
+    UInt(u64),
+    Bool(bool),
+    Str(String),
+}
+
+#[derive(Debug, PartialEq)]
+struct Point {
+    measurement: String,
+    tags: Vec<(String, String)>,
+    fields: Vec<(String, FieldValue)>,
+    timestamp: Option<i64>,
+}
+
+impl Point {
+    fn into_records(self, multiplier: i64) -> Vec<Value> {
+        let timestamp = self.timestamp.map(|ts| {
+            if multiplier < 0 {
+                ts / -multiplier
+            } else {
+                ts * multiplier
+            }
+        });
+        let mut labels = Map::new();
+        for (name, value) in self.tags {
+            labels.insert(format_label_name(&name), Value::String(value));
+        }
+        if let Some(timestamp) = timestamp {
+            labels.insert(
+                config::get_config().common.column_timestamp.clone(),
+                Value::Number(timestamp.into()),
+            );
+        }
+        labels.insert(TYPE_LABEL.to_string(), Value::String("gauge".to_string()));
+
+        self.fields
+            .into_iter()
+            .filter_map(|(field, value)| {
+                let value = match value {
+                    FieldValue::Float(v) => v,
+                    FieldValue::Int(v) => v as f64,
+                    FieldValue::UInt(v) => v as f64,
+                    FieldValue::Bool(v) => v as u8 as f64,
+                    FieldValue::Str(_) => return None,
+                };
+                let name = if field == "value" {
+                    self.measurement.clone()
+                } else {
+                    format!("{}_{field}", self.measurement)
+                };
+                let mut record = labels.clone();
+                record.insert(NAME_LABEL.to_string(), Value::String(name));
+                record.insert(
+                    VALUE_LABEL.to_string(),
+                    json::Number::from_f64(value)?.into(),
+                );
+                Some(Value::Object(record))
+            })
+            .collect()
+    }
+}
+
+/// Splits `s` at the first unescaped `sep` out of double quotes.
+fn split_unescaped(s: &str, sep: char, quotes: bool) -> (&str, Option<&str>) {
+    let mut escaped = false;
+    let mut quoted = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' if quotes => quoted = !quoted,
+            c if c == sep && !quoted => return (&s[..i], Some(&s[i + c.len_utf8()..])),
+            _ => {}
+        }
+    }
+    (s, None)
+}
+
+fn split_all_unescaped(mut s: &str, sep: char, quotes: bool) -> Vec<&str> {
+    let mut parts = Vec::new();
+    loop {
+        let (part, rest) = split_unescaped(s, sep, quotes);
+        parts.push(part);
+        match rest {
+            Some(rest) => s = rest,
+            None => return parts,
+        }
+    }
+}
+
+fn unescape(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c @ (',' | '=' | ' ' | '"' | '\\')) => ret.push(c),
+                Some(c) => {
+                    ret.push('\\');
+                    ret.push(c);
+                }
+                None => ret.push('\\'),
+            },
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+/// Parses a line, `<measurement>[,<tag>=<value>...] <field>=<value>[,<field>=<value>...]
+/// [timestamp]`.
+fn parse_line(line: &str) -> Result<Point> {
+    let (series, rest) = split_unescaped(line, ' ', false);
+    let rest = rest.ok_or_else(|| anyhow!("missing fields"))?;
+    let (fields, timestamp) = split_unescaped(rest.trim_start(), ' ', true);
+
+    let mut series = split_all_unescaped(series, ',', false).into_iter();
+    let measurement = unescape(series.next().unwrap_or_default());
+    if measurement.is_empty() {
+        return Err(anyhow!("missing measurement"));
+    }
+    let mut tags = Vec::new();
+    for tag in series {
+        match split_unescaped(tag, '=', false) {
+            (name, Some(value)) if !name.is_empty() && !value.is_empty() => {
+                tags.push((unescape(name), unescape(value)))
+            }
+            _ => return Err(anyhow!("invalid tag: {tag}")),
+        }
+    }
+
+    let mut parsed_fields = Vec::new();
+    for field in split_all_unescaped(fields, ',', true) {
+        match split_unescaped(field, '=', true) {
+            (name, Some(value)) if !name.is_empty() => {
+                parsed_fields.push((unescape(name), parse_field_value(value)?))
+            }
+            _ => return Err(anyhow!("invalid field: {field}")),
+        }
+    }
+    if parsed_fields.is_empty() {
+        return Err(anyhow!("missing fields"));
+    }
+
+    let timestamp = match timestamp.map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        Some(v) => Some(
+            v.parse::<i64>()
+                .map_err(|_| anyhow!("invalid timestamp: {v}"))?,
+        ),
+        None => None,
+    };
+
+    Ok(Point {
+        measurement,
+        tags,
+        fields: parsed_fields,
+        timestamp,
+    })
+}
+
+fn parse_field_value(value: &str) -> Result<FieldValue> {
+    if let Some(v) = value.strip_prefix('"') {
+        let v = v
+            .strip_suffix('"')
+            .ok_or_else(|| anyhow!("unterminated string: {value}"))?;
+        return Ok(FieldValue::Str(unescape(v)));
+    }
+    let invalid = || anyhow!("invalid field value: {value}");
+    if let Some(v) = value.strip_suffix('i') {
+        return v.parse().map(FieldValue::Int).map_err(|_| invalid());
+    }
+    if let Some(v) = value.strip_suffix('u') {
+        return v.parse().map(FieldValue::UInt).map_err(|_| invalid());
+    }
+    match value {
+        "t" | "T" | "true" | "True" | "TRUE" => Ok(FieldValue::Bool(true)),
+        "f" | "F" | "false" | "False" | "FALSE" => Ok(FieldValue::Bool(false)),
+        v => v
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .map(FieldValue::Float)
+            .ok_or_else(invalid),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let point = parse_line(
+            r#"cpu\ load,host=server\,01,region=us-west usage_idle=98.5,usage_user=1i,up=t,msg="a \"b\", c=d" 1465839830100400200"#,
+        )
+        .unwrap();
+        assert_eq!(point.measurement, "cpu load");
+        assert_eq!(
+            point.tags,
+            vec![
+                ("host".to_string(), "server,01".to_string()),
+                ("region".to_string(), "us-west".to_string()),
+            ]
+        );
+        assert_eq!(
+            point.fields,
+            vec![
+                ("usage_idle".to_string(), FieldValue::Float(98.5)),
+                ("usage_user".to_string(), FieldValue::Int(1)),
+                ("up".to_string(), FieldValue::Bool(true)),
+                (
+                    "msg".to_string(),
+                    FieldValue::Str("a \"b\", c=d".to_string())
+                ),
+            ]
+        );
+        assert_eq!(point.timestamp, Some(1465839830100400200));
+
+        let point = parse_line("mem free=10u").unwrap();
+        assert_eq!(
+            point.fields,
+            vec![("free".to_string(), FieldValue::UInt(10))]
+        );
+        assert_eq!(point.timestamp, None);
+
+        assert!(parse_line("cpu").is_err());
+        assert!(parse_line("cpu,host usage=1").is_err());
+        assert!(parse_line("cpu usage=abc").is_err());
+        assert!(parse_line("cpu usage=1 abc").is_err());
+    }
+
+    #[test]
+    fn test_into_records() {
+        let point = parse_line(
+            r#"disk,host=a,mount\ point=/ used=10i,value=0.5,status="ok" 1465839830100400200"#,
+        )
+        .unwrap();
+        let records = point.into_records(precision_multiplier(None).unwrap());
+        assert_eq!(records.len(), 2);
+        let ts_field = config::get_config().common.column_timestamp.clone();
+        assert_eq!(records[0][NAME_LABEL], "disk_used");
+        assert_eq!(records[0][TYPE_LABEL], "gauge");
+        assert_eq!(records[0]["host"], "a");
+        assert_eq!(records[0]["mount_point"], "/");
+        assert_eq!(records[0][VALUE_LABEL], 10.0);
+        assert_eq!(records[0][&ts_field], 1465839830100400_i64);
+        assert_eq!(records[1][NAME_LABEL], "disk");
+        assert_eq!(records[1][VALUE_LABEL], 0.5);
+    }
+
+    #[test]
+    fn test_precision_multiplier() {
+        assert_eq!(precision_multiplier(Some("s")).unwrap(), 1_000_000);
+        assert_eq!(precision_multiplier(Some("ms")).unwrap(), 1_000);
+        assert_eq!(precision_multiplier(Some("u")).unwrap(), 1);
+        assert!(precision_multiplier(Some("x")).is_err());
+    }
+}
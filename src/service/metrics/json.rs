@@ -41,7 +41,9 @@ use crate::{
     service::{
         alerts::alert::AlertExt,
         db, format_stream_name,
-        ingestion::{evaluate_trigger, get_write_partition_key, write_file, TriggerAlertData},
+        ingestion::{
+            evaluate_trigger, get_write_partition_key, quota, write_file, TriggerAlertData,
+        },
         pipeline::batch_execution::ExecutablePipeline,
         schema::check_for_schema,
         self_reporting::report_request_usage_stats,
@@ -49,6 +51,24 @@ use crate::{
 };
 
 pub async fn ingest(org_id: &str, body: web::Bytes) -> Result<IngestionResponse> {
+    let records: Vec<json::Value> = json::from_slice(&body)?;
+    ingest_records(
+        org_id,
+        records,
+        UsageType::JsonMetrics,
+        "/api/org/ingest/metrics/_json",
+    )
+    .await
+}
+
+/// Ingests metric records having the `__name__`, `__type__` and `value` fields and their labels,
+/// `endpoint` is the label of the request metrics.
+pub async fn ingest_records(
+    org_id: &str,
+    records: Vec<json::Value>,
+    usage_type: UsageType,
+    endpoint: &str,
+) -> Result<IngestionResponse> {
     let start = std::time::Instant::now();
     let started_at = chrono::Utc::now().timestamp_micros();
 
@@ -65,6 +85,9 @@ pub async fn ingest(org_id: &str, body: web::Bytes) -> Result<IngestionResponse>
         ));
     }
 
+    // check the ingestion quota of the organization
+    quota::check(org_id, StreamType::Metrics, None)?;
+
     // check memtable
     if let Err(e) = ingester::check_memtable_size() {
         return Ok(IngestionResponse {
@@ -91,8 +114,7 @@ pub async fn ingest(org_id: &str, body: web::Bytes) -> Result<IngestionResponse>
     // records buffer
     let mut json_data_by_stream: HashMap<String, Vec<(json::Value, String)>> = HashMap::new();
 
-    let reader: Vec<json::Value> = json::from_slice(&body)?;
-    for record in reader.into_iter() {
+    for record in records.into_iter() {
         // JSON Flattening
         let mut record = flatten::flatten(record)?;
         // check data type
@@ -426,6 +448,10 @@ pub async fn ingest(org_id: &str, body: web::Bytes) -> Result<IngestionResponse>
         }
     }
 
+    // the request is rejected as a whole when the quota of one of its streams is exhausted, so
+    // that it can be retried without writing twice the other streams
+    quota::check_streams(org_id, StreamType::Metrics, stream_data_buf.keys())?;
+
     // write data to wal
     for (stream_name, stream_data) in stream_data_buf {
         // check if we are allowed to ingest
@@ -441,6 +467,7 @@ pub async fn ingest(org_id: &str, body: web::Bytes) -> Result<IngestionResponse>
 
         let writer =
             ingester::get_writer(0, org_id, &StreamType::Metrics.to_string(), &stream_name).await;
+        quota::consume(org_id, StreamType::Metrics, &stream_name, &stream_data);
         let mut req_stats = write_file(&writer, &stream_name, stream_data).await;
 
         req_stats.response_time = start.elapsed().as_secs_f64();
@@ -457,7 +484,7 @@ pub async fn ingest(org_id: &str, body: web::Bytes) -> Result<IngestionResponse>
             org_id,
             &stream_name,
             StreamType::Metrics,
-            UsageType::JsonMetrics,
+            usage_type,
             fns_length as _,
             started_at,
         )
@@ -467,7 +494,7 @@ pub async fn ingest(org_id: &str, body: web::Bytes) -> Result<IngestionResponse>
     let time = start.elapsed().as_secs_f64();
     metrics::HTTP_RESPONSE_TIME
         .with_label_values(&[
-            "/api/org/ingest/metrics/_json",
+            endpoint,
             "200",
             org_id,
             "",
@@ -476,7 +503,7 @@ pub async fn ingest(org_id: &str, body: web::Bytes) -> Result<IngestionResponse>
         .observe(time);
     metrics::HTTP_INCOMING_REQUESTS
         .with_label_values(&[
-            "/api/org/ingest/metrics/_json",
+            endpoint,
             "200",
             org_id,
             "",
@@ -19,10 +19,12 @@ use regex::Regex;
 
 use crate::common::meta::prom::{Metadata, HASH_LABEL, METADATA_LABEL, VALUE_LABEL};
 
+pub mod influxdb;
 pub mod json;
 pub mod otlp_grpc;
 pub mod otlp_http;
 pub mod prom;
+pub mod statsd;
 
 const EXCLUDE_LABELS: [&str; 5] = [
     VALUE_LABEL,
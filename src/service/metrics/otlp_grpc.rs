@@ -48,7 +48,7 @@ use crate::{
         ingestion::{
             evaluate_trigger,
             grpc::{get_exemplar_val, get_metric_val, get_val},
-            write_file, TriggerAlertData,
+            quota, write_file, TriggerAlertData,
         },
         metrics::{format_label_name, get_exclude_labels},
         pipeline::batch_execution::ExecutablePipeline,
@@ -80,6 +80,9 @@ pub async fn handle_grpc_request(
         )));
     }
 
+    // check the ingestion quota of the organization
+    quota::check(org_id, StreamType::Metrics, None)?;
+
     // check memtable
     if let Err(e) = ingester::check_memtable_size() {
         return Ok(
@@ -480,6 +483,10 @@ pub async fn handle_grpc_request(
         }
     }
 
+    // the request is rejected as a whole when the quota of one of its streams is exhausted, so
+    // that it can be retried without writing twice the other streams
+    quota::check_streams(org_id, StreamType::Metrics, metric_data_map.keys())?;
+
     // write data to wal
     for (stream_name, stream_data) in metric_data_map {
         // stream_data could be empty if metric value is nan, check it
@@ -501,6 +508,7 @@ pub async fn handle_grpc_request(
         // write to file
         let writer =
             ingester::get_writer(0, org_id, &StreamType::Metrics.to_string(), &stream_name).await;
+        quota::consume(org_id, StreamType::Metrics, &stream_name, &stream_data);
         let mut req_stats = write_file(&writer, &stream_name, stream_data).await;
 
         let fns_length: usize =
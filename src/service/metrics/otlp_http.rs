@@ -51,7 +51,7 @@ use crate::{
     service::{
         alerts::alert::AlertExt,
         db, format_stream_name,
-        ingestion::{evaluate_trigger, get_val_for_attr, write_file, TriggerAlertData},
+        ingestion::{evaluate_trigger, get_val_for_attr, quota, write_file, TriggerAlertData},
         metrics::{format_label_name, get_exclude_labels, otlp_grpc::handle_grpc_request},
         pipeline::batch_execution::ExecutablePipeline,
         schema::{check_for_schema, stream_schema_exists},
@@ -69,6 +69,9 @@ pub async fn metrics_proto_handler(
     match handle_grpc_request(org_id, request, false).await {
         Ok(res) => Ok(res),
         Err(e) => {
+            if let Some(e) = e.downcast_ref::<quota::QuotaExceeded>() {
+                return Ok(e.http_response());
+            }
             log::error!("error processing request/{org_id}/metrics/otlp: {}", e);
             Ok(
                 HttpResponse::InternalServerError().json(MetaHttpResponse::error(
@@ -102,6 +105,11 @@ pub async fn metrics_json_handler(
         )));
     }
 
+    // check the ingestion quota of the organization
+    if let Err(e) = quota::check(org_id, StreamType::Metrics, None) {
+        return Ok(e.http_response());
+    }
+
     // check memtable
     if let Err(e) = ingester::check_memtable_size() {
         return Ok(
@@ -569,6 +577,12 @@ pub async fn metrics_json_handler(
         }
     }
 
+    // the request is rejected as a whole when the quota of one of its streams is exhausted, so
+    // that it can be retried without writing twice the other streams
+    if let Err(e) = quota::check_streams(org_id, StreamType::Metrics, metric_data_map.keys()) {
+        return Ok(e.http_response());
+    }
+
     // write data to wal
     for (stream_name, stream_data) in metric_data_map {
         // stream_data could be empty if metric value is nan, check it
@@ -590,6 +604,7 @@ pub async fn metrics_json_handler(
         // write to file
         let writer =
             ingester::get_writer(0, org_id, &StreamType::Metrics.to_string(), &stream_name).await;
+        quota::consume(org_id, StreamType::Metrics, &stream_name, &stream_data);
         let mut req_stats = write_file(&writer, &stream_name, stream_data).await;
 
         let fns_length: usize =
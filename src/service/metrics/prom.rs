@@ -48,7 +48,7 @@ use crate::{
     service::{
         alerts::alert::AlertExt,
         db, format_stream_name,
-        ingestion::{evaluate_trigger, write_file, TriggerAlertData},
+        ingestion::{evaluate_trigger, quota, write_file, TriggerAlertData},
         metrics::format_label_name,
         pipeline::batch_execution::ExecutablePipeline,
         schema::{check_for_schema, stream_schema_exists},
@@ -76,6 +76,9 @@ pub async fn remote_write(
         ));
     }
 
+    // check the ingestion quota of the organization
+    quota::check(org_id, StreamType::Metrics, None)?;
+
     // check memtable
     if let Err(e) = ingester::check_memtable_size() {
         return Err(anyhow::Error::msg(e.to_string()));
@@ -506,6 +509,10 @@ pub async fn remote_write(
         }
     }
 
+    // the request is rejected as a whole when the quota of one of its streams is exhausted, so
+    // that it can be retried without writing twice the other streams
+    quota::check_streams(org_id, StreamType::Metrics, metric_data_map.keys())?;
+
     // write data to wal
     for (stream_name, stream_data) in metric_data_map {
         // stream_data could be empty if metric value is nan, check it
@@ -527,6 +534,7 @@ pub async fn remote_write(
         // write to file
         let writer =
             ingester::get_writer(0, org_id, &StreamType::Metrics.to_string(), &stream_name).await;
+        quota::consume(org_id, StreamType::Metrics, &stream_name, &stream_data);
         let mut req_stats = write_file(&writer, &stream_name, stream_data).await;
 
         let fns_length: usize =
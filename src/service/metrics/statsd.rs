@@ -0,0 +1,454 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! StatsD and DogStatsD metrics, the samples are aggregated in memory and the series updated
+//! since the previous flush are ingested at every flush interval:
+//! - counters are ingested as cumulative counters,
+//! - gauges keep their value between flushes, which can be changed by a signed value,
+//! - timers, histograms and distributions are ingested as summaries,
+//! - sets are ingested as gauges counting the unique values received during the interval.
+//!
+//! The counters and timers not updated during an interval are evicted, so a counter restarts from
+//! zero when it is updated again. The gauges are only evicted after `GAUGE_MAX_IDLE_FLUSHES`
+//! intervals without update, so a relative update still applies to the previous value. The
+//! counters are cumulative per node, the records are labeled with the `instance` of the node.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use config::{
+    cluster::LOCAL_NODE,
+    meta::self_reporting::usage::UsageType,
+    utils::json::{self, Map, Value},
+};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use super::format_label_name;
+use crate::common::meta::prom::{NAME_LABEL, TYPE_LABEL, VALUE_LABEL};
+
+/// Quantiles of the summaries of the timers
+const TIMER_QUANTILES: [f64; 3] = [0.5, 0.9, 0.99];
+
+/// Flushes without update after which a gauge is evicted
+const GAUGE_MAX_IDLE_FLUSHES: u32 = 60;
+
+static AGGREGATOR: Lazy<Mutex<Aggregator>> = Lazy::new(Default::default);
+
+/// Aggregates the samples of a StatsD packet, the invalid lines are skipped.
+pub fn ingest(packet: &str) {
+    let mut samples = Vec::new();
+    for line in packet.lines() {
+        let line = line.trim();
+        // DogStatsD events and service checks aren't metrics
+        if line.is_empty() || line.starts_with("_e{") || line.starts_with("_sc|") {
+            continue;
+        }
+        match parse_line(line) {
+            Ok(v) => samples.extend(v),
+            Err(e) => log::warn!("Invalid StatsD line {line:?}: {e}"),
+        }
+    }
+    let mut aggregator = AGGREGATOR.lock();
+    for sample in samples {
+        aggregator.add(sample);
+    }
+}
+
+/// Ingests the series updated since the previous flush into the org of the StatsD listener.
+pub async fn flush() -> Result<()> {
+    let timestamp = chrono::Utc::now().timestamp_micros();
+    let records = AGGREGATOR.lock().flush(timestamp);
+    if records.is_empty() {
+        return Ok(());
+    }
+    let org_id = &config::get_config().tcp.statsd_org;
+    super::json::ingest_records(
+        org_id,
+        records,
+        UsageType::StatsD,
+        "/api/org/ingest/metrics/_statsd",
+    )
+    .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SampleValue {
+    /// Value already divided by the sample rate
+    Counter(f64),
+    Gauge {
+        value: f64,
+        relative: bool,
+    },
+    /// Value and number of occurrences it stands for, per the sample rate
+    Timer(f64, f64),
+    Set(String),
+}
+
+#[derive(Debug, PartialEq)]
+struct Sample {
+    name: String,
+    tags: Vec<(String, String)>,
+    value: SampleValue,
+}
+
+/// Parses a line, `<name>:<value>[:<value>...]|<type>[|@<sample rate>][|#<tag>[:<value>],...]`,
+/// into one sample per value.
+fn parse_line(line: &str) -> Result<Vec<Sample>> {
+    let (name, rest) = line
+        .split_once(':')
+        .ok_or_else(|| anyhow!("missing value"))?;
+    if name.is_empty() {
+        return Err(anyhow!("missing name"));
+    }
+    let mut parts = rest.split('|');
+    let values = parts.next().unwrap_or_default();
+    let metric_type = parts.next().ok_or_else(|| anyhow!("missing type"))?;
+
+    let mut sample_rate = 1.0;
+    let mut tags = Vec::new();
+    for part in parts {
+        if let Some(rate) = part.strip_prefix('@') {
+            sample_rate = rate
+                .parse::<f64>()
+                .ok()
+                .filter(|v| *v > 0.0 && *v <= 1.0)
+                .ok_or_else(|| anyhow!("invalid sample rate: {rate}"))?;
+        } else if let Some(v) = part.strip_prefix('#') {
+            for tag in v.split(',').filter(|v| !v.is_empty()) {
+                let (name, value) = tag.split_once(':').unwrap_or((tag, "true"));
+                tags.push((format_label_name(name), value.to_string()));
+            }
+        }
+        // the other DogStatsD extensions, e.g. the container id, are ignored
+    }
+    tags.sort();
+    tags.dedup_by(|a, b| a.0 == b.0);
+
+    let parse_number = |v: &str| {
+        v.parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .ok_or_else(|| anyhow!("invalid value: {v}"))
+    };
+    values
+        .split(':')
+        .map(|v| {
+            let value = match metric_type {
+                "c" => SampleValue::Counter(parse_number(v)? / sample_rate),
+                "g" => SampleValue::Gauge {
+                    value: parse_number(v)?,
+                    relative: v.starts_with(['+', '-']),
+                },
+                "ms" | "h" | "d" => SampleValue::Timer(parse_number(v)?, 1.0 / sample_rate),
+                "s" => SampleValue::Set(v.to_string()),
+                t => return Err(anyhow!("invalid type: {t}")),
+            };
+            Ok(Sample {
+                name: name.to_string(),
+                tags: tags.clone(),
+                value,
+            })
+        })
+        .collect()
+}
+
+type SeriesKey = (String, Vec<(String, String)>);
+
+#[derive(Default)]
+struct TimerState {
+    /// Values received since the previous flush
+    values: Vec<f64>,
+    sum: f64,
+    count: f64,
+}
+
+#[derive(Default)]
+struct GaugeState {
+    value: f64,
+    updated: bool,
+    /// Flushes since the last update
+    idle_flushes: u32,
+}
+
+#[derive(Default)]
+struct Aggregator {
+    /// Cumulative value of the counters and if they were updated since the previous flush
+    counters: HashMap<SeriesKey, (f64, bool)>,
+    gauges: HashMap<SeriesKey, GaugeState>,
+    timers: HashMap<SeriesKey, TimerState>,
+    sets: HashMap<SeriesKey, HashSet<String>>,
+}
+
+impl Aggregator {
+    fn add(&mut self, sample: Sample) {
+        let key = (sample.name, sample.tags);
+        match sample.value {
+            SampleValue::Counter(v) => {
+                let counter = self.counters.entry(key).or_default();
+                *counter = (counter.0 + v, true);
+            }
+            SampleValue::Gauge { value, relative } => {
+                let gauge = self.gauges.entry(key).or_default();
+                gauge.value = if relative { gauge.value + value } else { value };
+                gauge.updated = true;
+                gauge.idle_flushes = 0;
+            }
+            SampleValue::Timer(v, count) => {
+                let timer = self.timers.entry(key).or_default();
+                timer.values.push(v);
+                timer.sum += v * count;
+                timer.count += count;
+            }
+            SampleValue::Set(v) => {
+                self.sets.entry(key).or_default().insert(v);
+            }
+        }
+    }
+
+    /// Returns the records of the series updated since the previous flush, the series which
+    /// weren't updated since the previous flush are evicted, except the gauges which are kept
+    /// for `GAUGE_MAX_IDLE_FLUSHES` flushes.
+    fn flush(&mut self, timestamp: i64) -> Vec<Value> {
+        let mut records = Vec::new();
+        self.counters.retain(|(name, tags), (value, updated)| {
+            if std::mem::take(updated) {
+                records.push(new_record(name, "counter", tags, None, *value, timestamp));
+                true
+            } else {
+                false
+            }
+        });
+        self.gauges.retain(|(name, tags), gauge| {
+            if std::mem::take(&mut gauge.updated) {
+                records.push(new_record(
+                    name,
+                    "gauge",
+                    tags,
+                    None,
+                    gauge.value,
+                    timestamp,
+                ));
+                return true;
+            }
+            gauge.idle_flushes += 1;
+            gauge.idle_flushes < GAUGE_MAX_IDLE_FLUSHES
+        });
+        self.timers.retain(|(name, tags), timer| {
+            if timer.values.is_empty() {
+                return false;
+            }
+            let mut values = std::mem::take(&mut timer.values);
+            values.sort_by(|a, b| a.total_cmp(b));
+            for quantile in TIMER_QUANTILES {
+                let value = values[quantile_index(values.len(), quantile)];
+                records.push(new_record(
+                    name,
+                    "summary",
+                    tags,
+                    Some(quantile),
+                    value,
+                    timestamp,
+                ));
+            }
+            let sum_name = format!("{name}_sum");
+            records.push(new_record(
+                &sum_name, "summary", tags, None, timer.sum, timestamp,
+            ));
+            let count_name = format!("{name}_count");
+            records.push(new_record(
+                &count_name,
+                "summary",
+                tags,
+                None,
+                timer.count,
+                timestamp,
+            ));
+            true
+        });
+        for ((name, tags), values) in self.sets.drain() {
+            let value = values.len() as f64;
+            records.push(new_record(&name, "gauge", &tags, None, value, timestamp));
+        }
+        records
+    }
+}
+
+/// Index of the nearest rank of the quantile in sorted values.
+fn quantile_index(len: usize, quantile: f64) -> usize {
+    ((quantile * len as f64).ceil() as usize).clamp(1, len) - 1
+}
+
+fn new_record(
+    name: &str,
+    metric_type: &str,
+    tags: &[(String, String)],
+    quantile: Option<f64>,
+    value: f64,
+    timestamp: i64,
+) -> Value {
+    let mut record = Map::with_capacity(tags.len() + 6);
+    for (name, value) in tags {
+        record.insert(name.to_string(), Value::String(value.to_string()));
+    }
+    record.insert(
+        "instance".to_string(),
+        Value::String(LOCAL_NODE.name.clone()),
+    );
+    if let Some(quantile) = quantile {
+        record.insert("quantile".to_string(), Value::String(quantile.to_string()));
+    }
+    record.insert(NAME_LABEL.to_string(), Value::String(name.to_string()));
+    record.insert(
+        TYPE_LABEL.to_string(),
+        Value::String(metric_type.to_string()),
+    );
+    record.insert(
+        config::get_config().common.column_timestamp.clone(),
+        Value::Number(timestamp.into()),
+    );
+    record.insert(
+        VALUE_LABEL.to_string(),
+        json::Number::from_f64(value)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+    );
+    Value::Object(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line("page.views:2|c|@0.5|#env:prod,region:us-west,canary").unwrap(),
+            vec![Sample {
+                name: "page.views".to_string(),
+                tags: vec![
+                    ("canary".to_string(), "true".to_string()),
+                    ("env".to_string(), "prod".to_string()),
+                    ("region".to_string(), "us-west".to_string()),
+                ],
+                value: SampleValue::Counter(4.0),
+            }]
+        );
+        assert_eq!(
+            parse_line("queue.size:-3|g").unwrap()[0].value,
+            SampleValue::Gauge {
+                value: -3.0,
+                relative: true
+            }
+        );
+        let samples = parse_line("request.time:10:20|ms|@0.1").unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[1].value, SampleValue::Timer(20.0, 10.0));
+        assert_eq!(
+            parse_line("users:alice|s").unwrap()[0].value,
+            SampleValue::Set("alice".to_string())
+        );
+
+        assert!(parse_line("page.views").is_err());
+        assert!(parse_line("page.views:1").is_err());
+        assert!(parse_line("page.views:abc|c").is_err());
+        assert!(parse_line("page.views:1|x").is_err());
+        assert!(parse_line("page.views:1|c|@2").is_err());
+    }
+
+    #[test]
+    fn test_aggregator() {
+        let mut aggregator = Aggregator::default();
+        for line in [
+            "hits:1|c",
+            "hits:2|c",
+            "temp:20|g",
+            "temp:+5|g",
+            "latency:30|ms",
+            "latency:10|ms",
+            "latency:20|ms",
+            "users:a|s",
+            "users:b|s",
+            "users:a|s",
+        ] {
+            for sample in parse_line(line).unwrap() {
+                aggregator.add(sample);
+            }
+        }
+        let records = aggregator.flush(1);
+        let find = |name: &str, quantile: Option<&str>| {
+            records
+                .iter()
+                .find(|r| {
+                    r[NAME_LABEL] == name && r.get("quantile").and_then(|v| v.as_str()) == quantile
+                })
+                .map(|r| r[VALUE_LABEL].as_f64().unwrap())
+        };
+        assert_eq!(find("hits", None), Some(3.0));
+        assert_eq!(find("temp", None), Some(25.0));
+        assert_eq!(find("latency", Some("0.5")), Some(20.0));
+        assert_eq!(find("latency", Some("0.99")), Some(30.0));
+        assert_eq!(find("latency_sum", None), Some(60.0));
+        assert_eq!(find("latency_count", None), Some(3.0));
+        assert_eq!(find("users", None), Some(2.0));
+        assert_eq!(records.len(), 8);
+
+        // only the updated series are flushed, the counters are cumulative
+        aggregator.add(parse_line("hits:1|c").unwrap().remove(0));
+        let records = aggregator.flush(2);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0][VALUE_LABEL], 4.0);
+        assert_eq!(records[0]["instance"], LOCAL_NODE.name.as_str());
+        assert_eq!(aggregator.counters.len(), 1);
+        assert_eq!(aggregator.gauges.len(), 1);
+        assert!(aggregator.timers.is_empty());
+
+        // the series not updated since the previous flush are evicted
+        assert!(aggregator.flush(3).is_empty());
+        assert!(aggregator.counters.is_empty());
+        aggregator.add(parse_line("hits:1|c").unwrap().remove(0));
+        assert_eq!(aggregator.flush(4)[0][VALUE_LABEL], 1.0);
+    }
+
+    #[test]
+    fn test_aggregator_gauge() {
+        let mut aggregator = Aggregator::default();
+        aggregator.add(parse_line("temp:20|g").unwrap().remove(0));
+        assert_eq!(aggregator.flush(1)[0][VALUE_LABEL], 20.0);
+
+        // the idle gauges aren't flushed, but the relative updates still apply to them
+        assert!(aggregator.flush(2).is_empty());
+        aggregator.add(parse_line("temp:+5|g").unwrap().remove(0));
+        assert_eq!(aggregator.flush(3)[0][VALUE_LABEL], 25.0);
+
+        for i in 1..GAUGE_MAX_IDLE_FLUSHES {
+            assert!(aggregator.flush(3 + i as i64).is_empty());
+            assert_eq!(aggregator.gauges.len(), 1);
+        }
+        assert!(aggregator.flush(100).is_empty());
+        assert!(aggregator.gauges.is_empty());
+        aggregator.add(parse_line("temp:+5|g").unwrap().remove(0));
+        assert_eq!(aggregator.flush(101)[0][VALUE_LABEL], 5.0);
+    }
+
+    #[test]
+    fn test_quantile_index() {
+        assert_eq!(quantile_index(1, 0.99), 0);
+        assert_eq!(quantile_index(10, 0.5), 4);
+        assert_eq!(quantile_index(10, 0.9), 8);
+        assert_eq!(quantile_index(100, 0.99), 98);
+    }
+}
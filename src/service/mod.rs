@@ -35,6 +35,7 @@ pub mod metrics;
 pub mod organization;
 pub mod pipeline;
 pub mod promql;
+pub mod rum;
 pub mod schema;
 pub mod search;
 pub mod self_reporting;
@@ -0,0 +1,679 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Source maps of the RUM applications. They are uploaded per service and version, and the
+//! `error.stack` of the ingested RUM events is symbolicated with them: the frames are mapped back
+//! to their original file, line and function into `error.stack_symbolicated`, next to the raw
+//! stack.
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use actix_web::web;
+use anyhow::{anyhow, Result};
+use config::utils::json;
+use infra::storage;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::Deserialize;
+
+use crate::common::meta::sourcemap::SourceMapFile;
+
+const SOURCEMAPS_PREFIX: &str = "sourcemaps";
+
+/// Field of the RUM `error` object the symbolicated stack is stored into
+pub const SYMBOLICATED_STACK_FIELD: &str = "stack_symbolicated";
+
+/// Source maps, or their absence, are reloaded from the storage after this delay
+const CACHE_TTL: Duration = Duration::from_secs(300);
+const CACHE_MAX_ENTRIES: usize = 100;
+
+type CachedSourceMap = (Option<Arc<SourceMap>>, Instant);
+
+static SOURCE_MAPS: Lazy<RwLock<HashMap<String, CachedSourceMap>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Stores the source map of a minified file, the map is validated first.
+pub async fn upload(
+    org_id: &str,
+    service: &str,
+    version: &str,
+    minified_file: &str,
+    data: web::Bytes,
+) -> Result<()> {
+    let key = storage_key(org_id, service, version, minified_file)?;
+    SourceMap::parse(&data)?;
+    storage::put(&key, data).await?;
+    SOURCE_MAPS.write().remove(&key);
+    Ok(())
+}
+
+/// Lists the source maps of the organization, optionally of a service and version only.
+pub async fn list(
+    org_id: &str,
+    service: Option<&str>,
+    version: Option<&str>,
+) -> Result<Vec<SourceMapFile>> {
+    let org_prefix = format!("{SOURCEMAPS_PREFIX}/{org_id}/");
+    let prefix = match service {
+        Some(service) => format!("{org_prefix}{}/", check_path_segment(service)?),
+        None => org_prefix.clone(),
+    };
+    let mut files: Vec<SourceMapFile> = storage::list(&prefix)
+        .await?
+        .into_iter()
+        .filter_map(|key| {
+            // the remote storages may return the keys with the bucket prefix
+            let (_, path) = key.split_once(&org_prefix)?;
+            let mut parts = path.splitn(3, '/');
+            Some(SourceMapFile {
+                service: parts.next()?.to_string(),
+                version: parts.next()?.to_string(),
+                minified_file: parts.next()?.to_string(),
+            })
+        })
+        .filter(|file| version.map_or(true, |v| file.version == v))
+        .collect();
+    files.sort_by(|a, b| {
+        (&a.service, &a.version, &a.minified_file).cmp(&(&b.service, &b.version, &b.minified_file))
+    });
+    Ok(files)
+}
+
+/// Deletes the source map of a minified file, or all the source maps of the version when no file
+/// is given. Returns the number of deleted source maps.
+pub async fn delete(
+    org_id: &str,
+    service: &str,
+    version: &str,
+    minified_file: Option<&str>,
+) -> Result<usize> {
+    let keys = match minified_file {
+        Some(file) => {
+            let key = storage_key(org_id, service, version, file)?;
+            if storage::head(&key).await.is_err() {
+                return Ok(0);
+            }
+            vec![key]
+        }
+        None => list(org_id, Some(service), Some(version))
+            .await?
+            .into_iter()
+            .map(|f| storage_key(org_id, &f.service, &f.version, &f.minified_file))
+            .collect::<Result<Vec<_>>>()?,
+    };
+    if keys.is_empty() {
+        return Ok(0);
+    }
+    let files = keys.iter().map(|k| k.as_str()).collect::<Vec<_>>();
+    storage::del(&files).await?;
+    let mut cache = SOURCE_MAPS.write();
+    for key in keys.iter() {
+        cache.remove(key);
+    }
+    Ok(keys.len())
+}
+
+/// Adds the symbolicated stack to the RUM records of a newline delimited json body which have an
+/// `error.stack`, the body is returned as is when there is nothing to symbolicate.
+pub async fn symbolicate_records(
+    org_id: &str,
+    body: web::Bytes,
+    extend_json: &HashMap<String, json::Value>,
+) -> web::Bytes {
+    if !body.windows(7).any(|w| w == b"\"stack\"") {
+        return body;
+    }
+    let mut changed = false;
+    let mut buf = Vec::with_capacity(body.len());
+    for line in body.split(|c| *c == b'\n') {
+        match symbolicate_record(org_id, line, extend_json).await {
+            Some(record) => {
+                buf.extend(record);
+                changed = true;
+            }
+            None => buf.extend_from_slice(line),
+        }
+        buf.push(b'\n');
+    }
+    if changed {
+        buf.into()
+    } else {
+        body
+    }
+}
+
+async fn symbolicate_record(
+    org_id: &str,
+    line: &[u8],
+    extend_json: &HashMap<String, json::Value>,
+) -> Option<Vec<u8>> {
+    let mut record: json::Value = json::from_slice(line).ok()?;
+    let stack = record.get("error")?.get("stack")?.as_str()?;
+    // the tags of the query string win over the fields of the record, as on ingestion
+    let field = |name: &str| {
+        extend_json
+            .get(name)
+            .or_else(|| record.get(name))
+            .and_then(|v| v.as_str())
+    };
+    let symbolicated = symbolicate(org_id, field("service")?, field("version")?, stack).await?;
+    record["error"][SYMBOLICATED_STACK_FIELD] = json::Value::String(symbolicated);
+    json::to_vec(&record).ok()
+}
+
+/// Symbolicates a stack trace with the source maps of the service version, `None` when none of
+/// its frames could be mapped.
+pub async fn symbolicate(
+    org_id: &str,
+    service: &str,
+    version: &str,
+    stack: &str,
+) -> Option<String> {
+    let mut maps = HashMap::new();
+    for frame in stack.lines().filter_map(Frame::parse) {
+        let Some(path) = minified_path(frame.file) else {
+            continue;
+        };
+        if maps.contains_key(&path) {
+            continue;
+        }
+        let Ok(key) = storage_key(org_id, service, version, &path) else {
+            return None;
+        };
+        if let Some(map) = get_source_map(&key).await {
+            maps.insert(path, map);
+        }
+    }
+    if maps.is_empty() {
+        return None;
+    }
+    symbolicate_stack(stack, &maps)
+}
+
+async fn get_source_map(key: &str) -> Option<Arc<SourceMap>> {
+    if let Some((map, loaded_at)) = SOURCE_MAPS.read().get(key) {
+        if loaded_at.elapsed() < CACHE_TTL {
+            return map.clone();
+        }
+    }
+    let map = match storage::get(key).await {
+        Ok(data) => match SourceMap::parse(&data) {
+            Ok(map) => Some(Arc::new(map)),
+            Err(e) => {
+                log::error!("[RUM] Invalid source map {}: {}", key, e);
+                None
+            }
+        },
+        Err(object_store::Error::NotFound { .. }) => None,
+        Err(e) => {
+            // not cached, it's retried with the next stack
+            log::error!("[RUM] Error while loading source map {}: {}", key, e);
+            return None;
+        }
+    };
+    let mut cache = SOURCE_MAPS.write();
+    if cache.len() >= CACHE_MAX_ENTRIES {
+        cache.retain(|_, (_, loaded_at)| loaded_at.elapsed() < CACHE_TTL);
+        if cache.len() >= CACHE_MAX_ENTRIES {
+            cache.clear();
+        }
+    }
+    cache.insert(key.to_string(), (map.clone(), Instant::now()));
+    map
+}
+
+fn storage_key(org_id: &str, service: &str, version: &str, minified_file: &str) -> Result<String> {
+    let path = minified_path(minified_file)
+        .ok_or_else(|| anyhow!("Invalid minified file: {minified_file}"))?;
+    Ok(format!(
+        "{SOURCEMAPS_PREFIX}/{org_id}/{}/{}/{path}",
+        check_path_segment(service)?,
+        check_path_segment(version)?,
+    ))
+}
+
+fn check_path_segment(v: &str) -> Result<&str> {
+    if v.is_empty() || v == "." || v == ".." || v.contains('/') || v.contains('\\') {
+        return Err(anyhow!("Invalid service or version: {v}"));
+    }
+    Ok(v)
+}
+
+/// Path of a minified file, without the scheme, the host, the query nor the fragment of its url,
+/// so that the frames match the source maps whatever the host the application is served from.
+fn minified_path(url: &str) -> Option<String> {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/').map(|(_, path)| path)?,
+        None => url,
+    };
+    let path = path.trim_start_matches('/');
+    if path.is_empty()
+        || path
+            .split('/')
+            .any(|s| s.is_empty() || s == "." || s == "..")
+    {
+        return None;
+    }
+    Some(path.to_string())
+}
+
+/// A frame of a stack trace in the V8 (`at fn (file:line:column)`) or the Gecko / WebKit
+/// (`fn@file:line:column`) format.
+#[derive(Debug, PartialEq)]
+struct Frame<'a> {
+    /// Leading `    at ` of the V8 frames, empty for the other ones
+    prefix: &'a str,
+    function: &'a str,
+    file: &'a str,
+    line: u32,
+    column: u32,
+}
+
+impl<'a> Frame<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("at ") {
+            let prefix = &line[..line.len() - rest.len()];
+            let (function, location) = match rest.strip_suffix(')') {
+                Some(v) => v.rsplit_once(" (")?,
+                None => ("", rest),
+            };
+            let (file, line, column) = Self::parse_location(location)?;
+            return Some(Self {
+                prefix,
+                function,
+                file,
+                line,
+                column,
+            });
+        }
+        let (function, location) = trimmed.split_once('@')?;
+        let (file, line, column) = Self::parse_location(location)?;
+        Some(Self {
+            prefix: "",
+            function,
+            file,
+            line,
+            column,
+        })
+    }
+
+    fn parse_location(location: &str) -> Option<(&str, u32, u32)> {
+        let mut parts = location.rsplitn(3, ':');
+        let column = parts.next()?.parse().ok()?;
+        let line = parts.next()?.parse().ok()?;
+        let file = parts.next().filter(|f| !f.is_empty())?;
+        Some((file, line, column))
+    }
+
+    fn format(&self, function: &str, file: &str, line: u32, column: u32) -> String {
+        match (self.prefix.is_empty(), function.is_empty()) {
+            (false, false) => format!("{}{function} ({file}:{line}:{column})", self.prefix),
+            (false, true) => format!("{}{file}:{line}:{column}", self.prefix),
+            (true, _) => format!("{function}@{file}:{line}:{column}"),
+        }
+    }
+}
+
+/// Rewrites the frames of the stack which can be mapped, the other lines are kept as is.
+///
+/// The name a source map records at a position is the one of the token there, so the original
+/// name of a function is found at the call site of its caller, that is in the next frame. The
+/// minified name is kept when the caller can't be mapped.
+fn symbolicate_stack(stack: &str, maps: &HashMap<String, Arc<SourceMap>>) -> Option<String> {
+    let lines: Vec<&str> = stack.lines().collect();
+    let frames: Vec<Option<Frame>> = lines.iter().map(|line| Frame::parse(line)).collect();
+    let locations: Vec<Option<OriginalLocation>> = frames
+        .iter()
+        .map(|frame| {
+            let frame = frame.as_ref()?;
+            let map = maps.get(&minified_path(frame.file)?)?;
+            map.lookup(frame.line.checked_sub(1)?, frame.column.saturating_sub(1))
+        })
+        .collect();
+    if locations.iter().all(|v| v.is_none()) {
+        return None;
+    }
+
+    let mut symbolicated = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        match (&frames[i], &locations[i]) {
+            (Some(frame), Some(location)) => {
+                let function = locations
+                    .get(i + 1)
+                    .and_then(|caller| caller.as_ref()?.name)
+                    .unwrap_or(frame.function);
+                symbolicated.push(frame.format(
+                    function,
+                    location.source,
+                    location.line,
+                    location.column,
+                ));
+            }
+            _ => symbolicated.push(line.to_string()),
+        }
+    }
+    Some(symbolicated.join("\n"))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSourceMap {
+    version: u32,
+    #[serde(default)]
+    source_root: Option<String>,
+    #[serde(default)]
+    sources: Vec<Option<String>>,
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    mappings: String,
+    #[serde(default)]
+    sections: Option<json::Value>,
+}
+
+/// Decoded source map of the revision 3 format.
+#[derive(Debug)]
+pub struct SourceMap {
+    sources: Vec<String>,
+    names: Vec<String>,
+    /// Mappings of each generated line, ordered by generated column
+    lines: Vec<Vec<Mapping>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Mapping {
+    column: u32,
+    original: Option<OriginalPosition>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct OriginalPosition {
+    source: u32,
+    line: u32,
+    column: u32,
+    name: Option<u32>,
+}
+
+/// Original position of a generated one, the line and the column are 1-based.
+#[derive(Debug, PartialEq)]
+pub struct OriginalLocation<'a> {
+    pub source: &'a str,
+    pub line: u32,
+    pub column: u32,
+    pub name: Option<&'a str>,
+}
+
+impl SourceMap {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let raw: RawSourceMap = json::from_slice(data)?;
+        if raw.version != 3 {
+            return Err(anyhow!("Unsupported source map version: {}", raw.version));
+        }
+        if raw.sections.is_some() {
+            return Err(anyhow!("Indexed source maps are not supported"));
+        }
+        let source_root = raw.source_root.unwrap_or_default();
+        let sources = raw
+            .sources
+            .into_iter()
+            .map(|source| {
+                let source = source.unwrap_or_default();
+                if source_root.is_empty() || source_root.ends_with('/') {
+                    format!("{source_root}{source}")
+                } else {
+                    format!("{source_root}/{source}")
+                }
+            })
+            .collect();
+        Ok(Self {
+            sources,
+            names: raw.names,
+            lines: decode_mappings(&raw.mappings)?,
+        })
+    }
+
+    /// Original position of a generated position, both 0-based.
+    pub fn lookup(&self, line: u32, column: u32) -> Option<OriginalLocation<'_>> {
+        let mappings = self.lines.get(line as usize)?;
+        let idx = mappings.partition_point(|m| m.column <= column);
+        let original = mappings.get(idx.checked_sub(1)?)?.original?;
+        Some(OriginalLocation {
+            source: self.sources.get(original.source as usize)?,
+            line: original.line + 1,
+            column: original.column + 1,
+            name: original
+                .name
+                .and_then(|name| self.names.get(name as usize))
+                .map(|name| name.as_str()),
+        })
+    }
+}
+
+fn decode_mappings(mappings: &str) -> Result<Vec<Vec<Mapping>>> {
+    let to_u32 = |v: i64| u32::try_from(v).map_err(|_| anyhow!("Invalid mappings: {v}"));
+    // all the fields but the generated column are relative to the previous segment of the map
+    let (mut source, mut line, mut column, mut name) = (0i64, 0i64, 0i64, 0i64);
+    let mut lines = Vec::new();
+    for generated_line in mappings.split(';') {
+        let mut generated_column = 0i64;
+        let mut line_mappings = Vec::new();
+        for segment in generated_line.split(',').filter(|s| !s.is_empty()) {
+            let fields = decode_vlq(segment)?;
+            generated_column += fields[0];
+            let original = match fields.len() {
+                1 => None,
+                4 | 5 => {
+                    source += fields[1];
+                    line += fields[2];
+                    column += fields[3];
+                    let name = match fields.get(4) {
+                        Some(v) => {
+                            name += v;
+                            Some(to_u32(name)?)
+                        }
+                        None => None,
+                    };
+                    Some(OriginalPosition {
+                        source: to_u32(source)?,
+                        line: to_u32(line)?,
+                        column: to_u32(column)?,
+                        name,
+                    })
+                }
+                _ => return Err(anyhow!("Invalid mapping segment: {segment}")),
+            };
+            line_mappings.push(Mapping {
+                column: to_u32(generated_column)?,
+                original,
+            });
+        }
+        line_mappings.sort_by_key(|m| m.column);
+        lines.push(line_mappings);
+    }
+    Ok(lines)
+}
+
+/// Decodes the base64 VLQ values of a mapping segment.
+fn decode_vlq(segment: &str) -> Result<Vec<i64>> {
+    let mut values = Vec::with_capacity(5);
+    let (mut value, mut shift) = (0i64, 0u32);
+    for c in segment.bytes() {
+        let digit = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(anyhow!("Invalid mapping segment: {segment}")),
+        } as i64;
+        if shift > 32 {
+            return Err(anyhow!("Invalid mapping segment: {segment}"));
+        }
+        value += (digit & 0x1f) << shift;
+        if digit & 0x20 != 0 {
+            shift += 5;
+            continue;
+        }
+        values.push(if value & 1 == 1 {
+            -(value >> 1)
+        } else {
+            value >> 1
+        });
+        value = 0;
+        shift = 0;
+    }
+    if shift != 0 {
+        return Err(anyhow!("Invalid mapping segment: {segment}"));
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // main.js `function add(a,b){return a+b}function run(){return add(1,2)}` minified from
+    // src/math.js and src/app.js
+    const SOURCE_MAP: &str = r#"{
+        "version": 3,
+        "file": "main.js",
+        "sourceRoot": "webpack://app",
+        "sources": ["src/math.js", "src/app.js"],
+        "names": ["add", "run"],
+        "mappings": "AAAA,SAASA,SACP,WCDF,SAASC,MACP,OAAOD"
+    }"#;
+
+    #[test]
+    fn test_decode_vlq() {
+        assert_eq!(decode_vlq("AAAA").unwrap(), vec![0, 0, 0, 0]);
+        assert_eq!(decode_vlq("AACA").unwrap(), vec![0, 0, 1, 0]);
+        assert_eq!(decode_vlq("D").unwrap(), vec![-1]);
+        assert_eq!(decode_vlq("gB").unwrap(), vec![16]);
+        assert_eq!(decode_vlq("2Hw+BC").unwrap(), vec![123, 1000, 1]);
+        assert!(decode_vlq("g").is_err());
+        assert!(decode_vlq("A=").is_err());
+        assert!(decode_vlq("gggggggggggggB").is_err());
+    }
+
+    #[test]
+    fn test_source_map_lookup() {
+        let map = SourceMap::parse(SOURCE_MAP.as_bytes()).unwrap();
+        assert_eq!(
+            map.lookup(0, 9),
+            Some(OriginalLocation {
+                source: "webpack://app/src/math.js",
+                line: 1,
+                column: 10,
+                name: Some("add"),
+            })
+        );
+        // between two mappings
+        assert_eq!(
+            map.lookup(0, 20).map(|l| (l.source, l.line, l.column)),
+            Some(("webpack://app/src/math.js", 2, 3))
+        );
+        assert_eq!(
+            map.lookup(0, 51),
+            Some(OriginalLocation {
+                source: "webpack://app/src/app.js",
+                line: 2,
+                column: 10,
+                name: Some("add"),
+            })
+        );
+        assert_eq!(map.lookup(1, 0), None);
+
+        assert!(SourceMap::parse(br#"{"version": 2, "mappings": ""}"#).is_err());
+        assert!(SourceMap::parse(br#"{"version": 3, "sections": []}"#).is_err());
+        assert!(SourceMap::parse(br#"{"version": 3, "mappings": "A*"}"#).is_err());
+        assert!(SourceMap::parse(br#"{"version": 3, "mappings": "AA"}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_frame() {
+        assert_eq!(
+            Frame::parse("    at r (https://example.com/static/main.js?v=1:1:49)"),
+            Some(Frame {
+                prefix: "    at ",
+                function: "r",
+                file: "https://example.com/static/main.js?v=1",
+                line: 1,
+                column: 49,
+            })
+        );
+        assert_eq!(Frame::parse("    at async Promise.all (index 0)"), None);
+        assert_eq!(
+            Frame::parse("at https://example.com/main.js:10:2").map(|f| (f.function, f.line)),
+            Some(("", 10))
+        );
+        assert_eq!(
+            Frame::parse("n@https://example.com/main.js:1:19"),
+            Some(Frame {
+                prefix: "",
+                function: "n",
+                file: "https://example.com/main.js",
+                line: 1,
+                column: 19,
+            })
+        );
+        assert_eq!(Frame::parse("TypeError: x is undefined"), None);
+        assert_eq!(Frame::parse("n@https://example.com/main.js"), None);
+    }
+
+    #[test]
+    fn test_minified_path() {
+        assert_eq!(
+            minified_path("https://example.com/static/js/main.js?v=1#a").as_deref(),
+            Some("static/js/main.js")
+        );
+        assert_eq!(minified_path("/main.js").as_deref(), Some("main.js"));
+        assert_eq!(
+            minified_path("static/main.js").as_deref(),
+            Some("static/main.js")
+        );
+        assert_eq!(minified_path("https://example.com/"), None);
+        assert_eq!(minified_path("../secret.js"), None);
+        assert!(storage_key("default", "web", "1.0.0", "/main.js").is_ok());
+        assert!(storage_key("default", "web", "..", "/main.js").is_err());
+        assert!(storage_key("default", "a/b", "1.0.0", "/main.js").is_err());
+    }
+
+    #[test]
+    fn test_symbolicate_stack() {
+        let maps = HashMap::from([(
+            "static/main.js".to_string(),
+            Arc::new(SourceMap::parse(SOURCE_MAP.as_bytes()).unwrap()),
+        )]);
+        let stack = "TypeError: boom\n    at r (https://example.com/static/main.js:1:27)\n    at n (https://example.com/static/main.js:1:52)\n    at https://cdn.example.com/vendor.js:1:1";
+        assert_eq!(
+            symbolicate_stack(stack, &maps).unwrap(),
+            "TypeError: boom\n    at add (webpack://app/src/math.js:2:3)\n    at n (webpack://app/src/app.js:2:10)\n    at https://cdn.example.com/vendor.js:1:1"
+        );
+        assert_eq!(
+            symbolicate_stack("r@https://example.com/static/main.js:1:27", &maps).unwrap(),
+            "r@webpack://app/src/math.js:2:3"
+        );
+        assert_eq!(
+            symbolicate_stack("at https://cdn.example.com/vendor.js:1:1", &maps),
+            None
+        );
+    }
+}
@@ -32,9 +32,11 @@ use config::{
 use datafusion::{
     common::tree_node::TreeNode,
     error::DataFusionError,
-    physical_plan::{displayable, visit_execution_plan, ExecutionPlan},
+    execution::TaskContext,
+    physical_plan::{displayable, execute_stream, visit_execution_plan, ExecutionPlan},
     prelude::SessionContext,
 };
+use futures::StreamExt;
 use hashbrown::{HashMap, HashSet};
 use infra::{
     dist_lock,
@@ -42,6 +44,7 @@ use infra::{
     file_list::FileId,
 };
 use proto::cluster_rpc::{self, SearchQuery};
+use tokio::sync::mpsc;
 use tracing::{info_span, Instrument};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
@@ -62,6 +65,11 @@ use crate::{
     },
 };
 
+/// Receives the record batches of a streamed search as the plan produces them
+pub type RecordBatchSender = mpsc::Sender<Result<RecordBatch>>;
+
+/// Runs the search on the cluster, the record batches are collected, or sent to the sender as
+/// they are produced, in which case none are returned.
 #[async_recursion]
 #[tracing::instrument(
     name = "service:search:flight:leader",
@@ -73,6 +81,7 @@ pub async fn search(
     sql: Arc<Sql>,
     mut req: Request,
     query: SearchQuery,
+    sender: Option<RecordBatchSender>,
 ) -> Result<(Vec<RecordBatch>, ScanStats, usize, bool, usize, String)> {
     let start = std::time::Instant::now();
     let cfg = get_config();
@@ -257,6 +266,7 @@ pub async fn search(
             nodes,
             partitioned_file_lists,
             idx_file_list,
+            sender,
         )
         .instrument(datafusion_span)
         .await
@@ -322,6 +332,7 @@ pub async fn run_datafusion(
     nodes: Vec<Node>,
     partitioned_file_lists: HashMap<String, Vec<Vec<i64>>>,
     idx_file_list: Vec<FileKey>,
+    sender: Option<RecordBatchSender>,
 ) -> Result<(Vec<RecordBatch>, ScanStats, String)> {
     let cfg = get_config();
     let ctx = generate_context(&req, &sql, cfg.limit.cpu_num).await?;
@@ -395,7 +406,12 @@ pub async fn run_datafusion(
     }
 
     // run datafusion
-    let ret = datafusion::physical_plan::collect(physical_plan.clone(), ctx.task_ctx()).await;
+    let ret = match sender {
+        Some(sender) => send_batches(physical_plan.clone(), ctx.task_ctx(), sender)
+            .await
+            .map(|_| vec![]),
+        None => datafusion::physical_plan::collect(physical_plan.clone(), ctx.task_ctx()).await,
+    };
     let mut visit = ScanStatsVisitor::new();
     let _ = visit_execution_plan(physical_plan.as_ref(), &mut visit);
     if let Err(e) = ret {
@@ -408,6 +424,25 @@ pub async fn run_datafusion(
     }
 }
 
+/// Sends the record batches as the plan produces them, it stops when the receiver is dropped.
+async fn send_batches(
+    physical_plan: Arc<dyn ExecutionPlan>,
+    task_ctx: Arc<TaskContext>,
+    sender: RecordBatchSender,
+) -> datafusion::error::Result<()> {
+    let mut stream = execute_stream(physical_plan, task_ctx)?;
+    while let Some(batch) = stream.next().await {
+        let batch = batch?;
+        if batch.num_rows() == 0 {
+            continue;
+        }
+        if sender.send(Ok(batch)).await.is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
 pub async fn get_online_querier_nodes(
     trace_id: &str,
     node_group: Option<RoleGroup>,
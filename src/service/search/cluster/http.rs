@@ -73,10 +73,10 @@ pub async fn search(
         )
         .await
     } else {
-        flight::search(&trace_id, sql.clone(), req, query).await
+        flight::search(&trace_id, sql.clone(), req, query, None).await
     };
     #[cfg(not(feature = "enterprise"))]
-    let ret = flight::search(&trace_id, sql.clone(), req, query).await;
+    let ret = flight::search(&trace_id, sql.clone(), req, query, None).await;
 
     let (merge_batches, scan_stats, took_wait, is_partial, idx_took, partial_err) = match ret {
         Ok(v) => v,
@@ -0,0 +1,1245 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Elasticsearch `_search` compatibility, the subset of the query DSL used by Grafana and Kibana
+//! like clients is translated into SQL queries on the stream named by the index.
+//!
+//! Supported queries are `match_all`, `bool`, `term`, `terms`, `range`, `exists`, `prefix`,
+//! `wildcard`, `match`, `match_phrase` and `query_string`. Supported aggregations are
+//! `date_histogram` and `terms` with `avg`, `sum`, `min`, `max`, `value_count` and `cardinality`
+//! sub-aggregations, the metric aggregations are also supported at the top level.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use config::{
+    get_config,
+    meta::{search, stream::StreamType},
+    utils::{
+        json,
+        time::{parse_i64_to_timestamp_micros, parse_str_to_timestamp_micros},
+    },
+};
+use infra::errors::Error;
+use serde::Deserialize;
+
+/// Timestamp field of the ES clients, it is mapped to the timestamp column
+pub const ES_TIMESTAMP_FIELD: &str = "@timestamp";
+
+const MAX_HISTOGRAM_BUCKETS: i64 = 10_000;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchRequest {
+    #[serde(default)]
+    pub query: Option<json::Value>,
+    #[serde(default)]
+    pub from: Option<i64>,
+    #[serde(default)]
+    pub size: Option<i64>,
+    #[serde(default)]
+    pub sort: Option<json::Value>,
+    #[serde(default, alias = "aggregations")]
+    pub aggs: json::Map<String, json::Value>,
+}
+
+/// The query clauses of the supported query DSL
+const DSL_QUERIES: [&str; 12] = [
+    "match_all",
+    "match_none",
+    "bool",
+    "term",
+    "terms",
+    "range",
+    "exists",
+    "prefix",
+    "wildcard",
+    "match",
+    "match_phrase",
+    "query_string",
+];
+
+/// Returns true when the body of a `_search` request is clearly an ES one, the `query` is a
+/// query DSL clause or it has aggregations.
+pub fn is_dsl_request(body: &[u8]) -> bool {
+    let Ok(json::Value::Object(v)) = json::from_slice::<json::Value>(body) else {
+        return false;
+    };
+    if v.contains_key("aggs") || v.contains_key("aggregations") {
+        return true;
+    }
+    match v.get("query") {
+        Some(json::Value::Object(query)) => {
+            query.len() == 1 && query.keys().all(|k| DSL_QUERIES.contains(&k.as_str()))
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Aggregation {
+    DateHistogram {
+        interval: i64,
+        min_doc_count: i64,
+        bounds: Option<(i64, i64)>,
+    },
+    Terms,
+    Metrics,
+}
+
+#[derive(Debug)]
+struct AggregationQuery {
+    name: String,
+    kind: Aggregation,
+    metrics: Vec<String>,
+    sql: String,
+    size: i64,
+}
+
+/// The SQL queries of a search request.
+#[derive(Debug)]
+pub struct EsSearch {
+    stream_name: String,
+    start_time: i64,
+    end_time: i64,
+    from: i64,
+    size: i64,
+    hits_sql: String,
+    aggs: Vec<AggregationQuery>,
+}
+
+impl EsSearch {
+    /// Translates a search request on the stream `stream_name`.
+    pub fn new(stream_name: &str, req: &SearchRequest) -> Result<Self> {
+        let mut translator = Translator::new();
+        let condition = match &req.query {
+            Some(query) => translator.condition(query, true)?,
+            None => None,
+        };
+        let from_where = match &condition {
+            Some(condition) => format!("FROM {} WHERE {condition}", quote(stream_name)),
+            None => format!("FROM {}", quote(stream_name)),
+        };
+
+        let size = req.size.unwrap_or(10);
+        let hits_sql = if size > 0 {
+            format!(
+                "SELECT * {from_where} ORDER BY {}",
+                translator.order_by(req.sort.as_ref())?
+            )
+        } else {
+            format!("SELECT COUNT(*) AS \"count\" {from_where}")
+        };
+        let aggs = req
+            .aggs
+            .iter()
+            .map(|(name, agg)| translator.aggregation(name, agg, &from_where))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            stream_name: stream_name.to_string(),
+            start_time: translator.start_time.unwrap_or_default(),
+            end_time: translator
+                .end_time
+                .unwrap_or_else(|| Utc::now().timestamp_micros()),
+            from: req.from.unwrap_or_default(),
+            size,
+            hits_sql,
+            aggs,
+        })
+    }
+
+    fn request(&self, sql: &str, from: i64, size: i64, track_total_hits: bool) -> search::Request {
+        search::Request {
+            query: search::Query {
+                sql: sql.to_string(),
+                from,
+                size,
+                start_time: self.start_time,
+                end_time: self.end_time,
+                track_total_hits,
+                ..Default::default()
+            },
+            encoding: search::RequestEncoding::Empty,
+            regions: vec![],
+            clusters: vec![],
+            timeout: 0,
+            search_type: Some(search::SearchEventType::Other),
+            search_event_context: None,
+        }
+    }
+
+    /// Runs the queries and returns the response in the ES format.
+    pub async fn search(
+        &self,
+        trace_id: &str,
+        org_id: &str,
+        user_id: Option<String>,
+    ) -> Result<json::Value, Error> {
+        let start = std::time::Instant::now();
+        let cfg = get_config();
+
+        let (total, hits) = if self.size > 0 {
+            let req = self.request(&self.hits_sql, self.from, self.size, true);
+            let res =
+                super::search(trace_id, org_id, StreamType::Logs, user_id.clone(), &req).await?;
+            let hits = res
+                .hits
+                .into_iter()
+                .enumerate()
+                .map(|(i, hit)| self.hit(i, hit, &cfg.common.column_timestamp))
+                .collect::<Vec<_>>();
+            (res.total, hits)
+        } else {
+            let req = self.request(&self.hits_sql, 0, 1, false);
+            let res =
+                super::search(trace_id, org_id, StreamType::Logs, user_id.clone(), &req).await?;
+            let total = res
+                .hits
+                .first()
+                .and_then(|hit| hit.get("count"))
+                .and_then(|v| v.as_u64())
+                .unwrap_or_default();
+            (total as usize, vec![])
+        };
+
+        let mut aggregations = json::Map::new();
+        for agg in self.aggs.iter() {
+            let req = self.request(&agg.sql, 0, agg.size, false);
+            let res =
+                super::search(trace_id, org_id, StreamType::Logs, user_id.clone(), &req).await?;
+            aggregations.insert(agg.name.clone(), aggregation_response(agg, res.hits));
+        }
+
+        let mut res = json::json!({
+            "took": start.elapsed().as_millis() as u64,
+            "timed_out": false,
+            "_shards": {"total": 1, "successful": 1, "skipped": 0, "failed": 0},
+            "hits": {
+                "total": {"value": total, "relation": "eq"},
+                "max_score": null,
+                "hits": hits,
+            },
+        });
+        if !aggregations.is_empty() {
+            res["aggregations"] = json::Value::Object(aggregations);
+        }
+        Ok(res)
+    }
+
+    fn hit(&self, i: usize, mut hit: json::Value, ts_column: &str) -> json::Value {
+        let id = hit
+            .get("_id")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| (self.from as usize + i).to_string());
+        // the clients expect the timestamp field in the documents
+        if let Some(source) = hit.as_object_mut() {
+            if !source.contains_key(ES_TIMESTAMP_FIELD) {
+                if let Some(timestamp) = source.get(ts_column).and_then(|v| v.as_i64()) {
+                    source.insert(
+                        ES_TIMESTAMP_FIELD.to_string(),
+                        json::Value::String(format_timestamp(timestamp)),
+                    );
+                }
+            }
+        }
+        json::json!({
+            "_index": self.stream_name,
+            "_id": id,
+            "_score": null,
+            "_source": hit,
+        })
+    }
+}
+
+fn aggregation_response(agg: &AggregationQuery, rows: Vec<json::Value>) -> json::Value {
+    let bucket = |row: &json::Value, key: json::Value| {
+        let mut bucket = json::Map::new();
+        if let Aggregation::DateHistogram { .. } = agg.kind {
+            let key = key.as_i64().unwrap_or_default();
+            bucket.insert("key".to_string(), json::Value::from(key / 1000));
+            bucket.insert(
+                "key_as_string".to_string(),
+                json::Value::String(format_timestamp(key)),
+            );
+        } else {
+            bucket.insert("key".to_string(), key);
+        }
+        bucket.insert(
+            "doc_count".to_string(),
+            row.get("doc_count")
+                .cloned()
+                .unwrap_or_else(|| json::Value::from(0)),
+        );
+        for metric in agg.metrics.iter() {
+            let value = row.get(metric).cloned().unwrap_or(json::Value::Null);
+            bucket.insert(metric.clone(), json::json!({ "value": value }));
+        }
+        json::Value::Object(bucket)
+    };
+
+    match &agg.kind {
+        Aggregation::Metrics => {
+            let value = rows
+                .first()
+                .and_then(|row| row.get(&agg.name))
+                .cloned()
+                .unwrap_or(json::Value::Null);
+            json::json!({ "value": value })
+        }
+        Aggregation::Terms => {
+            let buckets = rows
+                .iter()
+                .map(|row| bucket(row, row.get("key").cloned().unwrap_or(json::Value::Null)))
+                .collect::<Vec<_>>();
+            json::json!({
+                "doc_count_error_upper_bound": 0,
+                "sum_other_doc_count": 0,
+                "buckets": buckets,
+            })
+        }
+        Aggregation::DateHistogram {
+            interval,
+            min_doc_count,
+            bounds,
+        } => {
+            let mut buckets = rows
+                .iter()
+                .filter_map(|row| {
+                    row.get("key")
+                        .and_then(|v| v.as_i64())
+                        .map(|key| (key, bucket(row, json::Value::from(key))))
+                })
+                .collect::<Vec<_>>();
+            if *min_doc_count == 0 {
+                buckets = fill_histogram(buckets, *interval, *bounds, |key| {
+                    bucket(&json::json!({"doc_count": 0}), json::Value::from(key))
+                });
+            }
+            json::json!({ "buckets": buckets.into_iter().map(|(_, b)| b).collect::<Vec<_>>() })
+        }
+    }
+}
+
+/// Adds the empty buckets of a histogram, from the extended bounds when set.
+fn fill_histogram(
+    buckets: Vec<(i64, json::Value)>,
+    interval: i64,
+    bounds: Option<(i64, i64)>,
+    empty: impl Fn(i64) -> json::Value,
+) -> Vec<(i64, json::Value)> {
+    let first = buckets.first().map(|(key, _)| *key);
+    let last = buckets.last().map(|(key, _)| *key);
+    let (min, max) = match (bounds, first, last) {
+        (Some((min, max)), Some(first), Some(last)) => (
+            first.min(min - min % interval),
+            last.max(max - max % interval),
+        ),
+        (Some((min, max)), ..) => (min - min % interval, max - max % interval),
+        (None, Some(first), Some(last)) => (first, last),
+        _ => return buckets,
+    };
+    if (max - min) / interval > MAX_HISTOGRAM_BUCKETS {
+        return buckets;
+    }
+
+    let mut buckets = buckets.into_iter().peekable();
+    let mut ret = Vec::new();
+    let mut key = min;
+    while key <= max {
+        match buckets.next_if(|(k, _)| *k == key) {
+            Some(bucket) => ret.push(bucket),
+            None => ret.push((key, empty(key))),
+        }
+        key += interval;
+    }
+    ret.extend(buckets);
+    ret
+}
+
+fn format_timestamp(micros: i64) -> String {
+    DateTime::from_timestamp_micros(micros)
+        .unwrap_or_default()
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn quote_str(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn sql_value(value: &json::Value) -> Result<String> {
+    match value {
+        json::Value::String(v) => Ok(quote_str(v)),
+        json::Value::Number(v) => Ok(v.to_string()),
+        json::Value::Bool(v) => Ok(v.to_string()),
+        v => Err(anyhow!("unsupported value: {v}")),
+    }
+}
+
+/// Returns the only key of a clause object, e.g. the field of `{"field": "value"}`.
+fn single_entry(value: &json::Value) -> Result<(&String, &json::Value)> {
+    value
+        .as_object()
+        .and_then(|v| v.iter().next())
+        .ok_or_else(|| anyhow!("malformed query, expected an object: {value}"))
+}
+
+struct Translator {
+    ts_column: String,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+}
+
+impl Translator {
+    fn new() -> Self {
+        Self {
+            ts_column: get_config().common.column_timestamp.clone(),
+            start_time: None,
+            end_time: None,
+        }
+    }
+
+    fn field(&self, name: &str) -> String {
+        let name = name.strip_suffix(".keyword").unwrap_or(name);
+        if name == ES_TIMESTAMP_FIELD {
+            quote(&self.ts_column)
+        } else {
+            quote(name)
+        }
+    }
+
+    fn is_timestamp(&self, name: &str) -> bool {
+        name == ES_TIMESTAMP_FIELD || name == self.ts_column
+    }
+
+    /// Translates a query clause into a SQL condition, none for `match_all`. The range of the
+    /// timestamp field narrows the time range of the search when the clause is `required`.
+    fn condition(&mut self, query: &json::Value, required: bool) -> Result<Option<String>> {
+        let (kind, body) = single_entry(query)?;
+        match kind.as_str() {
+            "match_all" => Ok(None),
+            "match_none" => Ok(Some("FALSE".to_string())),
+            "bool" => self.bool_condition(body, required),
+            "term" => {
+                let (field, value) = single_entry(body)?;
+                let value = value.get("value").unwrap_or(value);
+                Ok(Some(format!(
+                    "{} = {}",
+                    self.field(field),
+                    sql_value(value)?
+                )))
+            }
+            "terms" => {
+                let (field, values) = body
+                    .as_object()
+                    .and_then(|v| v.iter().find(|(k, _)| *k != "boost"))
+                    .ok_or_else(|| anyhow!("malformed terms query: {body}"))?;
+                let values = values
+                    .as_array()
+                    .ok_or_else(|| anyhow!("terms query expects an array: {body}"))?;
+                if values.is_empty() {
+                    return Ok(Some("FALSE".to_string()));
+                }
+                let values = values.iter().map(sql_value).collect::<Result<Vec<_>>>()?;
+                Ok(Some(format!(
+                    "{} IN ({})",
+                    self.field(field),
+                    values.join(", ")
+                )))
+            }
+            "range" => {
+                let (field, range) = single_entry(body)?;
+                self.range_condition(field, range, required)
+            }
+            "exists" => {
+                let field = body
+                    .get("field")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow!("malformed exists query: {body}"))?;
+                Ok(Some(format!("{} IS NOT NULL", self.field(field))))
+            }
+            "prefix" | "wildcard" => {
+                let (field, value) = single_entry(body)?;
+                let value = value
+                    .get("value")
+                    .or_else(|| value.get("wildcard"))
+                    .unwrap_or(value)
+                    .as_str()
+                    .ok_or_else(|| anyhow!("malformed {kind} query: {body}"))?;
+                let pattern = if kind == "prefix" {
+                    format!("{}%", escape_like(value))
+                } else {
+                    wildcard_to_like(value)
+                };
+                Ok(Some(format!(
+                    "{} LIKE {}",
+                    self.field(field),
+                    quote_str(&pattern)
+                )))
+            }
+            "match" | "match_phrase" => {
+                let (field, value) = single_entry(body)?;
+                let value = json::get_string_value(value.get("query").unwrap_or(value));
+                Ok(Some(format!(
+                    "str_match_ignore_case({}, {})",
+                    self.field(field),
+                    quote_str(&value)
+                )))
+            }
+            "query_string" => {
+                let query = body
+                    .get("query")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow!("malformed query_string query: {body}"))?;
+                let default_and = body
+                    .get("default_operator")
+                    .and_then(|v| v.as_str())
+                    .map(|v| v.eq_ignore_ascii_case("and"))
+                    .unwrap_or_default();
+                QueryStringParser::new(self, query, default_and).parse()
+            }
+            _ => Err(anyhow!("unsupported query: {kind}")),
+        }
+    }
+
+    fn bool_condition(&mut self, body: &json::Value, required: bool) -> Result<Option<String>> {
+        let clauses = |name: &str| -> Vec<&json::Value> {
+            match body.get(name) {
+                Some(json::Value::Array(v)) => v.iter().collect(),
+                Some(v) => vec![v],
+                None => vec![],
+            }
+        };
+
+        let mut conditions = Vec::new();
+        for clause in clauses("must").into_iter().chain(clauses("filter")) {
+            if let Some(condition) = self.condition(clause, required)? {
+                conditions.push(condition);
+            }
+        }
+
+        // should clauses only filter without must or filter clauses, unless a minimum is set
+        let should = clauses("should");
+        let minimum_should_match = body
+            .get("minimum_should_match")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(if conditions.is_empty() { 1 } else { 0 });
+        if !should.is_empty() && minimum_should_match > 0 {
+            let mut any = Vec::new();
+            for clause in should {
+                match self.condition(clause, false)? {
+                    Some(condition) => any.push(condition),
+                    None => {
+                        any.clear();
+                        break;
+                    }
+                }
+            }
+            if !any.is_empty() {
+                conditions.push(format!("({})", any.join(" OR ")));
+            }
+        }
+
+        for clause in clauses("must_not") {
+            let condition = self
+                .condition(clause, false)?
+                .unwrap_or_else(|| "TRUE".to_string());
+            conditions.push(format!("NOT ({condition})"));
+        }
+
+        Ok(match conditions.len() {
+            0 => None,
+            1 => conditions.pop(),
+            _ => Some(format!("({})", conditions.join(" AND "))),
+        })
+    }
+
+    fn range_condition(
+        &mut self,
+        field: &str,
+        range: &json::Value,
+        required: bool,
+    ) -> Result<Option<String>> {
+        let Some(range) = range.as_object() else {
+            return Err(anyhow!("malformed range query: {range}"));
+        };
+        let is_timestamp = self.is_timestamp(field);
+        let mut conditions = Vec::new();
+        for (op, value) in range.iter() {
+            let op = match op.as_str() {
+                "gt" => ">",
+                "gte" | "from" => ">=",
+                "lt" => "<",
+                "lte" | "to" => "<=",
+                _ => continue,
+            };
+            if value.is_null() {
+                continue;
+            }
+            let value = if is_timestamp {
+                let timestamp = parse_date(value)?;
+                if required {
+                    self.narrow_time_range(op, timestamp);
+                }
+                timestamp.to_string()
+            } else {
+                sql_value(value)?
+            };
+            conditions.push(format!("{} {op} {value}", self.field(field)));
+        }
+        Ok(match conditions.len() {
+            0 => None,
+            1 => conditions.pop(),
+            _ => Some(format!("({})", conditions.join(" AND "))),
+        })
+    }
+
+    fn narrow_time_range(&mut self, op: &str, timestamp: i64) {
+        match op {
+            ">" | ">=" => {
+                let start = if op == ">" { timestamp + 1 } else { timestamp };
+                self.start_time = Some(self.start_time.map_or(start, |v| v.max(start)));
+            }
+            _ => {
+                // the end time of the searches is exclusive
+                let end = if op == "<=" { timestamp + 1 } else { timestamp };
+                self.end_time = Some(self.end_time.map_or(end, |v| v.min(end)));
+            }
+        }
+    }
+
+    fn order_by(&self, sort: Option<&json::Value>) -> Result<String> {
+        let items = match sort {
+            Some(json::Value::Array(v)) => v.iter().collect(),
+            Some(v) => vec![v],
+            None => vec![],
+        };
+        let mut order_by = Vec::new();
+        for item in items {
+            let (field, order) = match item {
+                json::Value::String(field) => (field.as_str(), None),
+                item => {
+                    let (field, order) = single_entry(item)?;
+                    let order = order.get("order").unwrap_or(order).as_str();
+                    (field.as_str(), order)
+                }
+            };
+            if field == "_score" || field == "_doc" {
+                continue;
+            }
+            let desc = order
+                .map(|v| v.eq_ignore_ascii_case("desc"))
+                .unwrap_or_default();
+            order_by.push(format!(
+                "{} {}",
+                self.field(field),
+                if desc { "DESC" } else { "ASC" }
+            ));
+        }
+        if order_by.is_empty() {
+            order_by.push(format!("{} DESC", quote(&self.ts_column)));
+        }
+        Ok(order_by.join(", "))
+    }
+
+    fn metric(&self, kind: &str, body: &json::Value) -> Result<String> {
+        let field = body
+            .get("field")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("{kind} aggregation expects a field"))?;
+        let field = self.field(field);
+        match kind {
+            "avg" | "sum" | "min" | "max" => Ok(format!("{kind}({field})")),
+            "value_count" => Ok(format!("COUNT({field})")),
+            "cardinality" => Ok(format!("COUNT(DISTINCT {field})")),
+            _ => Err(anyhow!("unsupported aggregation: {kind}")),
+        }
+    }
+
+    fn aggregation(
+        &self,
+        name: &str,
+        agg: &json::Value,
+        from_where: &str,
+    ) -> Result<AggregationQuery> {
+        let Some(agg) = agg.as_object() else {
+            return Err(anyhow!("malformed aggregation: {name}"));
+        };
+        let (kind, body) = agg
+            .iter()
+            .find(|(k, _)| *k != "aggs" && *k != "aggregations" && *k != "meta")
+            .ok_or_else(|| anyhow!("malformed aggregation: {name}"))?;
+
+        let mut metrics = Vec::new();
+        let mut metric_columns = String::new();
+        if let Some(sub_aggs) = agg
+            .get("aggs")
+            .or_else(|| agg.get("aggregations"))
+            .and_then(|v| v.as_object())
+        {
+            for (sub_name, sub_agg) in sub_aggs {
+                let (sub_kind, sub_body) = single_entry(sub_agg)?;
+                let expr = self.metric(sub_kind, sub_body).map_err(|_| {
+                    anyhow!("unsupported sub-aggregation {sub_kind} of {name}, only metric aggregations are supported")
+                })?;
+                metric_columns.push_str(&format!(", {expr} AS {}", quote(sub_name)));
+                metrics.push(sub_name.to_string());
+            }
+        }
+
+        match kind.as_str() {
+            "date_histogram" => {
+                let field = body
+                    .get("field")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or(ES_TIMESTAMP_FIELD);
+                let interval = ["fixed_interval", "calendar_interval", "interval"]
+                    .iter()
+                    .find_map(|k| body.get(*k).and_then(|v| v.as_str()))
+                    .ok_or_else(|| anyhow!("date_histogram {name} expects an interval"))?;
+                let interval = parse_interval(interval)?;
+                let min_doc_count = body
+                    .get("min_doc_count")
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or(0);
+                let bounds = body.get("extended_bounds").and_then(|bounds| {
+                    let min = parse_date(bounds.get("min")?).ok()?;
+                    let max = parse_date(bounds.get("max")?).ok()?;
+                    Some((min, max))
+                });
+                let key = format!("({} / {interval}) * {interval}", self.field(field));
+                Ok(AggregationQuery {
+                    name: name.to_string(),
+                    kind: Aggregation::DateHistogram {
+                        interval,
+                        min_doc_count,
+                        bounds,
+                    },
+                    metrics,
+                    sql: format!(
+                        "SELECT {key} AS \"key\", COUNT(*) AS \"doc_count\"{metric_columns} {from_where} GROUP BY {key} ORDER BY \"key\" ASC"
+                    ),
+                    size: MAX_HISTOGRAM_BUCKETS,
+                })
+            }
+            "terms" => {
+                let field = body
+                    .get("field")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow!("terms aggregation {name} expects a field"))?;
+                let field = self.field(field);
+                let size = body.get("size").and_then(|v| v.as_i64()).unwrap_or(10);
+                let order = match body.get("order") {
+                    Some(order) => {
+                        let (key, order) = single_entry(order)?;
+                        let column = match key.as_str() {
+                            "_count" => "\"doc_count\"".to_string(),
+                            "_key" | "_term" => "\"key\"".to_string(),
+                            key if metrics.iter().any(|m| m == key) => quote(key),
+                            key => return Err(anyhow!("unsupported terms order: {key}")),
+                        };
+                        let desc = order
+                            .as_str()
+                            .map(|v| v.eq_ignore_ascii_case("desc"))
+                            .unwrap_or_default();
+                        format!("{column} {}", if desc { "DESC" } else { "ASC" })
+                    }
+                    None => "\"doc_count\" DESC".to_string(),
+                };
+                Ok(AggregationQuery {
+                    name: name.to_string(),
+                    kind: Aggregation::Terms,
+                    metrics,
+                    sql: format!(
+                        "SELECT {field} AS \"key\", COUNT(*) AS \"doc_count\"{metric_columns} {from_where} GROUP BY {field} ORDER BY {order}"
+                    ),
+                    size,
+                })
+            }
+            kind => {
+                if !metrics.is_empty() {
+                    return Err(anyhow!(
+                        "{kind} aggregation {name} can't have sub-aggregations"
+                    ));
+                }
+                let expr = self.metric(kind, body)?;
+                Ok(AggregationQuery {
+                    name: name.to_string(),
+                    kind: Aggregation::Metrics,
+                    metrics: vec![],
+                    sql: format!("SELECT {expr} AS {} {from_where}", quote(name)),
+                    size: 1,
+                })
+            }
+        }
+    }
+}
+
+/// Parses a date of a range query to microseconds, e.g. epoch milliseconds, RFC3339 or date math
+/// like `now-15m`.
+fn parse_date(value: &json::Value) -> Result<i64> {
+    match value {
+        json::Value::Number(v) => v
+            .as_i64()
+            .or_else(|| v.as_f64().map(|v| v as i64))
+            .map(parse_i64_to_timestamp_micros)
+            .ok_or_else(|| anyhow!("invalid date: {v}")),
+        json::Value::String(v) => match v.strip_prefix("now") {
+            Some(math) => {
+                let now = Utc::now().timestamp_micros();
+                // rounding, e.g. `now/d`, is ignored
+                let math = math.split('/').next().unwrap_or_default();
+                if math.is_empty() {
+                    return Ok(now);
+                }
+                let (sign, duration) = math.split_at(1);
+                let duration = parse_interval(duration)?;
+                match sign {
+                    "-" => Ok(now - duration),
+                    "+" => Ok(now + duration),
+                    _ => Err(anyhow!("invalid date math: {v}")),
+                }
+            }
+            None => parse_str_to_timestamp_micros(v),
+        },
+        v => Err(anyhow!("invalid date: {v}")),
+    }
+}
+
+/// Parses an interval, e.g. `30s`, `5m` or `1d`, to microseconds.
+fn parse_interval(interval: &str) -> Result<i64> {
+    let interval = interval.trim();
+    let calendar = match interval {
+        "second" => Some("1s"),
+        "minute" => Some("1m"),
+        "hour" => Some("1h"),
+        "day" => Some("1d"),
+        "week" => Some("1w"),
+        _ => None,
+    };
+    let interval = calendar.unwrap_or(interval);
+    let pos = interval
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| anyhow!("invalid interval: {interval}"))?;
+    let (value, unit) = interval.split_at(pos);
+    let value = value
+        .parse::<i64>()
+        .map_err(|_| anyhow!("invalid interval: {interval}"))?;
+    let duration = match unit {
+        "ms" => Duration::try_milliseconds(value),
+        "s" => Duration::try_seconds(value),
+        "m" => Duration::try_minutes(value),
+        "h" => Duration::try_hours(value),
+        "d" => Duration::try_days(value),
+        "w" => Duration::try_weeks(value),
+        _ => None,
+    };
+    duration
+        .and_then(|v| v.num_microseconds())
+        .filter(|v| *v > 0)
+        .ok_or_else(|| anyhow!("unsupported interval: {interval}"))
+}
+
+fn escape_like(value: &str) -> String {
+    value.replace('%', "\\%").replace('_', "\\_")
+}
+
+fn wildcard_to_like(value: &str) -> String {
+    escape_like(value).replace('*', "%").replace('?', "_")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term {
+        field: Option<String>,
+        value: String,
+        quoted: bool,
+    },
+}
+
+/// Parser of the Lucene syntax subset of the `query_string` queries: terms, `field:value`,
+/// quoted phrases, `*` and `?` wildcards, `AND`/`OR`/`NOT` and parentheses.
+struct QueryStringParser<'a> {
+    translator: &'a Translator,
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+    default_and: bool,
+}
+
+impl<'a> QueryStringParser<'a> {
+    fn new(translator: &'a Translator, query: &str, default_and: bool) -> Self {
+        Self {
+            translator,
+            tokens: tokenize(query).into_iter().peekable(),
+            default_and,
+        }
+    }
+
+    fn parse(mut self) -> Result<Option<String>> {
+        if self.tokens.peek().is_none() {
+            return Ok(None);
+        }
+        let condition = self.or()?;
+        match self.tokens.next() {
+            None => Ok(condition),
+            Some(token) => Err(anyhow!("unexpected {token:?} in query_string")),
+        }
+    }
+
+    fn or(&mut self) -> Result<Option<String>> {
+        let mut condition = self.and()?;
+        while self.tokens.next_if_eq(&Token::Or).is_some() {
+            let right = self.and()?;
+            condition = combine(condition, right, "OR");
+        }
+        Ok(condition)
+    }
+
+    fn and(&mut self) -> Result<Option<String>> {
+        let mut condition = self.unary()?;
+        loop {
+            let op = match self.tokens.peek() {
+                Some(Token::And) => {
+                    self.tokens.next();
+                    "AND"
+                }
+                Some(Token::Or) | Some(Token::RParen) | None => break,
+                Some(_) if self.default_and => "AND",
+                Some(_) => "OR",
+            };
+            let right = self.unary()?;
+            condition = combine(condition, right, op);
+        }
+        Ok(condition)
+    }
+
+    fn unary(&mut self) -> Result<Option<String>> {
+        if self.tokens.next_if_eq(&Token::Not).is_some() {
+            let condition = self.unary()?.unwrap_or_else(|| "TRUE".to_string());
+            return Ok(Some(format!("NOT ({condition})")));
+        }
+        match self.tokens.next() {
+            Some(Token::LParen) => {
+                let condition = self.or()?;
+                if self.tokens.next() != Some(Token::RParen) {
+                    return Err(anyhow!("missing closing parenthesis in query_string"));
+                }
+                Ok(condition.map(|v| format!("({v})")))
+            }
+            Some(Token::Term {
+                field,
+                value,
+                quoted,
+            }) => Ok(self.term(field.as_deref(), &value, quoted)),
+            Some(token) => Err(anyhow!("unexpected {token:?} in query_string")),
+            None => Err(anyhow!("unexpected end of query_string")),
+        }
+    }
+
+    fn term(&self, field: Option<&str>, value: &str, quoted: bool) -> Option<String> {
+        let has_wildcard = !quoted && (value.contains('*') || value.contains('?'));
+        match field {
+            None | Some("*") if value == "*" => None,
+            None => Some(format!("match_all({})", quote_str(value))),
+            Some(field) if value == "*" => {
+                Some(format!("{} IS NOT NULL", self.translator.field(field)))
+            }
+            Some(field) if has_wildcard => Some(format!(
+                "{} LIKE {}",
+                self.translator.field(field),
+                quote_str(&wildcard_to_like(value))
+            )),
+            Some(field) if quoted => Some(format!(
+                "str_match({}, {})",
+                self.translator.field(field),
+                quote_str(value)
+            )),
+            Some(field) => Some(format!(
+                "{} = {}",
+                self.translator.field(field),
+                quote_str(value)
+            )),
+        }
+    }
+}
+
+fn combine(left: Option<String>, right: Option<String>, op: &str) -> Option<String> {
+    match (left, right) {
+        (Some(left), Some(right)) => Some(format!("{left} {op} {right}")),
+        // `*` matches everything
+        (None, _) | (_, None) if op == "OR" => None,
+        (left, right) => left.or(right),
+    }
+}
+
+fn tokenize(query: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            _ => {
+                let mut word = String::new();
+                let mut quoted = false;
+                let mut quote_start = 0;
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    chars.next();
+                    match c {
+                        '"' => {
+                            if !quoted {
+                                quoted = true;
+                                quote_start = word.len();
+                            }
+                            while let Some(c) = chars.next() {
+                                match c {
+                                    '"' => break,
+                                    '\\' => word.extend(chars.next()),
+                                    c => word.push(c),
+                                }
+                            }
+                        }
+                        '\\' => word.extend(chars.next()),
+                        c => word.push(c),
+                    }
+                }
+                if quoted {
+                    // the field, if any, is before the quoted value
+                    let (field, value) = word.split_at(quote_start);
+                    let field = field
+                        .strip_suffix(':')
+                        .filter(|v| !v.is_empty())
+                        .map(|v| v.to_string());
+                    let value = value.to_string();
+                    tokens.push(Token::Term {
+                        field,
+                        value,
+                        quoted,
+                    });
+                    continue;
+                }
+                match word.as_str() {
+                    "AND" | "&&" => tokens.push(Token::And),
+                    "OR" | "||" => tokens.push(Token::Or),
+                    "NOT" | "!" => tokens.push(Token::Not),
+                    _ => {
+                        let word = match word.strip_prefix(['-', '!']) {
+                            Some(word) if !word.is_empty() => {
+                                tokens.push(Token::Not);
+                                word
+                            }
+                            _ => word.strip_prefix('+').unwrap_or(&word),
+                        };
+                        let (field, value) = match word.split_once(':') {
+                            Some((field, value)) if !field.is_empty() && !value.is_empty() => {
+                                (Some(field.to_string()), value.to_string())
+                            }
+                            _ => (None, word.to_string()),
+                        };
+                        tokens.push(Token::Term {
+                            field,
+                            value,
+                            quoted,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translate(req: json::Value) -> EsSearch {
+        let req: SearchRequest = json::from_value(req).unwrap();
+        EsSearch::new("logs", &req).unwrap()
+    }
+
+    #[test]
+    fn test_is_dsl_request() {
+        assert!(is_dsl_request(br#"{"query": {"match_all": {}}}"#));
+        assert!(is_dsl_request(br#"{"size": 0, "aggs": {}}"#));
+        assert!(!is_dsl_request(b""));
+        assert!(!is_dsl_request(br#"{}"#));
+        assert!(!is_dsl_request(
+            br#"{"query": {"sql": "select * from logs"}}"#
+        ));
+        // an invalid search request isn't taken for a DSL one
+        assert!(!is_dsl_request(
+            br#"{"query": {"sql": "select * from logs", "start_time": "now"}}"#
+        ));
+        assert!(!is_dsl_request(br#"{"query": {"unknown": {}}}"#));
+        assert!(!is_dsl_request(b"not json"));
+    }
+
+    #[test]
+    fn test_translate_bool_query() {
+        let search = translate(json::json!({
+            "size": 20,
+            "query": {"bool": {
+                "filter": [
+                    {"range": {"@timestamp": {"gte": 1700000000000_i64, "lte": 1700003600000_i64, "format": "epoch_millis"}}},
+                    {"term": {"level.keyword": "error"}},
+                    {"terms": {"status": [500, 503]}}
+                ],
+                "must_not": [{"exists": {"field": "ignored"}}],
+                "should": [{"match": {"message": "timeout"}}]
+            }},
+            "sort": [{"@timestamp": {"order": "desc"}}]
+        }));
+        assert_eq!(search.start_time, 1700000000000000);
+        assert_eq!(search.end_time, 1700003600000001);
+        assert_eq!(search.size, 20);
+        assert_eq!(
+            search.hits_sql,
+            "SELECT * FROM \"logs\" WHERE ((\"_timestamp\" >= 1700000000000000 AND \"_timestamp\" <= 1700003600000000) AND \"level\" = 'error' AND \"status\" IN (500, 503) AND NOT (\"ignored\" IS NOT NULL)) ORDER BY \"_timestamp\" DESC"
+        );
+    }
+
+    #[test]
+    fn test_translate_query_string() {
+        let search = translate(json::json!({
+            "query": {"query_string": {"query": "level:error AND (host:web-* OR \"connection refused\") NOT user:\"o'brien\""}}
+        }));
+        assert_eq!(
+            search.hits_sql,
+            "SELECT * FROM \"logs\" WHERE \"level\" = 'error' AND (\"host\" LIKE 'web-%' OR match_all('connection refused')) OR NOT (str_match(\"user\", 'o''brien')) ORDER BY \"_timestamp\" DESC"
+        );
+
+        let search = translate(json::json!({"query": {"query_string": {"query": "*"}}}));
+        assert_eq!(
+            search.hits_sql,
+            "SELECT * FROM \"logs\" ORDER BY \"_timestamp\" DESC"
+        );
+
+        let req: SearchRequest =
+            json::from_value(json::json!({"query": {"query_string": {"query": "(a OR b"}}}))
+                .unwrap();
+        assert!(EsSearch::new("logs", &req).is_err());
+    }
+
+    #[test]
+    fn test_translate_aggregations() {
+        let search = translate(json::json!({
+            "size": 0,
+            "aggs": {
+                "2": {
+                    "date_histogram": {"field": "@timestamp", "fixed_interval": "30s", "min_doc_count": 0},
+                    "aggs": {"1": {"avg": {"field": "took"}}}
+                },
+                "hosts": {"terms": {"field": "host.keyword", "size": 5, "order": {"_key": "asc"}}},
+                "users": {"cardinality": {"field": "user"}}
+            }
+        }));
+        assert_eq!(
+            search.hits_sql,
+            "SELECT COUNT(*) AS \"count\" FROM \"logs\""
+        );
+        assert_eq!(search.aggs.len(), 3);
+        let histogram = search.aggs.iter().find(|a| a.name == "2").unwrap();
+        assert_eq!(
+            histogram.sql,
+            "SELECT (\"_timestamp\" / 30000000) * 30000000 AS \"key\", COUNT(*) AS \"doc_count\", avg(\"took\") AS \"1\" FROM \"logs\" GROUP BY (\"_timestamp\" / 30000000) * 30000000 ORDER BY \"key\" ASC"
+        );
+        let terms = search.aggs.iter().find(|a| a.name == "hosts").unwrap();
+        assert_eq!(terms.size, 5);
+        assert_eq!(
+            terms.sql,
+            "SELECT \"host\" AS \"key\", COUNT(*) AS \"doc_count\" FROM \"logs\" GROUP BY \"host\" ORDER BY \"key\" ASC"
+        );
+        let users = search.aggs.iter().find(|a| a.name == "users").unwrap();
+        assert_eq!(
+            users.sql,
+            "SELECT COUNT(DISTINCT \"user\") AS \"users\" FROM \"logs\""
+        );
+
+        let req: SearchRequest = json::from_value(json::json!({
+            "aggs": {"a": {"terms": {"field": "x"}, "aggs": {"b": {"terms": {"field": "y"}}}}}
+        }))
+        .unwrap();
+        assert!(EsSearch::new("logs", &req).is_err());
+    }
+
+    #[test]
+    fn test_aggregation_response() {
+        let agg = AggregationQuery {
+            name: "2".to_string(),
+            kind: Aggregation::DateHistogram {
+                interval: 30_000_000,
+                min_doc_count: 0,
+                bounds: None,
+            },
+            metrics: vec!["1".to_string()],
+            sql: String::new(),
+            size: 0,
+        };
+        let res = aggregation_response(
+            &agg,
+            vec![
+                json::json!({"key": 1700000010000000_i64, "doc_count": 3, "1": 1.5}),
+                json::json!({"key": 1700000070000000_i64, "doc_count": 1, "1": 2.0}),
+            ],
+        );
+        let buckets = res["buckets"].as_array().unwrap();
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(buckets[0]["key"], 1700000010000_i64);
+        assert_eq!(buckets[0]["key_as_string"], "2023-11-14T22:13:30.000Z");
+        assert_eq!(buckets[0]["1"]["value"], 1.5);
+        assert_eq!(buckets[1]["doc_count"], 0);
+        assert_eq!(buckets[2]["doc_count"], 1);
+    }
+
+    #[test]
+    fn test_parse_date_and_interval() {
+        assert_eq!(
+            parse_date(&json::json!(1700000000000_i64)).unwrap(),
+            1700000000000000
+        );
+        assert_eq!(
+            parse_date(&json::json!("2023-11-14T22:13:20Z")).unwrap(),
+            1700000000000000
+        );
+        let now = Utc::now().timestamp_micros();
+        let v = parse_date(&json::json!("now-15m")).unwrap();
+        assert!((now - 900_000_000 - v).abs() < 5_000_000);
+        assert_eq!(parse_interval("30s").unwrap(), 30_000_000);
+        assert_eq!(parse_interval("hour").unwrap(), 3_600_000_000);
+        assert_eq!(parse_interval("500ms").unwrap(), 500_000);
+        assert!(parse_interval("1M").is_err());
+        assert!(parse_interval("abc").is_err());
+    }
+}
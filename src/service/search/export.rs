@@ -0,0 +1,527 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Export of search results as CSV, Parquet or Arrow IPC files. The file is written as the
+//! record batches are received and its chunks are either streamed in the response or uploaded
+//! into the object storage under `exports/{org_id}/`, to be downloaded with a link valid for
+//! `ZO_EXPORT_LINK_TTL` hours. The link doesn't need credentials, the expiration time and a random
+//! token are part of the file name.
+
+use std::str::FromStr;
+
+use arrow::{array::RecordBatch, csv::WriterBuilder, ipc::writer::FileWriter};
+use bytes::Bytes;
+use chrono::{Duration, Utc};
+use config::{
+    get_config,
+    meta::{
+        search,
+        stream::{FileMeta, StreamType},
+    },
+    utils::{parquet::new_parquet_writer, rand::generate_random_string},
+};
+use futures::{future::BoxFuture, stream::BoxStream};
+use infra::storage;
+use object_store::WriteMultipart;
+use parquet::{arrow::async_writer::AsyncFileWriter, errors::ParquetError};
+use serde::Serialize;
+use tokio::sync::mpsc;
+use utoipa::ToSchema;
+
+use crate::service::db;
+
+const EXPORTS_PREFIX: &str = "exports";
+const TOKEN_LEN: usize = 32;
+/// Number of chunks buffered between the writer and the response or the upload
+const CHANNEL_SIZE: usize = 2;
+/// Number of parts uploaded concurrently into the object storage
+const UPLOAD_CONCURRENCY: usize = 4;
+
+/// Receives the chunks of the exported file, ends with an error if the export fails.
+pub type ChunkReceiver = mpsc::Receiver<Result<Bytes, anyhow::Error>>;
+type ChunkSender = mpsc::Sender<Result<Bytes, anyhow::Error>>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+    /// Arrow IPC file format
+    Arrow,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "parquet" => Ok(ExportFormat::Parquet),
+            "arrow" => Ok(ExportFormat::Arrow),
+            _ => Err(format!(
+                "Invalid export format: {s}, supported formats are csv, parquet and arrow"
+            )),
+        }
+    }
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Arrow => "arrow",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+            ExportFormat::Arrow => "application/vnd.apache.arrow.file",
+        }
+    }
+
+    fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            "csv" => Some(ExportFormat::Csv),
+            "parquet" => Some(ExportFormat::Parquet),
+            "arrow" => Some(ExportFormat::Arrow),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Writes the column names as the first line
+    pub header: bool,
+    pub delimiter: u8,
+    pub quote: u8,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            header: true,
+            delimiter: b',',
+            quote: b'"',
+        }
+    }
+}
+
+/// The exported file exceeds the size limit of the organization.
+#[derive(Debug)]
+pub struct ExportTooLarge {
+    /// in MB
+    pub limit: usize,
+}
+
+impl std::fmt::Display for ExportTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "The exported file exceeds the limit of {} MB, please narrow down the query",
+            self.limit
+        )
+    }
+}
+
+impl std::error::Error for ExportTooLarge {}
+
+/// Link to an exported file in the object storage.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct ExportLink {
+    pub url: String,
+    /// in bytes
+    pub size: usize,
+    /// in microseconds
+    pub expires_at: i64,
+}
+
+/// Runs the search and returns the chunks of the exported file, which is written in the
+/// background. An empty result gives an empty file.
+pub async fn export(
+    trace_id: &str,
+    org_id: &str,
+    stream_type: StreamType,
+    user_id: Option<String>,
+    req: &search::Request,
+    format: ExportFormat,
+    csv_options: &CsvOptions,
+) -> Result<ChunkReceiver, anyhow::Error> {
+    let max_size = max_size(org_id).await;
+    let rx = super::stream::search(trace_id, org_id, stream_type, user_id, req).await?;
+    let (tx, chunks) = mpsc::channel(CHANNEL_SIZE);
+    let csv_options = csv_options.clone();
+    tokio::task::spawn(async move {
+        if let Err(e) = write(rx, format, &csv_options, max_size, &tx).await {
+            let _ = tx.send(Err(e)).await;
+        }
+    });
+    Ok(chunks)
+}
+
+/// Uploads the chunks of the exported file into the object storage and returns its download
+/// link, the upload is aborted if the export fails.
+pub async fn upload(
+    org_id: &str,
+    format: ExportFormat,
+    mut chunks: ChunkReceiver,
+) -> Result<ExportLink, anyhow::Error> {
+    let cfg = get_config();
+    let expires_at = Utc::now() + Duration::try_hours(cfg.limit.export_link_ttl).unwrap();
+    let file_name = format!(
+        "{}_{}.{}",
+        expires_at.timestamp(),
+        generate_random_string(TOKEN_LEN),
+        format.extension()
+    );
+    let key = file_key(org_id, &file_name);
+    let upload = storage::DEFAULT.put_multipart(&key.as_str().into()).await?;
+    let mut writer = WriteMultipart::new(upload);
+    let mut size = 0;
+    while let Some(chunk) = chunks.recv().await {
+        let ret = match chunk {
+            Ok(chunk) => match writer.wait_for_capacity(UPLOAD_CONCURRENCY).await {
+                Ok(()) => {
+                    size += chunk.len();
+                    writer.write(&chunk);
+                    Ok(())
+                }
+                Err(e) => Err(e.into()),
+            },
+            Err(e) => Err(e),
+        };
+        if let Err(e) = ret {
+            if let Err(e) = writer.abort().await {
+                log::error!("[EXPORT] Error while aborting the upload of {key}: {e}");
+            }
+            return Err(e);
+        }
+    }
+    writer.finish().await?;
+    Ok(ExportLink {
+        url: format!(
+            "{}{}/exports/{org_id}/{file_name}",
+            cfg.common.web_url, cfg.common.base_uri
+        ),
+        size,
+        expires_at: expires_at.timestamp_micros(),
+    })
+}
+
+/// The format, size and content of an exported file.
+pub type ExportFile = (
+    ExportFormat,
+    usize,
+    BoxStream<'static, object_store::Result<Bytes>>,
+);
+
+/// Returns the exported file streamed from the object storage, `None` when the link is invalid
+/// or expired.
+pub async fn download(org_id: &str, file_name: &str) -> Result<Option<ExportFile>, anyhow::Error> {
+    let Some((expires_at, format)) = parse_file_name(file_name) else {
+        return Ok(None);
+    };
+    if expires_at <= Utc::now().timestamp() {
+        return Ok(None);
+    }
+    let key = file_key(org_id, file_name);
+    match storage::DEFAULT.get(&key.as_str().into()).await {
+        Ok(ret) => Ok(Some((format, ret.meta.size, ret.into_stream()))),
+        Err(object_store::Error::NotFound { .. }) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Deletes the expired exported files.
+pub async fn cleanup() -> Result<(), anyhow::Error> {
+    let now = Utc::now().timestamp();
+    let files = storage::list(&format!("{EXPORTS_PREFIX}/")).await?;
+    let expired = files
+        .iter()
+        .filter(|file| {
+            file.rsplit('/')
+                .next()
+                .and_then(parse_file_name)
+                .map_or(true, |(expires_at, _)| expires_at <= now)
+        })
+        .map(|file| file.as_str())
+        .collect::<Vec<_>>();
+    if expired.is_empty() {
+        return Ok(());
+    }
+    storage::del(&expired).await?;
+    log::info!("[EXPORT] Deleted {} expired exported files", expired.len());
+    Ok(())
+}
+
+/// Returns the size limit in MB of the exported files of the organization.
+async fn max_size(org_id: &str) -> usize {
+    let limit = get_config().limit.export_max_size;
+    match db::organization::get_org_setting(org_id).await {
+        Ok(setting) if setting.export_max_size > 0 => setting.export_max_size.min(limit),
+        _ => limit,
+    }
+}
+
+/// Writes the received record batches into a file whose chunks are sent as they are written,
+/// stops as soon as the file exceeds `max_size` MB.
+async fn write(
+    mut rx: mpsc::Receiver<infra::errors::Result<RecordBatch>>,
+    format: ExportFormat,
+    csv_options: &CsvOptions,
+    max_size: usize,
+    tx: &ChunkSender,
+) -> Result<(), anyhow::Error> {
+    let Some(first) = rx.recv().await else {
+        return Ok(());
+    };
+    let mut batch = first?;
+    let schema = batch.schema();
+    let check_size = |size: usize| {
+        if size > max_size * 1024 * 1024 {
+            Err(ExportTooLarge { limit: max_size })
+        } else {
+            Ok(())
+        }
+    };
+    let mut size = 0;
+
+    match format {
+        ExportFormat::Csv => {
+            let mut header = csv_options.header;
+            loop {
+                // the header is only written before the first batch
+                let mut buf = Vec::new();
+                let mut writer = WriterBuilder::new()
+                    .with_header(header)
+                    .with_delimiter(csv_options.delimiter)
+                    .with_quote(csv_options.quote)
+                    .build(&mut buf);
+                writer.write(&batch)?;
+                drop(writer);
+                header = false;
+                size += buf.len();
+                check_size(size)?;
+                send_chunk(tx, buf).await?;
+                match rx.recv().await {
+                    Some(v) => batch = v?,
+                    None => break,
+                }
+            }
+        }
+        ExportFormat::Parquet => {
+            let meta = FileMeta::default();
+            let mut writer = new_parquet_writer(ChunkWriter(tx.clone()), &schema, &[], &meta);
+            loop {
+                writer.write(&batch).await?;
+                check_size(writer.bytes_written() + writer.in_progress_size())?;
+                match rx.recv().await {
+                    Some(v) => batch = v?,
+                    None => break,
+                }
+            }
+            writer.close().await?;
+        }
+        ExportFormat::Arrow => {
+            let mut writer = FileWriter::try_new(Vec::new(), &schema)?;
+            loop {
+                writer.write(&batch)?;
+                let buf = std::mem::take(writer.get_mut());
+                size += buf.len();
+                check_size(size)?;
+                send_chunk(tx, buf).await?;
+                match rx.recv().await {
+                    Some(v) => batch = v?,
+                    None => break,
+                }
+            }
+            writer.finish()?;
+            send_chunk(tx, std::mem::take(writer.get_mut())).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn send_chunk(tx: &ChunkSender, chunk: Vec<u8>) -> Result<(), anyhow::Error> {
+    tx.send(Ok(chunk.into()))
+        .await
+        .map_err(|_| anyhow::anyhow!("The export was aborted"))
+}
+
+/// Sends the bytes written by the parquet writer as chunks of the exported file.
+struct ChunkWriter(ChunkSender);
+
+impl AsyncFileWriter for ChunkWriter {
+    fn write(&mut self, bs: Bytes) -> BoxFuture<'_, parquet::errors::Result<()>> {
+        Box::pin(async move {
+            self.0
+                .send(Ok(bs))
+                .await
+                .map_err(|_| ParquetError::General("The export was aborted".to_string()))
+        })
+    }
+
+    fn complete(&mut self) -> BoxFuture<'_, parquet::errors::Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+fn file_key(org_id: &str, file_name: &str) -> String {
+    format!("{EXPORTS_PREFIX}/{org_id}/{file_name}")
+}
+
+/// Parses `{expires_at}_{token}.{ext}`, the expiration time is in seconds.
+fn parse_file_name(file_name: &str) -> Option<(i64, ExportFormat)> {
+    let (name, ext) = file_name.rsplit_once('.')?;
+    let (expires_at, token) = name.split_once('_')?;
+    if token.len() != TOKEN_LEN || !token.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some((expires_at.parse().ok()?, ExportFormat::from_extension(ext)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, sync::Arc};
+
+    use arrow::{
+        array::{Int64Array, StringArray},
+        ipc::reader::FileReader,
+    };
+    use arrow_schema::{DataType, Field, Schema};
+    use config::utils::parquet::read_recordbatch_from_bytes;
+
+    use super::*;
+
+    fn new_batch(start: i64) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("_timestamp", DataType::Int64, false),
+            Field::new("log", DataType::Utf8, true),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![start, start + 1])),
+                Arc::new(StringArray::from(vec![Some("a;\"b\""), None])),
+            ],
+        )
+        .unwrap()
+    }
+
+    async fn write_batches(
+        format: ExportFormat,
+        csv_options: &CsvOptions,
+        max_size: usize,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        let (tx, rx) = mpsc::channel(2);
+        tx.send(Ok(new_batch(1))).await.unwrap();
+        tx.send(Ok(new_batch(3))).await.unwrap();
+        drop(tx);
+        write_file(rx, format, csv_options, max_size).await
+    }
+
+    /// Writes the record batches and collects the chunks of the file.
+    async fn write_file(
+        rx: mpsc::Receiver<infra::errors::Result<RecordBatch>>,
+        format: ExportFormat,
+        csv_options: &CsvOptions,
+        max_size: usize,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        let (tx, mut chunks) = mpsc::channel(CHANNEL_SIZE);
+        let (ret, data) = tokio::join!(
+            async move { write(rx, format, csv_options, max_size, &tx).await },
+            async {
+                let mut data = Vec::new();
+                while let Some(chunk) = chunks.recv().await {
+                    data.extend_from_slice(&chunk?);
+                }
+                Ok::<_, anyhow::Error>(data)
+            }
+        );
+        ret?;
+        data
+    }
+
+    #[tokio::test]
+    async fn test_write_csv() {
+        let data = write_batches(ExportFormat::Csv, &CsvOptions::default(), 1)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::str::from_utf8(&data).unwrap(),
+            "_timestamp,log\n1,\"a;\"\"b\"\"\"\n2,\n3,\"a;\"\"b\"\"\"\n4,\n"
+        );
+
+        let options = CsvOptions {
+            header: false,
+            delimiter: b';',
+            quote: b'\'',
+        };
+        let data = write_batches(ExportFormat::Csv, &options, 1).await.unwrap();
+        assert_eq!(
+            std::str::from_utf8(&data).unwrap(),
+            "1;'a;\"b\"'\n2;\n3;'a;\"b\"'\n4;\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_parquet() {
+        let data = write_batches(ExportFormat::Parquet, &CsvOptions::default(), 1)
+            .await
+            .unwrap();
+        let (_, batches) = read_recordbatch_from_bytes(&data.into()).await.unwrap();
+        let rows = batches.iter().map(|b| b.num_rows()).sum::<usize>();
+        assert_eq!(rows, 4);
+    }
+
+    #[tokio::test]
+    async fn test_write_arrow() {
+        let data = write_batches(ExportFormat::Arrow, &CsvOptions::default(), 1)
+            .await
+            .unwrap();
+        let reader = FileReader::try_new(Cursor::new(data), None).unwrap();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(batches, vec![new_batch(1), new_batch(3)]);
+    }
+
+    #[tokio::test]
+    async fn test_write_too_large() {
+        let (tx, rx) = mpsc::channel(2);
+        let handle = tokio::spawn(async move {
+            let batch = new_batch(1);
+            while tx.send(Ok(batch.clone())).await.is_ok() {}
+        });
+        let err = write_file(rx, ExportFormat::Csv, &CsvOptions::default(), 1)
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<ExportTooLarge>().is_some());
+        // the search stops once the receiver is dropped
+        handle.await.unwrap();
+    }
+
+    #[test]
+    fn test_parse_file_name() {
+        let token = "a".repeat(TOKEN_LEN);
+        assert_eq!(
+            parse_file_name(&format!("1700000000_{token}.csv")),
+            Some((1700000000, ExportFormat::Csv))
+        );
+        assert_eq!(parse_file_name(&format!("1700000000_{token}.json")), None);
+        assert_eq!(parse_file_name("1700000000_short.csv"), None);
+        assert_eq!(parse_file_name(&format!("never_{token}.arrow")), None);
+    }
+}
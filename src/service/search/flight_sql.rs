@@ -0,0 +1,308 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Queries of the Arrow Flight SQL clients. Each organization is a catalog whose schemas are the
+//! stream types, so a stream is addressed as `"default"`, `logs."default"` or
+//! `"org"."logs"."default"`, the tables without schema being logs streams. The searched time
+//! range comes from the `_timestamp` conditions of the query, the last
+//! `ZO_FLIGHT_SQL_DEFAULT_TIME_RANGE` hours by default.
+
+use std::{collections::HashSet, ops::ControlFlow, sync::Arc};
+
+use arrow::array::RecordBatch;
+use arrow_schema::SchemaRef;
+use chrono::Utc;
+use config::{
+    get_config,
+    meta::{search, sql::Sql as MetaSql, stream::StreamType},
+};
+use datafusion::{common::TableReference, datasource::MemTable, prelude::SessionContext};
+use infra::errors;
+use sqlparser::{
+    ast::{visit_relations_mut, ObjectName, Query, Statement as SqlStatement, Visit, Visitor},
+    dialect::GenericDialect,
+    parser::Parser,
+};
+use tokio::sync::mpsc;
+
+use crate::service::{db, search::datafusion::exec::register_udf};
+
+/// Stream types exposed as the schemas of the catalogs
+pub const SCHEMAS: [StreamType; 4] = [
+    StreamType::Logs,
+    StreamType::Metrics,
+    StreamType::Traces,
+    StreamType::EnrichmentTables,
+];
+
+/// SQL query of a client, the tables are referenced by their stream name only.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Statement {
+    pub sql: String,
+    pub stream_type: StreamType,
+    pub stream_names: Vec<String>,
+}
+
+impl Statement {
+    /// Parses the query and removes the catalog and the schema from the table names.
+    pub fn parse(org_id: &str, sql: &str) -> Result<Self, anyhow::Error> {
+        let mut statements = Parser::parse_sql(&GenericDialect {}, sql)?;
+        if statements.len() != 1 {
+            return Err(anyhow::anyhow!("Only one query can be executed at a time"));
+        }
+        let mut statement = statements.remove(0);
+        if !matches!(statement, SqlStatement::Query(_)) {
+            return Err(anyhow::anyhow!("Only SELECT queries are supported"));
+        }
+
+        // the relations defined by the common table expressions aren't streams
+        let mut ctes = CteVisitor::default();
+        let _ = statement.visit(&mut ctes);
+
+        let mut stream_type = None;
+        let mut stream_names = Vec::new();
+        let ret = visit_relations_mut(&mut statement, |name: &mut ObjectName| {
+            if name.0.len() == 1 && ctes.names.contains(&name.0[0].value) {
+                return ControlFlow::Continue(());
+            }
+            let mut idents = name.0.clone();
+            let Some(table) = idents.pop() else {
+                return ControlFlow::Continue(());
+            };
+            let table_type = match idents.pop() {
+                Some(schema) => match SCHEMAS.iter().find(|t| t.to_string() == schema.value) {
+                    Some(t) => *t,
+                    None => return ControlFlow::Break(format!("Unknown schema: {}", schema.value)),
+                },
+                None => StreamType::Logs,
+            };
+            if let Some(catalog) = idents.pop() {
+                if catalog.value != org_id {
+                    return ControlFlow::Break(format!("Unknown catalog: {}", catalog.value));
+                }
+            }
+            if !idents.is_empty() {
+                return ControlFlow::Break(format!("Invalid table name: {name}"));
+            }
+            match stream_type {
+                Some(t) if t != table_type => {
+                    return ControlFlow::Break(
+                        "The tables of a query must be of the same stream type".to_string(),
+                    );
+                }
+                _ => stream_type = Some(table_type),
+            }
+            stream_names.push(table.value.clone());
+            name.0 = vec![table];
+            ControlFlow::Continue(())
+        });
+        if let ControlFlow::Break(e) = ret {
+            return Err(anyhow::anyhow!(e));
+        }
+        if stream_names.is_empty() {
+            return Err(anyhow::anyhow!("The query must read a stream"));
+        }
+        stream_names.sort();
+        stream_names.dedup();
+
+        Ok(Self {
+            sql: statement.to_string(),
+            stream_type: stream_type.unwrap_or_default(),
+            stream_names,
+        })
+    }
+
+    /// Returns the schema of the rows, the query is planned against empty tables.
+    pub async fn schema(&self, org_id: &str) -> Result<SchemaRef, anyhow::Error> {
+        let ctx = SessionContext::new();
+        register_udf(&ctx, org_id)?;
+        for name in self.stream_names.iter() {
+            let schema = infra::schema::get(org_id, name, self.stream_type).await?;
+            ctx.register_table(
+                TableReference::bare(name.as_str()),
+                Arc::new(MemTable::try_new(Arc::new(schema), vec![vec![]])?),
+            )?;
+        }
+        let df = ctx.sql(&self.sql).await?;
+        Ok(Arc::new(df.schema().as_arrow().clone()))
+    }
+
+    /// Returns the searched time range in microseconds.
+    fn time_range(&self, now: i64) -> (i64, i64) {
+        let (start, end) = MetaSql::new(&self.sql)
+            .ok()
+            .and_then(|sql| sql.time_range)
+            .unwrap_or_default();
+        // the end of the search is exclusive
+        let end = if end > 0 { end + 1 } else { now };
+        let start = if start > 0 {
+            start
+        } else {
+            end - get_config().grpc.flight_sql_default_time_range * 3600 * 1_000_000
+        };
+        (start, end)
+    }
+}
+
+/// Collects the names of the common table expressions of the query and of its subqueries.
+#[derive(Default)]
+struct CteVisitor {
+    names: HashSet<String>,
+}
+
+impl Visitor for CteVisitor {
+    type Break = ();
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<Self::Break> {
+        if let Some(with) = &query.with {
+            self.names.extend(
+                with.cte_tables
+                    .iter()
+                    .map(|cte| cte.alias.name.value.clone()),
+            );
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Starts the search of the query, see [`super::stream::search`].
+pub async fn search(
+    trace_id: &str,
+    org_id: &str,
+    user_id: &str,
+    statement: &Statement,
+) -> errors::Result<mpsc::Receiver<errors::Result<RecordBatch>>> {
+    let (mut start_time, end_time) = statement.time_range(Utc::now().timestamp_micros());
+    for name in statement.stream_names.iter() {
+        if let Some(settings) =
+            infra::schema::get_settings(org_id, name, statement.stream_type).await
+        {
+            let max_query_range = settings.max_query_range;
+            if max_query_range > 0 && (end_time - start_time) > max_query_range * 3600 * 1_000_000 {
+                start_time = end_time - max_query_range * 3600 * 1_000_000;
+            }
+        }
+    }
+
+    let req = search::Request {
+        query: search::Query {
+            sql: statement.sql.clone(),
+            size: 0, // all the rows
+            start_time,
+            end_time,
+            ..Default::default()
+        },
+        encoding: search::RequestEncoding::Empty,
+        regions: vec![],
+        clusters: vec![],
+        timeout: 0,
+        search_type: None,
+        search_event_context: None,
+    };
+    super::stream::search(
+        trace_id,
+        org_id,
+        statement.stream_type,
+        Some(user_id.to_string()),
+        &req,
+    )
+    .await
+}
+
+/// Returns the streams of the organization with their schema.
+pub async fn list_tables(
+    org_id: &str,
+) -> Result<Vec<(StreamType, String, arrow_schema::Schema)>, anyhow::Error> {
+    let mut tables = Vec::new();
+    for stream_type in SCHEMAS {
+        let mut streams = db::schema::list(org_id, Some(stream_type), true).await?;
+        streams.sort_by(|a, b| a.stream_name.cmp(&b.stream_name));
+        tables.extend(
+            streams
+                .into_iter()
+                .map(|s| (stream_type, s.stream_name, s.schema)),
+        );
+    }
+    Ok(tables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_statement() {
+        let statement = Statement::parse("default", "SELECT * FROM \"k8s\"").unwrap();
+        assert_eq!(statement.sql, "SELECT * FROM \"k8s\"");
+        assert_eq!(statement.stream_type, StreamType::Logs);
+        assert_eq!(statement.stream_names, vec!["k8s".to_string()]);
+
+        let statement = Statement::parse(
+            "default",
+            "SELECT count(*) FROM \"default\".metrics.cpu a JOIN metrics.cpu b ON a.x = b.x",
+        )
+        .unwrap();
+        assert_eq!(
+            statement.sql,
+            "SELECT count(*) FROM cpu AS a JOIN cpu AS b ON a.x = b.x"
+        );
+        assert_eq!(statement.stream_type, StreamType::Metrics);
+        assert_eq!(statement.stream_names, vec!["cpu".to_string()]);
+
+        let statement = Statement::parse(
+            "default",
+            "WITH errors AS (SELECT * FROM logs.k8s WHERE level = 'error') SELECT count(*) FROM errors",
+        )
+        .unwrap();
+        assert_eq!(
+            statement.sql,
+            "WITH errors AS (SELECT * FROM k8s WHERE level = 'error') SELECT count(*) FROM errors"
+        );
+        assert_eq!(statement.stream_names, vec!["k8s".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_statement_errors() {
+        for sql in [
+            "SELECT 1",
+            "WITH a AS (SELECT 1) SELECT * FROM a",
+            "SELECT * FROM a; SELECT * FROM b",
+            "DELETE FROM k8s",
+            "SELECT * FROM unknown.k8s",
+            "SELECT * FROM other.logs.k8s",
+            "SELECT * FROM logs.a JOIN traces.b ON a.x = b.x",
+        ] {
+            assert!(Statement::parse("default", sql).is_err(), "{sql}");
+        }
+    }
+
+    #[test]
+    fn test_time_range() {
+        let hour = 3600 * 1_000_000;
+        let now = 100 * hour;
+        let statement = Statement::parse("default", "SELECT * FROM k8s").unwrap();
+        assert_eq!(statement.time_range(now), (now - 24 * hour, now));
+
+        let statement = Statement::parse(
+            "default",
+            "SELECT * FROM k8s WHERE _timestamp >= 1700000000000000 AND _timestamp <= 1700003600000000",
+        )
+        .unwrap();
+        assert_eq!(
+            statement.time_range(now),
+            (1700000000000000, 1700003600000001)
+        );
+    }
+}
@@ -744,6 +744,12 @@ async fn search_tantivy_index(
                 let field = tantivy_schema.get_field(&field).unwrap();
                 warm_terms.insert(field, HashMap::new());
             }
+        } else {
+            // the in list, range and not conditions need to scan the whole field
+            for field in condition.get_tantivy_full_warm_fields() {
+                let field = tantivy_schema.get_field(&field).unwrap();
+                warm_terms.insert(field, HashMap::new());
+            }
         }
         warm_up_terms(&tantivy_searcher, &warm_terms).await?;
     }
@@ -1,6 +1,7 @@
 use std::{
     collections::HashSet,
     fmt::{self, Debug, Formatter},
+    ops::Bound,
     sync::Arc,
 };
 
@@ -9,15 +10,18 @@ use datafusion::{
     arrow::datatypes::{DataType, SchemaRef},
     logical_expr::Operator,
     physical_plan::{
-        expressions::{BinaryExpr, Column, LikeExpr, Literal},
+        expressions::{BinaryExpr, Column, IsNotNullExpr, LikeExpr, Literal, NotExpr},
         PhysicalExpr,
     },
     scalar::ScalarValue,
 };
 use serde::{Deserialize, Serialize};
-use sqlparser::ast::{BinaryOperator, Expr, FunctionArguments};
+use sqlparser::ast::{BinaryOperator, Expr, FunctionArguments, UnaryOperator, Value};
 use tantivy::{
-    query::{BooleanQuery, Occur, PhrasePrefixQuery, Query, RegexQuery, TermQuery},
+    query::{
+        BooleanQuery, Occur, PhrasePrefixQuery, Query, RangeQuery, RegexQuery, TermQuery,
+        TermSetQuery,
+    },
     schema::{Field, IndexRecordOption, Schema},
     Term,
 };
@@ -38,6 +42,11 @@ pub fn get_index_condition_from_expr(
         }
 
         let multi_condition = Condition::from_expr(e);
+        // the index can't tell the NULL values apart from the empty strings,
+        // so keep these filters in the sql to check the matched rows again
+        if multi_condition.need_recheck() {
+            other_expr.push(e);
+        }
         index_condition.add_condition(multi_condition);
     }
 
@@ -78,7 +87,13 @@ impl Debug for IndexCondition {
 pub enum Condition {
     // field, value
     Equal(String, String),
+    // field, values
+    In(String, Vec<String>),
+    // field, lower bound, upper bound
+    Range(String, Bound<String>, Bound<String>),
     MatchAll(String),
+    // only wrap the leaf conditions, see `Condition::negate`
+    Not(Box<Condition>),
     Or(Box<Condition>, Box<Condition>),
     And(Box<Condition>, Box<Condition>),
 }
@@ -119,6 +134,16 @@ impl IndexCondition {
             })
     }
 
+    // the fields that can't be warmed up by terms, need to warm up the whole field
+    pub fn get_tantivy_full_warm_fields(&self) -> HashSet<String> {
+        self.conditions
+            .iter()
+            .fold(HashSet::new(), |mut acc, condition| {
+                acc.extend(condition.get_tantivy_full_warm_fields());
+                acc
+            })
+    }
+
     pub fn get_schema_fields(&self, fst_fields: &[String]) -> HashSet<String> {
         self.conditions
             .iter()
@@ -158,7 +183,16 @@ impl Condition {
     pub fn to_query(&self) -> String {
         match self {
             Condition::Equal(field, value) => format!("{}={}", field, value),
+            Condition::In(field, values) => format!("{} IN ({})", field, values.join(",")),
+            Condition::Range(field, lower, upper) => match (lower, upper) {
+                (Bound::Included(v), Bound::Unbounded) => format!("{}>={}", field, v),
+                (Bound::Excluded(v), Bound::Unbounded) => format!("{}>{}", field, v),
+                (Bound::Unbounded, Bound::Included(v)) => format!("{}<={}", field, v),
+                (Bound::Unbounded, Bound::Excluded(v)) => format!("{}<{}", field, v),
+                _ => format!("{}:{}", field, format_range(lower, upper)),
+            },
             Condition::MatchAll(value) => format!("{}:{}", INDEX_FIELD_NAME_FOR_ALL, value),
+            Condition::Not(condition) => format!("NOT {}", condition.to_query()),
             Condition::Or(left, right) => format!("({} OR {})", left.to_query(), right.to_query()),
             Condition::And(left, right) => {
                 format!("({} AND {})", left.to_query(), right.to_query())
@@ -182,6 +216,83 @@ impl Condition {
                 };
                 Condition::Equal(field, value)
             }
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::NotEq,
+                right,
+            } => Condition::from_expr(&Expr::BinaryOp {
+                left: left.clone(),
+                op: BinaryOperator::Eq,
+                right: right.clone(),
+            })
+            .negate(),
+            Expr::BinaryOp {
+                left,
+                op:
+                    op @ (BinaryOperator::Gt
+                    | BinaryOperator::GtEq
+                    | BinaryOperator::Lt
+                    | BinaryOperator::LtEq),
+                right,
+            } => {
+                // make sure the field is always on the left side
+                let (field, value, op) = if is_value(left) && is_field(right) {
+                    (get_field_name(right), get_value(left), flip_operator(op))
+                } else if is_value(right) && is_field(left) {
+                    (get_field_name(left), get_value(right), op.clone())
+                } else {
+                    unreachable!()
+                };
+                match op {
+                    BinaryOperator::Gt => {
+                        Condition::Range(field, Bound::Excluded(value), Bound::Unbounded)
+                    }
+                    BinaryOperator::GtEq => {
+                        Condition::Range(field, Bound::Included(value), Bound::Unbounded)
+                    }
+                    BinaryOperator::Lt => {
+                        Condition::Range(field, Bound::Unbounded, Bound::Excluded(value))
+                    }
+                    BinaryOperator::LtEq => {
+                        Condition::Range(field, Bound::Unbounded, Bound::Included(value))
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let condition =
+                    Condition::In(get_field_name(expr), list.iter().map(get_value).collect());
+                if *negated {
+                    condition.negate()
+                } else {
+                    condition
+                }
+            }
+            Expr::Between {
+                expr,
+                negated,
+                low,
+                high,
+            } => {
+                let condition = Condition::Range(
+                    get_field_name(expr),
+                    Bound::Included(get_value(low)),
+                    Bound::Included(get_value(high)),
+                );
+                if *negated {
+                    condition.negate()
+                } else {
+                    condition
+                }
+            }
+            Expr::UnaryOp {
+                op: UnaryOperator::Not,
+                expr,
+            } => Condition::from_expr(expr).negate(),
             Expr::Function(func) => {
                 if func.name.to_string().to_lowercase() != "match_all" {
                     unreachable!()
@@ -216,6 +327,21 @@ impl Condition {
         }
     }
 
+    // push the NOT operator down to the leaf conditions by De Morgan's laws,
+    // so that `Condition::Not` only wraps the single field conditions
+    pub fn negate(self) -> Self {
+        match self {
+            Condition::Not(condition) => *condition,
+            Condition::Or(left, right) => {
+                Condition::And(Box::new(left.negate()), Box::new(right.negate()))
+            }
+            Condition::And(left, right) => {
+                Condition::Or(Box::new(left.negate()), Box::new(right.negate()))
+            }
+            condition => Condition::Not(Box::new(condition)),
+        }
+    }
+
     pub fn to_tantivy_query(
         &self,
         schema: &Schema,
@@ -227,6 +353,23 @@ impl Condition {
                 let term = Term::from_field_text(field, value);
                 Box::new(TermQuery::new(term, IndexRecordOption::Basic))
             }
+            Condition::In(field, values) => {
+                let field = schema.get_field(field)?;
+                let terms = values
+                    .iter()
+                    .map(|value| Term::from_field_text(field, value))
+                    .collect::<Vec<_>>();
+                Box::new(TermSetQuery::new(terms))
+            }
+            Condition::Range(field, lower, upper) => {
+                // make sure the field exists in the tantivy schema
+                schema.get_field(field)?;
+                Box::new(RangeQuery::new_str_bounds(
+                    field.to_string(),
+                    lower.as_ref().map(|v| v.as_str()),
+                    upper.as_ref().map(|v| v.as_str()),
+                ))
+            }
             Condition::MatchAll(value) => {
                 if value.starts_with("*") && value.ends_with("*") {
                     let value = format!(".*{}.*", value.trim_matches('*'));
@@ -263,6 +406,30 @@ impl Condition {
                     }
                 }
             }
+            Condition::Not(condition) => {
+                // the documents that don't have the field should not be matched,
+                // same as the NULL value in sql, so we need to make sure the field
+                // has value first and then exclude the matched documents
+                let mut queries: Vec<(Occur, Box<dyn Query>)> = condition
+                    .get_tantivy_fields()
+                    .into_iter()
+                    .map(|field| {
+                        (
+                            Occur::Must,
+                            Box::new(RangeQuery::new_str_bounds(
+                                field,
+                                Bound::Unbounded,
+                                Bound::Unbounded,
+                            )) as _,
+                        )
+                    })
+                    .collect();
+                queries.push((
+                    Occur::MustNot,
+                    condition.to_tantivy_query(schema, default_fields)?,
+                ));
+                Box::new(BooleanQuery::new(queries))
+            }
             Condition::Or(left, right) => {
                 let left_query = left.to_tantivy_query(schema, default_fields)?;
                 let right_query = right.to_tantivy_query(schema, default_fields)?;
@@ -282,15 +449,33 @@ impl Condition {
         })
     }
 
+    // the index stores the NULL values as empty strings, so the conditions that
+    // can match an empty string return a superset of the rows, e.g. `field != 'a'`
+    // or `field < 'b'` also match the NULL values, which is wrong in sql
+    pub fn need_recheck(&self) -> bool {
+        match self {
+            Condition::Equal(_, value) => value.is_empty(),
+            Condition::In(_, values) => values.iter().any(|v| v.is_empty()),
+            Condition::Range(..) | Condition::Not(_) => true,
+            Condition::MatchAll(_) => false,
+            Condition::Or(left, right) | Condition::And(left, right) => {
+                left.need_recheck() || right.need_recheck()
+            }
+        }
+    }
+
     pub fn get_tantivy_fields(&self) -> HashSet<String> {
         let mut fields = HashSet::new();
         match self {
-            Condition::Equal(field, _) => {
+            Condition::Equal(field, _) | Condition::In(field, _) | Condition::Range(field, ..) => {
                 fields.insert(field.clone());
             }
             Condition::MatchAll(_) => {
                 fields.insert(INDEX_FIELD_NAME_FOR_ALL.to_string());
             }
+            Condition::Not(condition) => {
+                fields.extend(condition.get_tantivy_fields());
+            }
             Condition::Or(left, right) | Condition::And(left, right) => {
                 fields.extend(left.get_tantivy_fields());
                 fields.extend(right.get_tantivy_fields());
@@ -299,15 +484,38 @@ impl Condition {
         fields
     }
 
+    // TermSetQuery, RangeQuery and the NOT condition need to scan the term dictionary,
+    // so the terms collected from the query are not enough to warm up these fields
+    pub fn get_tantivy_full_warm_fields(&self) -> HashSet<String> {
+        let mut fields = HashSet::new();
+        match self {
+            Condition::Equal(..) | Condition::MatchAll(_) => {}
+            Condition::In(field, _) | Condition::Range(field, ..) => {
+                fields.insert(field.clone());
+            }
+            Condition::Not(condition) => {
+                fields.extend(condition.get_tantivy_fields());
+            }
+            Condition::Or(left, right) | Condition::And(left, right) => {
+                fields.extend(left.get_tantivy_full_warm_fields());
+                fields.extend(right.get_tantivy_full_warm_fields());
+            }
+        }
+        fields
+    }
+
     pub fn get_schema_fields(&self, fst_fields: &[String]) -> HashSet<String> {
         let mut fields = HashSet::new();
         match self {
-            Condition::Equal(field, _) => {
+            Condition::Equal(field, _) | Condition::In(field, _) | Condition::Range(field, ..) => {
                 fields.insert(field.clone());
             }
             Condition::MatchAll(_) => {
                 fields.extend(fst_fields.iter().cloned());
             }
+            Condition::Not(condition) => {
+                fields.extend(condition.get_schema_fields(fst_fields));
+            }
             Condition::Or(left, right) | Condition::And(left, right) => {
                 fields.extend(left.get_schema_fields(fst_fields));
                 fields.extend(right.get_schema_fields(fst_fields));
@@ -329,6 +537,46 @@ impl Condition {
                 let right = get_scalar_value(value, field.data_type())?;
                 Ok(Arc::new(BinaryExpr::new(left, Operator::Eq, right)))
             }
+            Condition::In(name, values) => {
+                let index = schema.index_of(name).unwrap();
+                let field = schema.field(index);
+                let mut expr_list: Vec<Arc<dyn PhysicalExpr>> = Vec::with_capacity(values.len());
+                for value in values.iter() {
+                    let left = Arc::new(Column::new(name, index));
+                    let right = get_scalar_value(value, field.data_type())?;
+                    expr_list.push(Arc::new(BinaryExpr::new(left, Operator::Eq, right)));
+                }
+                if expr_list.is_empty() {
+                    return Err(anyhow::anyhow!("The IN list can't be empty"));
+                }
+                Ok(disjunction(expr_list))
+            }
+            Condition::Range(name, lower, upper) => {
+                let index = schema.index_of(name).unwrap();
+                let field = schema.field(index);
+                let mut expr_list: Vec<Arc<dyn PhysicalExpr>> = Vec::with_capacity(2);
+                let lower = match lower {
+                    Bound::Included(v) => Some((Operator::GtEq, v)),
+                    Bound::Excluded(v) => Some((Operator::Gt, v)),
+                    Bound::Unbounded => None,
+                };
+                let upper = match upper {
+                    Bound::Included(v) => Some((Operator::LtEq, v)),
+                    Bound::Excluded(v) => Some((Operator::Lt, v)),
+                    Bound::Unbounded => None,
+                };
+                for (op, value) in lower.into_iter().chain(upper) {
+                    let left = Arc::new(Column::new(name, index));
+                    let right = get_scalar_value(value, field.data_type())?;
+                    expr_list.push(Arc::new(BinaryExpr::new(left, op, right)));
+                }
+                if expr_list.is_empty() {
+                    return Ok(Arc::new(IsNotNullExpr::new(Arc::new(Column::new(
+                        name, index,
+                    )))));
+                }
+                Ok(conjunction(expr_list))
+            }
             Condition::MatchAll(value) => {
                 let term = Arc::new(Literal::new(ScalarValue::Utf8(Some(format!("%{value}%")))));
                 let mut expr_list: Vec<Arc<dyn PhysicalExpr>> =
@@ -349,6 +597,10 @@ impl Condition {
                 }
                 Ok(disjunction(expr_list))
             }
+            Condition::Not(condition) => {
+                let expr = condition.to_physical_expr(schema, fst_fields)?;
+                Ok(Arc::new(NotExpr::new(expr)))
+            }
             Condition::Or(left, right) => {
                 let left = left.to_physical_expr(schema, fst_fields)?;
                 let right = right.to_physical_expr(schema, fst_fields)?;
@@ -364,19 +616,26 @@ impl Condition {
 }
 
 // check if function is match_all and only have one argument
-// check if binary operator is equal and one side is field and the other side is value
-// and the field is in the index_fields
+// check if binary operator is comparison and one side is field and the other side is value
+// check if in list only have values and between only have string values
+// and the field is in the index_fields, the index_fields should only have Utf8 fields
 fn is_expr_valid_for_index(expr: &Expr, index_fields: &HashSet<String>) -> bool {
     match expr {
         Expr::BinaryOp {
             left,
-            op: BinaryOperator::Eq,
+            op:
+                op @ (BinaryOperator::Eq
+                | BinaryOperator::NotEq
+                | BinaryOperator::Gt
+                | BinaryOperator::GtEq
+                | BinaryOperator::Lt
+                | BinaryOperator::LtEq),
             right,
         } => {
-            let field = if is_value(left) && is_field(right) {
-                right
+            let (field, value) = if is_value(left) && is_field(right) {
+                (right, left)
             } else if is_value(right) && is_field(left) {
-                left
+                (left, right)
             } else {
                 return false;
             };
@@ -384,6 +643,12 @@ fn is_expr_valid_for_index(expr: &Expr, index_fields: &HashSet<String>) -> bool
             if !index_fields.contains(&get_field_name(field)) {
                 return false;
             }
+            // the range query compares the terms as strings, `code > 5` should
+            // compare as number, so only use the index for string literals
+            if !matches!(op, BinaryOperator::Eq | BinaryOperator::NotEq) && !is_string_value(value)
+            {
+                return false;
+            }
         }
         Expr::BinaryOp {
             left,
@@ -405,6 +670,35 @@ fn is_expr_valid_for_index(expr: &Expr, index_fields: &HashSet<String>) -> bool
                 return false;
             }
         }
+        Expr::InList {
+            expr,
+            list,
+            negated: _,
+        } => {
+            if !is_field(expr) || !index_fields.contains(&get_field_name(expr)) {
+                return false;
+            }
+            if list.is_empty() || !list.iter().all(is_value) {
+                return false;
+            }
+        }
+        Expr::Between {
+            expr,
+            negated: _,
+            low,
+            high,
+        } => {
+            if !is_field(expr) || !index_fields.contains(&get_field_name(expr)) {
+                return false;
+            }
+            if !is_string_value(low) || !is_string_value(high) {
+                return false;
+            }
+        }
+        Expr::UnaryOp {
+            op: UnaryOperator::Not,
+            expr,
+        } => return is_expr_valid_for_index(expr, index_fields),
         Expr::Nested(expr) => return is_expr_valid_for_index(expr, index_fields),
         _ => return false,
     }
@@ -427,6 +721,38 @@ fn get_value(expr: &Expr) -> String {
     }
 }
 
+fn is_string_value(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Value(Value::SingleQuotedString(_) | Value::DoubleQuotedString(_))
+    )
+}
+
+// `5 < field` equals to `field > 5`
+fn flip_operator(op: &BinaryOperator) -> BinaryOperator {
+    match op {
+        BinaryOperator::Gt => BinaryOperator::Lt,
+        BinaryOperator::GtEq => BinaryOperator::LtEq,
+        BinaryOperator::Lt => BinaryOperator::Gt,
+        BinaryOperator::LtEq => BinaryOperator::GtEq,
+        op => op.clone(),
+    }
+}
+
+fn format_range(lower: &Bound<String>, upper: &Bound<String>) -> String {
+    let lower = match lower {
+        Bound::Included(v) => format!("[{v}"),
+        Bound::Excluded(v) => format!("({v}"),
+        Bound::Unbounded => "(*".to_string(),
+    };
+    let upper = match upper {
+        Bound::Included(v) => format!("{v}]"),
+        Bound::Excluded(v) => format!("{v})"),
+        Bound::Unbounded => "*)".to_string(),
+    };
+    format!("{lower},{upper}")
+}
+
 fn disjunction(exprs: Vec<Arc<dyn PhysicalExpr>>) -> Arc<dyn PhysicalExpr> {
     if exprs.len() == 1 {
         exprs[0].clone()
@@ -466,3 +792,145 @@ fn get_scalar_value(value: &str, data_type: &DataType) -> Result<Arc<Literal>, a
         _ => unimplemented!(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::{
+        array::{Array, BooleanArray, StringArray},
+        record_batch::RecordBatch,
+    };
+    use tantivy::{collector::DocSetCollector, doc, schema::TextFieldIndexing, Index};
+
+    use super::*;
+
+    fn search(condition: Condition) -> Vec<u32> {
+        let mut builder = Schema::builder();
+        let index_opts = tantivy::schema::TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_index_option(IndexRecordOption::Basic)
+                .set_tokenizer("raw")
+                .set_fieldnorms(false),
+        );
+        let all = builder.add_text_field(INDEX_FIELD_NAME_FOR_ALL, index_opts.clone());
+        let name = builder.add_text_field("name", index_opts.clone());
+        let code = builder.add_text_field("code", index_opts);
+        let schema = builder.build();
+        let index = Index::create_in_ram(schema.clone());
+        let mut writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        writer
+            .add_document(doc!(all => "foo", name => "a", code => "200"))
+            .unwrap();
+        writer
+            .add_document(doc!(all => "bar", name => "b", code => "500"))
+            .unwrap();
+        // the NULL value is stored as an empty string
+        writer
+            .add_document(doc!(all => "", name => "", code => "1000"))
+            .unwrap();
+        writer.add_document(doc!(code => "99")).unwrap();
+        writer.commit().unwrap();
+
+        let mut index_condition = IndexCondition::new();
+        index_condition.add_condition(condition);
+        let query = index_condition.to_tantivy_query(schema, all).unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let mut docs = searcher
+            .search(&query, &DocSetCollector)
+            .unwrap()
+            .into_iter()
+            .map(|doc| doc.doc_id)
+            .collect::<Vec<_>>();
+        docs.sort();
+        docs
+    }
+
+    fn evaluate(condition: Condition) -> Vec<Option<bool>> {
+        let schema = Arc::new(arrow_schema::Schema::new(vec![
+            arrow_schema::Field::new("name", DataType::Utf8, true),
+            arrow_schema::Field::new("code", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![Some("a"), Some("b"), None])),
+                Arc::new(StringArray::from(vec![Some("200"), Some("500"), None])),
+            ],
+        )
+        .unwrap();
+        let expr = condition.to_physical_expr(&schema, &[]).unwrap();
+        let result = expr
+            .evaluate(&batch)
+            .unwrap()
+            .into_array(batch.num_rows())
+            .unwrap();
+        result
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .unwrap()
+            .iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_to_tantivy_query() {
+        let equal = Condition::Equal("name".to_string(), "a".to_string());
+        assert!(!equal.need_recheck());
+        assert_eq!(search(equal), vec![0]);
+
+        let values = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(
+            search(Condition::In("name".to_string(), values)),
+            vec![0, 1]
+        );
+        assert_eq!(search(Condition::MatchAll("bar".to_string())), vec![1]);
+
+        // the range compares the terms as strings, same as the Utf8 field in sql
+        let range = Condition::Range(
+            "code".to_string(),
+            Bound::Included("500".to_string()),
+            Bound::Unbounded,
+        );
+        assert!(range.need_recheck());
+        assert_eq!(search(range), vec![1, 3]);
+
+        // the documents without the field are excluded, but the NULL values
+        // are matched, so the condition should be checked again in sql
+        let not_equal = Condition::Equal("name".to_string(), "a".to_string()).negate();
+        assert!(not_equal.need_recheck());
+        assert_eq!(search(not_equal), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_to_physical_expr() {
+        assert_eq!(
+            evaluate(Condition::Equal("name".to_string(), "a".to_string())),
+            vec![Some(true), Some(false), None]
+        );
+        assert_eq!(
+            evaluate(Condition::Equal("name".to_string(), "a".to_string()).negate()),
+            vec![Some(false), Some(true), None]
+        );
+        assert_eq!(
+            evaluate(Condition::In(
+                "name".to_string(),
+                vec!["b".to_string(), "c".to_string()]
+            )),
+            vec![Some(false), Some(true), None]
+        );
+        assert_eq!(
+            evaluate(Condition::Range(
+                "code".to_string(),
+                Bound::Excluded("200".to_string()),
+                Bound::Included("500".to_string()),
+            )),
+            vec![Some(false), Some(true), None]
+        );
+        assert_eq!(
+            evaluate(Condition::Or(
+                Box::new(Condition::Equal("name".to_string(), "a".to_string())),
+                Box::new(Condition::Equal("code".to_string(), "500".to_string())),
+            )),
+            vec![Some(true), Some(true), None]
+        );
+    }
+}
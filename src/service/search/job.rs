@@ -0,0 +1,557 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Asynchronous search jobs. A job is executed in the background by the querier it was
+//! submitted to, page by page, and each page of `ZO_SEARCH_JOB_PART_SIZE` rows is written as a
+//! parquet file into the object storage, so the results can be fetched while the job is still
+//! running and outlive the HTTP connection. The jobs and their results are deleted after
+//! `ZO_SEARCH_JOB_TTL` hours.
+
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use config::{
+    cluster::LOCAL_NODE,
+    get_config, ider,
+    meta::{
+        search,
+        search_job::{SearchJob, SearchJobResults, SearchJobStatus},
+        stream::{FileMeta, StreamType},
+    },
+    utils::{
+        arrow::record_batches_to_json_rows,
+        json,
+        parquet::{read_recordbatch_from_bytes, write_recordbatch_to_parquet},
+        record_batch_ext::convert_json_to_record_batch,
+        schema::infer_json_schema_from_values,
+    },
+};
+use dashmap::DashMap;
+use infra::storage;
+use once_cell::sync::Lazy;
+use tokio::task::AbortHandle;
+
+use crate::service::db;
+
+const RESULTS_PREFIX: &str = "search_jobs";
+
+/// Jobs running on this node, they are aborted when canceled
+static RUNNING_JOBS: Lazy<DashMap<String, AbortHandle>> = Lazy::new(DashMap::new);
+
+/// The user already has the maximum number of pending or running search jobs.
+#[derive(Debug)]
+pub struct TooManySearchJobs {
+    pub limit: usize,
+}
+
+impl std::fmt::Display for TooManySearchJobs {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Too many search jobs, a user can have at most {} pending or running jobs",
+            self.limit
+        )
+    }
+}
+
+impl std::error::Error for TooManySearchJobs {}
+
+/// Saves the job and starts it in the background.
+pub async fn submit(
+    org_id: &str,
+    user_id: &str,
+    stream_type: StreamType,
+    req: search::Request,
+) -> Result<SearchJob, anyhow::Error> {
+    let cfg = get_config();
+    let limit = cfg.limit.search_job_max_per_user;
+    if limit > 0 {
+        let active = db::search_job::list(org_id)
+            .await?
+            .into_iter()
+            .filter(|job| job.user_id == user_id && job.status.is_active())
+            .count();
+        if active >= limit {
+            return Err(TooManySearchJobs { limit }.into());
+        }
+    }
+
+    let now = Utc::now();
+    let job = SearchJob {
+        id: ider::uuid(),
+        org_id: org_id.to_string(),
+        user_id: user_id.to_string(),
+        stream_type,
+        request: req,
+        status: SearchJobStatus::Pending,
+        error: None,
+        node: LOCAL_NODE.name.clone(),
+        created_at: now.timestamp_micros(),
+        started_at: 0,
+        ended_at: 0,
+        expires_at: (now + Duration::try_hours(cfg.limit.search_job_ttl).unwrap())
+            .timestamp_micros(),
+        total: 0,
+        parts: Vec::new(),
+        columns: Vec::new(),
+        scan_size: 0,
+        scan_records: 0,
+    };
+    db::search_job::set(&job).await?;
+
+    let job_id = job.id.clone();
+    let handle = tokio::task::spawn(run(job.clone()));
+    RUNNING_JOBS.insert(job_id, handle.abort_handle());
+    Ok(job)
+}
+
+/// Returns the job if it belongs to the user.
+pub async fn get(
+    org_id: &str,
+    user_id: &str,
+    job_id: &str,
+) -> Result<Option<SearchJob>, anyhow::Error> {
+    Ok(db::search_job::get(org_id, job_id)
+        .await?
+        .filter(|job| job.user_id == user_id))
+}
+
+pub async fn list(org_id: &str, user_id: &str) -> Result<Vec<SearchJob>, anyhow::Error> {
+    Ok(db::search_job::list(org_id)
+        .await?
+        .into_iter()
+        .filter(|job| job.user_id == user_id)
+        .collect())
+}
+
+/// Returns the result rows `offset..offset + size` written so far.
+pub async fn results(
+    job: &SearchJob,
+    offset: usize,
+    size: usize,
+) -> Result<SearchJobResults, anyhow::Error> {
+    let mut hits = Vec::with_capacity(size.min(job.total.saturating_sub(offset)));
+    for (part, part_offset) in job.parts_in_range(offset, size) {
+        let data = storage::get(&part_key(job, part)).await?;
+        let (_, batches) = read_recordbatch_from_bytes(&data).await?;
+        let rows = record_batches_to_json_rows(&batches.iter().collect::<Vec<_>>())?;
+        let remaining = size - hits.len();
+        hits.extend(
+            rows.into_iter()
+                .skip(offset.saturating_sub(part_offset))
+                .take(remaining)
+                .map(json::Value::Object),
+        );
+    }
+    Ok(SearchJobResults {
+        job_id: job.id.clone(),
+        status: job.status,
+        total: job.total,
+        offset,
+        size: hits.len(),
+        columns: job.columns.clone(),
+        hits,
+    })
+}
+
+/// Cancels a pending or running job, the rows written so far are deleted.
+pub async fn cancel(mut job: SearchJob) -> Result<SearchJob, anyhow::Error> {
+    if !job.status.is_active() {
+        return Ok(job);
+    }
+    // the job is aborted here when it runs on this node, otherwise its querier stops it
+    // before writing the next part
+    if let Some((_, handle)) = RUNNING_JOBS.remove(&job.id) {
+        handle.abort();
+    }
+    job.status = SearchJobStatus::Canceled;
+    job.ended_at = Utc::now().timestamp_micros();
+    db::search_job::set(&job).await?;
+    delete_results(&job).await?;
+    Ok(job)
+}
+
+/// Cancels the job if needed and deletes it with its results.
+pub async fn delete(job: SearchJob) -> Result<(), anyhow::Error> {
+    let job = cancel(job).await?;
+    delete_results(&job).await?;
+    db::search_job::delete(&job.org_id, &job.id).await
+}
+
+/// Deletes the expired jobs, and fails the ones this node was running before a restart.
+pub async fn cleanup() -> Result<(), anyhow::Error> {
+    RUNNING_JOBS.retain(|_, handle| !handle.is_finished());
+    let now = Utc::now().timestamp_micros();
+    for mut job in db::search_job::list("").await? {
+        if job.expires_at <= now {
+            if let Some((_, handle)) = RUNNING_JOBS.remove(&job.id) {
+                handle.abort();
+            }
+            if let Err(e) = delete_results(&job).await {
+                log::error!(
+                    "[SEARCH_JOB] Error while deleting results of job {}: {}",
+                    job.id,
+                    e
+                );
+                continue;
+            }
+            db::search_job::delete(&job.org_id, &job.id).await?;
+            log::info!("[SEARCH_JOB] Deleted expired job {}", job.id);
+        } else if job.status.is_active()
+            && job.node == LOCAL_NODE.name
+            && !RUNNING_JOBS.contains_key(&job.id)
+        {
+            job.status = SearchJobStatus::Failed;
+            job.error = Some("The job was interrupted by a restart of its querier".to_string());
+            job.ended_at = now;
+            db::search_job::set(&job).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn run(mut job: SearchJob) {
+    job.status = SearchJobStatus::Running;
+    job.started_at = Utc::now().timestamp_micros();
+    // the job may have been canceled before the task started
+    match db::search_job::set_if_active(&job).await {
+        Ok(true) => {}
+        Ok(false) => {
+            RUNNING_JOBS.remove(&job.id);
+            return;
+        }
+        Err(e) => log::error!("[SEARCH_JOB] Error while saving job {}: {}", job.id, e),
+    }
+
+    let ret = execute(&mut job).await;
+    RUNNING_JOBS.remove(&job.id);
+    match ret {
+        Ok(true) => job.status = SearchJobStatus::Finished,
+        Ok(false) => return, // canceled
+        Err(e) => {
+            log::error!("[SEARCH_JOB] Job {} failed: {}", job.id, e);
+            job.status = SearchJobStatus::Failed;
+            job.error = Some(e.to_string());
+        }
+    }
+    job.ended_at = Utc::now().timestamp_micros();
+    // a cancellation landing after the last part is kept
+    if let Err(e) = db::search_job::set_if_active(&job).await {
+        log::error!("[SEARCH_JOB] Error while saving job {}: {}", job.id, e);
+    }
+}
+
+/// Runs the search page by page, returns false when the job was canceled meanwhile.
+async fn execute(job: &mut SearchJob) -> Result<bool, anyhow::Error> {
+    let cfg = get_config();
+    let mut max_rows = if job.request.query.size > 0 {
+        job.request.query.size
+    } else {
+        i64::MAX
+    };
+    if cfg.limit.search_job_max_rows > 0 {
+        max_rows = max_rows.min(cfg.limit.search_job_max_rows);
+    }
+
+    let mut req = job.request.clone();
+    loop {
+        let remaining = max_rows - job.total as i64;
+        if remaining <= 0 {
+            break;
+        }
+        req.query.from = job.request.query.from + job.total as i64;
+        req.query.size = remaining.min(cfg.limit.search_job_part_size);
+        let res = super::search(
+            &job.id,
+            &job.org_id,
+            job.stream_type,
+            Some(job.user_id.clone()),
+            &req,
+        )
+        .await?;
+
+        // the job may have been canceled meanwhile, from any querier
+        if !is_active(job).await? {
+            return Ok(false);
+        }
+        if res.hits.is_empty() {
+            break;
+        }
+        write_part(job, job.parts.len(), &res.hits).await?;
+
+        if job.columns.is_empty() {
+            job.columns = res.columns;
+        }
+        job.parts.push(res.hits.len());
+        job.total += res.hits.len();
+        job.scan_size += res.scan_size;
+        job.scan_records += res.scan_records;
+        // the cancellation may have deleted the results before the part was written
+        if !db::search_job::set_if_active(job).await? {
+            delete_results(job).await?;
+            return Ok(false);
+        }
+
+        if (res.hits.len() as i64) < req.query.size {
+            break;
+        }
+    }
+    Ok(true)
+}
+
+async fn is_active(job: &SearchJob) -> Result<bool, anyhow::Error> {
+    Ok(db::search_job::get(&job.org_id, &job.id)
+        .await?
+        .is_some_and(|v| v.status.is_active()))
+}
+
+async fn write_part(
+    job: &SearchJob,
+    part: usize,
+    hits: &[json::Value],
+) -> Result<(), anyhow::Error> {
+    let schema = Arc::new(infer_json_schema_from_values(
+        hits.iter(),
+        StreamType::Logs,
+    )?);
+    let records = hits.iter().cloned().map(Arc::new).collect::<Vec<_>>();
+    let batch = convert_json_to_record_batch(&schema, &records)?;
+    let meta = FileMeta {
+        records: batch.num_rows() as i64,
+        ..Default::default()
+    };
+    let data = write_recordbatch_to_parquet(schema, &[batch], &[], &meta).await?;
+    storage::put(&part_key(job, part), data.into()).await?;
+    Ok(())
+}
+
+async fn delete_results(job: &SearchJob) -> Result<(), anyhow::Error> {
+    let prefix = format!("{RESULTS_PREFIX}/{}/{}/", job.org_id, job.id);
+    let files = storage::list(&prefix).await?;
+    if files.is_empty() {
+        return Ok(());
+    }
+    let files = files.iter().map(|f| f.as_str()).collect::<Vec<_>>();
+    storage::del(&files).await?;
+    Ok(())
+}
+
+fn part_key(job: &SearchJob, part: usize) -> String {
+    format!("{RESULTS_PREFIX}/{}/{}/{part}.parquet", job.org_id, job.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use infra::db as infra_db;
+
+    use super::*;
+
+    fn new_job(
+        org_id: &str,
+        user_id: &str,
+        status: SearchJobStatus,
+        parts: Vec<usize>,
+    ) -> SearchJob {
+        let now = Utc::now();
+        SearchJob {
+            id: ider::uuid(),
+            org_id: org_id.to_string(),
+            user_id: user_id.to_string(),
+            stream_type: StreamType::Logs,
+            request: json::from_str(r#"{"query": {"sql": "select * from k8s"}}"#).unwrap(),
+            status,
+            error: None,
+            node: LOCAL_NODE.name.clone(),
+            created_at: now.timestamp_micros(),
+            started_at: 0,
+            ended_at: 0,
+            expires_at: (now + Duration::try_hours(1).unwrap()).timestamp_micros(),
+            total: parts.iter().sum(),
+            parts,
+            columns: vec!["n".to_string()],
+            scan_size: 0,
+            scan_records: 0,
+        }
+    }
+
+    /// Saves the job and writes its parts, the rows are numbered from 0.
+    async fn save_job(job: &SearchJob) {
+        db::search_job::set(job).await.unwrap();
+        let mut n = 0;
+        for (part, rows) in job.parts.iter().enumerate() {
+            let hits = (n..n + rows)
+                .map(|n| json::json!({ "n": n }))
+                .collect::<Vec<_>>();
+            write_part(job, part, &hits).await.unwrap();
+            n += rows;
+        }
+    }
+
+    fn results_prefix(job: &SearchJob) -> String {
+        format!("{RESULTS_PREFIX}/{}/{}/", job.org_id, job.id)
+    }
+
+    #[tokio::test]
+    async fn test_submit() {
+        infra_db::create_table().await.unwrap();
+        let req = json::from_str(r#"{"query": {"sql": "select * from k8s"}}"#).unwrap();
+        let job = submit("job_submit", "user@zo.dev", StreamType::Logs, req)
+            .await
+            .unwrap();
+        assert_eq!(job.status, SearchJobStatus::Pending);
+        assert!(get("job_submit", "user@zo.dev", &job.id)
+            .await
+            .unwrap()
+            .is_some());
+        // the jobs are only visible to their user
+        assert!(get("job_submit", "other@zo.dev", &job.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(list("job_submit", "other@zo.dev").await.unwrap().is_empty());
+        delete(job).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_submit_limit() {
+        infra_db::create_table().await.unwrap();
+        let limit = get_config().limit.search_job_max_per_user;
+        for _ in 0..limit {
+            let mut job = new_job("job_limit", "user@zo.dev", SearchJobStatus::Running, vec![]);
+            // not run by this node, so the cleanup doesn't fail it
+            job.node = "querier-test".to_string();
+            db::search_job::set(&job).await.unwrap();
+        }
+        let job = new_job(
+            "job_limit",
+            "user@zo.dev",
+            SearchJobStatus::Finished,
+            vec![],
+        );
+        db::search_job::set(&job).await.unwrap();
+
+        let req = json::from_str(r#"{"query": {"sql": "select * from k8s"}}"#).unwrap();
+        let err = submit("job_limit", "user@zo.dev", StreamType::Logs, req)
+            .await
+            .unwrap_err();
+        assert!(err.is::<TooManySearchJobs>());
+    }
+
+    #[tokio::test]
+    async fn test_results() {
+        infra_db::create_table().await.unwrap();
+        let job = new_job(
+            "job_results",
+            "user@zo.dev",
+            SearchJobStatus::Finished,
+            vec![3, 3, 2],
+        );
+        save_job(&job).await;
+
+        let res = results(&job, 2, 4).await.unwrap();
+        assert_eq!(res.total, 8);
+        assert_eq!(res.offset, 2);
+        assert_eq!(res.size, 4);
+        let rows = res
+            .hits
+            .iter()
+            .map(|v| v["n"].as_i64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(rows, vec![2, 3, 4, 5]);
+
+        let res = results(&job, 6, 10).await.unwrap();
+        assert_eq!(res.size, 2);
+        assert_eq!(res.hits[1]["n"], 7);
+        assert_eq!(results(&job, 8, 10).await.unwrap().size, 0);
+        delete(job).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancel() {
+        infra_db::create_table().await.unwrap();
+        let job = new_job(
+            "job_cancel",
+            "user@zo.dev",
+            SearchJobStatus::Running,
+            vec![2],
+        );
+        save_job(&job).await;
+        assert_eq!(storage::list(&results_prefix(&job)).await.unwrap().len(), 1);
+        // the progress is saved while the job is active
+        let mut running = job.clone();
+        running.total = 5;
+        assert!(db::search_job::set_if_active(&running).await.unwrap());
+
+        let job = cancel(job).await.unwrap();
+        assert_eq!(job.status, SearchJobStatus::Canceled);
+        // the progress saved after the cancellation doesn't overwrite it
+        assert!(!db::search_job::set_if_active(&running).await.unwrap());
+        let saved = db::search_job::get("job_cancel", &job.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.status, SearchJobStatus::Canceled);
+        assert!(storage::list(&results_prefix(&job))
+            .await
+            .unwrap()
+            .is_empty());
+
+        // a job canceled before its task started isn't run
+        run(job.clone()).await;
+        let saved = db::search_job::get("job_cancel", &job.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.status, SearchJobStatus::Canceled);
+        delete(job).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cleanup() {
+        infra_db::create_table().await.unwrap();
+        let mut expired = new_job(
+            "job_cleanup",
+            "user@zo.dev",
+            SearchJobStatus::Finished,
+            vec![2],
+        );
+        expired.expires_at = Utc::now().timestamp_micros() - 1;
+        save_job(&expired).await;
+        // an active job of this node which isn't running anymore
+        let interrupted = new_job(
+            "job_cleanup",
+            "user@zo.dev",
+            SearchJobStatus::Running,
+            vec![],
+        );
+        save_job(&interrupted).await;
+
+        cleanup().await.unwrap();
+        assert!(db::search_job::get("job_cleanup", &expired.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(storage::list(&results_prefix(&expired))
+            .await
+            .unwrap()
+            .is_empty());
+        let saved = db::search_job::get("job_cleanup", &interrupted.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.status, SearchJobStatus::Failed);
+        delete(saved).await.unwrap();
+    }
+}
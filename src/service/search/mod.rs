@@ -66,12 +66,18 @@ use crate::{
 pub(crate) mod cache;
 pub(crate) mod cluster;
 pub(crate) mod datafusion;
+pub(crate) mod es;
+pub(crate) mod export;
+pub(crate) mod flight_sql;
 pub(crate) mod grpc;
 pub(crate) mod index;
+pub(crate) mod job;
 pub(crate) mod request;
 pub(crate) mod sql;
+pub(crate) mod stream;
 #[cfg(feature = "enterprise")]
 pub(crate) mod super_cluster;
+pub(crate) mod tail;
 pub(crate) mod tantivy;
 pub(crate) mod utils;
 
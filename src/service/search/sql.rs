@@ -27,7 +27,7 @@ use config::{
     utils::sql::AGGREGATE_UDF_LIST,
     ID_COL_NAME, ORIGINAL_DATA_COL_NAME,
 };
-use datafusion::arrow::datatypes::Schema;
+use datafusion::arrow::datatypes::{DataType, Schema};
 use hashbrown::HashMap;
 use infra::{
     errors::{Error, ErrorCodes},
@@ -44,7 +44,8 @@ use sqlparser::{
     ast::{
         BinaryOperator, DuplicateTreatment, Expr, Function, FunctionArg, FunctionArgExpr,
         FunctionArgumentList, FunctionArguments, GroupByExpr, Ident, ObjectName, Query, Select,
-        SelectItem, SetExpr, Statement, TableFactor, TableWithJoins, VisitMut, VisitorMut,
+        SelectItem, SetExpr, Statement, TableFactor, TableWithJoins, UnaryOperator, VisitMut,
+        VisitorMut,
     },
     dialect::PostgreSqlDialect,
     parser::Parser,
@@ -564,7 +565,16 @@ impl IndexVisitor {
         let index_fields = if let Some((_, schema)) = schemas.iter().next() {
             let stream_settings = unwrap_stream_settings(schema.schema());
             let index_fields = get_stream_setting_index_fields(&stream_settings);
-            index_fields.into_iter().collect::<HashSet<_>>()
+            // only the Utf8 fields are stored in the index
+            index_fields
+                .into_iter()
+                .filter(|f| {
+                    schema
+                        .schema()
+                        .field_with_name(f)
+                        .is_ok_and(|f| f.data_type() == &DataType::Utf8)
+                })
+                .collect::<HashSet<_>>()
         } else {
             HashSet::new()
         };
@@ -1320,9 +1330,29 @@ fn checking_inverted_index_inner(index_fields: &HashSet<&String>, expr: &Expr) -
                 checking_inverted_index_inner(index_fields, left)
                     && checking_inverted_index_inner(index_fields, right)
             }
-            BinaryOperator::Eq => checking_inverted_index_inner(index_fields, left),
+            BinaryOperator::Eq
+            | BinaryOperator::NotEq
+            | BinaryOperator::Gt
+            | BinaryOperator::GtEq
+            | BinaryOperator::Lt
+            | BinaryOperator::LtEq => checking_inverted_index_inner(index_fields, left),
             _ => false,
         },
+        Expr::InList {
+            expr,
+            list: _,
+            negated: _,
+        } => checking_inverted_index_inner(index_fields, expr),
+        Expr::Between {
+            expr,
+            negated: _,
+            low: _,
+            high: _,
+        } => checking_inverted_index_inner(index_fields, expr),
+        Expr::UnaryOp {
+            op: UnaryOperator::Not,
+            expr,
+        } => checking_inverted_index_inner(index_fields, expr),
         Expr::Like {
             negated: _,
             expr,
@@ -1525,6 +1555,49 @@ mod tests {
         assert_eq!(statement.to_string(), expected_sql);
     }
 
+    #[test]
+    fn test_index_visitor5() {
+        let sql = "SELECT * FROM t WHERE status IN ('500', '502') AND name != 'a' AND code >= '200' AND '300' > code AND code > 5 AND code BETWEEN 1 AND 5 AND age > 1";
+        let mut statement = sqlparser::parser::Parser::parse_sql(&GenericDialect {}, &sql)
+            .unwrap()
+            .pop()
+            .unwrap();
+        let mut index_fields = HashSet::new();
+        index_fields.insert("name".to_string());
+        index_fields.insert("status".to_string());
+        index_fields.insert("code".to_string());
+        let mut index_visitor = IndexVisitor::new_from_index_fields(index_fields, true);
+        statement.visit(&mut index_visitor);
+        let expected = "status IN (500,502) AND NOT name=a AND code>=200 AND code<300";
+        let expected_sql = "SELECT * FROM t WHERE name <> 'a' AND (code >= '200') AND ('300' > code) AND (code > 5) AND (code BETWEEN 1 AND 5) AND (age > 1)";
+        assert_eq!(
+            index_visitor.index_condition.clone().unwrap().to_query(),
+            expected
+        );
+        assert_eq!(statement.to_string(), expected_sql);
+    }
+
+    #[test]
+    fn test_index_visitor6() {
+        let sql = "SELECT * FROM t WHERE NOT (name = 'a' OR match_all('foo')) AND code BETWEEN '1' AND '5' AND name NOT IN ('b', 'c')";
+        let mut statement = sqlparser::parser::Parser::parse_sql(&GenericDialect {}, &sql)
+            .unwrap()
+            .pop()
+            .unwrap();
+        let mut index_fields = HashSet::new();
+        index_fields.insert("name".to_string());
+        index_fields.insert("code".to_string());
+        let mut index_visitor = IndexVisitor::new_from_index_fields(index_fields, true);
+        statement.visit(&mut index_visitor);
+        let expected = "(NOT name=a AND NOT _all:foo) AND code:[1,5] AND NOT name IN (b,c)";
+        let expected_sql = "SELECT * FROM t WHERE NOT (name = 'a' OR match_all('foo')) AND (code BETWEEN '1' AND '5') AND (name NOT IN ('b', 'c'))";
+        assert_eq!(
+            index_visitor.index_condition.clone().unwrap().to_query(),
+            expected
+        );
+        assert_eq!(statement.to_string(), expected_sql);
+    }
+
     #[test]
     fn test_track_total_hits1() {
         let sql = "SELECT * FROM t WHERE name = 'a'";
@@ -0,0 +1,335 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Streamed search responses. The record batches are sent to the client as the cluster search
+//! produces them, as NDJSON or as an Arrow IPC stream, so the querier doesn't buffer the whole
+//! result. The rows are the ones of the SQL query, query functions aren't applied. The usage of
+//! the search is reported once it's finished.
+
+use std::{str::FromStr, sync::Arc};
+
+use arrow::{array::RecordBatch, ipc::writer::StreamWriter};
+use bytes::Bytes;
+use chrono::Utc;
+use config::{
+    meta::{
+        search,
+        self_reporting::usage::{RequestStats, UsageType},
+        stream::StreamType,
+    },
+    metrics,
+    utils::{arrow::record_batches_to_json_rows, json},
+    QUERY_WITH_NO_LIMIT,
+};
+use futures::{future, Stream, StreamExt};
+use infra::errors::{Error, Result};
+#[cfg(feature = "enterprise")]
+use o2_enterprise::enterprise::search::TaskStatus;
+use proto::cluster_rpc::SearchQuery;
+use tokio::sync::mpsc;
+use tracing::Instrument;
+
+use super::{cluster::flight, request::Request, sql::Sql};
+use crate::service::self_reporting::report_request_usage_stats;
+
+/// Number of record batches buffered between the search and the response
+const CHANNEL_SIZE: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// One JSON object per line
+    Ndjson,
+    /// Arrow IPC streaming format
+    Arrow,
+}
+
+impl FromStr for StreamFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ndjson" => Ok(StreamFormat::Ndjson),
+            "arrow" => Ok(StreamFormat::Arrow),
+            _ => Err(format!(
+                "Invalid response format: {s}, supported formats are ndjson and arrow"
+            )),
+        }
+    }
+}
+
+impl StreamFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            StreamFormat::Ndjson => "application/x-ndjson",
+            StreamFormat::Arrow => "application/vnd.apache.arrow.stream",
+        }
+    }
+}
+
+/// Starts the search on the cluster, the record batches are received from the returned
+/// receiver, which ends with an error if the search fails. A request without `size` returns all
+/// the rows.
+pub async fn search(
+    trace_id: &str,
+    org_id: &str,
+    stream_type: StreamType,
+    user_id: Option<String>,
+    in_req: &search::Request,
+) -> Result<mpsc::Receiver<Result<RecordBatch>>> {
+    let mut query: SearchQuery = in_req.query.clone().into();
+    if query.size <= 0 {
+        query.size = QUERY_WITH_NO_LIMIT;
+    }
+    let req = Request::new(
+        trace_id.to_string(),
+        org_id.to_string(),
+        stream_type,
+        in_req.timeout,
+        user_id.clone(),
+        Some((query.start_time, query.end_time)),
+        in_req.search_type.map(|v| v.to_string()),
+    );
+    let sql = Arc::new(Sql::new_from_req(&req, &query).await?);
+
+    #[cfg(feature = "enterprise")]
+    super::SEARCH_SERVER
+        .insert(
+            trace_id.to_string(),
+            TaskStatus::new_leader(
+                vec![],
+                true,
+                user_id.clone(),
+                Some(org_id.to_string()),
+                Some(stream_type.to_string()),
+                Some(in_req.query.sql.clone()),
+                Some(in_req.query.start_time),
+                Some(in_req.query.end_time),
+                in_req.search_type.map(|v| v.to_string()),
+            ),
+        )
+        .await;
+
+    let (tx, rx) = mpsc::channel(CHANNEL_SIZE);
+    let trace_id = trace_id.to_string();
+    let org_id = org_id.to_string();
+    let in_req = in_req.clone();
+    let all_streams = sql.stream_names.join(",");
+    let started_at = Utc::now().timestamp_micros();
+    let span = tracing::Span::current();
+    tokio::task::spawn(
+        async move {
+            let start = std::time::Instant::now();
+            // the batches are counted on their way to the response
+            let (batch_tx, mut batch_rx) = mpsc::channel(CHANNEL_SIZE);
+            let forward = async {
+                let mut records = 0;
+                while let Some(ret) = batch_rx.recv().await {
+                    if let Ok(batch) = &ret {
+                        records += batch.num_rows();
+                    }
+                    // the client is gone
+                    if tx.send(ret).await.is_err() {
+                        break;
+                    }
+                }
+                records
+            };
+            let (ret, records) = tokio::join!(
+                flight::search(&trace_id, sql, req, query, Some(batch_tx)),
+                forward
+            );
+            #[cfg(feature = "enterprise")]
+            super::SEARCH_SERVER.remove(&trace_id, false).await;
+            metrics::QUERY_RUNNING_NUMS
+                .with_label_values(&[&org_id])
+                .dec();
+            match ret {
+                Ok((_, scan_stats, ..)) => {
+                    log::info!(
+                        "[trace_id {trace_id}] stream->search: done, records: {records}, scan_size: {} MB, took: {} ms",
+                        scan_stats.original_size,
+                        start.elapsed().as_millis()
+                    );
+                    let req_stats = RequestStats {
+                        records: records as i64,
+                        response_time: start.elapsed().as_secs_f64(),
+                        size: scan_stats.original_size as f64,
+                        request_body: Some(in_req.query.sql),
+                        user_email: user_id,
+                        min_ts: Some(in_req.query.start_time),
+                        max_ts: Some(in_req.query.end_time),
+                        search_type: in_req.search_type,
+                        search_event_context: in_req.search_event_context,
+                        trace_id: Some(trace_id.clone()),
+                        ..Default::default()
+                    };
+                    report_request_usage_stats(
+                        req_stats,
+                        &org_id,
+                        &all_streams,
+                        stream_type,
+                        UsageType::Search,
+                        0,
+                        started_at,
+                    )
+                    .await;
+                }
+                Err(e) => {
+                    log::error!("[trace_id {trace_id}] stream->search: err: {:?}", e);
+                    let _ = tx.send(Err(e)).await;
+                }
+            }
+        }
+        .instrument(span),
+    );
+    Ok(rx)
+}
+
+/// Encodes the received record batches into the chunks of the response body.
+pub fn encode(
+    rx: mpsc::Receiver<Result<RecordBatch>>,
+    format: StreamFormat,
+) -> impl Stream<Item = Result<Bytes>> {
+    futures::stream::unfold(Some((rx, BatchEncoder::new(format))), |state| async move {
+        let (mut rx, mut encoder) = state?;
+        match rx.recv().await {
+            Some(Ok(batch)) => {
+                let ret = encoder.encode(&batch);
+                let next = ret.is_ok().then_some((rx, encoder));
+                Some((ret, next))
+            }
+            Some(Err(e)) => Some((Err(e), None)),
+            None => Some((encoder.finish(), None)),
+        }
+    })
+    .filter(|ret| future::ready(!matches!(ret, Ok(chunk) if chunk.is_empty())))
+}
+
+pub struct BatchEncoder {
+    format: StreamFormat,
+    /// Created with the schema of the first batch
+    writer: Option<StreamWriter<Vec<u8>>>,
+}
+
+impl BatchEncoder {
+    pub fn new(format: StreamFormat) -> Self {
+        Self {
+            format,
+            writer: None,
+        }
+    }
+
+    pub fn encode(&mut self, batch: &RecordBatch) -> Result<Bytes> {
+        match self.format {
+            StreamFormat::Ndjson => {
+                let rows = record_batches_to_json_rows(&[batch])
+                    .map_err(|e| Error::Message(e.to_string()))?;
+                let mut buf = Vec::new();
+                for row in rows {
+                    if row.is_empty() {
+                        continue;
+                    }
+                    buf.extend(json::to_vec(&row)?);
+                    buf.push(b'\n');
+                }
+                Ok(buf.into())
+            }
+            StreamFormat::Arrow => {
+                let writer = match self.writer.as_mut() {
+                    Some(writer) => writer,
+                    None => self
+                        .writer
+                        .insert(StreamWriter::try_new(Vec::new(), &batch.schema())?),
+                };
+                writer.write(batch)?;
+                Ok(std::mem::take(writer.get_mut()).into())
+            }
+        }
+    }
+
+    /// Returns the end of the stream, nothing is returned for an Arrow stream without batches
+    /// as its schema is unknown.
+    pub fn finish(&mut self) -> Result<Bytes> {
+        match self.writer.take() {
+            Some(writer) => Ok(writer.into_inner()?.into()),
+            None => Ok(Bytes::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use arrow::{
+        array::{Int64Array, StringArray},
+        ipc::reader::StreamReader,
+    };
+    use arrow_schema::{DataType, Field, Schema};
+
+    use super::*;
+
+    fn new_batch(start: i64) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("_timestamp", DataType::Int64, false),
+            Field::new("log", DataType::Utf8, true),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![start, start + 1])),
+                Arc::new(StringArray::from(vec![Some("a"), None])),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_encode_ndjson() {
+        let mut encoder = BatchEncoder::new(StreamFormat::Ndjson);
+        let chunk = encoder.encode(&new_batch(1)).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&chunk).unwrap(),
+            "{\"_timestamp\":1,\"log\":\"a\"}\n{\"_timestamp\":2}\n"
+        );
+        assert!(encoder.finish().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_encode_arrow() {
+        let mut encoder = BatchEncoder::new(StreamFormat::Arrow);
+        let mut body = Vec::new();
+        body.extend_from_slice(&encoder.encode(&new_batch(1)).unwrap());
+        body.extend_from_slice(&encoder.encode(&new_batch(3)).unwrap());
+        body.extend_from_slice(&encoder.finish().unwrap());
+
+        let reader = StreamReader::try_new(Cursor::new(body), None).unwrap();
+        let batches = reader.collect::<std::result::Result<Vec<_>, _>>().unwrap();
+        assert_eq!(batches, vec![new_batch(1), new_batch(3)]);
+    }
+
+    #[test]
+    fn test_stream_format() {
+        assert_eq!(
+            StreamFormat::from_str("NDJSON").unwrap(),
+            StreamFormat::Ndjson
+        );
+        assert_eq!(
+            StreamFormat::from_str("arrow").unwrap(),
+            StreamFormat::Arrow
+        );
+        assert!(StreamFormat::from_str("csv").is_err());
+    }
+}
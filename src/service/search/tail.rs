@@ -0,0 +1,426 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Live tail of a stream. The querier serving the websocket polls the memtables of all the
+//! ingesters every `ZO_TAIL_POLL_INTERVAL` milliseconds for the records newer than its cursor on
+//! each of them. The cursors are the `_timestamp` of the records, so the records ingested with a
+//! timestamp older than the cursor, e.g. late logs, aren't tailed.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    sync::Arc,
+    time::Instant,
+};
+
+use chrono::Utc;
+use config::{
+    get_config,
+    meta::{cluster::get_internal_grpc_token, stream::StreamType},
+    utils::{arrow::record_batches_to_json_rows, json},
+};
+use datafusion::{
+    datasource::MemTable,
+    error::DataFusionError,
+    execution::session_state::SessionStateBuilder,
+    prelude::{SessionConfig, SessionContext},
+};
+use futures::future::join_all;
+use infra::schema::get_stream_setting_fts_fields;
+use proto::cluster_rpc;
+use serde::Serialize;
+use sqlparser::{dialect::PostgreSqlDialect, parser::Parser, tokenizer::Token};
+use tonic::{
+    codec::CompressionEncoding,
+    metadata::{MetadataKey, MetadataValue},
+    Request,
+};
+
+use crate::{
+    common::infra::cluster::get_cached_online_ingester_nodes,
+    service::{
+        grpc::get_cached_channel,
+        search::datafusion::{exec::register_udf, optimizer::rewrite_match::RewriteMatch},
+    },
+};
+
+const TABLE_NAME: &str = "tail";
+
+/// Message pushed to the websocket clients
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TailEvent {
+    /// New records, sorted by `_timestamp`
+    Hits {
+        hits: Vec<json::Value>,
+    },
+    /// Number of new records skipped because of the rate limit
+    Dropped {
+        count: usize,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Checks the filter is a single SQL condition, it's interpolated into the queries of the
+/// ingesters.
+pub fn validate_filter(filter: &str) -> Result<(), anyhow::Error> {
+    if filter.trim().is_empty() {
+        return Ok(());
+    }
+    let mut parser = Parser::new(&PostgreSqlDialect {}).try_with_sql(filter)?;
+    parser.parse_expr()?;
+    if parser.peek_token().token != Token::EOF {
+        return Err(anyhow::anyhow!(
+            "The filter must be a single SQL condition, e.g. match_all('error')"
+        ));
+    }
+    Ok(())
+}
+
+/// Reads the records of the local memtables newer than the start time of the request and
+/// matching its filter, sorted by `_timestamp`.
+pub async fn read_memtable(
+    req: &cluster_rpc::TailRequest,
+) -> Result<Vec<json::Value>, anyhow::Error> {
+    validate_filter(&req.filter)?;
+    let time_range = Some((req.start_time, i64::MAX));
+    let mut data = ingester::read_from_memtable(
+        &req.org_id,
+        &req.stream_type,
+        &req.stream_name,
+        time_range,
+        &[],
+    )
+    .await?;
+    data.extend(
+        ingester::read_from_immutable(
+            &req.org_id,
+            &req.stream_type,
+            &req.stream_name,
+            time_range,
+            &[],
+        )
+        .await?,
+    );
+    if data.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let stream_type = StreamType::from(req.stream_type.as_str());
+    let stream_settings =
+        infra::schema::get_settings(&req.org_id, &req.stream_name, stream_type).await;
+    let fts_fields = get_stream_setting_fts_fields(&stream_settings);
+    let ts_col = &get_config().common.column_timestamp;
+    let mut condition = format!("\"{ts_col}\" >= {}", req.start_time);
+    if !req.filter.trim().is_empty() {
+        condition = format!("{condition} AND ({})", req.filter);
+    }
+    let sql = format!(
+        "SELECT * FROM \"{TABLE_NAME}\" WHERE {condition} ORDER BY \"{ts_col}\" LIMIT {}",
+        req.limit
+    );
+
+    // the memtables of the different schema versions of the stream are queried separately
+    let mut hits = Vec::new();
+    for (schema, entries) in data {
+        if entries.is_empty() {
+            continue;
+        }
+        let fields = fts_fields
+            .iter()
+            .filter(|f| schema.field_with_name(f).is_ok())
+            .cloned()
+            .collect();
+        let state = SessionStateBuilder::new()
+            .with_config(SessionConfig::new())
+            .with_default_features()
+            .with_optimizer_rules(vec![Arc::new(RewriteMatch::new(fields))])
+            .build();
+        let ctx = SessionContext::new_with_state(state);
+        register_udf(&ctx, &req.org_id)?;
+        let batches = entries.iter().map(|e| e.data.clone()).collect();
+        ctx.register_table(
+            TABLE_NAME,
+            Arc::new(MemTable::try_new(schema, vec![batches])?),
+        )?;
+        let batches = match ctx.sql(&sql).await {
+            Ok(df) => df.collect().await?,
+            // the filter uses a field missing in this schema version
+            Err(e) if matches!(e.find_root(), DataFusionError::SchemaError(..)) => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let rows = record_batches_to_json_rows(&batches.iter().collect::<Vec<_>>())?;
+        hits.extend(rows.into_iter().map(json::Value::Object));
+    }
+    hits.sort_by_key(get_timestamp);
+    hits.truncate(req.limit as usize);
+    Ok(hits)
+}
+
+/// Live tail of a stream for a websocket connection.
+pub struct Tail {
+    org_id: String,
+    stream_type: StreamType,
+    stream_name: String,
+    filter: String,
+    start_time: i64,
+    /// Cursor on each ingester, by node uuid
+    cursors: HashMap<String, Cursor>,
+    limiter: RateLimiter,
+}
+
+impl Tail {
+    pub fn new(org_id: &str, stream_type: StreamType, stream_name: &str, filter: &str) -> Self {
+        Self {
+            org_id: org_id.to_string(),
+            stream_type,
+            stream_name: stream_name.to_string(),
+            filter: filter.to_string(),
+            start_time: Utc::now().timestamp_micros(),
+            cursors: HashMap::new(),
+            limiter: RateLimiter::new(get_config().limit.tail_max_records_per_sec),
+        }
+    }
+
+    /// Polls the ingesters, returns the new records allowed by the rate limit and the number of
+    /// the dropped ones.
+    pub async fn poll(&mut self) -> Result<(Vec<json::Value>, usize), anyhow::Error> {
+        let nodes = get_cached_online_ingester_nodes().await.unwrap_or_default();
+        let limit = get_config().limit.tail_max_records_per_sec as i64;
+        let tasks = nodes.into_iter().map(|node| {
+            let req = cluster_rpc::TailRequest {
+                org_id: self.org_id.clone(),
+                stream_type: self.stream_type.to_string(),
+                stream_name: self.stream_name.clone(),
+                start_time: self
+                    .cursors
+                    .get(&node.uuid)
+                    .map_or(self.start_time, |c| c.timestamp),
+                filter: self.filter.clone(),
+                limit,
+            };
+            async move {
+                let ret = fetch(&node.grpc_addr, req).await;
+                (node, ret)
+            }
+        });
+
+        let responses = join_all(tasks).await;
+
+        let mut hits = Vec::new();
+        let mut error = None;
+        let mut has_response = false;
+        for (node, ret) in responses {
+            match ret {
+                Ok(v) => {
+                    has_response = true;
+                    let cursor = self
+                        .cursors
+                        .entry(node.uuid)
+                        .or_insert_with(|| Cursor::new(self.start_time));
+                    hits.extend(cursor.advance(v));
+                }
+                Err(e) => {
+                    log::error!(
+                        "[TAIL] Error while tailing {}/{}/{} from node {}: {}",
+                        self.org_id,
+                        self.stream_type,
+                        self.stream_name,
+                        node.grpc_addr,
+                        e
+                    );
+                    error = Some(e);
+                }
+            }
+        }
+        if let (false, Some(e)) = (has_response, error) {
+            return Err(e);
+        }
+
+        hits.sort_by_key(get_timestamp);
+        let allowed = self.limiter.acquire(hits.len(), Instant::now());
+        let dropped = hits.len() - allowed;
+        hits.truncate(allowed);
+        Ok((hits, dropped))
+    }
+}
+
+/// Position of a tail on an ingester, the records newer than the timestamp, and the ones with the
+/// same timestamp which weren't seen yet, are new.
+#[derive(Debug)]
+struct Cursor {
+    timestamp: i64,
+    /// Hashes of the records of `timestamp` already seen
+    seen: HashSet<u64>,
+}
+
+impl Cursor {
+    fn new(timestamp: i64) -> Self {
+        Self {
+            timestamp,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Removes the records already seen and moves the cursor to the newest record, the records
+    /// must be sorted by `_timestamp`.
+    fn advance(&mut self, hits: Vec<json::Value>) -> Vec<json::Value> {
+        let mut new_hits = Vec::with_capacity(hits.len());
+        for hit in hits {
+            let ts = get_timestamp(&hit);
+            if ts < self.timestamp {
+                continue;
+            }
+            if ts > self.timestamp {
+                self.timestamp = ts;
+                self.seen.clear();
+            }
+            let mut hasher = DefaultHasher::new();
+            hit.to_string().hash(&mut hasher);
+            if self.seen.insert(hasher.finish()) {
+                new_hits.push(hit);
+            }
+        }
+        new_hits
+    }
+}
+
+/// Token bucket refilled with `rate` tokens per second, it holds at most a second of tokens.
+#[derive(Debug)]
+struct RateLimiter {
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl RateLimiter {
+    fn new(rate: usize) -> Self {
+        Self {
+            rate: rate as f64,
+            tokens: rate as f64,
+            last: Instant::now(),
+        }
+    }
+
+    /// Takes up to `n` tokens, returns the number of tokens taken.
+    fn acquire(&mut self, n: usize, now: Instant) -> usize {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.last = now;
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        let taken = (self.tokens as usize).min(n);
+        self.tokens -= taken as f64;
+        taken
+    }
+}
+
+fn get_timestamp(hit: &json::Value) -> i64 {
+    hit.get(&get_config().common.column_timestamp)
+        .and_then(|v| v.as_i64())
+        .unwrap_or_default()
+}
+
+async fn fetch(
+    node_addr: &str,
+    req: cluster_rpc::TailRequest,
+) -> Result<Vec<json::Value>, anyhow::Error> {
+    let cfg = get_config();
+    let org_id: MetadataValue<_> = req.org_id.parse()?;
+    let org_header_key: MetadataKey<_> = cfg.grpc.org_header_key.parse()?;
+    let token: MetadataValue<_> = get_internal_grpc_token().parse()?;
+    let mut request = tonic::Request::new(req);
+    request.set_timeout(std::time::Duration::from_secs(cfg.limit.query_timeout));
+
+    let channel = get_cached_channel(node_addr).await?;
+    let mut client = cluster_rpc::search_client::SearchClient::with_interceptor(
+        channel,
+        move |mut req: Request<()>| {
+            req.metadata_mut().insert("authorization", token.clone());
+            req.metadata_mut()
+                .insert(org_header_key.clone(), org_id.clone());
+            Ok(req)
+        },
+    );
+    client = client
+        .send_compressed(CompressionEncoding::Gzip)
+        .accept_compressed(CompressionEncoding::Gzip)
+        .max_decoding_message_size(cfg.grpc.max_message_size * 1024 * 1024)
+        .max_encoding_message_size(cfg.grpc.max_message_size * 1024 * 1024);
+    let response = client.tail(request).await?.into_inner();
+    Ok(json::from_slice(&response.hits)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_validate_filter() {
+        assert!(validate_filter("").is_ok());
+        assert!(validate_filter("level = 'error' AND match_all('timeout')").is_ok());
+        assert!(validate_filter("code >= 500 OR host LIKE 'web-%'").is_ok());
+        assert!(validate_filter("level = 'error') OR (1 = 1").is_err());
+        assert!(validate_filter("1 = 1; DROP TABLE tail").is_err());
+        assert!(validate_filter("level = 'error' UNION SELECT 1").is_err());
+    }
+
+    #[test]
+    fn test_cursor_advance() {
+        let mut cursor = Cursor::new(10);
+        let hits = vec![
+            json::json!({"_timestamp": 9, "log": "old"}),
+            json::json!({"_timestamp": 10, "log": "a"}),
+            json::json!({"_timestamp": 12, "log": "b"}),
+            json::json!({"_timestamp": 12, "log": "c"}),
+        ];
+        let new_hits = cursor.advance(hits);
+        assert_eq!(new_hits.len(), 3);
+        assert_eq!(cursor.timestamp, 12);
+
+        // the records of the cursor's timestamp are returned again by the ingester
+        let hits = vec![
+            json::json!({"_timestamp": 12, "log": "b"}),
+            json::json!({"_timestamp": 12, "log": "c"}),
+            json::json!({"_timestamp": 12, "log": "d"}),
+            json::json!({"_timestamp": 13, "log": "e"}),
+        ];
+        let new_hits = cursor.advance(hits);
+        assert_eq!(
+            new_hits,
+            vec![
+                json::json!({"_timestamp": 12, "log": "d"}),
+                json::json!({"_timestamp": 13, "log": "e"}),
+            ]
+        );
+        assert_eq!(cursor.timestamp, 13);
+        assert_eq!(cursor.seen.len(), 1);
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(100);
+        limiter.last = start;
+        assert_eq!(limiter.acquire(30, start), 30);
+        assert_eq!(limiter.acquire(100, start), 70);
+        assert_eq!(limiter.acquire(10, start), 0);
+        assert_eq!(limiter.acquire(100, start + Duration::from_millis(500)), 50);
+        // the bucket holds at most a second of tokens
+        assert_eq!(limiter.acquire(1000, start + Duration::from_secs(10)), 100);
+    }
+}
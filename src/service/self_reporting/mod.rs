@@ -177,6 +177,61 @@ pub async fn report_request_usage_stats(
     }
 }
 
+/// Records the ingestion requests rejected because of the ingestion quota of the organization
+/// or of the stream, `stream_name` is empty when the organization quota was exceeded and
+/// `count` is the number of requests rejected.
+pub async fn report_quota_rejection(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    reason: String,
+    count: i64,
+    timestamp: i64,
+) {
+    if !get_config().common.usage_enabled {
+        return;
+    }
+    let now = DateTime::from_timestamp_micros(timestamp).unwrap();
+    publish_usage(vec![UsageData {
+        _timestamp: timestamp,
+        event: UsageType::QuotaExceeded.into(),
+        day: now.day(),
+        hour: now.hour(),
+        month: now.month(),
+        year: now.year(),
+        event_time_hour: format!(
+            "{:04}{:02}{:02}{:02}",
+            now.year(),
+            now.month(),
+            now.day(),
+            now.hour()
+        ),
+        org_id: org_id.to_owned(),
+        request_body: reason,
+        size: 0.0,
+        unit: "MB".to_owned(),
+        user_email: "".to_owned(),
+        response_time: 0.0,
+        function: None,
+        num_records: count,
+        dropped_records: 0,
+        stream_type,
+        stream_name: stream_name.to_owned(),
+        min_ts: None,
+        max_ts: None,
+        cached_ratio: None,
+        compressed_size: None,
+        search_type: None,
+        search_event_context: None,
+        trace_id: None,
+        took_wait_in_queue: None,
+        result_cache_ratio: None,
+        is_partial: false,
+        work_group: None,
+    }])
+    .await;
+}
+
 async fn publish_usage(usages: Vec<UsageData>) {
     let cfg = get_config();
     if !cfg.common.usage_enabled {
@@ -302,6 +302,9 @@ pub async fn update_stream_settings(
             if let Some(approx_partition) = update_settings.approx_partition {
                 settings.approx_partition = approx_partition;
             }
+            if let Some(span_metrics_enabled) = update_settings.span_metrics_enabled {
+                settings.span_metrics_enabled = span_metrics_enabled;
+            }
 
             if let Some(flatten_level) = update_settings.flatten_level {
                 settings.flatten_level = Some(flatten_level);
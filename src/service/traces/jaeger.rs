@@ -0,0 +1,854 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Jaeger spans, either the Thrift binary encoded batches sent by the Jaeger clients to the
+//! collector, or the json traces of the Jaeger query API. They are converted into an OTLP
+//! request as the OpenTelemetry collector's jaeger receiver does: the process is the resource,
+//! the logs are the events and the tags are the attributes.
+
+use std::{collections::HashMap, io::Error};
+
+use actix_web::{http, web, HttpResponse};
+use anyhow::{anyhow, Result};
+use opentelemetry_proto::tonic::{
+    collector::trace::v1::ExportTraceServiceRequest,
+    common::v1::{any_value::Value, KeyValue},
+    resource::v1::Resource,
+    trace::v1::{
+        span::{Event, Link, SpanKind},
+        status::StatusCode,
+        ResourceSpans, ScopeSpans, Span, Status,
+    },
+};
+use serde::Deserialize;
+
+use super::{new_attribute, zipkin::decode_id, SERVICE_NAME};
+use crate::common::meta::http::HttpResponse as MetaHttpResponse;
+
+const TAG_SPAN_KIND: &str = "span.kind";
+const TAG_ERROR: &str = "error";
+const LOG_EVENT_FIELD: &str = "event";
+
+pub async fn ingest(
+    org_id: &str,
+    body: web::Bytes,
+    is_thrift: bool,
+    in_stream_name: Option<&str>,
+) -> Result<HttpResponse, Error> {
+    let batches = if is_thrift {
+        thrift::decode_batch(&body).map(|v| vec![v])
+    } else {
+        decode_json(&body)
+    };
+    let batches = match batches {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("[TRACE] Invalid jaeger spans: {}", e);
+            return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+                http::StatusCode::BAD_REQUEST.into(),
+                format!("Invalid jaeger spans: {}", e),
+            )));
+        }
+    };
+    let request = ExportTraceServiceRequest {
+        resource_spans: batches.into_iter().map(convert_batch).collect(),
+    };
+    super::handle_converted_request(org_id, request, in_stream_name).await
+}
+
+/// The spans of a process, the model shared by the thrift and json formats.
+#[derive(Debug, Default, PartialEq)]
+struct Batch {
+    service_name: String,
+    process_tags: Vec<KeyValue>,
+    spans: Vec<JaegerSpan>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct JaegerSpan {
+    trace_id: Vec<u8>,
+    span_id: Vec<u8>,
+    parent_span_id: Vec<u8>,
+    operation_name: String,
+    /// Trace id, span id and if it's a `FOLLOWS_FROM` reference
+    references: Vec<(Vec<u8>, Vec<u8>, bool)>,
+    flags: u32,
+    /// Epoch microseconds
+    start_time: u64,
+    /// Microseconds
+    duration: u64,
+    tags: Vec<KeyValue>,
+    /// Epoch microseconds and fields
+    logs: Vec<(u64, Vec<KeyValue>)>,
+}
+
+fn convert_batch(batch: Batch) -> ResourceSpans {
+    let mut attributes = vec![new_attribute(
+        SERVICE_NAME,
+        Value::StringValue(batch.service_name),
+    )];
+    attributes.extend(batch.process_tags);
+    ResourceSpans {
+        resource: Some(Resource {
+            attributes,
+            ..Default::default()
+        }),
+        scope_spans: vec![ScopeSpans {
+            spans: batch.spans.into_iter().map(convert_span).collect(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+fn convert_span(span: JaegerSpan) -> Span {
+    let mut parent_span_id = span.parent_span_id;
+    let mut links = Vec::new();
+    for (trace_id, span_id, follows_from) in span.references {
+        // the first CHILD_OF reference is the parent, the thrift spans may also have it set
+        if !follows_from && trace_id == span.trace_id {
+            if parent_span_id.is_empty() {
+                parent_span_id = span_id;
+                continue;
+            }
+            if parent_span_id == span_id {
+                continue;
+            }
+        }
+        links.push(Link {
+            trace_id,
+            span_id,
+            ..Default::default()
+        });
+    }
+
+    let mut kind = SpanKind::Unspecified;
+    let mut status = None;
+    let mut attributes = Vec::with_capacity(span.tags.len());
+    for tag in span.tags {
+        let value = tag.value.as_ref().and_then(|v| v.value.as_ref());
+        match (tag.key.as_str(), value) {
+            (TAG_SPAN_KIND, Some(Value::StringValue(v))) => {
+                kind = match v.as_str() {
+                    "client" => SpanKind::Client,
+                    "server" => SpanKind::Server,
+                    "producer" => SpanKind::Producer,
+                    "consumer" => SpanKind::Consumer,
+                    "internal" => SpanKind::Internal,
+                    _ => SpanKind::Unspecified,
+                };
+                continue;
+            }
+            (TAG_ERROR, Some(Value::BoolValue(true))) => {
+                status = Some(Status {
+                    code: StatusCode::Error as i32,
+                    ..Default::default()
+                });
+            }
+            _ => {}
+        }
+        attributes.push(tag);
+    }
+
+    let events = span
+        .logs
+        .into_iter()
+        .map(|(timestamp, fields)| {
+            let mut name = String::new();
+            let mut attributes = Vec::with_capacity(fields.len());
+            for field in fields {
+                match field.value.as_ref().and_then(|v| v.value.as_ref()) {
+                    Some(Value::StringValue(v)) if field.key == LOG_EVENT_FIELD => {
+                        name = v.to_string()
+                    }
+                    _ => attributes.push(field),
+                }
+            }
+            Event {
+                time_unix_nano: timestamp * 1000,
+                name,
+                attributes,
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    Span {
+        trace_id: span.trace_id,
+        span_id: span.span_id,
+        parent_span_id,
+        flags: span.flags,
+        name: span.operation_name,
+        kind: kind as i32,
+        start_time_unix_nano: span.start_time * 1000,
+        end_time_unix_nano: (span.start_time + span.duration) * 1000,
+        attributes,
+        events,
+        links,
+        status,
+        ..Default::default()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonTraces {
+    data: Vec<JsonTrace>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonTrace {
+    spans: Vec<JsonSpan>,
+    #[serde(default)]
+    processes: HashMap<String, JsonProcess>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonSpan {
+    #[serde(rename = "traceID")]
+    trace_id: String,
+    #[serde(rename = "spanID")]
+    span_id: String,
+    #[serde(default)]
+    operation_name: String,
+    #[serde(default)]
+    references: Vec<JsonReference>,
+    #[serde(default)]
+    flags: u32,
+    start_time: u64,
+    #[serde(default)]
+    duration: u64,
+    #[serde(default)]
+    tags: Vec<JsonTag>,
+    #[serde(default)]
+    logs: Vec<JsonLog>,
+    #[serde(rename = "processID", default)]
+    process_id: String,
+    #[serde(default)]
+    process: Option<JsonProcess>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonReference {
+    ref_type: String,
+    #[serde(rename = "traceID")]
+    trace_id: String,
+    #[serde(rename = "spanID")]
+    span_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonProcess {
+    service_name: String,
+    #[serde(default)]
+    tags: Vec<JsonTag>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct JsonTag {
+    key: String,
+    #[serde(rename = "type", default)]
+    tag_type: String,
+    value: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonLog {
+    timestamp: u64,
+    #[serde(default)]
+    fields: Vec<JsonTag>,
+}
+
+/// Decodes the traces of the Jaeger query API, `{"data": [<trace>...]}` or a single trace, into
+/// one batch per process.
+fn decode_json(body: &[u8]) -> Result<Vec<Batch>> {
+    let traces = match serde_json::from_slice::<JsonTraces>(body) {
+        Ok(v) => v.data,
+        Err(_) => vec![serde_json::from_slice::<JsonTrace>(body)?],
+    };
+
+    let mut batches: HashMap<String, Batch> = HashMap::new();
+    for trace in traces {
+        for span in trace.spans {
+            let process = span
+                .process
+                .clone()
+                .or_else(|| trace.processes.get(&span.process_id).cloned())
+                .ok_or_else(|| anyhow!("process of span {} not found", span.span_id))?;
+            let batch = batches
+                .entry(process.service_name.clone())
+                .or_insert_with(|| Batch {
+                    service_name: process.service_name,
+                    process_tags: process.tags.into_iter().map(json_tag).collect(),
+                    spans: vec![],
+                });
+            batch.spans.push(json_span(span)?);
+        }
+    }
+    Ok(batches.into_values().collect())
+}
+
+fn json_span(span: JsonSpan) -> Result<JaegerSpan> {
+    let references = span
+        .references
+        .into_iter()
+        .map(|r| {
+            Ok((
+                decode_id(&r.trace_id, 16)?,
+                decode_id(&r.span_id, 8)?,
+                r.ref_type == "FOLLOWS_FROM",
+            ))
+        })
+        .collect::<Result<_>>()?;
+    Ok(JaegerSpan {
+        trace_id: decode_id(&span.trace_id, 16)?,
+        span_id: decode_id(&span.span_id, 8)?,
+        parent_span_id: vec![],
+        operation_name: span.operation_name,
+        references,
+        flags: span.flags,
+        start_time: span.start_time,
+        duration: span.duration,
+        tags: span.tags.into_iter().map(json_tag).collect(),
+        logs: span
+            .logs
+            .into_iter()
+            .map(|log| {
+                (
+                    log.timestamp,
+                    log.fields.into_iter().map(json_tag).collect(),
+                )
+            })
+            .collect(),
+    })
+}
+
+fn json_tag(tag: JsonTag) -> KeyValue {
+    let value = match (tag.tag_type.as_str(), tag.value) {
+        ("bool", serde_json::Value::Bool(v)) => Value::BoolValue(v),
+        ("int64", serde_json::Value::Number(v)) if v.is_i64() => {
+            Value::IntValue(v.as_i64().unwrap_or_default())
+        }
+        ("float64", serde_json::Value::Number(v)) => {
+            Value::DoubleValue(v.as_f64().unwrap_or_default())
+        }
+        (_, serde_json::Value::String(v)) => Value::StringValue(v),
+        (_, v) => Value::StringValue(v.to_string()),
+    };
+    new_attribute(tag.key, value)
+}
+
+/// Decoding of the `Batch` struct of jaeger.thrift encoded with the Thrift binary protocol.
+mod thrift {
+    use std::collections::HashMap;
+
+    use anyhow::{anyhow, Result};
+    use opentelemetry_proto::tonic::common::v1::{any_value::Value, KeyValue};
+
+    use super::{new_attribute, Batch, JaegerSpan};
+
+    const T_STOP: u8 = 0;
+    const T_BOOL: u8 = 2;
+    const T_BYTE: u8 = 3;
+    const T_DOUBLE: u8 = 4;
+    const T_I16: u8 = 6;
+    const T_I32: u8 = 8;
+    const T_I64: u8 = 10;
+    const T_STRING: u8 = 11;
+    const T_STRUCT: u8 = 12;
+    const T_MAP: u8 = 13;
+    const T_SET: u8 = 14;
+    const T_LIST: u8 = 15;
+
+    const MAX_DEPTH: usize = 32;
+    /// Elements preallocated for a list, the sizes are read from the payload
+    const MAX_PREALLOCATED: usize = 1024;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub(super) enum ThriftValue {
+        Bool(bool),
+        Double(f64),
+        Int(i64),
+        Binary(Vec<u8>),
+        Struct(HashMap<i16, ThriftValue>),
+        List(Vec<ThriftValue>),
+        Map,
+    }
+
+    struct Reader<'a> {
+        buf: &'a [u8],
+        pos: usize,
+    }
+
+    impl<'a> Reader<'a> {
+        fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+            if self.buf.len() - self.pos < len {
+                return Err(anyhow!("unexpected end of data"));
+            }
+            let ret = &self.buf[self.pos..self.pos + len];
+            self.pos += len;
+            Ok(ret)
+        }
+
+        fn read_u8(&mut self) -> Result<u8> {
+            Ok(self.take(1)?[0])
+        }
+
+        fn read_i16(&mut self) -> Result<i16> {
+            Ok(i16::from_be_bytes(self.take(2)?.try_into()?))
+        }
+
+        fn read_i32(&mut self) -> Result<i32> {
+            Ok(i32::from_be_bytes(self.take(4)?.try_into()?))
+        }
+
+        fn read_i64(&mut self) -> Result<i64> {
+            Ok(i64::from_be_bytes(self.take(8)?.try_into()?))
+        }
+
+        fn read_size(&mut self) -> Result<usize> {
+            let size = self.read_i32()?;
+            // every element takes at least a byte, which bounds the allocations
+            if size < 0 || size as usize > self.buf.len() - self.pos {
+                return Err(anyhow!("invalid size: {size}"));
+            }
+            Ok(size as usize)
+        }
+
+        fn read_value(&mut self, value_type: u8, depth: usize) -> Result<ThriftValue> {
+            if depth > MAX_DEPTH {
+                return Err(anyhow!("too deeply nested"));
+            }
+            Ok(match value_type {
+                T_BOOL => ThriftValue::Bool(self.read_u8()? != 0),
+                T_BYTE => ThriftValue::Int(self.read_u8()? as i8 as i64),
+                T_DOUBLE => ThriftValue::Double(f64::from_bits(self.read_i64()? as u64)),
+                T_I16 => ThriftValue::Int(self.read_i16()? as i64),
+                T_I32 => ThriftValue::Int(self.read_i32()? as i64),
+                T_I64 => ThriftValue::Int(self.read_i64()?),
+                T_STRING => {
+                    let len = self.read_size()?;
+                    ThriftValue::Binary(self.take(len)?.to_vec())
+                }
+                T_STRUCT => {
+                    let mut fields = HashMap::new();
+                    loop {
+                        let field_type = self.read_u8()?;
+                        if field_type == T_STOP {
+                            break;
+                        }
+                        let id = self.read_i16()?;
+                        fields.insert(id, self.read_value(field_type, depth + 1)?);
+                    }
+                    ThriftValue::Struct(fields)
+                }
+                T_LIST | T_SET => {
+                    let element_type = self.read_u8()?;
+                    let size = self.read_size()?;
+                    let mut values = Vec::with_capacity(size.min(MAX_PREALLOCATED));
+                    for _ in 0..size {
+                        values.push(self.read_value(element_type, depth + 1)?);
+                    }
+                    ThriftValue::List(values)
+                }
+                T_MAP => {
+                    // no map in the jaeger model, they're skipped
+                    let key_type = self.read_u8()?;
+                    let value_type = self.read_u8()?;
+                    let size = self.read_size()?;
+                    for _ in 0..size {
+                        self.read_value(key_type, depth + 1)?;
+                        self.read_value(value_type, depth + 1)?;
+                    }
+                    ThriftValue::Map
+                }
+                t => return Err(anyhow!("invalid type: {t}")),
+            })
+        }
+    }
+
+    type Fields = HashMap<i16, ThriftValue>;
+
+    fn get_struct(fields: &Fields, id: i16) -> Option<&Fields> {
+        match fields.get(&id) {
+            Some(ThriftValue::Struct(v)) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn get_list(fields: &Fields, id: i16) -> &[ThriftValue] {
+        match fields.get(&id) {
+            Some(ThriftValue::List(v)) => v,
+            _ => &[],
+        }
+    }
+
+    fn get_int(fields: &Fields, id: i16) -> Option<i64> {
+        match fields.get(&id) {
+            Some(ThriftValue::Int(v)) => Some(*v),
+            _ => None,
+        }
+    }
+
+    fn get_string(fields: &Fields, id: i16) -> Option<String> {
+        match fields.get(&id) {
+            Some(ThriftValue::Binary(v)) => Some(String::from_utf8_lossy(v).to_string()),
+            _ => None,
+        }
+    }
+
+    fn structs(values: &[ThriftValue]) -> impl Iterator<Item = &Fields> {
+        values.iter().filter_map(|v| match v {
+            ThriftValue::Struct(v) => Some(v),
+            _ => None,
+        })
+    }
+
+    fn trace_id(fields: &Fields, low_id: i16, high_id: i16) -> Vec<u8> {
+        let high = get_int(fields, high_id).unwrap_or_default();
+        let low = get_int(fields, low_id).unwrap_or_default();
+        [high.to_be_bytes(), low.to_be_bytes()].concat()
+    }
+
+    /// `struct Tag { 1: string key, 2: TagType vType, 3: string vStr, 4: double vDouble,
+    /// 5: bool vBool, 6: i64 vLong, 7: binary vBinary }`
+    fn tag(fields: &Fields) -> Option<KeyValue> {
+        let key = get_string(fields, 1)?;
+        // the value is in the field following the type, e.g. vStr for STRING (0)
+        let value_type = get_int(fields, 2)?;
+        let value = match (value_type, fields.get(&(value_type as i16 + 3))) {
+            (0, Some(ThriftValue::Binary(v))) => {
+                Value::StringValue(String::from_utf8_lossy(v).to_string())
+            }
+            (1, Some(ThriftValue::Double(v))) => Value::DoubleValue(*v),
+            (2, Some(ThriftValue::Bool(v))) => Value::BoolValue(*v),
+            (3, Some(ThriftValue::Int(v))) => Value::IntValue(*v),
+            (4, Some(ThriftValue::Binary(v))) => Value::BytesValue(v.clone()),
+            _ => return None,
+        };
+        Some(new_attribute(key, value))
+    }
+
+    fn tags(values: &[ThriftValue]) -> Vec<KeyValue> {
+        structs(values).filter_map(tag).collect()
+    }
+
+    /// `struct Span { 1: i64 traceIdLow, 2: i64 traceIdHigh, 3: i64 spanId, 4: i64 parentSpanId,
+    /// 5: string operationName, 6: list<SpanRef> references, 7: i32 flags, 8: i64 startTime,
+    /// 9: i64 duration, 10: list<Tag> tags, 11: list<Log> logs }`
+    fn span(fields: &Fields) -> Result<JaegerSpan> {
+        let span_id = get_int(fields, 3).ok_or_else(|| anyhow!("missing span id"))?;
+        let parent_span_id = get_int(fields, 4).unwrap_or_default();
+        // struct SpanRef { 1: SpanRefType refType, 2: i64 traceIdLow, 3: i64 traceIdHigh,
+        // 4: i64 spanId }
+        let references = structs(get_list(fields, 6))
+            .map(|r| {
+                (
+                    trace_id(r, 2, 3),
+                    get_int(r, 4).unwrap_or_default().to_be_bytes().to_vec(),
+                    get_int(r, 1) == Some(1),
+                )
+            })
+            .collect();
+        // struct Log { 1: i64 timestamp, 2: list<Tag> fields }
+        let logs = structs(get_list(fields, 11))
+            .map(|l| {
+                (
+                    get_int(l, 1).unwrap_or_default() as u64,
+                    tags(get_list(l, 2)),
+                )
+            })
+            .collect();
+        Ok(JaegerSpan {
+            trace_id: trace_id(fields, 1, 2),
+            span_id: span_id.to_be_bytes().to_vec(),
+            parent_span_id: if parent_span_id == 0 {
+                vec![]
+            } else {
+                parent_span_id.to_be_bytes().to_vec()
+            },
+            operation_name: get_string(fields, 5).unwrap_or_default(),
+            references,
+            flags: get_int(fields, 7).unwrap_or_default() as u32,
+            start_time: get_int(fields, 8).unwrap_or_default() as u64,
+            duration: get_int(fields, 9).unwrap_or_default() as u64,
+            tags: tags(get_list(fields, 10)),
+            logs,
+        })
+    }
+
+    /// `struct Batch { 1: Process process, 2: list<Span> spans }` where
+    /// `struct Process { 1: string serviceName, 2: list<Tag> tags }`.
+    pub(super) fn decode_batch(body: &[u8]) -> Result<Batch> {
+        let mut reader = Reader { buf: body, pos: 0 };
+        let ThriftValue::Struct(batch) = reader.read_value(T_STRUCT, 0)? else {
+            unreachable!()
+        };
+        let process = get_struct(&batch, 1).ok_or_else(|| anyhow!("missing process"))?;
+        Ok(Batch {
+            service_name: get_string(process, 1).unwrap_or_default(),
+            process_tags: tags(get_list(process, 2)),
+            spans: structs(get_list(&batch, 2))
+                .map(span)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    #[cfg(test)]
+    pub(super) mod tests {
+        use super::*;
+
+        /// Minimal Thrift binary protocol writer to build the test batches.
+        #[derive(Default)]
+        pub struct Writer(pub Vec<u8>);
+
+        impl Writer {
+            pub fn field(&mut self, field_type: u8, id: i16) -> &mut Self {
+                self.0.push(field_type);
+                self.0.extend(id.to_be_bytes());
+                self
+            }
+
+            pub fn i64(&mut self, id: i16, v: i64) -> &mut Self {
+                self.field(T_I64, id);
+                self.0.extend(v.to_be_bytes());
+                self
+            }
+
+            pub fn i32(&mut self, id: i16, v: i32) -> &mut Self {
+                self.field(T_I32, id);
+                self.0.extend(v.to_be_bytes());
+                self
+            }
+
+            pub fn bool(&mut self, id: i16, v: bool) -> &mut Self {
+                self.field(T_BOOL, id);
+                self.0.push(v as u8);
+                self
+            }
+
+            pub fn string(&mut self, id: i16, v: &str) -> &mut Self {
+                self.field(T_STRING, id);
+                self.0.extend((v.len() as i32).to_be_bytes());
+                self.0.extend(v.as_bytes());
+                self
+            }
+
+            pub fn list(&mut self, id: i16, elements: &[Vec<u8>]) -> &mut Self {
+                self.field(T_LIST, id);
+                self.0.push(T_STRUCT);
+                self.0.extend((elements.len() as i32).to_be_bytes());
+                for element in elements {
+                    self.0.extend(element);
+                }
+                self
+            }
+
+            pub fn begin_struct(&mut self, id: i16) -> &mut Self {
+                self.field(T_STRUCT, id)
+            }
+
+            pub fn end_struct(&mut self) -> &mut Self {
+                self.0.push(T_STOP);
+                self
+            }
+
+            pub fn build(&mut self) -> Vec<u8> {
+                self.end_struct();
+                std::mem::take(&mut self.0)
+            }
+        }
+
+        pub fn string_tag(key: &str, value: &str) -> Vec<u8> {
+            Writer::default()
+                .string(1, key)
+                .i32(2, 0)
+                .string(3, value)
+                .build()
+        }
+
+        pub fn bool_tag(key: &str, value: bool) -> Vec<u8> {
+            Writer::default()
+                .string(1, key)
+                .i32(2, 2)
+                .bool(5, value)
+                .build()
+        }
+
+        #[test]
+        fn test_reader() {
+            let mut reader = Reader {
+                buf: &[T_STRING, 0, 1, 0xff, 0xff, 0xff, 0xff, T_STOP],
+                pos: 0,
+            };
+            assert!(reader.read_value(T_STRUCT, 0).is_err());
+
+            let mut reader = Reader {
+                buf: &[T_I32, 0, 1, 0, 0],
+                pos: 0,
+            };
+            assert!(reader.read_value(T_STRUCT, 0).is_err());
+
+            let nested = [vec![T_STRUCT, 0, 1]; 64].concat();
+            let mut reader = Reader {
+                buf: &nested,
+                pos: 0,
+            };
+            assert!(reader.read_value(T_STRUCT, 0).is_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        thrift::tests::{bool_tag, string_tag, Writer},
+        *,
+    };
+
+    #[test]
+    fn test_decode_thrift() {
+        let log = Writer::default()
+            .i64(1, 1700000000001000)
+            .list(
+                2,
+                &[string_tag("event", "retry"), string_tag("attempt", "2")],
+            )
+            .build();
+        let reference = Writer::default()
+            .i32(1, 1)
+            .i64(2, 5)
+            .i64(3, 0)
+            .i64(4, 7)
+            .build();
+        let span = Writer::default()
+            .i64(1, 2)
+            .i64(2, 1)
+            .i64(3, 3)
+            .i64(4, 4)
+            .string(5, "get")
+            .list(6, &[reference])
+            .i32(7, 1)
+            .i64(8, 1700000000000000)
+            .i64(9, 2500)
+            .list(
+                10,
+                &[
+                    string_tag("span.kind", "server"),
+                    bool_tag("error", true),
+                    string_tag("http.method", "GET"),
+                ],
+            )
+            .list(11, &[log])
+            .build();
+        let body = Writer::default()
+            .begin_struct(1)
+            .string(1, "frontend")
+            .list(2, &[string_tag("hostname", "host-1")])
+            .end_struct()
+            .list(2, &[span])
+            .build();
+
+        let batch = thrift::decode_batch(&body).unwrap();
+        assert_eq!(batch.service_name, "frontend");
+        assert_eq!(
+            batch.process_tags,
+            vec![new_attribute(
+                "hostname",
+                Value::StringValue("host-1".to_string())
+            )]
+        );
+        let resource_spans = convert_batch(batch);
+        let span = &resource_spans.scope_spans[0].spans[0];
+        assert_eq!(
+            hex::encode(&span.trace_id),
+            "00000000000000010000000000000002"
+        );
+        assert_eq!(hex::encode(&span.span_id), "0000000000000003");
+        assert_eq!(hex::encode(&span.parent_span_id), "0000000000000004");
+        assert_eq!(span.name, "get");
+        assert_eq!(span.kind, SpanKind::Server as i32);
+        assert_eq!(span.flags, 1);
+        assert_eq!(span.start_time_unix_nano, 1700000000000000000);
+        assert_eq!(span.end_time_unix_nano, 1700000000002500000);
+        assert_eq!(span.status.as_ref().unwrap().code, StatusCode::Error as i32);
+        assert_eq!(span.attributes.len(), 2);
+        assert_eq!(span.links.len(), 1);
+        assert_eq!(hex::encode(&span.links[0].span_id), "0000000000000007");
+        assert_eq!(span.events[0].name, "retry");
+        assert_eq!(span.events[0].time_unix_nano, 1700000000001000000);
+        assert_eq!(
+            span.events[0].attributes,
+            vec![new_attribute(
+                "attempt",
+                Value::StringValue("2".to_string())
+            )]
+        );
+
+        assert!(thrift::decode_batch(&body[..body.len() - 10]).is_err());
+        assert!(thrift::decode_batch(&Writer::default().build()).is_err());
+    }
+
+    #[test]
+    fn test_decode_json() {
+        let body = r#"{"data": [{
+            "traceID": "5982fe77008310cc80f1da5e10147517",
+            "spans": [
+                {
+                    "traceID": "5982fe77008310cc80f1da5e10147517",
+                    "spanID": "bd7a977555f6b982",
+                    "operationName": "get /api",
+                    "references": [{"refType": "CHILD_OF", "traceID": "5982fe77008310cc80f1da5e10147517", "spanID": "ebf33e1a81dc6f71"}],
+                    "startTime": 1700000000000000,
+                    "duration": 2500,
+                    "tags": [
+                        {"key": "span.kind", "type": "string", "value": "client"},
+                        {"key": "http.status_code", "type": "int64", "value": 200}
+                    ],
+                    "logs": [{"timestamp": 1700000000001000, "fields": [{"key": "event", "type": "string", "value": "sent"}]}],
+                    "processID": "p1"
+                }
+            ],
+            "processes": {"p1": {"serviceName": "frontend", "tags": [{"key": "ip", "type": "string", "value": "10.0.0.1"}]}}
+        }]}"#;
+        let batches = decode_json(body.as_bytes()).unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].service_name, "frontend");
+        let resource_spans = convert_batch(batches.into_iter().next().unwrap());
+        assert_eq!(
+            resource_spans.resource.as_ref().unwrap().attributes.len(),
+            2
+        );
+        let span = &resource_spans.scope_spans[0].spans[0];
+        assert_eq!(hex::encode(&span.parent_span_id), "ebf33e1a81dc6f71");
+        assert!(span.links.is_empty());
+        assert_eq!(span.kind, SpanKind::Client as i32);
+        assert_eq!(
+            span.attributes,
+            vec![new_attribute("http.status_code", Value::IntValue(200))]
+        );
+        assert_eq!(span.events[0].name, "sent");
+
+        assert!(decode_json(
+            br#"{"spans": [{"traceID": "1", "spanID": "2", "startTime": 1, "processID": "p1"}]}"#
+        )
+        .is_err());
+        assert!(decode_json(b"[]").is_err());
+    }
+}
@@ -37,6 +37,7 @@ use opentelemetry_proto::tonic::{
     collector::trace::v1::{
         ExportTracePartialSuccess, ExportTraceServiceRequest, ExportTraceServiceResponse,
     },
+    common::v1::{any_value, AnyValue, KeyValue},
     trace::v1::{status::StatusCode, Status},
 };
 use prost::Message;
@@ -51,16 +52,21 @@ use crate::{
     service::{
         alerts::alert::AlertExt,
         db, format_stream_name,
-        ingestion::{evaluate_trigger, grpc::get_val, write_file, TriggerAlertData},
+        ingestion::{evaluate_trigger, grpc::get_val, quota, write_file, TriggerAlertData},
         metadata::{
-            distinct_values::DvItem, trace_list_index::TraceListItem, write, MetadataItem,
-            MetadataType,
+            distinct_values::DvItem, service_graph::SgItem, trace_list_index::TraceListItem, write,
+            MetadataItem, MetadataType,
         },
         schema::{check_for_schema, stream_schema_exists},
         self_reporting::report_request_usage_stats,
     },
 };
 
+pub mod jaeger;
+pub mod span_metrics;
+pub mod tree;
+pub mod zipkin;
+
 const PARENT_SPAN_ID: &str = "reference.parent_span_id";
 const PARENT_TRACE_ID: &str = "reference.parent_trace_id";
 const REF_TYPE: &str = "reference.ref_type";
@@ -173,6 +179,12 @@ pub async fn handle_trace_request(
         Some(name) => format_stream_name(name),
         None => "default".to_owned(),
     };
+    if let Err(e) = quota::check(org_id, StreamType::Traces, Some(&traces_stream_name)) {
+        return match req_type {
+            RequestType::Grpc => Err(Error::other(e)),
+            _ => Ok(e.http_response()),
+        };
+    }
     let min_ts = (Utc::now()
         - Duration::try_hours(cfg.limit.ingest_allowed_upto)
             .expect("configuration error: too large ingest_allowed_upto"))
@@ -491,6 +503,15 @@ pub async fn handle_trace_request(
         return format_response(partial_success, req_type);
     }
 
+    // the request is rejected as a whole when the quota of one of its streams is exhausted, so
+    // that it can be retried without writing twice the other streams
+    if let Err(e) = quota::check_streams(org_id, StreamType::Traces, json_data_by_stream.keys()) {
+        return match req_type {
+            RequestType::Grpc => Err(Error::other(e)),
+            _ => Ok(e.http_response()),
+        };
+    }
+
     if let Err(e) = write_traces_by_stream(org_id, (started_at, &start), json_data_by_stream).await
     {
         log::error!("Error while writing traces: {}", e);
@@ -508,6 +529,9 @@ pub async fn handle_trace_request(
         _ => "/api/otlp/v1/traces",
     };
 
+    // record span metrics for the streams which enabled them
+    span_metrics::record(org_id, &span_metrics).await;
+
     if cfg.common.traces_span_metrics_enabled {
         // record span metrics
         for m in span_metrics {
@@ -551,6 +575,28 @@ pub async fn handle_trace_request(
     format_response(partial_success, req_type)
 }
 
+/// Ingests a request converted from another trace format, the clients of these formats expect
+/// `202 Accepted` on success.
+async fn handle_converted_request(
+    org_id: &str,
+    request: ExportTraceServiceRequest,
+    in_stream_name: Option<&str>,
+) -> Result<HttpResponse, Error> {
+    let res = handle_trace_request(org_id, request, RequestType::HttpJson, in_stream_name).await?;
+    if res.status() == http::StatusCode::OK {
+        Ok(HttpResponse::Accepted().finish())
+    } else {
+        Ok(res)
+    }
+}
+
+fn new_attribute(key: impl Into<String>, value: any_value::Value) -> KeyValue {
+    KeyValue {
+        key: key.into(),
+        value: Some(AnyValue { value: Some(value) }),
+    }
+}
+
 fn get_span_status(status: Option<Status>) -> String {
     match status {
         Some(v) => match v.code() {
@@ -705,6 +751,11 @@ async fn write_traces(
     let mut data_buf: HashMap<String, SchemaRecords> = HashMap::new();
     let mut distinct_values = Vec::with_capacity(16);
     let mut trace_index_values = Vec::with_capacity(json_data.len());
+    let mut service_graph_values = if cfg.common.traces_service_graph_enabled {
+        Vec::with_capacity(json_data.len())
+    } else {
+        Vec::new()
+    };
 
     // Start write data
     for (timestamp, record_val) in json_data {
@@ -738,6 +789,26 @@ async fn write_traces(
             .as_str()
             .unwrap()
             .to_string();
+        if cfg.common.traces_service_graph_enabled {
+            let get_str = |key: &str| {
+                record_val
+                    .get(key)
+                    .map(json::get_string_value)
+                    .unwrap_or_default()
+            };
+            service_graph_values.push(MetadataItem::ServiceGraph(SgItem {
+                stream_name: stream_name.to_string(),
+                trace_id: trace_id.clone(),
+                span_id: get_str("span_id"),
+                parent_span_id: get_str("reference_parent_span_id"),
+                service_name: service_name.to_string(),
+                is_error: get_str("span_status") == "ERROR",
+                duration: record_val
+                    .get("duration")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or_default(),
+            }));
+        }
         trace_index_values.push(MetadataItem::TraceListIndexer(TraceListItem {
             _timestamp: timestamp,
             stream_name: stream_name.to_string(),
@@ -797,6 +868,7 @@ async fn write_traces(
     // write data to wal
     let writer =
         ingester::get_writer(0, org_id, &StreamType::Traces.to_string(), stream_name).await;
+    quota::consume(org_id, StreamType::Traces, stream_name, &data_buf);
     let req_stats = write_file(&writer, stream_name, data_buf).await;
     if let Err(e) = writer.sync().await {
         log::error!("ingestion error while syncing writer: {}", e);
@@ -816,6 +888,13 @@ async fn write_traces(
         }
     }
 
+    // send service graph spans
+    if !service_graph_values.is_empty() {
+        if let Err(e) = write(org_id, MetadataType::ServiceGraph, service_graph_values).await {
+            log::error!("Error while writing service graph values: {}", e);
+        }
+    }
+
     // only one trigger per request
     evaluate_trigger(triggers).await;
 
@@ -0,0 +1,387 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RED (rate, errors, duration) metrics generated from the ingested spans.
+//!
+//! Streams with `span_metrics_enabled` in their settings get the series
+//! `traces_spanmetrics_calls_total` and `traces_spanmetrics_duration_milliseconds_*`
+//! written into their own organization through the OTLP metrics ingestion, so that
+//! they can be queried and alerted on with PromQL. The series are labeled with the name of the
+//! ingester counting them, and dropped once they haven't been updated for
+//! `MAX_IDLE_EXPORTS` exports.
+
+use std::collections::HashMap;
+
+use config::{
+    cluster::LOCAL_NODE, get_config, meta::stream::StreamType, metrics::SPAN_METRICS_BUCKET,
+};
+use once_cell::sync::Lazy;
+use opentelemetry_proto::tonic::{
+    collector::metrics::v1::ExportMetricsServiceRequest,
+    common::v1::{any_value, AnyValue, InstrumentationScope, KeyValue},
+    metrics::v1::{
+        metric::Data, number_data_point, AggregationTemporality, Histogram, HistogramDataPoint,
+        Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics, Sum,
+    },
+};
+use parking_lot::RwLock;
+use tokio::time;
+
+use crate::{job::metrics::TraceMetricsItem, service::metrics::otlp_grpc::handle_grpc_request};
+
+const CALLS_METRIC_NAME: &str = "traces_spanmetrics_calls_total";
+const DURATION_METRIC_NAME: &str = "traces_spanmetrics_duration_milliseconds";
+/// Series without spans during this many exports are dropped
+const MAX_IDLE_EXPORTS: u32 = 10;
+
+// org_id -> series
+static SPAN_METRICS: Lazy<RwLock<HashMap<String, HashMap<SeriesKey, SeriesValue>>>> =
+    Lazy::new(Default::default);
+
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+struct SeriesKey {
+    traces_stream_name: String,
+    service_name: String,
+    operation_name: String,
+    status_code: String,
+    span_kind: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct SeriesValue {
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+    // per bucket counts, the last one is for the durations over the last bound
+    buckets: Vec<u64>,
+    // the start of the cumulative series
+    start_time: u64,
+    // spans were observed since the last export
+    updated: bool,
+    idle_exports: u32,
+}
+
+impl SeriesValue {
+    fn new() -> Self {
+        Self {
+            start_time: now_nanos(),
+            ..Default::default()
+        }
+    }
+
+    fn observe(&mut self, duration: f64) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; SPAN_METRICS_BUCKET.len() + 1];
+            self.min = duration;
+            self.max = duration;
+        }
+        self.updated = true;
+        self.count += 1;
+        self.sum += duration;
+        self.min = self.min.min(duration);
+        self.max = self.max.max(duration);
+        let idx = SPAN_METRICS_BUCKET
+            .iter()
+            .position(|bound| duration <= *bound)
+            .unwrap_or(SPAN_METRICS_BUCKET.len());
+        self.buckets[idx] += 1;
+    }
+}
+
+/// Records the spans of the traces streams that have span metrics enabled.
+pub async fn record(org_id: &str, items: &[TraceMetricsItem]) {
+    let mut enabled_streams: HashMap<&str, bool> = HashMap::new();
+    for item in items {
+        if enabled_streams.contains_key(item.traces_stream_name.as_str()) {
+            continue;
+        }
+        let enabled =
+            infra::schema::get_settings(org_id, &item.traces_stream_name, StreamType::Traces)
+                .await
+                .is_some_and(|settings| settings.span_metrics_enabled);
+        enabled_streams.insert(&item.traces_stream_name, enabled);
+    }
+    if !enabled_streams.values().any(|v| *v) {
+        return;
+    }
+
+    let mut span_metrics = SPAN_METRICS.write();
+    let series = span_metrics.entry(org_id.to_string()).or_default();
+    for item in items {
+        if !enabled_streams
+            .get(item.traces_stream_name.as_str())
+            .copied()
+            .unwrap_or_default()
+        {
+            continue;
+        }
+        series
+            .entry(SeriesKey {
+                traces_stream_name: item.traces_stream_name.clone(),
+                service_name: item.service_name.clone(),
+                operation_name: item.span_name.clone(),
+                status_code: item.span_status.clone(),
+                span_kind: item.span_kind.clone(),
+            })
+            .or_insert_with(SeriesValue::new)
+            .observe(item.duration);
+    }
+}
+
+/// Periodically writes the span metrics of every organization into its metrics streams.
+pub async fn run() {
+    if !LOCAL_NODE.is_ingester() {
+        return;
+    }
+    let mut interval = time::interval(time::Duration::from_secs(
+        get_config().common.traces_span_metrics_export_interval,
+    ));
+    interval.tick().await; // trigger the first run
+    loop {
+        interval.tick().await;
+        export().await;
+    }
+}
+
+async fn export() {
+    // the series are cumulative, export a snapshot and keep counting
+    let snapshot = {
+        let mut span_metrics = SPAN_METRICS.write();
+        evict_idle_series(&mut span_metrics);
+        span_metrics.clone()
+    };
+    let time_unix_nano = now_nanos();
+    for (org_id, series) in snapshot {
+        let request = build_request(&series, time_unix_nano);
+        match handle_grpc_request(&org_id, request, true).await {
+            Ok(resp) if !resp.status().is_success() => {
+                log::error!(
+                    "[SPAN_METRICS] export for org {org_id} failed with status: {}",
+                    resp.status()
+                );
+            }
+            Err(e) => log::error!("[SPAN_METRICS] export for org {org_id} error: {e}"),
+            _ => {}
+        }
+    }
+}
+
+/// Drops the series which haven't been updated for `MAX_IDLE_EXPORTS` exports, the other ones
+/// are marked as not updated until the next export.
+fn evict_idle_series(span_metrics: &mut HashMap<String, HashMap<SeriesKey, SeriesValue>>) {
+    span_metrics.retain(|_, series| {
+        series.retain(|_, value| {
+            if value.updated {
+                value.updated = false;
+                value.idle_exports = 0;
+            } else {
+                value.idle_exports += 1;
+            }
+            value.idle_exports < MAX_IDLE_EXPORTS
+        });
+        !series.is_empty()
+    });
+}
+
+fn build_request(
+    series: &HashMap<SeriesKey, SeriesValue>,
+    time_unix_nano: u64,
+) -> ExportMetricsServiceRequest {
+    let mut calls = Vec::with_capacity(series.len());
+    let mut durations = Vec::with_capacity(series.len());
+    for (key, value) in series {
+        let start_time_unix_nano = value.start_time;
+        let attributes = vec![
+            // each ingester counts its own spans
+            string_attr("instance", &LOCAL_NODE.name),
+            string_attr("traces_stream_name", &key.traces_stream_name),
+            string_attr("service_name", &key.service_name),
+            string_attr("operation_name", &key.operation_name),
+            string_attr("status_code", &key.status_code),
+            string_attr("span_kind", &key.span_kind),
+        ];
+        calls.push(NumberDataPoint {
+            attributes: attributes.clone(),
+            start_time_unix_nano,
+            time_unix_nano,
+            value: Some(number_data_point::Value::AsDouble(value.count as f64)),
+            ..Default::default()
+        });
+        // the ingestion stores each bucket as a `_bucket{le="..."}` series, accumulate
+        // the counts to follow the prometheus `le` semantics expected by PromQL
+        let bucket_counts = value
+            .buckets
+            .iter()
+            .scan(0, |acc, count| {
+                *acc += count;
+                Some(*acc)
+            })
+            .collect();
+        durations.push(HistogramDataPoint {
+            attributes,
+            start_time_unix_nano,
+            time_unix_nano,
+            count: value.count,
+            sum: Some(value.sum),
+            bucket_counts,
+            explicit_bounds: SPAN_METRICS_BUCKET.to_vec(),
+            min: Some(value.min),
+            max: Some(value.max),
+            ..Default::default()
+        });
+    }
+
+    let metrics = vec![
+        Metric {
+            name: CALLS_METRIC_NAME.to_string(),
+            description: "number of spans".to_string(),
+            data: Some(Data::Sum(Sum {
+                data_points: calls,
+                aggregation_temporality: AggregationTemporality::Cumulative as i32,
+                is_monotonic: true,
+            })),
+            ..Default::default()
+        },
+        Metric {
+            name: DURATION_METRIC_NAME.to_string(),
+            description: "span duration milliseconds".to_string(),
+            unit: "ms".to_string(),
+            data: Some(Data::Histogram(Histogram {
+                data_points: durations,
+                aggregation_temporality: AggregationTemporality::Cumulative as i32,
+            })),
+            ..Default::default()
+        },
+    ];
+    ExportMetricsServiceRequest {
+        resource_metrics: vec![ResourceMetrics {
+            scope_metrics: vec![ScopeMetrics {
+                scope: Some(InstrumentationScope {
+                    name: "openobserve".to_string(),
+                    ..Default::default()
+                }),
+                metrics,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+}
+
+fn string_attr(key: &str, value: &str) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(AnyValue {
+            value: Some(any_value::Value::StringValue(value.to_string())),
+        }),
+    }
+}
+
+fn now_nanos() -> u64 {
+    chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_request() {
+        let key = SeriesKey {
+            traces_stream_name: "default".to_string(),
+            service_name: "api".to_string(),
+            operation_name: "GET /users".to_string(),
+            status_code: "OK".to_string(),
+            span_kind: "2".to_string(),
+        };
+        let mut value = SeriesValue::new();
+        for duration in [0.3, 3.0, 4.0, 100000.0] {
+            value.observe(duration);
+        }
+        assert_eq!(value.count, 4);
+        assert_eq!(value.min, 0.3);
+        assert_eq!(value.max, 100000.0);
+
+        let start_time = value.start_time;
+        let series = HashMap::from([(key, value)]);
+        let request = build_request(&series, start_time + 1);
+        let metrics = &request.resource_metrics[0].scope_metrics[0].metrics;
+        assert_eq!(metrics.len(), 2);
+
+        let Some(Data::Sum(sum)) = &metrics[0].data else {
+            panic!("calls should be a sum");
+        };
+        assert_eq!(
+            sum.data_points[0].value,
+            Some(number_data_point::Value::AsDouble(4.0))
+        );
+        assert_eq!(sum.data_points[0].attributes.len(), 6);
+        assert_eq!(sum.data_points[0].start_time_unix_nano, start_time);
+
+        let Some(Data::Histogram(hist)) = &metrics[1].data else {
+            panic!("duration should be a histogram");
+        };
+        let dp = &hist.data_points[0];
+        assert_eq!(dp.bucket_counts.len(), SPAN_METRICS_BUCKET.len() + 1);
+        // 0.3 <= 0.5, 3.0 and 4.0 <= 5.0, 100000.0 only in +Inf
+        assert_eq!(dp.bucket_counts[0], 0);
+        assert_eq!(dp.bucket_counts[1], 1);
+        assert_eq!(dp.bucket_counts[3], 3);
+        assert_eq!(dp.bucket_counts[SPAN_METRICS_BUCKET.len() - 1], 3);
+        assert_eq!(dp.bucket_counts[SPAN_METRICS_BUCKET.len()], 4);
+    }
+
+    #[test]
+    fn test_evict_idle_series() {
+        let key = SeriesKey {
+            traces_stream_name: "default".to_string(),
+            service_name: "api".to_string(),
+            operation_name: "GET /users".to_string(),
+            status_code: "OK".to_string(),
+            span_kind: "2".to_string(),
+        };
+        let mut value = SeriesValue::new();
+        value.observe(1.0);
+        let mut span_metrics =
+            HashMap::from([("default".to_string(), HashMap::from([(key.clone(), value)]))]);
+
+        evict_idle_series(&mut span_metrics);
+        let value = &span_metrics["default"][&key];
+        assert!(!value.updated);
+        assert_eq!(value.idle_exports, 0);
+
+        for _ in 1..MAX_IDLE_EXPORTS {
+            evict_idle_series(&mut span_metrics);
+        }
+        assert_eq!(
+            span_metrics["default"][&key].idle_exports,
+            MAX_IDLE_EXPORTS - 1
+        );
+        span_metrics
+            .get_mut("default")
+            .unwrap()
+            .get_mut(&key)
+            .unwrap()
+            .observe(2.0);
+        evict_idle_series(&mut span_metrics);
+        assert_eq!(span_metrics["default"][&key].idle_exports, 0);
+
+        for _ in 0..MAX_IDLE_EXPORTS {
+            evict_idle_series(&mut span_metrics);
+        }
+        assert!(span_metrics.is_empty());
+    }
+}
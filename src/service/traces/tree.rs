@@ -0,0 +1,421 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, HashMap};
+
+use config::{
+    get_config,
+    meta::{search, stream::StreamType},
+    utils::json,
+};
+use infra::errors::Result;
+
+use crate::{
+    common::meta::traces::{TraceServiceDuration, TraceSpanNode, TraceTree},
+    service::{metadata::trace_list_index, search as SearchService},
+};
+
+const PARENT_SPAN_ID_FIELD: &str = "reference_parent_span_id";
+const SPAN_STATUS_ERROR: &str = "ERROR";
+const PAGE_SIZE: i64 = 1000;
+/// Spans returned for one trace at most, the following ones are dropped
+const MAX_SPANS: usize = 100_000;
+/// Depth of the span trees at most, the deeper spans are returned as orphaned roots
+const MAX_DEPTH: usize = 1000;
+
+/// Fetches all the spans of `trace_id` and assembles them into a [TraceTree].
+///
+/// The trace list index is consulted first to narrow the search to the time range the
+/// spans were ingested in, falling back to `time_range` when the trace is not indexed.
+pub async fn get_trace(
+    search_trace_id: &str,
+    org_id: &str,
+    stream_name: &str,
+    trace_id: &str,
+    user_id: Option<String>,
+    time_range: (i64, i64),
+) -> Result<Option<TraceTree>> {
+    let cfg = get_config();
+    let (start_time, end_time) = match trace_list_index::get_trace_time_range(
+        org_id,
+        stream_name,
+        trace_id,
+        time_range,
+    )
+    .await
+    {
+        // the end time of search is exclusive
+        Ok(Some((start_time, end_time))) => (start_time, end_time + 1),
+        Ok(None) => time_range,
+        Err(e) => {
+            log::warn!(
+                "[trace_id {search_trace_id}] trace_list_index: get time range for {trace_id} error: {e}"
+            );
+            time_range
+        }
+    };
+
+    let mut req = search::Request {
+        query: search::Query {
+            sql: format!(
+                "SELECT * FROM \"{stream_name}\" WHERE trace_id = '{trace_id}' ORDER BY {} ASC",
+                cfg.common.column_timestamp
+            ),
+            from: 0,
+            size: PAGE_SIZE,
+            start_time,
+            end_time,
+            ..Default::default()
+        },
+        encoding: search::RequestEncoding::Empty,
+        regions: vec![],
+        clusters: vec![],
+        timeout: 0,
+        search_type: None,
+        search_event_context: None,
+    };
+
+    let mut spans = Vec::new();
+    loop {
+        let resp = SearchService::search(
+            search_trace_id,
+            org_id,
+            StreamType::Traces,
+            user_id.clone(),
+            &req,
+        )
+        .await?;
+        let resp_size = resp.hits.len() as i64;
+        spans.extend(resp.hits);
+        if resp_size < req.query.size {
+            break;
+        }
+        if spans.len() >= MAX_SPANS {
+            log::warn!(
+                "[trace_id {search_trace_id}] trace {trace_id} has more than {MAX_SPANS} spans, the rest are dropped"
+            );
+            spans.truncate(MAX_SPANS);
+            break;
+        }
+        req.query.from += req.query.size;
+    }
+
+    if spans.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(build_trace_tree(trace_id, spans)))
+}
+
+/// Assembles the spans of one trace into parent/child trees.
+///
+/// Spans without a parent are roots. Spans whose parent can't be found in `spans` are
+/// reported as orphaned and also returned as roots, so that no span is lost.
+pub fn build_trace_tree(trace_id: &str, spans: Vec<json::Value>) -> TraceTree {
+    let mut tree = TraceTree {
+        trace_id: trace_id.to_string(),
+        total_spans: spans.len(),
+        ..Default::default()
+    };
+
+    let mut nodes = spans
+        .into_iter()
+        .map(|span| Some(to_node(span)))
+        .collect::<Vec<_>>();
+    let span_index = nodes
+        .iter()
+        .enumerate()
+        .map(|(idx, node)| (node.as_ref().unwrap().span_id.clone(), idx))
+        .collect::<HashMap<_, _>>();
+
+    let mut roots = Vec::new();
+    let mut children_of: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (idx, node) in nodes.iter().enumerate() {
+        let node = node.as_ref().unwrap();
+        match node.parent_span_id.as_ref() {
+            None => roots.push(idx),
+            Some(parent_id) => match span_index.get(parent_id) {
+                Some(parent_idx) if *parent_idx != idx => {
+                    children_of.entry(*parent_idx).or_default().push(idx)
+                }
+                _ => {
+                    tree.orphaned_spans.push(node.span_id.clone());
+                    roots.push(idx);
+                }
+            },
+        }
+    }
+
+    let mut spans = Vec::with_capacity(roots.len());
+    for idx in roots {
+        if let Some(node) = take_node(idx, &mut nodes, &children_of) {
+            spans.push(node);
+        }
+    }
+    // spans referencing each other in a cycle, or below the max depth, can't be reached from any
+    // root
+    for idx in 0..nodes.len() {
+        let Some(node) = take_node(idx, &mut nodes, &children_of) else {
+            continue;
+        };
+        tree.orphaned_spans.push(node.span_id.clone());
+        spans.push(node);
+    }
+    spans.sort_by_key(|node| node.start_time);
+
+    // the critical path starts from the earliest real root span, or the earliest orphan
+    if let Some(root) = spans
+        .iter_mut()
+        .min_by_key(|node| (node.parent_span_id.is_some(), node.start_time))
+    {
+        let end_time = root.end_time;
+        mark_critical_path(root, end_time, &mut tree.critical_path);
+    }
+
+    let mut services: BTreeMap<String, TraceServiceDuration> = BTreeMap::new();
+    tree.start_time = u64::MAX;
+    for node in spans.iter() {
+        collect_stats(node, &mut tree, &mut services);
+    }
+    tree.start_time = tree.start_time.min(tree.end_time);
+    tree.duration = tree.end_time.saturating_sub(tree.start_time) / 1000;
+    tree.services = services.into_values().collect();
+    tree.spans = spans;
+    tree
+}
+
+fn to_node(span: json::Value) -> TraceSpanNode {
+    let get_str = |key: &str| {
+        span.get(key)
+            .map(json::get_string_value)
+            .unwrap_or_default()
+    };
+    let get_u64 = |key: &str| span.get(key).and_then(|v| v.as_u64()).unwrap_or_default();
+    let start_time = get_u64("start_time");
+    let end_time = get_u64("end_time").max(start_time);
+    let duration = (end_time - start_time) / 1000;
+    TraceSpanNode {
+        span_id: get_str("span_id"),
+        parent_span_id: Some(get_str(PARENT_SPAN_ID_FIELD)).filter(|v| !v.is_empty()),
+        service_name: get_str("service_name"),
+        operation_name: get_str("operation_name"),
+        span_kind: get_str("span_kind"),
+        span_status: get_str("span_status"),
+        start_time,
+        end_time,
+        duration,
+        self_duration: duration,
+        is_critical: false,
+        span,
+        children: Vec::new(),
+    }
+}
+
+// builds the subtree of `root` iteratively, a deep trace would overflow the stack otherwise.
+// the children below MAX_DEPTH are left in `nodes`.
+fn take_node(
+    root: usize,
+    nodes: &mut [Option<TraceSpanNode>],
+    children_of: &HashMap<usize, Vec<usize>>,
+) -> Option<TraceSpanNode> {
+    let node = nodes[root].take()?;
+    // the nodes being built with the position of their next child
+    let mut stack = vec![(node, root, 0)];
+    loop {
+        let (_, idx, pos) = stack.last_mut().unwrap();
+        if let Some(child_idx) = children_of
+            .get(&*idx)
+            .and_then(|children| children.get(*pos))
+        {
+            *pos += 1;
+            if stack.len() >= MAX_DEPTH {
+                continue;
+            }
+            if let Some(child) = nodes[*child_idx].take() {
+                stack.push((child, *child_idx, 0));
+            }
+            continue;
+        }
+
+        let (mut node, ..) = stack.pop().unwrap();
+        node.children.sort_by_key(|child| child.start_time);
+        node.self_duration = (node.end_time
+            - node.start_time
+            - covered_time(node.start_time, node.end_time, &node.children))
+            / 1000;
+        match stack.last_mut() {
+            Some((parent, ..)) => parent.children.push(node),
+            None => return Some(node),
+        }
+    }
+}
+
+// the length of the union of the children intervals, clipped to the parent span
+fn covered_time(start_time: u64, end_time: u64, children: &[TraceSpanNode]) -> u64 {
+    let mut covered = 0;
+    let mut cursor = start_time;
+    // children are sorted by start_time
+    for child in children {
+        let child_start = child.start_time.max(cursor);
+        let child_end = child.end_time.min(end_time);
+        if child_end > child_start {
+            covered += child_end - child_start;
+            cursor = child_end;
+        }
+    }
+    covered
+}
+
+// walk backward from the end of the span, the latest finishing child blocks the parent,
+// then move the cursor to the start of that child and look for the next blocking child
+fn mark_critical_path(root: &mut TraceSpanNode, cursor: u64, path: &mut Vec<String>) {
+    let mut stack = vec![(root, cursor)];
+    while let Some((node, cursor)) = stack.pop() {
+        node.is_critical = true;
+        path.push(node.span_id.clone());
+        let mut cursor = cursor.min(node.end_time);
+        let mut children = node.children.iter_mut().collect::<Vec<_>>();
+        children.sort_by_key(|child| std::cmp::Reverse(child.end_time));
+        let mut blocking = Vec::new();
+        for child in children {
+            if child.start_time >= cursor {
+                continue;
+            }
+            let child_start = child.start_time;
+            blocking.push((child, cursor));
+            cursor = child_start;
+        }
+        // the latest finishing child is visited first
+        stack.extend(blocking.into_iter().rev());
+    }
+}
+
+fn collect_stats(
+    root: &TraceSpanNode,
+    tree: &mut TraceTree,
+    services: &mut BTreeMap<String, TraceServiceDuration>,
+) {
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        tree.start_time = tree.start_time.min(node.start_time);
+        tree.end_time = tree.end_time.max(node.end_time);
+        let is_error = node.span_status == SPAN_STATUS_ERROR;
+        if is_error {
+            tree.error_spans += 1;
+        }
+        let service = services
+            .entry(node.service_name.clone())
+            .or_insert_with(|| TraceServiceDuration {
+                service_name: node.service_name.clone(),
+                ..Default::default()
+            });
+        service.span_count += 1;
+        if is_error {
+            service.error_count += 1;
+        }
+        service.duration += node.duration;
+        service.self_duration += node.self_duration;
+        stack.extend(node.children.iter());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use config::utils::json::json;
+
+    use super::*;
+
+    fn span(span_id: &str, parent: &str, service: &str, start: u64, end: u64) -> json::Value {
+        json!({
+            "trace_id": "t1",
+            "span_id": span_id,
+            "reference_parent_span_id": parent,
+            "service_name": service,
+            "operation_name": format!("op-{span_id}"),
+            "span_kind": "2",
+            "span_status": if span_id == "c" { "ERROR" } else { "UNSET" },
+            "start_time": start * 1000,
+            "end_time": end * 1000,
+        })
+    }
+
+    #[test]
+    fn test_build_trace_tree() {
+        let spans = vec![
+            span("a", "", "frontend", 0, 100),
+            span("b", "a", "api", 10, 40),
+            span("c", "a", "db", 30, 90),
+            span("d", "c", "db", 35, 60),
+            span("e", "x", "api", 50, 55),
+        ];
+        let tree = build_trace_tree("t1", spans);
+
+        assert_eq!(tree.total_spans, 5);
+        assert_eq!(tree.error_spans, 1);
+        assert_eq!(tree.duration, 100);
+        assert_eq!(tree.orphaned_spans, vec!["e".to_string()]);
+        assert_eq!(tree.spans.len(), 2);
+
+        let root = &tree.spans[0];
+        assert_eq!(root.span_id, "a");
+        assert_eq!(root.children.len(), 2);
+        // [10, 90) is covered by b and c
+        assert_eq!(root.self_duration, 20);
+        assert_eq!(root.children[1].children[0].span_id, "d");
+
+        assert_eq!(tree.critical_path, vec!["a", "c", "d", "b"]);
+
+        let db = tree
+            .services
+            .iter()
+            .find(|s| s.service_name == "db")
+            .unwrap();
+        assert_eq!(db.span_count, 2);
+        assert_eq!(db.error_count, 1);
+        assert_eq!(db.duration, 85);
+    }
+
+    #[test]
+    fn test_build_trace_tree_with_cycle() {
+        let spans = vec![span("a", "b", "api", 0, 10), span("b", "a", "api", 0, 10)];
+        let tree = build_trace_tree("t1", spans);
+        assert_eq!(tree.total_spans, 2);
+        assert_eq!(tree.spans.len(), 1);
+        assert_eq!(tree.orphaned_spans, vec!["a".to_string()]);
+        assert_eq!(tree.spans[0].children[0].span_id, "b");
+    }
+
+    #[test]
+    fn test_build_deep_trace_tree() {
+        let depth = 5 * MAX_DEPTH as u64;
+        let spans = (0..depth)
+            .map(|i| {
+                let parent = if i == 0 {
+                    String::new()
+                } else {
+                    (i - 1).to_string()
+                };
+                span(&i.to_string(), &parent, "api", i, 2 * depth - i)
+            })
+            .collect();
+        let tree = build_trace_tree("t1", spans);
+        assert_eq!(tree.total_spans, depth as usize);
+        assert_eq!(tree.spans.len(), 5);
+        assert_eq!(tree.orphaned_spans.len(), 4);
+        assert_eq!(tree.critical_path.len(), MAX_DEPTH);
+        assert_eq!(tree.spans[0].self_duration, 2);
+        let api = &tree.services[0];
+        assert_eq!(api.span_count, depth as usize);
+    }
+}
@@ -0,0 +1,343 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Zipkin JSON spans, they are converted into an OTLP request as the OpenTelemetry collector's
+//! zipkin receiver does: the local endpoint is the resource, the annotations are the events and
+//! the tags, or the binary annotations of the v1 spans, are the attributes.
+
+use std::{collections::HashMap, io::Error};
+
+use actix_web::{http, web, HttpResponse};
+use opentelemetry_proto::tonic::{
+    collector::trace::v1::ExportTraceServiceRequest,
+    common::v1::{any_value::Value, KeyValue},
+    resource::v1::Resource,
+    trace::v1::{
+        span::{Event, SpanKind},
+        status::StatusCode,
+        ResourceSpans, ScopeSpans, Span, Status,
+    },
+};
+use serde::Deserialize;
+
+use super::{new_attribute, SERVICE_NAME};
+use crate::common::meta::http::HttpResponse as MetaHttpResponse;
+
+const TAG_ERROR: &str = "error";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ZipkinSpan {
+    trace_id: String,
+    id: String,
+    #[serde(default)]
+    parent_id: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    kind: Option<String>,
+    /// Epoch microseconds
+    #[serde(default)]
+    timestamp: Option<u64>,
+    /// Microseconds
+    #[serde(default)]
+    duration: Option<u64>,
+    #[serde(default)]
+    local_endpoint: Option<Endpoint>,
+    #[serde(default)]
+    remote_endpoint: Option<Endpoint>,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+    /// Tags of the v1 spans
+    #[serde(default)]
+    binary_annotations: Vec<BinaryAnnotation>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Endpoint {
+    #[serde(default)]
+    service_name: Option<String>,
+    #[serde(default)]
+    ipv4: Option<String>,
+    #[serde(default)]
+    ipv6: Option<String>,
+    #[serde(default)]
+    port: Option<u16>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Annotation {
+    timestamp: u64,
+    value: String,
+    /// Endpoint of the v1 annotations
+    #[serde(default)]
+    endpoint: Option<Endpoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinaryAnnotation {
+    key: String,
+    value: serde_json::Value,
+    #[serde(default)]
+    endpoint: Option<Endpoint>,
+}
+
+pub async fn ingest(
+    org_id: &str,
+    body: web::Bytes,
+    in_stream_name: Option<&str>,
+) -> Result<HttpResponse, Error> {
+    let request = match decode(&body) {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("[TRACE] Invalid zipkin spans: {}", e);
+            return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+                http::StatusCode::BAD_REQUEST.into(),
+                format!("Invalid zipkin spans: {}", e),
+            )));
+        }
+    };
+    super::handle_converted_request(org_id, request, in_stream_name).await
+}
+
+fn decode(body: &[u8]) -> Result<ExportTraceServiceRequest, anyhow::Error> {
+    let spans: Vec<ZipkinSpan> = serde_json::from_slice(body)?;
+    let mut spans_by_service: HashMap<String, Vec<Span>> = HashMap::new();
+    for span in spans {
+        let service_name = span
+            .local_endpoint
+            .as_ref()
+            .or_else(|| span.annotations.iter().find_map(|a| a.endpoint.as_ref()))
+            .or_else(|| {
+                span.binary_annotations
+                    .iter()
+                    .find_map(|a| a.endpoint.as_ref())
+            })
+            .and_then(|e| e.service_name.clone())
+            .unwrap_or_default();
+        spans_by_service
+            .entry(service_name)
+            .or_default()
+            .push(convert_span(span)?);
+    }
+
+    let resource_spans = spans_by_service
+        .into_iter()
+        .map(|(service_name, spans)| ResourceSpans {
+            resource: Some(Resource {
+                attributes: vec![new_attribute(
+                    SERVICE_NAME,
+                    Value::StringValue(service_name),
+                )],
+                ..Default::default()
+            }),
+            scope_spans: vec![ScopeSpans {
+                spans,
+                ..Default::default()
+            }],
+            ..Default::default()
+        })
+        .collect();
+    Ok(ExportTraceServiceRequest { resource_spans })
+}
+
+fn convert_span(span: ZipkinSpan) -> Result<Span, anyhow::Error> {
+    let trace_id = decode_id(&span.trace_id, 16)?;
+    let span_id = decode_id(&span.id, 8)?;
+    let parent_span_id = match span.parent_id.as_deref() {
+        Some(id) if !id.is_empty() => decode_id(id, 8)?,
+        _ => vec![],
+    };
+    let kind = match span.kind.as_deref() {
+        Some("CLIENT") => SpanKind::Client,
+        Some("SERVER") => SpanKind::Server,
+        Some("PRODUCER") => SpanKind::Producer,
+        Some("CONSUMER") => SpanKind::Consumer,
+        _ => SpanKind::Unspecified,
+    };
+    let start_time = span.timestamp.unwrap_or_default() * 1000;
+    let end_time = start_time + span.duration.unwrap_or_default() * 1000;
+
+    let mut status = None;
+    let mut attributes = Vec::new();
+    for (key, value) in span.tags {
+        if key == TAG_ERROR {
+            status = Some(Status {
+                code: StatusCode::Error as i32,
+                message: value.clone(),
+            });
+        }
+        attributes.push(new_attribute(key, Value::StringValue(value)));
+    }
+    for annotation in span.binary_annotations {
+        if annotation.key == TAG_ERROR {
+            status = Some(Status {
+                code: StatusCode::Error as i32,
+                message: annotation.value.to_string(),
+            });
+        }
+        attributes.push(json_attribute(annotation.key, annotation.value));
+    }
+    if let Some(endpoint) = span.remote_endpoint {
+        if let Some(v) = endpoint.service_name {
+            attributes.push(new_attribute("peer.service", Value::StringValue(v)));
+        }
+        if let Some(v) = endpoint.ipv4.or(endpoint.ipv6) {
+            attributes.push(new_attribute("net.peer.ip", Value::StringValue(v)));
+        }
+        if let Some(v) = endpoint.port {
+            attributes.push(new_attribute("net.peer.port", Value::IntValue(v as i64)));
+        }
+    }
+
+    let events = span
+        .annotations
+        .into_iter()
+        .map(|annotation| Event {
+            time_unix_nano: annotation.timestamp * 1000,
+            name: annotation.value,
+            ..Default::default()
+        })
+        .collect();
+
+    Ok(Span {
+        trace_id,
+        span_id,
+        parent_span_id,
+        name: span.name.unwrap_or_default(),
+        kind: kind as i32,
+        start_time_unix_nano: start_time,
+        end_time_unix_nano: end_time,
+        attributes,
+        events,
+        status,
+        ..Default::default()
+    })
+}
+
+fn json_attribute(key: String, value: serde_json::Value) -> KeyValue {
+    let value = match value {
+        serde_json::Value::String(v) => Value::StringValue(v),
+        serde_json::Value::Bool(v) => Value::BoolValue(v),
+        serde_json::Value::Number(v) => match v.as_i64() {
+            Some(v) => Value::IntValue(v),
+            None => Value::DoubleValue(v.as_f64().unwrap_or_default()),
+        },
+        v => Value::StringValue(v.to_string()),
+    };
+    new_attribute(key, value)
+}
+
+/// Decodes a hex id, the shorter ones are left padded with zeros, e.g. the 64 bits trace ids.
+pub(super) fn decode_id(id: &str, len: usize) -> Result<Vec<u8>, anyhow::Error> {
+    if id.is_empty() || id.len() > len * 2 {
+        return Err(anyhow::anyhow!("invalid id: {id}"));
+    }
+    let id = format!("{:0>width$}", id, width = len * 2);
+    hex::decode(&id).map_err(|_| anyhow::anyhow!("invalid id: {id}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_id() {
+        assert_eq!(
+            decode_id("463ac35c9f6413ad", 16).unwrap(),
+            hex::decode("0000000000000000463ac35c9f6413ad").unwrap()
+        );
+        assert_eq!(decode_id("a", 8).unwrap(), vec![0, 0, 0, 0, 0, 0, 0, 10]);
+        assert!(decode_id("", 8).is_err());
+        assert!(decode_id("xyz", 8).is_err());
+        assert!(decode_id("00000000000000000", 8).is_err());
+    }
+
+    #[test]
+    fn test_decode() {
+        let body = r#"[{
+            "traceId": "5982fe77008310cc80f1da5e10147517",
+            "id": "bd7a977555f6b982",
+            "parentId": "ebf33e1a81dc6f71",
+            "name": "get /api",
+            "kind": "SERVER",
+            "timestamp": 1700000000000000,
+            "duration": 2500,
+            "localEndpoint": {"serviceName": "frontend", "ipv4": "10.0.0.1"},
+            "remoteEndpoint": {"serviceName": "browser", "ipv4": "10.0.0.2", "port": 8080},
+            "annotations": [{"timestamp": 1700000000001000, "value": "wr"}],
+            "tags": {"http.method": "GET", "error": "timeout"}
+        }]"#;
+        let request = decode(body.as_bytes()).unwrap();
+        assert_eq!(request.resource_spans.len(), 1);
+        let resource_spans = &request.resource_spans[0];
+        assert_eq!(
+            resource_spans.resource.as_ref().unwrap().attributes,
+            vec![new_attribute(
+                SERVICE_NAME,
+                Value::StringValue("frontend".to_string())
+            )]
+        );
+        let span = &resource_spans.scope_spans[0].spans[0];
+        assert_eq!(
+            hex::encode(&span.trace_id),
+            "5982fe77008310cc80f1da5e10147517"
+        );
+        assert_eq!(hex::encode(&span.parent_span_id), "ebf33e1a81dc6f71");
+        assert_eq!(span.kind, SpanKind::Server as i32);
+        assert_eq!(span.start_time_unix_nano, 1700000000000000000);
+        assert_eq!(span.end_time_unix_nano, 1700000000002500000);
+        assert_eq!(span.events.len(), 1);
+        assert_eq!(span.events[0].name, "wr");
+        assert_eq!(span.events[0].time_unix_nano, 1700000000001000000);
+        assert_eq!(span.status.as_ref().unwrap().code, StatusCode::Error as i32);
+        assert_eq!(span.status.as_ref().unwrap().message, "timeout");
+        assert!(span
+            .attributes
+            .contains(&new_attribute("net.peer.port", Value::IntValue(8080))));
+        assert!(span.attributes.contains(&new_attribute(
+            "http.method",
+            Value::StringValue("GET".to_string())
+        )));
+
+        let v1_body = r#"[{
+            "traceId": "463ac35c9f6413ad",
+            "id": "72485a3953bb6124",
+            "name": "query",
+            "timestamp": 1700000000000000,
+            "duration": 100,
+            "annotations": [{"timestamp": 1700000000000000, "value": "cs", "endpoint": {"serviceName": "db-client"}}],
+            "binaryAnnotations": [{"key": "db.rows", "value": 3}]
+        }]"#;
+        let request = decode(v1_body.as_bytes()).unwrap();
+        let resource_spans = &request.resource_spans[0];
+        assert_eq!(
+            resource_spans.resource.as_ref().unwrap().attributes[0],
+            new_attribute(SERVICE_NAME, Value::StringValue("db-client".to_string()))
+        );
+        let span = &resource_spans.scope_spans[0].spans[0];
+        assert!(span.parent_span_id.is_empty());
+        assert_eq!(
+            span.attributes,
+            vec![new_attribute("db.rows", Value::IntValue(3))]
+        );
+
+        assert!(decode(br#"[{"traceId": "xyz", "id": "1"}]"#).is_err());
+        assert!(decode(b"{}").is_err());
+    }
+}